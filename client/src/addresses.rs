@@ -0,0 +1,162 @@
+/*!
+ * Program-derived addresses
+ *
+ * Pools are derived from [b"pool", mint, denomination], with SOL_POOL_MINT
+ * standing in for native SOL, so each asset gets its own pool at every
+ * denomination. The accounts hanging off a pool are derived from its address.
+ */
+
+use anchor_lang::prelude::Pubkey;
+use nullifier::SOL_POOL_MINT;
+
+fn find(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &nullifier::ID).0
+}
+
+/// Pool for `denomination` base units of `mint`
+pub fn pool_address(mint: &Pubkey, denomination: u64) -> Pubkey {
+    find(&[b"pool", mint.as_ref(), &denomination.to_le_bytes()])
+}
+
+/// Native SOL pool for `denomination` lamports
+pub fn sol_pool_address(denomination: u64) -> Pubkey {
+    pool_address(&SOL_POOL_MINT, denomination)
+}
+
+/// Token account holding a token pool's deposits
+pub fn vault_address(pool: &Pubkey) -> Pubkey {
+    find(&[b"vault", pool.as_ref()])
+}
+
+/// PDA that owns a token pool's vault and signs its payouts
+pub fn vault_authority_address(pool: &Pubkey) -> Pubkey {
+    find(&[b"vault_authority", pool.as_ref()])
+}
+
+/// Account a SOL pool's withdrawal fees accrue in until distribute_fees
+pub fn fee_vault_address(pool: &Pubkey) -> Pubkey {
+    find(&[b"fee_vault", pool.as_ref()])
+}
+
+/// SHA256 MerkleTreeState of a pool's tree `generation`
+pub fn merkle_tree_address(pool: &Pubkey, generation: u32) -> Pubkey {
+    find(&[b"merkle_tree", pool.as_ref(), &generation.to_le_bytes()])
+}
+
+/// Poseidon MerkleTreeState of a dual-tree pool's tree `generation`
+pub fn poseidon_tree_address(pool: &Pubkey, generation: u32) -> Pubkey {
+    find(&[b"poseidon_tree", pool.as_ref(), &generation.to_le_bytes()])
+}
+
+/// CommitmentRecord of the deposit at `leaf_index`
+pub fn commitment_address(pool: &Pubkey, leaf_index: u32) -> Pubkey {
+    find(&[b"commitment", pool.as_ref(), &leaf_index.to_le_bytes()])
+}
+
+/// The pool's NullifierBloom
+pub fn nullifier_bloom_address(pool: &Pubkey) -> Pubkey {
+    find(&[b"nullifier_bloom", pool.as_ref()])
+}
+
+/// The pool's pre-PDA NullifierRegistry
+pub fn nullifier_registry_address(pool: &Pubkey) -> Pubkey {
+    find(&[b"nullifier_registry", pool.as_ref()])
+}
+
+/// Relayer allowlist of an Allowlisted pool
+pub fn pool_relayers_address(pool: &Pubkey) -> Pubkey {
+    find(&[b"pool_relayers", pool.as_ref()])
+}
+
+/// RelayerInfo published by `relayer`
+pub fn relayer_info_address(relayer: &Pubkey) -> Pubkey {
+    find(&[b"relayer", relayer.as_ref()])
+}
+
+/// Open misbehavior report against `relayer`
+pub fn relayer_report_address(relayer: &Pubkey) -> Pubkey {
+    find(&[b"relayer_report", relayer.as_ref()])
+}
+
+/// WithdrawalIntent for spending `nullifier_hash` from `pool` with a relayer
+/// `fee`; the intent's proof must name this address as its relayer
+pub fn withdrawal_intent_address(pool: &Pubkey, nullifier_hash: &[u8; 32], fee: u64) -> Pubkey {
+    find(&[b"intent", pool.as_ref(), nullifier_hash.as_ref(), &fee.to_le_bytes()])
+}
+
+/// Anonymity mining rate of a pool whose notes earn points
+pub fn anonymity_mining_address(pool: &Pubkey) -> Pubkey {
+    find(&[b"anonymity_mining", pool.as_ref()])
+}
+
+/// Anonymity points credited to withdrawals to `owner`
+pub fn anonymity_points_address(owner: &Pubkey) -> Pubkey {
+    find(&[b"anonymity_points", owner.as_ref()])
+}
+
+/// FIFO queue of a pool's queued withdrawal intents
+pub fn intent_queue_address(pool: &Pubkey) -> Pubkey {
+    find(&[b"intent_queue", pool.as_ref()])
+}
+
+/// Append-only log of admin actions
+pub fn admin_log_address() -> Pubkey {
+    find(&[b"admin_log"])
+}
+
+/// Creation bond locked for a pool created without the authority
+pub fn pool_bond_address(pool: &Pubkey) -> Pubkey {
+    find(&[b"pool_bond", pool.as_ref()])
+}
+
+/// Commitments of a pool flagged as non-withdrawable
+pub fn commitment_blocklist_address(pool: &Pubkey) -> Pubkey {
+    find(&[b"commitment_blocklist", pool.as_ref()])
+}
+
+/// Viewing key registered for the note at `leaf_index`
+pub fn viewing_key_address(pool: &Pubkey, leaf_index: u32) -> Pubkey {
+    find(&[b"viewing_key", pool.as_ref(), &leaf_index.to_le_bytes()])
+}
+
+/// Curated association set of a pool, for association set proofs
+pub fn association_set_address(pool: &Pubkey) -> Pubkey {
+    find(&[b"association_set", pool.as_ref()])
+}
+
+/// Verification key of the association set withdrawal circuit
+pub fn association_verification_key_address() -> Pubkey {
+    find(&[b"association_verification_key"])
+}
+
+/// Tainted commitments of a pool, for exclusion proofs
+pub fn tainted_set_address(pool: &Pubkey) -> Pubkey {
+    find(&[b"tainted_set", pool.as_ref()])
+}
+
+/// Verification key of the exclusion circuit
+pub fn exclusion_verification_key_address() -> Pubkey {
+    find(&[b"exclusion_verification_key"])
+}
+
+/// Proof that the withdrawal recorded at `spent_nullifier` excluded its
+/// deposit from the pool's tainted set
+pub fn innocence_proof_address(spent_nullifier: &Pubkey) -> Pubkey {
+    find(&[b"innocence_proof", spent_nullifier.as_ref()])
+}
+
+/// Depositor's opt-in receipt for the note at `leaf_index`
+pub fn deposit_receipt_address(pool: &Pubkey, leaf_index: u32) -> Pubkey {
+    find(&[b"deposit_receipt", pool.as_ref(), &leaf_index.to_le_bytes()])
+}
+
+/// Published contents of the note spent by the withdrawal recorded at
+/// `spent_nullifier`
+pub fn disclosed_note_address(spent_nullifier: &Pubkey) -> Pubkey {
+    find(&[b"disclosed_note", spent_nullifier.as_ref()])
+}
+
+/// Count of notes disclosed as withdrawn to `recipient`
+pub fn disclosure_registry_address(recipient: &Pubkey) -> Pubkey {
+    find(&[b"disclosure_registry", recipient.as_ref()])
+}
@@ -0,0 +1,64 @@
+/// Tests for program-derived addresses
+use anchor_lang::prelude::Pubkey;
+use nullifier::{DENOMINATION_1_SOL, DENOMINATION_100_USDC, SOL_POOL_MINT};
+
+use super::addresses::*;
+
+#[test]
+fn test_pool_address_matches_program_seeds() {
+    let mint = Pubkey::new_unique();
+    let (expected, _) = Pubkey::find_program_address(
+        &[b"pool", mint.as_ref(), &DENOMINATION_100_USDC.to_le_bytes()],
+        &nullifier::ID,
+    );
+
+    assert_eq!(pool_address(&mint, DENOMINATION_100_USDC), expected);
+    assert_eq!(sol_pool_address(DENOMINATION_1_SOL), pool_address(&SOL_POOL_MINT, DENOMINATION_1_SOL));
+}
+
+#[test]
+fn test_pools_at_same_denomination_are_distinct_per_mint() {
+    let usdc = Pubkey::new_unique();
+    let usdt = Pubkey::new_unique();
+
+    // 1 SOL in lamports and 1,000 USDC in base units are the same number
+    assert_eq!(DENOMINATION_1_SOL, 1_000 * 1_000_000);
+    let pools = [
+        sol_pool_address(DENOMINATION_1_SOL),
+        pool_address(&usdc, DENOMINATION_1_SOL),
+        pool_address(&usdt, DENOMINATION_1_SOL),
+    ];
+
+    assert_ne!(pools[0], pools[1]);
+    assert_ne!(pools[0], pools[2]);
+    assert_ne!(pools[1], pools[2]);
+}
+
+#[test]
+fn test_pool_accounts_follow_pool() {
+    let pool_a = sol_pool_address(DENOMINATION_1_SOL);
+    let pool_b = pool_address(&Pubkey::new_unique(), DENOMINATION_1_SOL);
+
+    assert_ne!(merkle_tree_address(&pool_a, 0), merkle_tree_address(&pool_b, 0));
+    assert_ne!(merkle_tree_address(&pool_a, 0), merkle_tree_address(&pool_a, 1));
+    assert_ne!(merkle_tree_address(&pool_a, 0), poseidon_tree_address(&pool_a, 0));
+    assert_ne!(vault_address(&pool_a), vault_address(&pool_b));
+    assert_ne!(vault_address(&pool_a), vault_authority_address(&pool_a));
+    assert_ne!(vault_authority_address(&pool_a), vault_authority_address(&pool_b));
+    assert_ne!(fee_vault_address(&pool_a), fee_vault_address(&pool_b));
+    assert_ne!(fee_vault_address(&pool_a), vault_address(&pool_a));
+    assert_ne!(anonymity_mining_address(&pool_a), anonymity_mining_address(&pool_b));
+    assert_ne!(anonymity_mining_address(&pool_a), anonymity_points_address(&pool_a));
+    assert_ne!(commitment_address(&pool_a, 0), commitment_address(&pool_a, 1));
+    assert_ne!(nullifier_bloom_address(&pool_a), nullifier_registry_address(&pool_a));
+}
+
+#[test]
+fn test_withdrawal_intent_address_commits_to_fee() {
+    let pool = sol_pool_address(DENOMINATION_1_SOL);
+    let intent = withdrawal_intent_address(&pool, &[7u8; 32], 5_000_000);
+
+    // Re-posting with another fee gives an address the proof wasn't made out to
+    assert_ne!(intent, withdrawal_intent_address(&pool, &[7u8; 32], 6_000_000));
+    assert_ne!(intent, withdrawal_intent_address(&pool, &[8u8; 32], 5_000_000));
+}
@@ -0,0 +1,22 @@
+/*!
+ * Off-chain client helpers for the nullifier program
+ *
+ * Everything here hashes through the program crate itself, so roots and
+ * proofs built by integrators are byte-identical to what the program checks.
+ */
+
+pub mod addresses;
+pub mod merkle_tree;
+pub mod nullifiers;
+
+pub use addresses::{pool_address, sol_pool_address};
+pub use merkle_tree::{MerkleTree, MerkleTreeError, TreeHash};
+pub use nullifiers::{spent_nullifier_address, NullifierSet, NullifierSetError};
+
+// Unit tests modules
+#[cfg(test)]
+mod addresses_test;
+#[cfg(test)]
+mod merkle_tree_test;
+#[cfg(test)]
+mod nullifiers_test;
@@ -0,0 +1,242 @@
+use std::fmt;
+
+use nullifier::merkle::{self, MerkleHasher, Sha256Hasher, CURRENT_POOL_VERSION, MERKLE_TREE_DEPTH};
+use nullifier::merkle_keccak::KeccakHasher;
+use nullifier::merkle_poseidon::PoseidonHasher;
+
+/// Hash used for the tree's internal nodes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TreeHash {
+    /// merkle::hash_pair, as used by the on-chain MerkleTreeState
+    Sha256,
+    /// merkle_poseidon::poseidon_hash, as used by the withdrawal circuit
+    Poseidon,
+    /// merkle_keccak::keccak_hash_pair, for pools whose tree EVM contracts verify
+    Keccak,
+}
+
+impl TreeHash {
+    /// The program's hasher for this kind of tree
+    pub fn hasher(&self) -> &'static dyn MerkleHasher {
+        match self {
+            TreeHash::Sha256 => &Sha256Hasher,
+            TreeHash::Poseidon => &PoseidonHasher,
+            TreeHash::Keccak => &KeccakHasher,
+        }
+    }
+
+    /// Leaf stored for `data` at `leaf_index` in a tree of the given pool
+    /// version; the index only affects index-bound versions
+    pub fn leaf_hash(&self, version: u8, data: &[u8; 32], leaf_index: u32) -> [u8; 32] {
+        self.hasher().leaf_hash(version, data, leaf_index)
+    }
+
+    /// Internal node of a tree of the given pool version
+    pub fn node_hash(&self, version: u8, left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+        self.hasher().node_hash(version, left, right)
+    }
+
+    /// Verify a proof of any depth given as slices, checking both hold
+    /// exactly `depth` entries
+    pub fn verify_proof(
+        &self,
+        version: u8,
+        commitment: &[u8; 32],
+        path: &[[u8; 32]],
+        indices: &[bool],
+        depth: usize,
+        root: &[u8; 32],
+    ) -> Result<bool, MerkleTreeError> {
+        self.hasher()
+            .verify_proof(version, commitment, path, indices, depth, root)
+            .map_err(|_| MerkleTreeError::InvalidProofLength)
+    }
+
+    pub fn zero_values(&self, version: u8) -> &'static [[u8; 32]; MERKLE_TREE_DEPTH + 1] {
+        self.hasher().zero_values(version)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MerkleTreeError {
+    /// All 2^MERKLE_TREE_DEPTH leaves are used
+    TreeFull,
+    /// No leaf has been inserted at this index
+    LeafIndexOutOfRange(u32),
+    /// Proof path or indices don't match the tree depth
+    InvalidProofLength,
+}
+
+impl fmt::Display for MerkleTreeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MerkleTreeError::TreeFull => write!(f, "Merkle tree is full"),
+            MerkleTreeError::LeafIndexOutOfRange(index) => {
+                write!(f, "No leaf at index {}", index)
+            }
+            MerkleTreeError::InvalidProofLength => {
+                write!(f, "Proof length does not match the tree depth")
+            }
+        }
+    }
+}
+
+impl std::error::Error for MerkleTreeError {}
+
+/// Full copy of a pool's Merkle tree
+///
+/// Keeps every non-empty node so any leaf's proof can be produced, in the
+/// (path, indices) form taken by withdraw and verify_versioned_merkle_proof.
+/// Leaves and nodes are hashed per the pool version, like the on-chain tree.
+#[derive(Clone, Debug)]
+pub struct MerkleTree {
+    hash: TreeHash,
+    version: u8,
+    /// layers[0] holds the leaves, layers[MERKLE_TREE_DEPTH] the root
+    layers: Vec<Vec<[u8; 32]>>,
+}
+
+impl MerkleTree {
+    pub fn new(hash: TreeHash, version: u8) -> Self {
+        Self {
+            hash,
+            version,
+            layers: vec![Vec::new(); MERKLE_TREE_DEPTH + 1],
+        }
+    }
+
+    /// Tree matching the on-chain SHA256 MerkleTreeState of a new pool
+    pub fn sha256() -> Self {
+        Self::new(TreeHash::Sha256, CURRENT_POOL_VERSION)
+    }
+
+    /// Tree matching the circuit's Poseidon tree of a new pool
+    pub fn poseidon() -> Self {
+        Self::new(TreeHash::Poseidon, CURRENT_POOL_VERSION)
+    }
+
+    /// Tree matching the on-chain Keccak256 MerkleTreeState of a new pool
+    /// switched to TREE_HASH_KECCAK
+    pub fn keccak() -> Self {
+        Self::new(TreeHash::Keccak, CURRENT_POOL_VERSION)
+    }
+
+    pub fn hash(&self) -> TreeHash {
+        self.hash
+    }
+
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    pub fn len(&self) -> u32 {
+        self.layers[0].len() as u32
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.layers[0].is_empty()
+    }
+
+    /// Leaves as stored in the tree, i.e. after leaf hashing
+    pub fn leaves(&self) -> &[[u8; 32]] {
+        &self.layers[0]
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        self.node(MERKLE_TREE_DEPTH, 0)
+    }
+
+    /// Append a commitment, hashed into a leaf for the tree's version, and
+    /// return its index
+    pub fn insert(&mut self, commitment: [u8; 32]) -> Result<u32, MerkleTreeError> {
+        let leaf_index = self.len();
+        if leaf_index as usize >= 1 << MERKLE_TREE_DEPTH {
+            return Err(MerkleTreeError::TreeFull);
+        }
+
+        let mut index = leaf_index as usize;
+        let mut current = self.hash.leaf_hash(self.version, &commitment, leaf_index);
+
+        self.layers[0].push(current);
+
+        for level in 0..MERKLE_TREE_DEPTH {
+            current = if index.is_multiple_of(2) {
+                self.hash.node_hash(self.version, &current, &self.node(level, index + 1))
+            } else {
+                self.hash.node_hash(self.version, &self.node(level, index - 1), &current)
+            };
+            index /= 2;
+
+            let parent_layer = &mut self.layers[level + 1];
+            if index < parent_layer.len() {
+                parent_layer[index] = current;
+            } else {
+                parent_layer.push(current);
+            }
+        }
+
+        Ok(leaf_index)
+    }
+
+    /// Sibling path and left/right flags for a leaf
+    /// indices[i] is true when the node at level i is a right child
+    pub fn proof(
+        &self,
+        leaf_index: u32,
+    ) -> Result<([[u8; 32]; MERKLE_TREE_DEPTH], [bool; MERKLE_TREE_DEPTH]), MerkleTreeError> {
+        if leaf_index >= self.len() {
+            return Err(MerkleTreeError::LeafIndexOutOfRange(leaf_index));
+        }
+
+        let mut path = [[0u8; 32]; MERKLE_TREE_DEPTH];
+        let mut indices = [false; MERKLE_TREE_DEPTH];
+        let mut index = leaf_index as usize;
+
+        for (level, (sibling, is_right)) in path.iter_mut().zip(indices.iter_mut()).enumerate() {
+            *is_right = index % 2 == 1;
+            *sibling = self.node(level, index ^ 1);
+            index /= 2;
+        }
+
+        Ok((path, indices))
+    }
+
+    /// Indexes at which `commitment` was inserted, in order
+    /// In index-bound versions each occurrence is a distinct leaf, so a
+    /// duplicate deposit shows up here rather than as two equal leaves
+    pub fn positions_of(&self, commitment: &[u8; 32]) -> Vec<u32> {
+        (0..self.len())
+            .filter(|&index| {
+                self.layers[0][index as usize] == self.hash.leaf_hash(self.version, commitment, index)
+            })
+            .collect()
+    }
+
+    /// Proof in withdraw's wire format: the siblings as a Vec and the
+    /// path indices packed into a bitfield
+    pub fn packed_proof(&self, leaf_index: u32) -> Result<(Vec<[u8; 32]>, u32), MerkleTreeError> {
+        let (path, indices) = self.proof(leaf_index)?;
+        Ok((path.to_vec(), merkle::pack_path_indices(&indices)))
+    }
+
+    /// Check a proof for `commitment` against `root`, using this tree's hash
+    /// and version
+    pub fn verify_proof(
+        &self,
+        commitment: &[u8; 32],
+        path: &[[u8; 32]],
+        indices: &[bool],
+        root: &[u8; 32],
+    ) -> Result<bool, MerkleTreeError> {
+        self.hash
+            .verify_proof(self.version, commitment, path, indices, MERKLE_TREE_DEPTH, root)
+    }
+
+    /// Node at (level, index), falling back to the empty-subtree value
+    fn node(&self, level: usize, index: usize) -> [u8; 32] {
+        self.layers[level]
+            .get(index)
+            .copied()
+            .unwrap_or(self.hash.zero_values(self.version)[level])
+    }
+}
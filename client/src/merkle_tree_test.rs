@@ -0,0 +1,240 @@
+/// Tests that the client tree stays byte-compatible with the program
+use super::merkle_tree::*;
+use anchor_lang::prelude::Pubkey;
+use nullifier::merkle::{
+    self, CURRENT_POOL_VERSION, MERKLE_TREE_DEPTH, POOL_VERSION_DOMAIN_SEPARATED, POOL_VERSION_LEGACY,
+};
+use nullifier::merkle_poseidon;
+use nullifier::test_utils;
+use nullifier::{MerkleTreeState, TREE_HASH_KECCAK, TREE_HASH_POSEIDON, TREE_HASH_SHA256};
+
+fn on_chain_tree(version: u8, hash: u8) -> MerkleTreeState {
+    test_utils::empty_tree_state(Pubkey::new_unique(), hash, version)
+}
+
+fn leaf(i: u32) -> [u8; 32] {
+    let mut leaf = [0u8; 32];
+    leaf[..4].copy_from_slice(&(i + 1).to_le_bytes());
+    leaf
+}
+
+#[test]
+fn test_empty_roots() {
+    assert_eq!(MerkleTree::sha256().root(), merkle::DOMAIN_ZERO_VALUES[MERKLE_TREE_DEPTH]);
+    assert_eq!(MerkleTree::sha256().root(), on_chain_tree(CURRENT_POOL_VERSION, TREE_HASH_SHA256).root());
+    assert_eq!(
+        MerkleTree::poseidon().root(),
+        merkle_poseidon::DOMAIN_ZERO_VALUES[MERKLE_TREE_DEPTH]
+    );
+    assert_eq!(
+        MerkleTree::new(TreeHash::Sha256, POOL_VERSION_LEGACY).root(),
+        merkle::ZERO_VALUES[MERKLE_TREE_DEPTH]
+    );
+}
+
+#[test]
+fn test_sha256_root_matches_on_chain_tree() {
+    for version in [POOL_VERSION_LEGACY, POOL_VERSION_DOMAIN_SEPARATED, CURRENT_POOL_VERSION] {
+        let mut client = MerkleTree::new(TreeHash::Sha256, version);
+        let mut on_chain = on_chain_tree(version, TREE_HASH_SHA256);
+
+        for i in 0..9 {
+            assert_eq!(client.insert(leaf(i)).unwrap(), i);
+            on_chain.insert(leaf(i)).unwrap();
+
+            assert_eq!(client.root(), on_chain.root());
+        }
+
+        assert_eq!(client.len(), 9);
+        assert_eq!(client.leaves()[3], merkle::leaf_hash(version, &leaf(3), 3));
+    }
+}
+
+#[test]
+fn test_poseidon_root_matches_on_chain_tree() {
+    let mut client = MerkleTree::poseidon();
+    let mut on_chain = on_chain_tree(CURRENT_POOL_VERSION, TREE_HASH_POSEIDON);
+
+    for i in 0..3 {
+        client.insert(leaf(i)).unwrap();
+        on_chain.insert(leaf(i)).unwrap();
+
+        assert_eq!(client.root(), on_chain.root());
+    }
+}
+
+#[test]
+fn test_keccak_root_matches_on_chain_tree() {
+    let mut client = MerkleTree::keccak();
+    let mut on_chain = on_chain_tree(CURRENT_POOL_VERSION, TREE_HASH_KECCAK);
+    assert_eq!(client.root(), on_chain.root());
+
+    for i in 0..5 {
+        client.insert(leaf(i)).unwrap();
+        on_chain.insert(leaf(i)).unwrap();
+
+        assert_eq!(client.root(), on_chain.root());
+    }
+
+    let (path, indices) = client.proof(4).unwrap();
+    assert_eq!(client.verify_proof(&leaf(4), &path, &indices, &on_chain.root()), Ok(true));
+}
+
+#[test]
+fn test_sha256_proofs_verify_on_chain() {
+    let mut tree = MerkleTree::sha256();
+    for i in 0..7 {
+        tree.insert(leaf(i)).unwrap();
+    }
+
+    for i in 0..7 {
+        let (path, indices) = tree.proof(i).unwrap();
+        assert!(merkle::verify_versioned_merkle_proof(
+            tree.version(),
+            &leaf(i),
+            &path,
+            &indices,
+            &tree.root()
+        ));
+    }
+
+    // A proof does not verify for a different leaf
+    let (path, indices) = tree.proof(2).unwrap();
+    assert!(!merkle::verify_versioned_merkle_proof(
+        tree.version(),
+        &leaf(3),
+        &path,
+        &indices,
+        &tree.root()
+    ));
+}
+
+#[test]
+fn test_poseidon_proofs_verify() {
+    let mut tree = MerkleTree::poseidon();
+    for i in 0..5 {
+        tree.insert(leaf(i)).unwrap();
+    }
+
+    for i in 0..5 {
+        let (path, indices) = tree.proof(i).unwrap();
+        assert!(merkle_poseidon::verify_versioned_merkle_proof(
+            tree.version(),
+            &leaf(i),
+            &path,
+            &indices,
+            &tree.root()
+        )
+        .unwrap());
+    }
+}
+
+#[test]
+fn test_proof_indices_follow_leaf_index() {
+    let mut tree = MerkleTree::sha256();
+    for i in 0..6 {
+        tree.insert(leaf(i)).unwrap();
+    }
+
+    let (_, indices) = tree.proof(5).unwrap();
+
+    // 5 = 0b101
+    assert!(indices[0]);
+    assert!(!indices[1]);
+    assert!(indices[2]);
+    assert!(indices[3..].iter().all(|is_right| !is_right));
+}
+
+#[test]
+fn test_proof_out_of_range() {
+    let mut tree = MerkleTree::sha256();
+    assert_eq!(tree.proof(0), Err(MerkleTreeError::LeafIndexOutOfRange(0)));
+
+    tree.insert(leaf(0)).unwrap();
+    assert!(tree.proof(0).is_ok());
+    assert_eq!(tree.proof(1), Err(MerkleTreeError::LeafIndexOutOfRange(1)));
+}
+
+#[test]
+fn test_verify_proof_slices() {
+    for mut tree in [MerkleTree::sha256(), MerkleTree::poseidon()] {
+        for i in 0..3 {
+            tree.insert(leaf(i)).unwrap();
+        }
+
+        let (path, indices) = tree.proof(1).unwrap();
+        assert_eq!(tree.verify_proof(&leaf(1), &path, &indices, &tree.root()), Ok(true));
+        assert_eq!(tree.verify_proof(&leaf(2), &path, &indices, &tree.root()), Ok(false));
+
+        // Truncated proofs are rejected rather than hashed to a shorter root
+        assert_eq!(
+            tree.verify_proof(&leaf(1), &path[1..], &indices[1..], &tree.root()),
+            Err(MerkleTreeError::InvalidProofLength)
+        );
+    }
+}
+
+#[test]
+fn test_verify_proof_other_depth() {
+    // A depth-2 tree holding leaves 0 and 1 of the current version
+    let hash = TreeHash::Sha256;
+    let zeros = hash.zero_values(CURRENT_POOL_VERSION);
+    let leaf0 = hash.leaf_hash(CURRENT_POOL_VERSION, &leaf(0), 0);
+    let leaf1 = hash.leaf_hash(CURRENT_POOL_VERSION, &leaf(1), 1);
+    let root = hash.node_hash(
+        CURRENT_POOL_VERSION,
+        &hash.node_hash(CURRENT_POOL_VERSION, &leaf0, &leaf1),
+        &zeros[1],
+    );
+
+    let path = [leaf0, zeros[1]];
+    let indices = [true, false];
+
+    assert_eq!(
+        hash.verify_proof(CURRENT_POOL_VERSION, &leaf(1), &path, &indices, 2, &root),
+        Ok(true)
+    );
+    assert_eq!(
+        hash.verify_proof(CURRENT_POOL_VERSION, &leaf(1), &path, &indices, 3, &root),
+        Err(MerkleTreeError::InvalidProofLength)
+    );
+}
+
+#[test]
+fn test_packed_proof() {
+    let mut tree = MerkleTree::sha256();
+    for i in 0..6 {
+        tree.insert(leaf(i)).unwrap();
+    }
+
+    let (path, indices) = tree.proof(5).unwrap();
+    let (packed_path, packed_indices) = tree.packed_proof(5).unwrap();
+
+    assert_eq!(packed_path, path.to_vec());
+    assert_eq!(packed_indices, 5);
+    assert_eq!(
+        merkle::unpack_path_indices(packed_indices, MERKLE_TREE_DEPTH).unwrap(),
+        indices.to_vec()
+    );
+}
+
+#[test]
+fn test_duplicate_commitment_positions() {
+    let mut tree = MerkleTree::sha256();
+    tree.insert(leaf(7)).unwrap();
+    tree.insert(leaf(1)).unwrap();
+    tree.insert(leaf(7)).unwrap();
+
+    // Index-bound leaves differ even though the commitment repeats
+    assert_ne!(tree.leaves()[0], tree.leaves()[2]);
+    assert_eq!(tree.positions_of(&leaf(7)), vec![0, 2]);
+    assert_eq!(tree.positions_of(&leaf(1)), vec![1]);
+    assert!(tree.positions_of(&leaf(2)).is_empty());
+
+    let (path, indices) = tree.proof(2).unwrap();
+    assert_eq!(tree.verify_proof(&leaf(7), &path, &indices, &tree.root()), Ok(true));
+
+    // The proof for index 2 does not carry over to index 0's position
+    let (path0, _) = tree.proof(0).unwrap();
+    assert_eq!(tree.verify_proof(&leaf(7), &path0, &indices, &tree.root()), Ok(false));
+}
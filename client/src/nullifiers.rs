@@ -0,0 +1,143 @@
+/*!
+ * Spent-nullifier lookups
+ *
+ * Withdrawals record each spend in a SpentNullifier PDA keyed by the
+ * pool-scoped nullifier, SHA256(nullifier || pool). The value is the raw
+ * nullifier for SHA256-phase withdrawals and the nullifier hash for ZK ones.
+ */
+
+use std::collections::HashSet;
+use std::fmt;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::AccountDeserialize;
+use nullifier::{NullifierRegistry, SpentNullifier};
+
+pub use nullifier::merkle::compute_pool_nullifier;
+
+/// Address of the SpentNullifier a withdrawal of `nullifier` from `pool` creates
+/// The account exists exactly when the note has been spent
+pub fn spent_nullifier_address(pool: &Pubkey, nullifier: &[u8; 32]) -> Pubkey {
+    let key = compute_pool_nullifier(nullifier, pool);
+    Pubkey::find_program_address(&[b"nullifier", pool.as_ref(), &key], &nullifier::ID).0
+}
+
+/// Remaining accounts for one migrate_nullifiers call over `count` entries
+/// of a registry holding `registry_nullifiers`, last entry first
+pub fn migration_accounts(pool: &Pubkey, registry_nullifiers: &[[u8; 32]], count: usize) -> Vec<Pubkey> {
+    registry_nullifiers
+        .iter()
+        .rev()
+        .take(count)
+        .map(|nullifier| spent_nullifier_address(pool, nullifier))
+        .collect()
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NullifierSetError {
+    /// Account data isn't a NullifierRegistry or SpentNullifier
+    InvalidAccount,
+    /// Account belongs to a different pool than the set
+    WrongPool(Pubkey),
+}
+
+impl fmt::Display for NullifierSetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NullifierSetError::InvalidAccount => write!(f, "Not a nullifier account"),
+            NullifierSetError::WrongPool(pool) => {
+                write!(f, "Nullifier account belongs to pool {}", pool)
+            }
+        }
+    }
+}
+
+impl std::error::Error for NullifierSetError {}
+
+/// Local copy of a pool's spent nullifiers
+///
+/// Fed from account data the caller fetched: the pool's pre-PDA registry
+/// once, then SpentNullifier accounts as they appear (a program account scan
+/// filtered on the pool, or accounts touched by withdrawals). `cursor` is the
+/// highest slot applied so far, so each sync only needs newer accounts.
+#[derive(Clone, Debug)]
+pub struct NullifierSet {
+    pool: Pubkey,
+    /// compute_pool_nullifier of every spent nullifier (hash)
+    spent: HashSet<[u8; 32]>,
+    cursor: u64,
+}
+
+impl NullifierSet {
+    pub fn new(pool: Pubkey) -> Self {
+        Self {
+            pool,
+            spent: HashSet::new(),
+            cursor: 0,
+        }
+    }
+
+    pub fn pool(&self) -> Pubkey {
+        self.pool
+    }
+
+    pub fn len(&self) -> usize {
+        self.spent.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.spent.is_empty()
+    }
+
+    /// Highest slot passed to apply_spent_nullifier
+    pub fn cursor(&self) -> u64 {
+        self.cursor
+    }
+
+    /// Whether a withdrawal revealing `nullifier` (the raw nullifier or the
+    /// ZK nullifier hash) has been seen
+    pub fn is_spent(&self, nullifier: &[u8; 32]) -> bool {
+        self.spent.contains(&compute_pool_nullifier(nullifier, &self.pool))
+    }
+
+    /// Add every entry of the pool's NullifierRegistry account
+    pub fn load_registry(&mut self, data: &[u8]) -> Result<(), NullifierSetError> {
+        let registry = NullifierRegistry::try_deserialize(&mut &data[..])
+            .map_err(|_| NullifierSetError::InvalidAccount)?;
+        self.check_pool(registry.pool)?;
+
+        for nullifier in &registry.nullifiers {
+            self.spent.insert(compute_pool_nullifier(nullifier, &self.pool));
+        }
+        Ok(())
+    }
+
+    /// Add one SpentNullifier account observed at `slot`
+    pub fn apply_spent_nullifier(&mut self, data: &[u8], slot: u64) -> Result<(), NullifierSetError> {
+        let spent = SpentNullifier::try_deserialize(&mut &data[..])
+            .map_err(|_| NullifierSetError::InvalidAccount)?;
+        self.check_pool(spent.pool)?;
+
+        self.spent.insert(spent.nullifier_hash);
+        self.cursor = self.cursor.max(slot);
+        Ok(())
+    }
+
+    /// Apply a batch of (slot, account data) updates, e.g. one sync's results
+    pub fn sync<'a>(
+        &mut self,
+        updates: impl IntoIterator<Item = (u64, &'a [u8])>,
+    ) -> Result<(), NullifierSetError> {
+        for (slot, data) in updates {
+            self.apply_spent_nullifier(data, slot)?;
+        }
+        Ok(())
+    }
+
+    fn check_pool(&self, pool: Pubkey) -> Result<(), NullifierSetError> {
+        if pool != self.pool {
+            return Err(NullifierSetError::WrongPool(pool));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,111 @@
+/// Tests for the spent-nullifier lookups
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::AccountSerialize;
+use nullifier::{NullifierRegistry, SpentNullifier};
+
+use super::nullifiers::*;
+
+#[test]
+fn test_pool_nullifier_is_scoped_to_pool() {
+    let nullifier = [7u8; 32];
+    let pool_a = Pubkey::new_unique();
+    let pool_b = Pubkey::new_unique();
+
+    assert_ne!(
+        compute_pool_nullifier(&nullifier, &pool_a),
+        compute_pool_nullifier(&nullifier, &pool_b)
+    );
+    assert_ne!(
+        spent_nullifier_address(&pool_a, &nullifier),
+        spent_nullifier_address(&pool_b, &nullifier)
+    );
+}
+
+#[test]
+fn test_spent_nullifier_address_matches_program_seeds() {
+    let nullifier = [9u8; 32];
+    let pool = Pubkey::new_unique();
+
+    let key = compute_pool_nullifier(&nullifier, &pool);
+    let (expected, _) =
+        Pubkey::find_program_address(&[b"nullifier", pool.as_ref(), key.as_ref()], &nullifier::ID);
+
+    assert_eq!(spent_nullifier_address(&pool, &nullifier), expected);
+}
+
+#[test]
+fn test_migration_accounts_start_from_last_entry() {
+    let pool = Pubkey::new_unique();
+    let registry = [[1u8; 32], [2u8; 32], [3u8; 32]];
+
+    let accounts = migration_accounts(&pool, &registry, 2);
+    assert_eq!(
+        accounts,
+        vec![
+            spent_nullifier_address(&pool, &registry[2]),
+            spent_nullifier_address(&pool, &registry[1]),
+        ]
+    );
+    assert_eq!(migration_accounts(&pool, &registry, 10).len(), 3);
+}
+
+fn spent_account(pool: Pubkey, nullifier: &[u8; 32]) -> Vec<u8> {
+    let spent = SpentNullifier {
+        pool,
+        nullifier_hash: compute_pool_nullifier(nullifier, &pool),
+        spent_at: 1_700_000_000,
+        bump: 255,
+    };
+    let mut data = Vec::new();
+    spent.try_serialize(&mut data).unwrap();
+    data
+}
+
+#[test]
+fn test_nullifier_set_sync() {
+    let pool = Pubkey::new_unique();
+    let mut set = NullifierSet::new(pool);
+
+    let registry = NullifierRegistry {
+        pool,
+        bump: 255,
+        nullifiers: vec![[1u8; 32]],
+    };
+    let mut registry_data = Vec::new();
+    registry.try_serialize(&mut registry_data).unwrap();
+    set.load_registry(&registry_data).unwrap();
+
+    let first = spent_account(pool, &[2u8; 32]);
+    let second = spent_account(pool, &[3u8; 32]);
+    set.sync([(10, first.as_slice()), (12, second.as_slice())]).unwrap();
+
+    assert_eq!(set.len(), 3);
+    assert_eq!(set.cursor(), 12);
+    assert!(set.is_spent(&[1u8; 32]));
+    assert!(set.is_spent(&[2u8; 32]));
+    assert!(set.is_spent(&[3u8; 32]));
+    assert!(!set.is_spent(&[4u8; 32]));
+
+    // Later updates only move the cursor forward
+    set.apply_spent_nullifier(&spent_account(pool, &[4u8; 32]), 11).unwrap();
+    assert_eq!(set.cursor(), 12);
+    assert!(set.is_spent(&[4u8; 32]));
+}
+
+#[test]
+fn test_nullifier_set_rejects_foreign_accounts() {
+    let pool = Pubkey::new_unique();
+    let other = Pubkey::new_unique();
+    let mut set = NullifierSet::new(pool);
+
+    assert_eq!(
+        set.apply_spent_nullifier(&spent_account(other, &[1u8; 32]), 1),
+        Err(NullifierSetError::WrongPool(other))
+    );
+    assert_eq!(
+        set.apply_spent_nullifier(&[0u8; 16], 1),
+        Err(NullifierSetError::InvalidAccount)
+    );
+    assert!(set.is_empty());
+    assert_eq!(set.cursor(), 0);
+}
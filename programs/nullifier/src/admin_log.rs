@@ -0,0 +1,185 @@
+/*!
+ * Admin action log
+ *
+ * Every instruction that needs the authority or its multisig appends an
+ * AdminLogEntry to the AdminLog account, [b"admin_log"], so the protocol's
+ * governance history can be rebuilt from chain state alone. That includes
+ * the guardian's pause and pools the authority creates without a bond.
+ *
+ * initialize creates the log, and migrate_config creates it for deployments
+ * from before it existed. Entries are laid out back to back after the
+ * AdminLog header and never rewritten: each append grows the account by
+ * AdminLogEntry::LEN, the signing authority paying the extra rent. Only the
+ * header is deserialized, so appending costs the same however long the log
+ * gets.
+ *
+ * An entry records the action, a SHA256 hash of its Borsh-serialized
+ * arguments, the slot and the signing authority. Auditors recompute the hash
+ * from the instruction data of the transaction at that slot.
+ */
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hash;
+use anchor_lang::system_program;
+
+use crate::MixerError;
+
+/// Header of the admin log; entries follow it in the account data
+#[account]
+pub struct AdminLog {
+    pub count: u64,                     // 8 - Entries appended so far
+    pub bump: u8,                       // 1
+}
+
+impl AdminLog {
+    pub const LEN: usize = 8 + 8 + 1;
+
+    /// Offset of entry `index` in the account data
+    pub fn entry_offset(index: u64) -> usize {
+        Self::LEN + index as usize * AdminLogEntry::LEN
+    }
+
+    /// Decode the entries of an admin log account's `data`
+    pub fn entries(data: &[u8]) -> Result<Vec<AdminLogEntry>> {
+        let log = AdminLog::try_deserialize(&mut &data[..])?;
+        (0..log.count)
+            .map(|index| {
+                let offset = Self::entry_offset(index);
+                let bytes = data
+                    .get(offset..offset + AdminLogEntry::LEN)
+                    .ok_or(ErrorCode::AccountDidNotDeserialize)?;
+                Ok(AdminLogEntry::try_from_slice(bytes)?)
+            })
+            .collect()
+    }
+}
+
+/// Admin instruction an entry records
+/// Serialized as its index: add new actions at the end
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AdminAction {
+    Pause,
+    Unpause,
+    Sunset,
+    UpdateAuthority,
+    UpdateFeeCollector,
+    SetAuthorityMembers,
+    SetAttestors,
+    SetGuardian,
+    SetRateLimits,
+    SetAtaReimbursementBps,
+    SetRelayerRebate,
+    SetMaxRelayerFeeBps,
+    SetFeeSplit,
+    SetRewardHook,
+    SetRelayerPolicy,
+    SetFeeCurve,
+    SetFeeHoliday,
+    UpdateMinDelay,
+    SetWithdrawMode,
+    CreateNftPool,
+    CreateLstPool,
+    UpdateLstExchangeRate,
+    InitCompressedTree,
+    RegisterCeremonyAttestation,
+    InitializeVerificationKey,
+    InitializeAllowedMints,
+    AddAllowedMint,
+    RemoveAllowedMint,
+    EnableAnonymityMining,
+    SetAnonymityMiningRate,
+    InitializePoolRelayers,
+    AddPoolRelayer,
+    RemovePoolRelayer,
+    SlashRelayer,
+    DismissRelayerReport,
+    EnableDualTree,
+    SetTreeHash,
+    ExportTreeState,
+    MigrateConfig,
+    ClosePool,
+    ScheduleForceClose,
+    CancelForceClose,
+    ForceCloseAccount,
+    CreatePool,
+    CreateTokenPool,
+    GuardianPause,
+    RenounceAuthority,
+    SetPermissionlessPools,
+    ForfeitPoolBond,
+    FlagPool,
+    UnflagPool,
+    FlagCommitment,
+    RequestCommitmentUnflag,
+    ProposeAssociationRoot,
+    RegisterAssociationCeremonyAttestation,
+    InitializeAssociationVerificationKey,
+    SetTaintedRoot,
+    RegisterExclusionCeremonyAttestation,
+    InitializeExclusionVerificationKey,
+    SetScreeningProgram,
+}
+
+/// One admin action
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct AdminLogEntry {
+    pub action: AdminAction,            // 1
+    pub params_hash: [u8; 32],          // 32 - SHA256 of the Borsh-serialized arguments
+    pub slot: u64,                      // 8
+    pub signer: Pubkey,                 // 32 - Authority (or authority member) that signed
+}
+
+impl AdminLogEntry {
+    pub const LEN: usize = 1 + 32 + 8 + 32;
+
+    pub fn new(
+        action: AdminAction,
+        params: &impl AnchorSerialize,
+        slot: u64,
+        signer: Pubkey,
+    ) -> Result<Self> {
+        Ok(Self {
+            action,
+            params_hash: hash(&params.try_to_vec()?).to_bytes(),
+            slot,
+            signer,
+        })
+    }
+}
+
+/// Append `entry` to `log`, growing it and topping up its rent from `payer`
+pub fn append<'info>(
+    log: &mut Account<'info, AdminLog>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    entry: &AdminLogEntry,
+) -> Result<()> {
+    let info = log.to_account_info();
+    let offset = AdminLog::entry_offset(log.count);
+    let new_len = offset + AdminLogEntry::LEN;
+
+    let top_up = Rent::get()?
+        .minimum_balance(new_len)
+        .saturating_sub(info.lamports());
+    if top_up > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                system_program::Transfer {
+                    from: payer.clone(),
+                    to: info.clone(),
+                },
+            ),
+            top_up,
+        )?;
+    }
+    info.realloc(new_len, false)?;
+
+    entry.serialize(&mut &mut info.try_borrow_mut_data()?[offset..])?;
+    log.count = log
+        .count
+        .checked_add(1)
+        .ok_or(MixerError::ArithmeticOverflow)?;
+
+    Ok(())
+}
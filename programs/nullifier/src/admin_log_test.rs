@@ -0,0 +1,102 @@
+/// Tests for the admin action log
+use anchor_lang::prelude::*;
+
+use super::admin_log::*;
+use super::{FeeCurve, RateLimit};
+
+fn log_data(entries: &[AdminLogEntry]) -> Vec<u8> {
+    let mut data = Vec::new();
+    AdminLog { count: entries.len() as u64, bump: 255 }
+        .try_serialize(&mut data)
+        .unwrap();
+    for entry in entries {
+        entry.serialize(&mut data).unwrap();
+    }
+    data
+}
+
+#[test]
+fn test_admin_log_sizes() {
+    assert_eq!(AdminLog::LEN, 8 + 8 + 1);
+
+    let entry = AdminLogEntry::new(AdminAction::Pause, &(), 1, Pubkey::new_unique()).unwrap();
+    assert_eq!(entry.try_to_vec().unwrap().len(), AdminLogEntry::LEN);
+    assert_eq!(AdminLog::entry_offset(0), AdminLog::LEN);
+    assert_eq!(AdminLog::entry_offset(2), AdminLog::LEN + 2 * AdminLogEntry::LEN);
+}
+
+#[test]
+fn test_admin_log_entries_decode_in_order() {
+    let signer = Pubkey::new_unique();
+    let entries = [
+        AdminLogEntry::new(AdminAction::Pause, &(), 10, signer).unwrap(),
+        AdminLogEntry::new(AdminAction::SetGuardian, &Pubkey::new_unique(), 11, signer).unwrap(),
+        AdminLogEntry::new(AdminAction::Unpause, &(), 12, signer).unwrap(),
+    ];
+
+    assert_eq!(AdminLog::entries(&log_data(&entries)).unwrap(), entries);
+
+    // A header counting more entries than the data holds is rejected
+    let mut truncated = log_data(&entries);
+    truncated.truncate(AdminLog::entry_offset(2));
+    assert!(AdminLog::entries(&truncated).is_err());
+}
+
+#[test]
+fn test_admin_log_params_hash() {
+    let signer = Pubkey::new_unique();
+    let limits = (RateLimit { max: 10, window: 60 }, RateLimit::default());
+    let entry = AdminLogEntry::new(AdminAction::SetRateLimits, &limits, 1, signer).unwrap();
+
+    // The hash is over the arguments as the instruction data encodes them
+    assert_eq!(
+        entry.params_hash,
+        anchor_lang::solana_program::hash::hash(&limits.try_to_vec().unwrap()).to_bytes()
+    );
+
+    let other = (RateLimit { max: 11, window: 60 }, RateLimit::default());
+    let other = AdminLogEntry::new(AdminAction::SetRateLimits, &other, 1, signer).unwrap();
+    assert_ne!(entry.params_hash, other.params_hash);
+
+    let pool = Pubkey::new_unique();
+    let curve = AdminLogEntry::new(
+        AdminAction::SetFeeCurve,
+        &(pool, FeeCurve::default()),
+        1,
+        signer,
+    )
+    .unwrap();
+    assert_eq!(curve.action, AdminAction::SetFeeCurve);
+}
+
+#[test]
+fn test_admin_log_verification_key_install() {
+    let (alpha_g1, beta_g2, gamma_g2, delta_g2) = ([1u8; 64], [2u8; 128], [3u8; 128], [4u8; 128]);
+    let ic = vec![[5u8; 64]; 8];
+    let entry = AdminLogEntry::new(
+        AdminAction::InitializeVerificationKey,
+        &(alpha_g1, beta_g2, gamma_g2, delta_g2, &ic),
+        1,
+        Pubkey::new_unique(),
+    )
+    .unwrap();
+
+    // Hashed as the instruction data lays the arguments out, one after another
+    let mut data = Vec::new();
+    alpha_g1.serialize(&mut data).unwrap();
+    beta_g2.serialize(&mut data).unwrap();
+    gamma_g2.serialize(&mut data).unwrap();
+    delta_g2.serialize(&mut data).unwrap();
+    ic.serialize(&mut data).unwrap();
+    assert_eq!(entry.params_hash, anchor_lang::solana_program::hash::hash(&data).to_bytes());
+}
+
+#[test]
+fn test_admin_action_indices_are_stable() {
+    // Entries already in a log decode by index, so actions are only appended
+    assert_eq!(AdminAction::Pause.try_to_vec().unwrap(), [0]);
+    assert_eq!(AdminAction::SetWithdrawMode.try_to_vec().unwrap(), [18]);
+    assert_eq!(AdminAction::CreateNftPool.try_to_vec().unwrap(), [19]);
+    assert_eq!(AdminAction::ForceCloseAccount.try_to_vec().unwrap(), [42]);
+    assert_eq!(AdminAction::GuardianPause.try_to_vec().unwrap(), [45]);
+}
@@ -0,0 +1,119 @@
+/*!
+ * Association sets
+ *
+ * Following Privacy Pools, a pool's authority can curate an association set:
+ * a Poseidon Merkle tree over the pool's deposits it considers clean, built
+ * off-chain and published as its root in the pool's AssociationSet account,
+ * [b"association_set", pool].
+ *
+ * A ZK withdrawal can opt in to proving its note belongs to that set. The
+ * association circuit extends the withdrawal circuit with one public input,
+ * the association root, and additionally proves the spent commitment is a
+ * leaf of the association tree. The withdrawer shows their deposit is one of
+ * the curated ones without showing which. The circuit has its own trusted
+ * setup and verification key, [b"association_verification_key"]; the
+ * nullifier hash is computed as in the withdrawal circuit, so a note spent
+ * one way can't be spent again the other.
+ *
+ * Root updates are timelocked: the authority proposes a root with
+ * propose_association_root and anyone activates it ASSOCIATION_ROOT_DELAY
+ * later, so withdrawers see a change to the set before proofs must use it.
+ * Proposing again before activation replaces the pending root and restarts
+ * the delay. Each activated root gets the next version number and is kept,
+ * with its activation time, in a history of the last ASSOCIATION_ROOT_HISTORY
+ * roots.
+ *
+ * Withdrawals are always proven against the current root. They emit
+ * AssociatedWithdrawal with its root and version, so a recipient can show
+ * which version of the set the funds were proven to come from.
+ */
+
+use anchor_lang::prelude::*;
+
+use crate::MixerError;
+
+/// Time between proposing an association root and activating it (1 day)
+pub const ASSOCIATION_ROOT_DELAY: i64 = 24 * 60 * 60;
+
+/// Activated roots an AssociationSet remembers, the current one included
+pub const ASSOCIATION_ROOT_HISTORY: usize = 8;
+
+/// One activated version of an association set
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct AssociationRoot {
+    pub root: [u8; 32],                 // 32
+    pub version: u32,                   // 4 - 1 for the first root activated
+    pub activated_at: i64,              // 8
+}
+
+impl AssociationRoot {
+    pub const LEN: usize = 32 + 4 + 8;
+}
+
+/// A pool's curated association set
+#[account]
+pub struct AssociationSet {
+    pub pool: Pubkey,                   // 32
+    pub root: [u8; 32],                 // 32 - Current root of the association tree, zero until one is activated
+    pub version: u32,                   // 4 - Roots activated so far, the current root's version
+    pub updated_at: i64,                // 8 - When the current root was activated
+    pub pending_root: [u8; 32],         // 32 - Proposed root, zero when none is pending
+    pub pending_at: i64,                // 8 - When pending_root can be activated
+    pub history: [AssociationRoot; ASSOCIATION_ROOT_HISTORY], // Last roots, version v at (v - 1) % ASSOCIATION_ROOT_HISTORY
+    pub bump: u8,                       // 1
+}
+
+impl AssociationSet {
+    pub const LEN: usize = 8 + 32 + 32 + 4 + 8 + 32 + 8
+        + AssociationRoot::LEN * ASSOCIATION_ROOT_HISTORY + 1;
+
+    /// Propose `root` at `now`. Returns when it can be activated
+    pub fn propose(&mut self, root: [u8; 32], now: i64) -> Result<i64> {
+        require!(root != [0u8; 32], MixerError::InvalidAssociationRoot);
+
+        let pending_at = now
+            .checked_add(ASSOCIATION_ROOT_DELAY)
+            .ok_or(MixerError::TimeCalculationError)?;
+        self.pending_root = root;
+        self.pending_at = pending_at;
+        Ok(pending_at)
+    }
+
+    /// Make the pending root current once its timelock has passed
+    pub fn activate(&mut self, now: i64) -> Result<AssociationRoot> {
+        require!(
+            self.pending_root != [0u8; 32],
+            MixerError::NoPendingAssociationRoot
+        );
+        require!(
+            now >= self.pending_at,
+            MixerError::AssociationRootTimelockNotElapsed
+        );
+
+        let version = self
+            .version
+            .checked_add(1)
+            .ok_or(MixerError::ArithmeticOverflow)?;
+        let activated = AssociationRoot {
+            root: self.pending_root,
+            version,
+            activated_at: now,
+        };
+        self.history[(version as usize - 1) % ASSOCIATION_ROOT_HISTORY] = activated;
+        self.root = activated.root;
+        self.version = version;
+        self.updated_at = now;
+        self.pending_root = [0u8; 32];
+        self.pending_at = 0;
+        Ok(activated)
+    }
+
+    /// Activated root `version`, if still in the history
+    pub fn root_version(&self, version: u32) -> Option<&AssociationRoot> {
+        if version == 0 || version > self.version {
+            return None;
+        }
+        let entry = &self.history[(version as usize - 1) % ASSOCIATION_ROOT_HISTORY];
+        (entry.version == version).then_some(entry)
+    }
+}
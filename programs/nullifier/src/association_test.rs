@@ -0,0 +1,95 @@
+/// Tests for association set roots
+use anchor_lang::prelude::{AccountSerialize, Pubkey};
+
+use super::association::*;
+
+const NOW: i64 = 1_700_000_000;
+
+fn association_set() -> AssociationSet {
+    AssociationSet {
+        pool: Pubkey::new_unique(),
+        root: [0u8; 32],
+        version: 0,
+        updated_at: 0,
+        pending_root: [0u8; 32],
+        pending_at: 0,
+        history: [AssociationRoot::default(); ASSOCIATION_ROOT_HISTORY],
+        bump: 255,
+    }
+}
+
+#[test]
+fn test_association_set_size() {
+    // AssociationSet: discriminator (8) + pool (32) + root (32) + version (4) + updated_at (8)
+    // + pending_root (32) + pending_at (8) + history (44 * 8) + bump (1)
+    assert_eq!(AssociationSet::LEN, 8 + 32 + 32 + 4 + 8 + 32 + 8 + 44 * 8 + 1);
+
+    let mut data = Vec::new();
+    association_set().try_serialize(&mut data).unwrap();
+    assert_eq!(data.len(), AssociationSet::LEN);
+}
+
+#[test]
+fn test_association_root_timelock() {
+    let mut set = association_set();
+
+    assert!(set.activate(NOW).is_err());
+    assert!(set.propose([0u8; 32], NOW).is_err());
+
+    assert_eq!(set.propose([1u8; 32], NOW).unwrap(), NOW + ASSOCIATION_ROOT_DELAY);
+    assert!(set.activate(NOW + ASSOCIATION_ROOT_DELAY - 1).is_err());
+    assert_eq!(set.root, [0u8; 32]);
+
+    let activated = set.activate(NOW + ASSOCIATION_ROOT_DELAY).unwrap();
+    assert_eq!(
+        activated,
+        AssociationRoot { root: [1u8; 32], version: 1, activated_at: NOW + ASSOCIATION_ROOT_DELAY }
+    );
+    assert_eq!(set.root, [1u8; 32]);
+    assert_eq!(set.version, 1);
+    assert_eq!(set.updated_at, NOW + ASSOCIATION_ROOT_DELAY);
+    assert_eq!(set.pending_root, [0u8; 32]);
+
+    // Activated once only
+    assert!(set.activate(NOW + 2 * ASSOCIATION_ROOT_DELAY).is_err());
+}
+
+#[test]
+fn test_association_root_proposal_replaced() {
+    let mut set = association_set();
+    set.propose([1u8; 32], NOW).unwrap();
+    set.propose([2u8; 32], NOW + 100).unwrap();
+
+    // The second proposal restarts the delay
+    assert!(set.activate(NOW + ASSOCIATION_ROOT_DELAY).is_err());
+    let activated = set.activate(NOW + 100 + ASSOCIATION_ROOT_DELAY).unwrap();
+    assert_eq!(activated.root, [2u8; 32]);
+    assert_eq!(activated.version, 1);
+}
+
+#[test]
+fn test_association_root_history() {
+    let mut set = association_set();
+    assert!(set.root_version(0).is_none());
+    assert!(set.root_version(1).is_none());
+
+    let versions = ASSOCIATION_ROOT_HISTORY as u32 + 3;
+    for version in 1..=versions {
+        let now = NOW + version as i64 * ASSOCIATION_ROOT_DELAY;
+        set.propose([version as u8; 32], now).unwrap();
+        set.activate(now + ASSOCIATION_ROOT_DELAY).unwrap();
+    }
+    assert_eq!(set.version, versions);
+    assert_eq!(set.root, [versions as u8; 32]);
+
+    // Only the last ASSOCIATION_ROOT_HISTORY versions are kept
+    for version in 1..=versions - ASSOCIATION_ROOT_HISTORY as u32 {
+        assert!(set.root_version(version).is_none());
+    }
+    for version in versions - ASSOCIATION_ROOT_HISTORY as u32 + 1..=versions {
+        let entry = set.root_version(version).unwrap();
+        assert_eq!(entry.root, [version as u8; 32]);
+        assert_eq!(entry.activated_at, NOW + (version as i64 + 1) * ASSOCIATION_ROOT_DELAY);
+    }
+    assert!(set.root_version(versions + 1).is_none());
+}
@@ -0,0 +1,49 @@
+/*!
+ * Bloom filter over spent nullifiers
+ *
+ * Each pool can keep a fixed-size filter that every withdrawal adds its
+ * nullifier (or nullifier hash) to. A clear bit at any probe position means
+ * the nullifier was never added, so wallets and relayers can rule out a spend
+ * from one account fetch, and withdrawals can skip scanning the pre-PDA
+ * NullifierRegistry. A hit only means "maybe spent".
+ *
+ * Probe positions are taken from SHA256("bloom" || key), two bytes per probe,
+ * so the filter behaves the same for raw SHA256-phase nullifiers and for
+ * Poseidon nullifier hashes whose top bits are constrained by the field.
+ */
+
+use sha2::{Digest, Sha256};
+
+/// Size of the bit array in bytes (65536 bits)
+pub const BLOOM_FILTER_BYTES: usize = 8192;
+
+/// Probes per key
+pub const BLOOM_HASHES: usize = 7;
+
+/// Bit positions probed for `key`
+pub fn bit_positions(key: &[u8; 32]) -> [usize; BLOOM_HASHES] {
+    let mut hasher = Sha256::new();
+    hasher.update(b"bloom");
+    hasher.update(key);
+    let digest: [u8; 32] = hasher.finalize().into();
+
+    let mut positions = [0usize; BLOOM_HASHES];
+    for (i, position) in positions.iter_mut().enumerate() {
+        *position = u16::from_le_bytes([digest[2 * i], digest[2 * i + 1]]) as usize;
+    }
+    positions
+}
+
+/// False means `key` was definitely never inserted
+pub fn might_contain(bits: &[u8; BLOOM_FILTER_BYTES], key: &[u8; 32]) -> bool {
+    bit_positions(key)
+        .iter()
+        .all(|&position| bits[position / 8] & (1 << (position % 8)) != 0)
+}
+
+/// Set every probe bit for `key`
+pub fn insert(bits: &mut [u8; BLOOM_FILTER_BYTES], key: &[u8; 32]) {
+    for position in bit_positions(key) {
+        bits[position / 8] |= 1 << (position % 8);
+    }
+}
@@ -0,0 +1,59 @@
+/// Tests for the spent-nullifier bloom filter
+use super::bloom::*;
+
+#[test]
+fn test_empty_filter_contains_nothing() {
+    let bits = [0u8; BLOOM_FILTER_BYTES];
+
+    for i in 0..=255u8 {
+        assert!(!might_contain(&bits, &[i; 32]));
+    }
+}
+
+#[test]
+fn test_inserted_keys_are_found() {
+    let mut bits = [0u8; BLOOM_FILTER_BYTES];
+    let keys: Vec<[u8; 32]> = (0..1000u32)
+        .map(|i| {
+            let mut key = [0u8; 32];
+            key[..4].copy_from_slice(&i.to_le_bytes());
+            key
+        })
+        .collect();
+
+    for key in &keys {
+        insert(&mut bits, key);
+    }
+
+    // No false negatives
+    assert!(keys.iter().all(|key| might_contain(&bits, key)));
+}
+
+#[test]
+fn test_false_positive_rate() {
+    let mut bits = [0u8; BLOOM_FILTER_BYTES];
+    for i in 0..1000u32 {
+        let mut key = [0u8; 32];
+        key[..4].copy_from_slice(&i.to_le_bytes());
+        insert(&mut bits, &key);
+    }
+
+    // With 1000 of 65536 bits' worth of keys the rate is far below 1%
+    let false_positives = (0..10_000u32)
+        .filter(|i| {
+            let mut key = [0xffu8; 32];
+            key[..4].copy_from_slice(&i.to_le_bytes());
+            might_contain(&bits, &key)
+        })
+        .count();
+    assert!(false_positives < 100, "{} false positives", false_positives);
+}
+
+#[test]
+fn test_bit_positions_are_in_range() {
+    for i in 0..=255u8 {
+        for position in bit_positions(&[i; 32]) {
+            assert!(position < BLOOM_FILTER_BYTES * 8);
+        }
+    }
+}
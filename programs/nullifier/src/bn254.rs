@@ -0,0 +1,150 @@
+/*!
+ * BN254 field and point validation helpers
+ *
+ * The alt_bn128 syscalls take big-endian field elements and points
+ * (G1 = x || y, G2 = x_im || x_re || y_im || y_re as in EIP-197), while
+ * light-poseidon and our Merkle code produce little-endian field elements.
+ * Everything that crosses between the two goes through this module.
+ */
+
+use ark_bn254::{Fq, Fq2, Fr, G1Affine, G2Affine};
+use ark_ff::{BigInt, BigInteger, PrimeField};
+
+/// Size of a serialized field element
+pub const FIELD_SIZE: usize = 32;
+
+/// Reverse a 32-byte field element between little- and big-endian
+pub fn reverse_endianness(bytes: &[u8; FIELD_SIZE]) -> [u8; FIELD_SIZE] {
+    let mut out = *bytes;
+    out.reverse();
+    out
+}
+
+/// Parse big-endian bytes into a 256-bit integer without reduction
+pub fn bigint_from_be(bytes: &[u8; FIELD_SIZE]) -> BigInt<4> {
+    let mut limbs = [0u64; 4];
+    for (i, limb) in limbs.iter_mut().enumerate() {
+        let start = FIELD_SIZE - (i + 1) * 8;
+        *limb = u64::from_be_bytes(bytes[start..start + 8].try_into().unwrap());
+    }
+    BigInt::new(limbs)
+}
+
+/// Check that big-endian bytes are a canonical scalar field element (< r)
+pub fn is_valid_fr(bytes: &[u8; FIELD_SIZE]) -> bool {
+    bigint_from_be(bytes) < Fr::MODULUS
+}
+
+/// Check that big-endian bytes are a canonical base field element (< q)
+pub fn is_valid_fq(bytes: &[u8; FIELD_SIZE]) -> bool {
+    bigint_from_be(bytes) < Fq::MODULUS
+}
+
+/// Reduce little-endian bytes into the scalar field
+pub fn fr_from_le_bytes(bytes: &[u8; FIELD_SIZE]) -> Fr {
+    Fr::from_le_bytes_mod_order(bytes)
+}
+
+/// Reduce big-endian bytes into the scalar field
+pub fn fr_from_be_bytes(bytes: &[u8; FIELD_SIZE]) -> Fr {
+    Fr::from_be_bytes_mod_order(bytes)
+}
+
+/// Serialize a scalar field element as little-endian bytes
+pub fn fr_to_le_bytes(field: &Fr) -> [u8; FIELD_SIZE] {
+    let mut bytes = [0u8; FIELD_SIZE];
+    let field_bytes = field.into_bigint().to_bytes_le();
+    bytes[..field_bytes.len()].copy_from_slice(&field_bytes);
+    bytes
+}
+
+/// Serialize a scalar field element as big-endian bytes
+pub fn fr_to_be_bytes(field: &Fr) -> [u8; FIELD_SIZE] {
+    reverse_endianness(&fr_to_le_bytes(field))
+}
+
+/// Convert little-endian bytes (Poseidon/Merkle output) into the canonical
+/// big-endian scalar the verifier expects, reducing mod r
+pub fn le_to_canonical_be(bytes: &[u8; FIELD_SIZE]) -> [u8; FIELD_SIZE] {
+    fr_to_be_bytes(&fr_from_le_bytes(bytes))
+}
+
+fn fq_from_be(bytes: &[u8]) -> Option<Fq> {
+    Fq::from_bigint(bigint_from_be(bytes.try_into().ok()?))
+}
+
+fn fq_to_be(field: &Fq) -> [u8; FIELD_SIZE] {
+    let mut bytes = [0u8; FIELD_SIZE];
+    let field_bytes = field.into_bigint().to_bytes_be();
+    bytes[FIELD_SIZE - field_bytes.len()..].copy_from_slice(&field_bytes);
+    bytes
+}
+
+/// Parse x || y (big-endian) into an affine G1 point without curve checks
+/// Returns None if a coordinate is not a canonical base field element
+pub fn g1_from_be(point: &[u8; 64]) -> Option<G1Affine> {
+    if *point == [0u8; 64] {
+        return Some(G1Affine::identity());
+    }
+
+    Some(G1Affine::new_unchecked(fq_from_be(&point[..32])?, fq_from_be(&point[32..])?))
+}
+
+/// Serialize an affine G1 point as x || y (big-endian)
+pub fn g1_to_be(point: &G1Affine) -> [u8; 64] {
+    let mut bytes = [0u8; 64];
+    if point.infinity {
+        return bytes;
+    }
+
+    bytes[..32].copy_from_slice(&fq_to_be(&point.x));
+    bytes[32..].copy_from_slice(&fq_to_be(&point.y));
+    bytes
+}
+
+/// Parse x_im || x_re || y_im || y_re (big-endian) into an affine G2 point
+/// without curve checks
+/// Returns None if a coordinate is not a canonical base field element
+pub fn g2_from_be(point: &[u8; 128]) -> Option<G2Affine> {
+    if *point == [0u8; 128] {
+        return Some(G2Affine::identity());
+    }
+
+    let x = Fq2::new(fq_from_be(&point[32..64])?, fq_from_be(&point[..32])?);
+    let y = Fq2::new(fq_from_be(&point[96..])?, fq_from_be(&point[64..96])?);
+    Some(G2Affine::new_unchecked(x, y))
+}
+
+/// Serialize an affine G2 point as x_im || x_re || y_im || y_re (big-endian)
+pub fn g2_to_be(point: &G2Affine) -> [u8; 128] {
+    let mut bytes = [0u8; 128];
+    if point.infinity {
+        return bytes;
+    }
+
+    bytes[..32].copy_from_slice(&fq_to_be(&point.x.c1));
+    bytes[32..64].copy_from_slice(&fq_to_be(&point.x.c0));
+    bytes[64..96].copy_from_slice(&fq_to_be(&point.y.c1));
+    bytes[96..].copy_from_slice(&fq_to_be(&point.y.c0));
+    bytes
+}
+
+/// Negate a G1 point: (x, y) -> (x, q - y)
+pub fn negate_g1(point: &[u8; 64]) -> Option<[u8; 64]> {
+    Some(g1_to_be(&-g1_from_be(point)?))
+}
+
+/// Check that x || y (big-endian) is a point on the G1 curve y^2 = x^3 + 3
+/// All zeros encodes the point at infinity, as in the alt_bn128 syscalls
+pub fn is_on_curve_g1(point: &[u8; 64]) -> bool {
+    g1_from_be(point).is_some_and(|point| point.is_on_curve())
+}
+
+/// Check that x_im || x_re || y_im || y_re (big-endian) is a point on the
+/// G2 twist and in the prime-order subgroup
+/// All zeros encodes the point at infinity, as in the alt_bn128 syscalls
+pub fn is_on_curve_g2(point: &[u8; 128]) -> bool {
+    g2_from_be(point).is_some_and(|point| {
+        point.is_on_curve() && point.is_in_correct_subgroup_assuming_on_curve()
+    })
+}
@@ -0,0 +1,152 @@
+/// Tests for BN254 field and point validation helpers
+use super::bn254::*;
+use ark_bn254::Fr;
+
+/// r - 1, big-endian
+const FR_MAX: [u8; 32] = [
+    0x30, 0x64, 0x4e, 0x72, 0xe1, 0x31, 0xa0, 0x29, 0xb8, 0x50, 0x45, 0xb6, 0x81, 0x81, 0x58, 0x5d,
+    0x28, 0x33, 0xe8, 0x48, 0x79, 0xb9, 0x70, 0x91, 0x43, 0xe1, 0xf5, 0x93, 0xf0, 0x00, 0x00, 0x00,
+];
+
+/// q - 1, big-endian
+const FQ_MAX: [u8; 32] = [
+    0x30, 0x64, 0x4e, 0x72, 0xe1, 0x31, 0xa0, 0x29, 0xb8, 0x50, 0x45, 0xb6, 0x81, 0x81, 0x58, 0x5d,
+    0x97, 0x81, 0x6a, 0x91, 0x68, 0x71, 0xca, 0x8d, 0x3c, 0x20, 0x8c, 0x16, 0xd8, 0x7c, 0xfd, 0x46,
+];
+
+fn g1_generator() -> [u8; 64] {
+    let mut point = [0u8; 64];
+    point[31] = 1;
+    point[63] = 2;
+    point
+}
+
+#[test]
+fn test_reverse_endianness() {
+    let mut bytes = [0u8; 32];
+    bytes[0] = 1;
+    bytes[31] = 2;
+
+    let reversed = reverse_endianness(&bytes);
+    assert_eq!(reversed[0], 2);
+    assert_eq!(reversed[31], 1);
+    assert_eq!(reverse_endianness(&reversed), bytes);
+}
+
+#[test]
+fn test_is_valid_fr() {
+    assert!(is_valid_fr(&[0u8; 32]));
+    assert!(is_valid_fr(&FR_MAX));
+
+    // r itself is not canonical
+    let mut r = FR_MAX;
+    r[31] += 1;
+    assert!(!is_valid_fr(&r));
+
+    assert!(!is_valid_fr(&[0xffu8; 32]));
+}
+
+#[test]
+fn test_is_valid_fq() {
+    assert!(is_valid_fq(&[0u8; 32]));
+    assert!(is_valid_fq(&FQ_MAX));
+
+    // q itself is not canonical
+    let mut q = FQ_MAX;
+    q[31] += 1;
+    assert!(!is_valid_fq(&q));
+
+    // Values between r and q are valid base field elements but not scalars
+    let mut between = FR_MAX;
+    between[31] += 1;
+    assert!(is_valid_fq(&between));
+    assert!(!is_valid_fr(&between));
+}
+
+#[test]
+fn test_fr_le_be_roundtrip() {
+    let field = Fr::from(123_456_789u64);
+
+    let le = fr_to_le_bytes(&field);
+    let be = fr_to_be_bytes(&field);
+
+    assert_eq!(be, reverse_endianness(&le));
+    assert_eq!(fr_from_le_bytes(&le), field);
+    assert_eq!(fr_from_be_bytes(&be), field);
+    assert_eq!(&be[24..], &123_456_789u64.to_be_bytes());
+}
+
+#[test]
+fn test_le_to_canonical_be_reduces() {
+    // Small values only change endianness
+    let mut small = [0u8; 32];
+    small[0] = 42;
+    let be = le_to_canonical_be(&small);
+    assert_eq!(be[31], 42);
+    assert!(be[..31].iter().all(|&b| b == 0));
+
+    // r (little-endian) reduces to zero
+    let r_le = {
+        let mut r = FR_MAX;
+        r[31] += 1;
+        reverse_endianness(&r)
+    };
+    assert_eq!(le_to_canonical_be(&r_le), [0u8; 32]);
+
+    // Out-of-range values are always reduced to a valid scalar
+    assert!(is_valid_fr(&le_to_canonical_be(&[0xffu8; 32])));
+}
+
+#[test]
+fn test_is_on_curve_g1() {
+    assert!(is_on_curve_g1(&g1_generator()));
+
+    // Point at infinity
+    assert!(is_on_curve_g1(&[0u8; 64]));
+
+    // (1, 3) is not on y^2 = x^3 + 3
+    let mut point = g1_generator();
+    point[63] = 3;
+    assert!(!is_on_curve_g1(&point));
+
+    // Non-canonical coordinates are rejected
+    assert!(!is_on_curve_g1(&[0xffu8; 64]));
+}
+
+#[test]
+fn test_is_on_curve_g1_negated_generator() {
+    // (1, q - 2) = -G is on the curve
+    let mut point = g1_generator();
+    let mut y = FQ_MAX;
+    y[31] -= 1;
+    point[32..].copy_from_slice(&y);
+    assert!(is_on_curve_g1(&point));
+}
+
+#[test]
+fn test_is_on_curve_g2() {
+    let mut generator = [0u8; 128];
+    let coords = [
+        "198e9393920d483a7260bfb731fb5d25f1aa493335a9e71297e485b7aef312c2",
+        "1800deef121f1e76426a00665e5c4479674322d4f75edadd46debd5cd992f6ed",
+        "090689d0585ff075ec9e99ad690c3395bc4b313370b38ef355acdadcd122975b",
+        "12c85ea5db8c6deb4aab71808dcb408fe3d1e7690c43d37b4ce6cc0166fa7daa",
+    ];
+    for (i, coord) in coords.iter().enumerate() {
+        for j in 0..32 {
+            generator[i * 32 + j] = u8::from_str_radix(&coord[j * 2..j * 2 + 2], 16).unwrap();
+        }
+    }
+    assert!(is_on_curve_g2(&generator));
+
+    // Point at infinity
+    assert!(is_on_curve_g2(&[0u8; 128]));
+
+    // Swapping real and imaginary parts leaves the curve
+    let mut swapped = generator;
+    swapped[..32].copy_from_slice(&generator[32..64]);
+    swapped[32..64].copy_from_slice(&generator[..32]);
+    assert!(!is_on_curve_g2(&swapped));
+
+    assert!(!is_on_curve_g2(&[1u8; 128]));
+}
@@ -0,0 +1,122 @@
+/*!
+ * spl-account-compression CPI helpers
+ *
+ * Pools can append commitments to a concurrent Merkle tree owned by the
+ * spl-account-compression program instead of creating a CommitmentRecord PDA
+ * per deposit. The published crate is pinned to an older Anchor, so the
+ * instructions are built by hand here: an 8-byte Anchor discriminator
+ * followed by the Borsh-encoded arguments.
+ *
+ * The pool PDA is the tree authority and signs every CPI with its seeds.
+ */
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+use sha2::{Digest, Sha256};
+
+/// spl-account-compression program
+pub const SPL_ACCOUNT_COMPRESSION_ID: Pubkey =
+    pubkey!("cmtDvXumGCrqC1Age74AVPhSRVXJMd8PJS91L8KbNCK");
+
+/// spl-noop program, receives the tree changelog so indexers can rebuild proofs
+pub const SPL_NOOP_ID: Pubkey = pubkey!("noopb9bkMVfRPU8AsbpTUg8AQkHtKwMYZiFUjNRtMmV");
+
+/// Anchor instruction discriminator: first 8 bytes of SHA256("global:<name>")
+pub fn instruction_discriminator(name: &str) -> [u8; 8] {
+    let mut hasher = Sha256::new();
+    hasher.update(b"global:");
+    hasher.update(name.as_bytes());
+    let hash: [u8; 32] = hasher.finalize().into();
+    hash[..8].try_into().unwrap()
+}
+
+/// Build init_empty_merkle_tree(max_depth, max_buffer_size)
+pub fn init_empty_merkle_tree_ix(
+    merkle_tree: &Pubkey,
+    authority: &Pubkey,
+    max_depth: u32,
+    max_buffer_size: u32,
+) -> Instruction {
+    let mut data = instruction_discriminator("init_empty_merkle_tree").to_vec();
+    data.extend_from_slice(&max_depth.to_le_bytes());
+    data.extend_from_slice(&max_buffer_size.to_le_bytes());
+
+    Instruction {
+        program_id: SPL_ACCOUNT_COMPRESSION_ID,
+        accounts: modify_accounts(merkle_tree, authority),
+        data,
+    }
+}
+
+/// Build append(leaf)
+pub fn append_ix(merkle_tree: &Pubkey, authority: &Pubkey, leaf: &[u8; 32]) -> Instruction {
+    let mut data = instruction_discriminator("append").to_vec();
+    data.extend_from_slice(leaf);
+
+    Instruction {
+        program_id: SPL_ACCOUNT_COMPRESSION_ID,
+        accounts: modify_accounts(merkle_tree, authority),
+        data,
+    }
+}
+
+/// Account list shared by init_empty_merkle_tree and append
+fn modify_accounts(merkle_tree: &Pubkey, authority: &Pubkey) -> Vec<AccountMeta> {
+    vec![
+        AccountMeta::new(*merkle_tree, false),
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new_readonly(SPL_NOOP_ID, false),
+    ]
+}
+
+/// Initialize a zeroed, pre-allocated tree account with the pool as authority
+pub fn init_empty_merkle_tree<'info>(
+    compression_program: &AccountInfo<'info>,
+    merkle_tree: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    noop_program: &AccountInfo<'info>,
+    signer_seeds: &[&[u8]],
+    max_depth: u32,
+    max_buffer_size: u32,
+) -> Result<()> {
+    let ix = init_empty_merkle_tree_ix(merkle_tree.key, authority.key, max_depth, max_buffer_size);
+
+    invoke_signed(
+        &ix,
+        &[
+            merkle_tree.clone(),
+            authority.clone(),
+            noop_program.clone(),
+            compression_program.clone(),
+        ],
+        &[signer_seeds],
+    )?;
+
+    Ok(())
+}
+
+/// Append a commitment to the pool's concurrent tree
+pub fn append<'info>(
+    compression_program: &AccountInfo<'info>,
+    merkle_tree: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    noop_program: &AccountInfo<'info>,
+    signer_seeds: &[&[u8]],
+    leaf: &[u8; 32],
+) -> Result<()> {
+    let ix = append_ix(merkle_tree.key, authority.key, leaf);
+
+    invoke_signed(
+        &ix,
+        &[
+            merkle_tree.clone(),
+            authority.clone(),
+            noop_program.clone(),
+            compression_program.clone(),
+        ],
+        &[signer_seeds],
+    )?;
+
+    Ok(())
+}
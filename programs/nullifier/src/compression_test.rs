@@ -0,0 +1,48 @@
+/// Tests for spl-account-compression CPI helpers
+use super::compression::*;
+use anchor_lang::prelude::Pubkey;
+
+#[test]
+fn test_instruction_discriminators() {
+    // Anchor discriminators published in the spl-account-compression IDL
+    assert_eq!(instruction_discriminator("append"), [149, 120, 18, 222, 236, 225, 88, 203]);
+    assert_eq!(
+        instruction_discriminator("init_empty_merkle_tree"),
+        [191, 11, 119, 7, 180, 107, 220, 110]
+    );
+}
+
+#[test]
+fn test_append_ix_layout() {
+    let tree = Pubkey::new_unique();
+    let authority = Pubkey::new_unique();
+    let leaf = [7u8; 32];
+
+    let ix = append_ix(&tree, &authority, &leaf);
+
+    assert_eq!(ix.program_id, SPL_ACCOUNT_COMPRESSION_ID);
+    assert_eq!(ix.data.len(), 8 + 32);
+    assert_eq!(&ix.data[8..], &leaf);
+
+    // merkle_tree (writable), authority (signer), noop
+    assert_eq!(ix.accounts.len(), 3);
+    assert_eq!(ix.accounts[0].pubkey, tree);
+    assert!(ix.accounts[0].is_writable && !ix.accounts[0].is_signer);
+    assert_eq!(ix.accounts[1].pubkey, authority);
+    assert!(ix.accounts[1].is_signer && !ix.accounts[1].is_writable);
+    assert_eq!(ix.accounts[2].pubkey, SPL_NOOP_ID);
+}
+
+#[test]
+fn test_init_empty_merkle_tree_ix_layout() {
+    let tree = Pubkey::new_unique();
+    let authority = Pubkey::new_unique();
+
+    let ix = init_empty_merkle_tree_ix(&tree, &authority, 20, 64);
+
+    assert_eq!(ix.data.len(), 8 + 4 + 4);
+    assert_eq!(&ix.data[8..12], &20u32.to_le_bytes());
+    assert_eq!(&ix.data[12..], &64u32.to_le_bytes());
+    assert_eq!(ix.accounts[0].pubkey, tree);
+    assert_eq!(ix.accounts[1].pubkey, authority);
+}
@@ -0,0 +1,164 @@
+/*!
+ * Voluntary disclosure registry
+ *
+ * After a withdrawal, the note's owner can publish what the note contained
+ * for tax or audit purposes with publish_note_disclosure. The program opens
+ * the note with its nullifier and secret and checks the withdrawal spent it,
+ * so every DisclosedNote, [b"disclosed_note", spent_nullifier], is verified
+ * rather than claimed. Once a note is spent its nullifier and secret protect
+ * nothing, so opening it costs no funds; it does give up the note's privacy
+ * for good.
+ *
+ * The withdrawal's recipient signs the publication, and each recipient's
+ * DisclosureRegistry, [b"disclosure_registry", recipient], counts the notes
+ * disclosed to it, so a set of its DisclosedNotes can be checked complete.
+ *
+ * get_compliance_report turns a recipient's DisclosedNotes into a
+ * ComplianceReport returned through set_return_data: one line per
+ * withdrawal plus totals per mint. Return data is capped at 1024 bytes, so a
+ * report covers at most MAX_REPORT_WITHDRAWALS notes; tooling pages through
+ * longer histories, and `disclosed` says how many there are in all.
+ */
+
+use anchor_lang::prelude::*;
+
+use crate::MixerError;
+
+/// Withdrawals one ComplianceReport can list within the return data limit
+pub const MAX_REPORT_WITHDRAWALS: usize = 8;
+
+/// Published contents of one withdrawn note
+#[account]
+pub struct DisclosedNote {
+    pub pool: Pubkey,                   // 32
+    pub mint: Pubkey,                   // 32 - Pool's mint, SOL_POOL_MINT for SOL
+    pub amount: u64,                    // 8 - Pool denomination
+    pub leaf_index: u32,                // 4
+    pub commitment: [u8; 32],           // 32 - Commitment the nullifier and secret open
+    pub nullifier_hash: [u8; 32],       // 32 - Key of the withdrawal's SpentNullifier
+    pub deposited_at: i64,              // 8
+    pub withdrawn_at: i64,              // 8
+    pub recipient: Pubkey,              // 32 - Withdrawal recipient, who published it
+    pub published_at: i64,              // 8
+    pub bump: u8,                       // 1
+}
+
+impl DisclosedNote {
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 4 + 32 + 32 + 8 + 8 + 32 + 8 + 1;
+}
+
+/// Number of notes disclosed as withdrawn to `recipient`
+#[account]
+pub struct DisclosureRegistry {
+    pub recipient: Pubkey,              // 32
+    pub count: u32,                     // 4
+    pub bump: u8,                       // 1
+}
+
+impl DisclosureRegistry {
+    pub const LEN: usize = 8 + 32 + 4 + 1;
+
+    /// Count one more disclosed note
+    pub fn record(&mut self) -> Result<()> {
+        self.count = self
+            .count
+            .checked_add(1)
+            .ok_or(MixerError::ArithmeticOverflow)?;
+        Ok(())
+    }
+}
+
+/// One disclosed withdrawal in a ComplianceReport
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Debug)]
+pub struct ReportedWithdrawal {
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub deposited_at: i64,
+    pub withdrawn_at: i64,
+}
+
+/// Sum of a ComplianceReport's withdrawals in one mint
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Debug)]
+pub struct MintTotal {
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub withdrawals: u32,
+}
+
+/// What get_compliance_report returns for a recipient
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Debug)]
+pub struct ComplianceReport {
+    pub recipient: Pubkey,
+    /// Notes disclosed to the recipient in all, per its DisclosureRegistry
+    pub disclosed: u32,
+    pub withdrawals: Vec<ReportedWithdrawal>,
+    pub totals: Vec<MintTotal>,
+    pub generated_at: i64,
+}
+
+impl ComplianceReport {
+    /// Report `notes`, which must all be distinct notes disclosed to the
+    /// registry's recipient, in the order given
+    pub fn build(registry: &DisclosureRegistry, notes: &[DisclosedNote], now: i64) -> Result<Self> {
+        require!(
+            notes.len() <= MAX_REPORT_WITHDRAWALS,
+            MixerError::ComplianceReportTooLarge
+        );
+
+        let mut report = Self {
+            recipient: registry.recipient,
+            disclosed: registry.count,
+            withdrawals: Vec::with_capacity(notes.len()),
+            totals: Vec::new(),
+            generated_at: now,
+        };
+
+        for (position, note) in notes.iter().enumerate() {
+            require!(
+                note.recipient == registry.recipient,
+                MixerError::DisclosureNotOfRecipient
+            );
+            require!(
+                notes[..position]
+                    .iter()
+                    .all(|other| other.pool != note.pool || other.nullifier_hash != note.nullifier_hash),
+                MixerError::DuplicateDisclosure
+            );
+
+            report.withdrawals.push(ReportedWithdrawal {
+                mint: note.mint,
+                amount: note.amount,
+                deposited_at: note.deposited_at,
+                withdrawn_at: note.withdrawn_at,
+            });
+            report.add_to_totals(note.mint, note.amount)?;
+        }
+
+        Ok(report)
+    }
+
+    /// Whether the report lists every note disclosed to the recipient
+    pub fn is_complete(&self) -> bool {
+        self.withdrawals.len() == self.disclosed as usize
+    }
+
+    fn add_to_totals(&mut self, mint: Pubkey, amount: u64) -> Result<()> {
+        let total = match self.totals.iter().position(|total| total.mint == mint) {
+            Some(position) => &mut self.totals[position],
+            None => {
+                self.totals.push(MintTotal {
+                    mint,
+                    amount: 0,
+                    withdrawals: 0,
+                });
+                self.totals.last_mut().unwrap()
+            }
+        };
+        total.amount = total
+            .amount
+            .checked_add(amount)
+            .ok_or(MixerError::ArithmeticOverflow)?;
+        total.withdrawals += 1;
+        Ok(())
+    }
+}
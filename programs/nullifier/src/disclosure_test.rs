@@ -0,0 +1,118 @@
+/// Tests for the voluntary disclosure registry
+use anchor_lang::prelude::{AccountSerialize, Pubkey};
+
+use super::disclosure::*;
+
+#[test]
+fn test_disclosure_account_sizes() {
+    let disclosed_note = DisclosedNote {
+        pool: Pubkey::new_unique(),
+        mint: Pubkey::default(),
+        amount: 1_000_000_000,
+        leaf_index: 3,
+        commitment: [1u8; 32],
+        nullifier_hash: [2u8; 32],
+        deposited_at: 1_700_000_000,
+        withdrawn_at: 1_700_086_400,
+        recipient: Pubkey::new_unique(),
+        published_at: 1_700_172_800,
+        bump: 255,
+    };
+    let mut data = Vec::new();
+    disclosed_note.try_serialize(&mut data).unwrap();
+    assert_eq!(data.len(), DisclosedNote::LEN);
+
+    // DisclosureRegistry: discriminator (8) + recipient (32) + count (4) + bump (1)
+    assert_eq!(DisclosureRegistry::LEN, 45);
+}
+
+#[test]
+fn test_disclosure_registry_counts() {
+    let mut registry = DisclosureRegistry {
+        recipient: Pubkey::new_unique(),
+        count: 0,
+        bump: 255,
+    };
+
+    registry.record().unwrap();
+    registry.record().unwrap();
+    assert_eq!(registry.count, 2);
+
+    registry.count = u32::MAX;
+    assert!(registry.record().is_err());
+}
+
+fn disclosed_note(recipient: Pubkey, mint: Pubkey, amount: u64, nullifier_hash: u8) -> DisclosedNote {
+    DisclosedNote {
+        pool: Pubkey::default(),
+        mint,
+        amount,
+        leaf_index: 0,
+        commitment: [1u8; 32],
+        nullifier_hash: [nullifier_hash; 32],
+        deposited_at: 1_700_000_000,
+        withdrawn_at: 1_700_086_400,
+        recipient,
+        published_at: 1_700_172_800,
+        bump: 255,
+    }
+}
+
+#[test]
+fn test_compliance_report_totals_by_mint() {
+    let recipient = Pubkey::new_unique();
+    let usdc = Pubkey::new_unique();
+    let registry = DisclosureRegistry { recipient, count: 4, bump: 255 };
+    let notes = [
+        disclosed_note(recipient, Pubkey::default(), 1_000_000_000, 1),
+        disclosed_note(recipient, usdc, 100_000_000, 2),
+        disclosed_note(recipient, Pubkey::default(), 1_000_000_000, 3),
+    ];
+
+    let report = ComplianceReport::build(&registry, &notes, 1_800_000_000).unwrap();
+    assert_eq!(report.recipient, recipient);
+    assert_eq!(report.withdrawals.len(), 3);
+    assert_eq!(report.withdrawals[1].mint, usdc);
+    assert_eq!(report.totals.len(), 2);
+    assert_eq!(report.totals[0].mint, Pubkey::default());
+    assert_eq!(report.totals[0].amount, 2_000_000_000);
+    assert_eq!(report.totals[0].withdrawals, 2);
+    assert_eq!(report.totals[1].amount, 100_000_000);
+
+    // One of the four disclosed notes was left out
+    assert!(!report.is_complete());
+    let registry = DisclosureRegistry { count: 3, ..registry };
+    assert!(ComplianceReport::build(&registry, &notes, 0).unwrap().is_complete());
+}
+
+#[test]
+fn test_compliance_report_rejects_foreign_and_duplicate_notes() {
+    let recipient = Pubkey::new_unique();
+    let registry = DisclosureRegistry { recipient, count: 2, bump: 255 };
+
+    let foreign = [disclosed_note(Pubkey::new_unique(), Pubkey::default(), 1, 1)];
+    assert!(ComplianceReport::build(&registry, &foreign, 0).is_err());
+
+    let note = disclosed_note(recipient, Pubkey::default(), 1, 1);
+    assert!(ComplianceReport::build(&registry, &[note.clone(), note], 0).is_err());
+}
+
+#[test]
+fn test_compliance_report_fits_return_data() {
+    use anchor_lang::prelude::AnchorSerialize;
+    use anchor_lang::solana_program::program::MAX_RETURN_DATA;
+
+    let recipient = Pubkey::new_unique();
+    let registry = DisclosureRegistry { recipient, count: u32::MAX, bump: 255 };
+
+    // Every note in its own mint is the largest report
+    let notes: Vec<DisclosedNote> = (0..MAX_REPORT_WITHDRAWALS as u8)
+        .map(|i| disclosed_note(recipient, Pubkey::new_unique(), u64::MAX, i))
+        .collect();
+    let report = ComplianceReport::build(&registry, &notes, 0).unwrap();
+    assert!(report.try_to_vec().unwrap().len() <= MAX_RETURN_DATA);
+
+    let mut too_many = notes.clone();
+    too_many.push(disclosed_note(recipient, Pubkey::default(), 1, 0xff));
+    assert!(ComplianceReport::build(&registry, &too_many, 0).is_err());
+}
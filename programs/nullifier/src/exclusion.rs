@@ -0,0 +1,107 @@
+/*!
+ * Exclusion proofs (proof of innocence)
+ *
+ * A pool's authority can publish a list of tainted commitments, e.g. deposits
+ * of stolen funds, as the root of a sparse Merkle tree in the pool's
+ * TaintedSet account, [b"tainted_set", pool]. The tree has the layout of
+ * sparse_merkle, keyed by commitment, so "not tainted" is shown by the
+ * commitment's leaf still being empty.
+ *
+ * After a ZK withdrawal, its withdrawer can prove the deposit it spent is not
+ * on the list without revealing which deposit that was. The exclusion circuit
+ * proves knowledge of a note whose:
+ *   1. commitment is a leaf of the pool's tree with root `root`
+ *   2. nullifier hash is Poseidon(nullifier, pool, circuit_version), the one
+ *      the withdrawal revealed
+ *   3. commitment's leaf in the tainted tree with root `tainted_root` is empty
+ *
+ * prove_innocence checks the proof against the pool's current tainted root
+ * and records an InnocenceProof, [b"innocence_proof", spent_nullifier], which
+ * exchanges can look up from the withdrawal alone. As the list grows, a
+ * withdrawer proves again to refresh it. SHA256-phase withdrawals reveal
+ * their commitment and need no proof.
+ *
+ * The circuit has its own trusted setup and verification key,
+ * [b"exclusion_verification_key"].
+ */
+
+use anchor_lang::prelude::*;
+
+use crate::bn254::le_to_canonical_be;
+use crate::groth16::{self, Groth16Proof, VerificationKey, NUM_PUBLIC_INPUTS};
+use crate::merkle_poseidon::{circuit_version_to_field_bytes, pool_to_field_bytes};
+
+/// Number of public inputs exposed by the exclusion circuit
+pub const NUM_EXCLUSION_PUBLIC_INPUTS: usize = 5;
+
+/// Size of the exclusion circuit's verification key
+pub const EXCLUSION_VERIFICATION_KEY_LEN: usize =
+    VerificationKey::LEN - 64 * (NUM_PUBLIC_INPUTS - NUM_EXCLUSION_PUBLIC_INPUTS);
+
+/// Public inputs of the exclusion circuit
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ExclusionPublicInputs {
+    // Root of the pool's tree holding the note's commitment
+    pub root: [u8; 32],
+    // Nullifier hash the note was withdrawn with
+    pub nullifier_hash: [u8; 32],
+    // Pool the note was withdrawn from
+    pub pool: Pubkey,
+    // Circuit version the nullifier hash was computed for
+    pub circuit_version: u8,
+    // Root of the tainted commitments' sparse Merkle tree
+    pub tainted_root: [u8; 32],
+}
+
+impl ExclusionPublicInputs {
+    /// Encode the public inputs as canonical big-endian scalars, in the order
+    /// the circuit declares them
+    pub fn to_field_elements(&self) -> Vec<[u8; 32]> {
+        vec![
+            le_to_canonical_be(&self.root),
+            le_to_canonical_be(&self.nullifier_hash),
+            le_to_canonical_be(&pool_to_field_bytes(&self.pool)),
+            le_to_canonical_be(&circuit_version_to_field_bytes(self.circuit_version)),
+            le_to_canonical_be(&self.tainted_root),
+        ]
+    }
+}
+
+/// Verify an exclusion proof
+pub fn verify_exclusion_proof(
+    proof: &Groth16Proof,
+    public_inputs: &ExclusionPublicInputs,
+    verification_key: &VerificationKey,
+) -> Result<bool> {
+    msg!("Verifying exclusion proof...");
+    msg!("Tainted root: {:?}", public_inputs.tainted_root);
+
+    groth16::verify_proof(proof, &public_inputs.to_field_elements(), verification_key)
+}
+
+/// A pool's published list of tainted commitments
+#[account]
+pub struct TaintedSet {
+    pub pool: Pubkey,                   // 32
+    pub root: [u8; 32],                 // 32 - Root of the tainted commitments' sparse Merkle tree
+    pub updated_at: i64,                // 8 - When the root was last set
+    pub bump: u8,                       // 1
+}
+
+impl TaintedSet {
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 1;
+}
+
+/// A withdrawal's proven exclusion from its pool's tainted list
+#[account]
+pub struct InnocenceProof {
+    pub pool: Pubkey,                   // 32
+    pub nullifier_hash: [u8; 32],       // 32 - Nullifier hash the withdrawal revealed
+    pub tainted_root: [u8; 32],         // 32 - Tainted root the proof excluded the deposit from
+    pub proven_at: i64,                 // 8
+    pub bump: u8,                       // 1
+}
+
+impl InnocenceProof {
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 8 + 1;
+}
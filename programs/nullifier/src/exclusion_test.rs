@@ -0,0 +1,121 @@
+/// Tests for exclusion (proof of innocence) proofs
+use anchor_lang::prelude::{AccountSerialize, Pubkey};
+use ark_bn254::{Fr, G1Affine, G2Affine};
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::Field;
+
+use super::exclusion::*;
+use crate::bn254::{fr_from_be_bytes, g1_to_be, g2_to_be};
+use crate::groth16::{is_canonical_g1, Groth16Proof, VerificationKey, CIRCUIT_VERSION};
+
+fn inputs() -> ExclusionPublicInputs {
+    ExclusionPublicInputs {
+        root: [11u8; 32],
+        nullifier_hash: [22u8; 32],
+        pool: Pubkey::new_unique(),
+        circuit_version: CIRCUIT_VERSION,
+        tainted_root: [33u8; 32],
+    }
+}
+
+/// Verification key with a known trapdoor and a proof satisfying the pairing
+/// equation for `inputs`, as in groth16_test
+fn proof_fixture(inputs: &ExclusionPublicInputs, seed: u64) -> (Groth16Proof, VerificationKey) {
+    let scalar = |n: u64| Fr::from(seed * 1_000 + n);
+    let g1 = |k: Fr| (G1Affine::generator() * k).into_affine();
+    let g2 = |k: Fr| (G2Affine::generator() * k).into_affine();
+
+    let (alpha, beta, gamma, delta) = (scalar(1), scalar(2), scalar(3), scalar(4));
+    let ic: Vec<Fr> = (0..=NUM_EXCLUSION_PUBLIC_INPUTS as u64).map(|i| scalar(10 + i)).collect();
+
+    let vk_x = inputs
+        .to_field_elements()
+        .iter()
+        .zip(&ic[1..])
+        .fold(ic[0], |acc, (input, u)| acc + fr_from_be_bytes(input) * u);
+
+    let (mut a, mut b) = (scalar(5), scalar(6));
+    if !is_canonical_g1(&g1_to_be(&g1(a))) {
+        a = -a;
+        b = -b;
+    }
+    let c = (a * b - alpha * beta - vk_x * gamma) * delta.inverse().unwrap();
+
+    let proof = Groth16Proof {
+        a: g1_to_be(&g1(a)),
+        b: g2_to_be(&g2(b)),
+        c: g1_to_be(&g1(c)),
+    };
+    let vk = VerificationKey {
+        alpha_g1: g1_to_be(&g1(alpha)),
+        beta_g2: g2_to_be(&g2(beta)),
+        gamma_g2: g2_to_be(&g2(gamma)),
+        delta_g2: g2_to_be(&g2(delta)),
+        ic: ic.iter().map(|u| g1_to_be(&g1(*u))).collect(),
+    };
+
+    (proof, vk)
+}
+
+#[test]
+fn test_exclusion_public_inputs() {
+    let inputs = inputs();
+    let elements = inputs.to_field_elements();
+
+    assert_eq!(elements.len(), NUM_EXCLUSION_PUBLIC_INPUTS);
+    assert_eq!(elements[0], [11u8; 32]);
+    assert_eq!(elements[1], [22u8; 32]);
+    assert_eq!(elements[3][31], CIRCUIT_VERSION);
+    assert_eq!(elements[4], [33u8; 32]);
+}
+
+#[test]
+fn test_exclusion_account_sizes() {
+    let (_, vk) = proof_fixture(&inputs(), 1);
+    let mut data = Vec::new();
+    vk.try_serialize(&mut data).unwrap();
+    assert_eq!(data.len(), EXCLUSION_VERIFICATION_KEY_LEN);
+
+    // TaintedSet: discriminator (8) + pool (32) + root (32) + updated_at (8) + bump (1)
+    assert_eq!(TaintedSet::LEN, 81);
+    // InnocenceProof: discriminator (8) + pool (32) + nullifier_hash (32) + tainted_root (32) + proven_at (8) + bump (1)
+    assert_eq!(InnocenceProof::LEN, 113);
+}
+
+#[test]
+fn test_verify_exclusion_proof() {
+    let inputs = inputs();
+    let (proof, vk) = proof_fixture(&inputs, 2);
+
+    assert!(verify_exclusion_proof(&proof, &inputs, &vk).unwrap());
+}
+
+#[test]
+fn test_exclusion_proof_bound_to_tainted_root_and_withdrawal() {
+    let inputs = inputs();
+    let (proof, vk) = proof_fixture(&inputs, 3);
+
+    // A proof against an older list doesn't pass for the current one
+    let mut tampered = inputs.clone();
+    tampered.tainted_root[0] ^= 1;
+    assert!(!verify_exclusion_proof(&proof, &tampered, &vk).unwrap());
+
+    // Nor can it be claimed for another withdrawal
+    let mut tampered = inputs.clone();
+    tampered.nullifier_hash[0] ^= 1;
+    assert!(!verify_exclusion_proof(&proof, &tampered, &vk).unwrap());
+
+    let mut tampered = inputs.clone();
+    tampered.pool = Pubkey::new_unique();
+    assert!(!verify_exclusion_proof(&proof, &tampered, &vk).unwrap());
+}
+
+#[test]
+fn test_exclusion_proof_rejects_other_circuit_key() {
+    let inputs = inputs();
+    let (proof, mut vk) = proof_fixture(&inputs, 4);
+
+    // The withdrawal circuit's key has more IC points
+    vk.ic.push(vk.ic[0]);
+    assert!(verify_exclusion_proof(&proof, &inputs, &vk).is_err());
+}
@@ -1,15 +1,27 @@
-/**
+/*!
  * Groth16 zkSNARK Verifier for Solana
  *
- * This module will integrate a Groth16 verifier for on-chain proof verification.
+ * On-chain proofs are verified with the alt_bn128 syscalls. Building with the
+ * `native-verify` feature swaps in a pure ark-bn254 pairing check instead, for
+ * off-chain tools and tests that want to exercise real proofs without the
+ * Solana runtime. Both paths check the same equation:
  *
- * Options for implementation:
- * 1. Light Protocol: https://github.com/Lightprotocol/light-protocol
- * 2. groth16-solana: https://github.com/anagrambuild/groth16-solana
- * 3. Custom implementation using ark-groth16
+ *   e(-A, B) * e(alpha, beta) * e(vk_x, gamma) * e(C, delta) == 1
  */
 
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::alt_bn128::compression::prelude::{
+    alt_bn128_g1_decompress, alt_bn128_g2_decompress,
+};
+use ark_bn254::Fq;
+use ark_ff::PrimeField;
+use sha2::{Digest, Sha256};
+
+use crate::bn254::{bigint_from_be, is_on_curve_g1, is_on_curve_g2, le_to_canonical_be};
+use crate::merkle_poseidon::{
+    circuit_version_to_field_bytes, pool_to_field_bytes, timestamp_to_field_bytes,
+};
+use crate::MixerError;
 
 // Proof structure (Groth16)
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
@@ -22,6 +34,58 @@ pub struct Groth16Proof {
     pub c: [u8; 64],
 }
 
+// Compressed proof structure (Groth16)
+// Each point carries only its x coordinate plus a sign flag for y,
+// cutting the proof from 256 to 128 bytes of instruction data
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct CompressedGroth16Proof {
+    // A point (compressed G1)
+    pub a: [u8; 32],
+    // B point (compressed G2)
+    pub b: [u8; 64],
+    // C point (compressed G1)
+    pub c: [u8; 32],
+}
+
+impl CompressedGroth16Proof {
+    /// Recover the uncompressed points expected by the pairing check
+    pub fn decompress(&self) -> Result<Groth16Proof> {
+        Ok(Groth16Proof {
+            a: alt_bn128_g1_decompress(&self.a).map_err(|_| MixerError::InvalidProofEncoding)?,
+            b: alt_bn128_g2_decompress(&self.b).map_err(|_| MixerError::InvalidProofEncoding)?,
+            c: alt_bn128_g1_decompress(&self.c).map_err(|_| MixerError::InvalidProofEncoding)?,
+        })
+    }
+}
+
+// Proof as submitted in instruction data, either encoding is accepted
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub enum ProofData {
+    Uncompressed(Groth16Proof),
+    Compressed(CompressedGroth16Proof),
+}
+
+impl ProofData {
+    /// Return the proof in uncompressed form, decompressing on-chain if needed
+    pub fn into_proof(self) -> Result<Groth16Proof> {
+        match self {
+            ProofData::Uncompressed(proof) => Ok(proof),
+            ProofData::Compressed(proof) => proof.decompress(),
+        }
+    }
+}
+
+/// Number of public inputs exposed by the withdrawal circuit
+pub const NUM_PUBLIC_INPUTS: usize = 7;
+
+/// Number of public inputs exposed by the association set withdrawal circuit:
+/// the withdrawal circuit's, then the association root
+pub const NUM_ASSOCIATION_PUBLIC_INPUTS: usize = NUM_PUBLIC_INPUTS + 1;
+
+/// Version of the withdrawal circuit the verification key was generated for
+/// Hashed into every nullifier hash, so bump it whenever the circuit changes
+pub const CIRCUIT_VERSION: u8 = 1;
+
 // Public inputs
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
 pub struct PublicInputs {
@@ -29,6 +93,77 @@ pub struct PublicInputs {
     pub root: [u8; 32],
     // Nullifier hash (public)
     pub nullifier_hash: [u8; 32],
+    // Deposit timestamp bound (public)
+    // The circuit proves the timestamp committed in the leaf is <= this value,
+    // so the program can enforce the delay without learning which leaf was spent
+    pub deposit_timestamp: i64,
+    // Transaction context hash (public)
+    // Binds the proof to the pool, recipient and nullifier hash so it can't be
+    // replayed against another pool or redirected to another recipient
+    pub context_hash: [u8; 32],
+    // Pool the note is spent from (public)
+    // The circuit proves nullifier_hash = Poseidon(nullifier, pool, circuit_version),
+    // so a note's nullifier hash differs between pools sharing a root structure
+    pub pool: Pubkey,
+    // Circuit version (public), see CIRCUIT_VERSION
+    pub circuit_version: u8,
+    // Relayer submitting the withdrawal (public)
+    // Taken from the transaction's signer, so a proof made out to one relayer
+    // fails verification if another relayer frontruns it to take the fee
+    pub relayer: Pubkey,
+    // Association set root (public), for association set proofs only
+    // The association circuit also proves the spent commitment is a leaf of
+    // the pool's curated association set with this root
+    pub association_root: Option<[u8; 32]>,
+}
+
+impl PublicInputs {
+    /// Encode the public inputs as canonical big-endian scalars for the
+    /// alt_bn128 syscalls, in the order the circuit declares them
+    /// Inputs are little-endian (as produced by merkle_poseidon) and reduced mod r
+    /// NUM_PUBLIC_INPUTS elements, or NUM_ASSOCIATION_PUBLIC_INPUTS with an
+    /// association root
+    pub fn to_field_elements(&self) -> Vec<[u8; 32]> {
+        let mut elements = vec![
+            le_to_canonical_be(&self.root),
+            le_to_canonical_be(&self.nullifier_hash),
+            le_to_canonical_be(&timestamp_to_field_bytes(self.deposit_timestamp)),
+            le_to_canonical_be(&self.context_hash),
+            le_to_canonical_be(&pool_to_field_bytes(&self.pool)),
+            le_to_canonical_be(&circuit_version_to_field_bytes(self.circuit_version)),
+            le_to_canonical_be(&self.relayer.to_bytes()),
+        ];
+        if let Some(association_root) = &self.association_root {
+            elements.push(le_to_canonical_be(association_root));
+        }
+        elements
+    }
+}
+
+/// Hash the transaction context a proof is bound to
+/// The top byte is cleared so the result is always a canonical field element
+pub fn compute_context_hash(
+    pool: &Pubkey,
+    recipient: &Pubkey,
+    nullifier_hash: &[u8; 32],
+) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(b"nullifier-withdraw-context");
+    hasher.update(pool.as_ref());
+    hasher.update(recipient.as_ref());
+    hasher.update(nullifier_hash);
+    let mut hash: [u8; 32] = hasher.finalize().into();
+    hash[31] = 0;
+    hash
+}
+
+/// Check that a G1 point (x || y, big-endian) uses the canonical y, i.e. y <= (q - 1) / 2
+///
+/// For any valid proof (A, B, C), (-A, -B, C) also verifies. Requiring the
+/// smaller of y and q - y for A leaves exactly one accepted encoding.
+pub fn is_canonical_g1(point: &[u8; 64]) -> bool {
+    let y: &[u8; 32] = point[32..].try_into().unwrap();
+    bigint_from_be(y) <= Fq::MODULUS_MINUS_ONE_DIV_TWO
 }
 
 /// Verify a Groth16 proof
@@ -37,25 +172,142 @@ pub struct PublicInputs {
 /// 1. The prover knows a secret and nullifier
 /// 2. The commitment (hash of secret + nullifier) is in the Merkle tree
 /// 3. The Merkle root matches the public input
-/// 4. The nullifier matches the public input
+/// 4. The nullifier hash is Poseidon(nullifier, pool, circuit_version)
+/// 5. The deposit timestamp committed in the leaf is <= the public bound
+/// 6. The proof is bound to the transaction context hash
+/// 7. With an association root, the commitment is also in the association set
 pub fn verify_groth16_proof(
     proof: &Groth16Proof,
     public_inputs: &PublicInputs,
     verification_key: &VerificationKey,
 ) -> Result<bool> {
-    // TODO: Implement actual Groth16 verification
-    // This requires pairing-based cryptography on the BN254 curve
-
-    // For now, this is a placeholder that will be replaced with
-    // either Light Protocol's verifier or groth16-solana
-
     msg!("Verifying Groth16 proof...");
     msg!("Root: {:?}", public_inputs.root);
     msg!("Nullifier: {:?}", public_inputs.nullifier_hash);
 
-    // Placeholder - always returns true for testing
-    // MUST be replaced with actual verification
-    Ok(true)
+    verify_proof(proof, &public_inputs.to_field_elements(), verification_key)
+}
+
+/// Verify a Groth16 proof of any of the program's circuits against its
+/// public inputs, already encoded as field elements
+pub fn verify_proof(
+    proof: &Groth16Proof,
+    inputs: &[[u8; 32]],
+    verification_key: &VerificationKey,
+) -> Result<bool> {
+    // Every proof point must be a valid curve point
+    require!(
+        is_on_curve_g1(&proof.a) && is_on_curve_g2(&proof.b) && is_on_curve_g1(&proof.c),
+        MixerError::InvalidProofPoint
+    );
+
+    // Reject the negated form of a proof (Groth16 malleability)
+    require!(is_canonical_g1(&proof.a), MixerError::MalleableProof);
+
+    // One IC point per public input plus the constant term
+    require!(
+        verification_key.ic.len() == inputs.len() + 1,
+        MixerError::InvalidVerificationKey
+    );
+
+    #[cfg(not(feature = "native-verify"))]
+    let valid = syscall::verify(proof, inputs, verification_key)?;
+    #[cfg(feature = "native-verify")]
+    let valid = native::verify(proof, inputs, verification_key)?;
+
+    Ok(valid)
+}
+
+/// Groth16 verification through the alt_bn128 syscalls
+#[cfg(not(feature = "native-verify"))]
+mod syscall {
+    use super::*;
+    use anchor_lang::solana_program::alt_bn128::prelude::{
+        alt_bn128_addition, alt_bn128_multiplication, alt_bn128_pairing,
+    };
+
+    use crate::bn254::negate_g1;
+
+    pub fn verify(
+        proof: &Groth16Proof,
+        inputs: &[[u8; 32]],
+        verification_key: &VerificationKey,
+    ) -> Result<bool> {
+        // vk_x = IC[0] + sum(input_i * IC[i + 1])
+        let mut vk_x = verification_key.ic[0];
+        for (input, ic) in inputs.iter().zip(&verification_key.ic[1..]) {
+            let product = alt_bn128_multiplication(&[&ic[..], &input[..]].concat())
+                .map_err(|_| MixerError::InvalidVerificationKey)?;
+            let sum = alt_bn128_addition(&[&vk_x[..], &product[..]].concat())
+                .map_err(|_| MixerError::InvalidVerificationKey)?;
+            vk_x.copy_from_slice(&sum);
+        }
+
+        let neg_a = negate_g1(&proof.a).ok_or(MixerError::InvalidProofPoint)?;
+
+        let pairing_input = [
+            &neg_a[..],
+            &proof.b[..],
+            &verification_key.alpha_g1[..],
+            &verification_key.beta_g2[..],
+            &vk_x[..],
+            &verification_key.gamma_g2[..],
+            &proof.c[..],
+            &verification_key.delta_g2[..],
+        ]
+        .concat();
+
+        let result = alt_bn128_pairing(&pairing_input)
+            .map_err(|_| MixerError::InvalidProofPoint)?;
+
+        // Output is a 32-byte big-endian 1 (success) or 0
+        Ok(result.last() == Some(&1))
+    }
+}
+
+/// Groth16 verification with a software ark-bn254 pairing
+#[cfg(feature = "native-verify")]
+mod native {
+    use super::*;
+    use ark_bn254::{Bn254, Fr};
+    use ark_ec::pairing::Pairing;
+    use ark_ec::{AffineRepr, CurveGroup};
+    use ark_ff::One;
+
+    use crate::bn254::{fr_from_be_bytes, g1_from_be, g2_from_be};
+
+    pub fn verify(
+        proof: &Groth16Proof,
+        inputs: &[[u8; 32]],
+        verification_key: &VerificationKey,
+    ) -> Result<bool> {
+        let g1 = |point| g1_from_be(point).ok_or(MixerError::InvalidProofPoint);
+        let g2 = |point| g2_from_be(point).ok_or(MixerError::InvalidProofPoint);
+
+        // vk_x = IC[0] + sum(input_i * IC[i + 1])
+        let mut vk_x = g1(&verification_key.ic[0])?.into_group();
+        for (input, ic) in inputs.iter().zip(&verification_key.ic[1..]) {
+            let scalar: Fr = fr_from_be_bytes(input);
+            vk_x += g1(ic)?.mul_bigint(scalar.into_bigint());
+        }
+
+        let result = Bn254::multi_pairing(
+            [
+                -g1(&proof.a)?,
+                g1(&verification_key.alpha_g1)?,
+                vk_x.into_affine(),
+                g1(&proof.c)?,
+            ],
+            [
+                g2(&proof.b)?,
+                g2(&verification_key.beta_g2)?,
+                g2(&verification_key.gamma_g2)?,
+                g2(&verification_key.delta_g2)?,
+            ],
+        );
+
+        Ok(result.0.is_one())
+    }
 }
 
 // Verification key structure
@@ -86,10 +338,59 @@ impl Default for VerificationKey {
 }
 
 impl VerificationKey {
+    /// Check every key component is a valid curve point
+    pub fn is_valid(&self) -> bool {
+        is_on_curve_g1(&self.alpha_g1)
+            && is_on_curve_g2(&self.beta_g2)
+            && is_on_curve_g2(&self.gamma_g2)
+            && is_on_curve_g2(&self.delta_g2)
+            && self.ic.iter().all(is_on_curve_g1)
+    }
+
+    /// SHA256 over all key components in declaration order
+    /// Must match the VK hash published by the trusted setup ceremony
+    pub fn hash(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(self.alpha_g1);
+        hasher.update(self.beta_g2);
+        hasher.update(self.gamma_g2);
+        hasher.update(self.delta_g2);
+        for point in &self.ic {
+            hasher.update(point);
+        }
+        hasher.finalize().into()
+    }
+
     pub const LEN: usize = 8 + // discriminator
         64 + // alpha_g1
         128 + // beta_g2
         128 + // gamma_g2
         128 + // delta_g2
-        4 + (64 * 3); // ic vector (3 public inputs: root, nullifier, constant)
+        4 + (64 * (NUM_PUBLIC_INPUTS + 1)); // ic vector (root, nullifier, deposit timestamp, context, pool, version, relayer, constant)
+
+    /// Size of the association set circuit's key, which has one more IC point
+    pub const ASSOCIATION_LEN: usize = Self::LEN + 64;
+}
+
+// Trusted setup attestation
+#[account]
+pub struct CeremonyAttestation {
+    // Hash of the Powers-of-Tau / phase-2 transcript
+    pub transcript_hash: [u8; 32],
+    // Expected VerificationKey::hash() of the ceremony output
+    pub vk_hash: [u8; 32],
+    // Authority that registered the attestation
+    pub attested_by: Pubkey,
+    // When the attestation was registered
+    pub timestamp: i64,
+    pub bump: u8,
+}
+
+impl CeremonyAttestation {
+    pub const LEN: usize = 8 + // discriminator
+        32 + // transcript_hash
+        32 + // vk_hash
+        32 + // attested_by
+        8 + // timestamp
+        1; // bump
 }
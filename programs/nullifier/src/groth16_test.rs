@@ -1,6 +1,13 @@
 /// Comprehensive tests for Groth16 zkSNARK verification
 use super::groth16::*;
-use anchor_lang::prelude::*;
+use crate::bn254::{fr_from_be_bytes, g1_to_be, g2_to_be};
+use anchor_lang::prelude::Pubkey;
+use ark_bn254::{Fr, G1Affine, G2Affine};
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::Field;
+use anchor_lang::solana_program::alt_bn128::compression::prelude::{
+    alt_bn128_g1_compress, alt_bn128_g2_compress,
+};
 
 #[test]
 fn test_proof_structure_sizes() {
@@ -50,6 +57,12 @@ fn test_public_inputs_structure() {
     let inputs = PublicInputs {
         root: [1u8; 32],
         nullifier_hash: [2u8; 32],
+        deposit_timestamp: 0,
+        context_hash: [0u8; 32],
+        pool: Pubkey::default(),
+        circuit_version: CIRCUIT_VERSION,
+        relayer: Pubkey::default(),
+        association_root: None,
     };
 
     assert_eq!(inputs.root.len(), 32);
@@ -63,6 +76,12 @@ fn test_public_inputs_clone() {
     let inputs1 = PublicInputs {
         root: [42u8; 32],
         nullifier_hash: [84u8; 32],
+        deposit_timestamp: 0,
+        context_hash: [0u8; 32],
+        pool: Pubkey::default(),
+        circuit_version: CIRCUIT_VERSION,
+        relayer: Pubkey::default(),
+        association_root: None,
     };
 
     let inputs2 = inputs1.clone();
@@ -90,10 +109,10 @@ fn test_verification_key_size() {
         128 + // beta_g2
         128 + // gamma_g2
         128 + // delta_g2
-        4 + (64 * 3); // ic vector (3 public inputs)
+        4 + (64 * 8); // ic vector (7 public inputs + constant)
 
     assert_eq!(VerificationKey::LEN, expected_size);
-    assert_eq!(VerificationKey::LEN, 652);
+    assert_eq!(VerificationKey::LEN, 972);
 }
 
 #[test]
@@ -116,48 +135,53 @@ fn test_verification_key_creation() {
 
 #[test]
 fn test_verify_groth16_proof_basic() {
-    let proof = Groth16Proof {
-        a: [0u8; 64],
-        b: [0u8; 128],
-        c: [0u8; 64],
-    };
-
     let public_inputs = PublicInputs {
         root: [1u8; 32],
         nullifier_hash: [2u8; 32],
+        deposit_timestamp: 1_700_000_000,
+        context_hash: [3u8; 32],
+        pool: Pubkey::default(),
+        circuit_version: CIRCUIT_VERSION,
+        relayer: Pubkey::default(),
+        association_root: None,
     };
 
-    let vk = VerificationKey::default();
+    let (proof, vk) = proof_fixture(&public_inputs, 1);
 
-    // Note: Current implementation is a placeholder that returns Ok(true)
     let result = verify_groth16_proof(&proof, &public_inputs, &vk);
     assert!(result.is_ok());
-    assert_eq!(result.unwrap(), true);
+    assert!(result.unwrap());
 }
 
 #[test]
 fn test_verify_groth16_proof_different_inputs() {
-    let proof = Groth16Proof {
-        a: [42u8; 64],
-        b: [84u8; 128],
-        c: [126u8; 64],
-    };
-
     let inputs1 = PublicInputs {
         root: [1u8; 32],
         nullifier_hash: [2u8; 32],
+        deposit_timestamp: 0,
+        context_hash: [0u8; 32],
+        pool: Pubkey::default(),
+        circuit_version: CIRCUIT_VERSION,
+        relayer: Pubkey::default(),
+        association_root: None,
     };
 
     let inputs2 = PublicInputs {
         root: [3u8; 32],
         nullifier_hash: [4u8; 32],
+        deposit_timestamp: 0,
+        context_hash: [0u8; 32],
+        pool: Pubkey::default(),
+        circuit_version: CIRCUIT_VERSION,
+        relayer: Pubkey::default(),
+        association_root: None,
     };
 
-    let vk = VerificationKey::default();
+    let (proof, vk) = proof_fixture(&inputs1, 2);
 
-    // Both should succeed with placeholder implementation
-    assert!(verify_groth16_proof(&proof, &inputs1, &vk).is_ok());
-    assert!(verify_groth16_proof(&proof, &inputs2, &vk).is_ok());
+    // The proof only verifies against the inputs it was generated for
+    assert!(verify_groth16_proof(&proof, &inputs1, &vk).unwrap());
+    assert!(!verify_groth16_proof(&proof, &inputs2, &vk).unwrap());
 }
 
 #[test]
@@ -195,6 +219,12 @@ fn test_public_inputs_field_element_size() {
     let inputs = PublicInputs {
         root: [0u8; 32],
         nullifier_hash: [0u8; 32],
+        deposit_timestamp: 0,
+        context_hash: [0u8; 32],
+        pool: Pubkey::default(),
+        circuit_version: CIRCUIT_VERSION,
+        relayer: Pubkey::default(),
+        association_root: None,
     };
 
     assert_eq!(inputs.root.len(), 32);
@@ -207,16 +237,16 @@ fn test_verification_key_ic_points() {
     // For our circuit: root, nullifier_hash, + constant = 3 points
     let expected_ic_count = 3;
 
-    let mut vk = VerificationKey::default();
-    vk.ic = vec![[0u8; 64]; expected_ic_count];
+    let vk = VerificationKey {
+        ic: vec![[0u8; 64]; expected_ic_count],
+        ..Default::default()
+    };
 
     assert_eq!(vk.ic.len(), expected_ic_count);
 }
 
 #[test]
 fn test_proof_serialization_size() {
-    use std::mem::size_of;
-
     // Total proof size should be 256 bytes (64 + 128 + 64)
     let expected_size = 64 + 128 + 64;
 
@@ -242,6 +272,12 @@ fn test_public_inputs_from_circuit() {
     let inputs = PublicInputs {
         root: merkle_root,
         nullifier_hash: nullifier,
+        deposit_timestamp: 0,
+        context_hash: [0u8; 32],
+        pool: Pubkey::default(),
+        circuit_version: CIRCUIT_VERSION,
+        relayer: Pubkey::default(),
+        association_root: None,
     };
 
     assert_eq!(inputs.root, merkle_root);
@@ -295,3 +331,633 @@ fn test_verification_key_ic_dynamic_size() {
     assert_eq!(vk.ic[1], [2u8; 64]);
     assert_eq!(vk.ic[2], [3u8; 64]);
 }
+
+#[test]
+fn test_public_inputs_count() {
+    // root, nullifier_hash, deposit_timestamp, context_hash, pool, circuit_version, relayer
+    assert_eq!(NUM_PUBLIC_INPUTS, 7);
+}
+
+#[test]
+fn test_public_inputs_to_field_elements() {
+    let inputs = PublicInputs {
+        root: [1u8; 32],
+        nullifier_hash: [2u8; 32],
+        deposit_timestamp: 1_700_000_000,
+        context_hash: [3u8; 32],
+        pool: Pubkey::default(),
+        circuit_version: CIRCUIT_VERSION,
+        relayer: Pubkey::default(),
+        association_root: None,
+    };
+
+    let elements = inputs.to_field_elements();
+
+    assert_eq!(elements.len(), NUM_PUBLIC_INPUTS);
+
+    // Byte-symmetric values below r read the same in either endianness
+    assert_eq!(elements[0], [1u8; 32]);
+    assert_eq!(elements[1], [2u8; 32]);
+    assert_eq!(elements[3], [3u8; 32]);
+
+    // Timestamp is encoded big-endian in the low-order (trailing) bytes
+    assert_eq!(&elements[2][24..], &1_700_000_000i64.to_be_bytes());
+    assert_eq!(&elements[2][..24], &[0u8; 24]);
+
+    // Default pool is zero, version sits in the last byte
+    assert_eq!(elements[4], [0u8; 32]);
+    assert_eq!(elements[5][31], CIRCUIT_VERSION);
+    assert_eq!(&elements[5][..31], &[0u8; 31]);
+}
+
+#[test]
+fn test_public_inputs_pool_and_version_binding() {
+    let inputs = PublicInputs {
+        root: [1u8; 32],
+        nullifier_hash: [2u8; 32],
+        deposit_timestamp: 0,
+        context_hash: [0u8; 32],
+        pool: Pubkey::new_unique(),
+        circuit_version: CIRCUIT_VERSION,
+        relayer: Pubkey::default(),
+        association_root: None,
+    };
+
+    let other_pool = PublicInputs {
+        pool: Pubkey::new_unique(),
+        ..inputs.clone()
+    };
+    let other_version = PublicInputs {
+        circuit_version: CIRCUIT_VERSION + 1,
+        ..inputs.clone()
+    };
+
+    // Same note spent from another pool or circuit must not share public inputs
+    assert_ne!(inputs.to_field_elements(), other_pool.to_field_elements());
+    assert_ne!(inputs.to_field_elements(), other_version.to_field_elements());
+}
+
+#[test]
+fn test_public_inputs_relayer_binding() {
+    let inputs = PublicInputs {
+        root: [1u8; 32],
+        nullifier_hash: [2u8; 32],
+        deposit_timestamp: 0,
+        context_hash: [0u8; 32],
+        pool: Pubkey::new_unique(),
+        circuit_version: CIRCUIT_VERSION,
+        relayer: Pubkey::new_unique(),
+        association_root: None,
+    };
+    let frontrunner = PublicInputs {
+        relayer: Pubkey::new_unique(),
+        ..inputs.clone()
+    };
+
+    // A proof made out to one relayer can't be submitted by another
+    assert_ne!(inputs.to_field_elements(), frontrunner.to_field_elements());
+    assert!(crate::bn254::is_valid_fr(&inputs.to_field_elements()[6]));
+}
+
+#[test]
+fn test_public_inputs_reduced_into_field() {
+    // 0xff..ff is larger than r and must be reduced before verification
+    let inputs = PublicInputs {
+        root: [0xffu8; 32],
+        nullifier_hash: [0xffu8; 32],
+        deposit_timestamp: 0,
+        context_hash: [0u8; 32],
+        pool: Pubkey::default(),
+        circuit_version: CIRCUIT_VERSION,
+        relayer: Pubkey::default(),
+        association_root: None,
+    };
+
+    let elements = inputs.to_field_elements();
+
+    for element in &elements {
+        assert!(crate::bn254::is_valid_fr(element));
+    }
+    assert_ne!(elements[0], [0xffu8; 32]);
+}
+
+#[test]
+fn test_verify_rejects_off_curve_points() {
+    let public_inputs = PublicInputs {
+        root: [1u8; 32],
+        nullifier_hash: [2u8; 32],
+        deposit_timestamp: 0,
+        context_hash: [0u8; 32],
+        pool: Pubkey::default(),
+        circuit_version: CIRCUIT_VERSION,
+        relayer: Pubkey::default(),
+        association_root: None,
+    };
+    let vk = VerificationKey::default();
+
+    let proof = Groth16Proof {
+        a: [1u8; 64],
+        b: g2_generator(),
+        c: g1_generator(),
+    };
+    assert!(verify_groth16_proof(&proof, &public_inputs, &vk).is_err());
+
+    let proof = Groth16Proof {
+        a: g1_generator(),
+        b: [2u8; 128],
+        c: g1_generator(),
+    };
+    assert!(verify_groth16_proof(&proof, &public_inputs, &vk).is_err());
+}
+
+#[test]
+fn test_verification_key_is_valid() {
+    let vk = VerificationKey {
+        alpha_g1: g1_generator(),
+        beta_g2: g2_generator(),
+        gamma_g2: g2_generator(),
+        delta_g2: g2_generator(),
+        ic: vec![g1_generator(); NUM_PUBLIC_INPUTS + 1],
+    };
+    assert!(vk.is_valid());
+
+    let mut invalid = vk.clone();
+    invalid.ic[2] = [7u8; 64];
+    assert!(!invalid.is_valid());
+
+    let mut invalid = vk.clone();
+    invalid.delta_g2 = [7u8; 128];
+    assert!(!invalid.is_valid());
+}
+
+#[test]
+fn test_public_inputs_timestamp_binding() {
+    let inputs1 = PublicInputs {
+        root: [1u8; 32],
+        nullifier_hash: [2u8; 32],
+        deposit_timestamp: 1_700_000_000,
+        context_hash: [0u8; 32],
+        pool: Pubkey::default(),
+        circuit_version: CIRCUIT_VERSION,
+        relayer: Pubkey::default(),
+        association_root: None,
+    };
+
+    let inputs2 = PublicInputs {
+        deposit_timestamp: 1_700_000_060,
+        ..inputs1.clone()
+    };
+
+    // Different timestamp bounds must produce different public inputs
+    assert_ne!(inputs1.to_field_elements(), inputs2.to_field_elements());
+}
+
+#[test]
+fn test_verification_key_hash_deterministic() {
+    let vk = VerificationKey {
+        alpha_g1: [1u8; 64],
+        beta_g2: [2u8; 128],
+        gamma_g2: [3u8; 128],
+        delta_g2: [4u8; 128],
+        ic: vec![[5u8; 64], [6u8; 64], [7u8; 64], [8u8; 64]],
+    };
+
+    assert_eq!(vk.hash(), vk.clone().hash());
+    assert_ne!(vk.hash(), [0u8; 32]);
+}
+
+#[test]
+fn test_verification_key_hash_covers_all_components() {
+    let vk = VerificationKey {
+        alpha_g1: [1u8; 64],
+        beta_g2: [2u8; 128],
+        gamma_g2: [3u8; 128],
+        delta_g2: [4u8; 128],
+        ic: vec![[5u8; 64], [6u8; 64], [7u8; 64], [8u8; 64]],
+    };
+    let original = vk.hash();
+
+    let mut tampered = vk.clone();
+    tampered.alpha_g1[0] ^= 1;
+    assert_ne!(tampered.hash(), original);
+
+    let mut tampered = vk.clone();
+    tampered.delta_g2[127] ^= 1;
+    assert_ne!(tampered.hash(), original);
+
+    let mut tampered = vk.clone();
+    tampered.ic[3][0] ^= 1;
+    assert_ne!(tampered.hash(), original);
+
+    // Dropping an IC point changes the hash too
+    let mut tampered = vk.clone();
+    tampered.ic.pop();
+    assert_ne!(tampered.hash(), original);
+}
+
+#[test]
+fn test_ceremony_attestation_size() {
+    // discriminator (8) + transcript_hash (32) + vk_hash (32) +
+    // attested_by (32) + timestamp (8) + bump (1)
+    assert_eq!(CeremonyAttestation::LEN, 8 + 32 + 32 + 32 + 8 + 1);
+    assert_eq!(CeremonyAttestation::LEN, 113);
+}
+
+#[test]
+fn test_context_hash_binds_pool_recipient_and_nullifier() {
+    let pool = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+    let nullifier_hash = [7u8; 32];
+
+    let hash = compute_context_hash(&pool, &recipient, &nullifier_hash);
+
+    // Deterministic
+    assert_eq!(hash, compute_context_hash(&pool, &recipient, &nullifier_hash));
+
+    // Any change to the context changes the hash
+    assert_ne!(hash, compute_context_hash(&Pubkey::new_unique(), &recipient, &nullifier_hash));
+    assert_ne!(hash, compute_context_hash(&pool, &Pubkey::new_unique(), &nullifier_hash));
+    assert_ne!(hash, compute_context_hash(&pool, &recipient, &[8u8; 32]));
+}
+
+#[test]
+fn test_context_hash_fits_in_field() {
+    for _ in 0..16 {
+        let hash = compute_context_hash(&Pubkey::new_unique(), &Pubkey::new_unique(), &[9u8; 32]);
+
+        // Most significant byte (little-endian) is always cleared
+        assert_eq!(hash[31], 0);
+    }
+}
+
+#[test]
+fn test_is_canonical_g1() {
+    // Point at infinity / zero y is canonical
+    assert!(is_canonical_g1(&[0u8; 64]));
+
+    // Small y is canonical
+    let mut point = [0u8; 64];
+    point[63] = 2;
+    assert!(is_canonical_g1(&point));
+
+    // (q - 1) / 2 is the largest canonical y
+    let half = hex_to_32("183227397098d014dc2822db40c0ac2ecbc0b548b438e5469e10460b6c3e7ea3");
+    point[32..].copy_from_slice(&half);
+    assert!(is_canonical_g1(&point));
+
+    // (q + 1) / 2 is the negated form
+    let mut above_half = half;
+    above_half[31] += 1;
+    point[32..].copy_from_slice(&above_half);
+    assert!(!is_canonical_g1(&point));
+
+    // x coordinate doesn't affect canonicity
+    let mut point = [0xffu8; 64];
+    point[32..].copy_from_slice(&[0u8; 32]);
+    assert!(is_canonical_g1(&point));
+}
+
+#[test]
+fn test_verify_rejects_negated_proof() {
+    // (1, q - 2) = -G, the negation of the canonical generator (1, 2)
+    let mut a = g1_generator();
+    a[32..].copy_from_slice(&hex_to_32("30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd45"));
+
+    let proof = Groth16Proof {
+        a,
+        b: [0u8; 128],
+        c: [0u8; 64],
+    };
+
+    let public_inputs = PublicInputs {
+        root: [1u8; 32],
+        nullifier_hash: [2u8; 32],
+        deposit_timestamp: 0,
+        context_hash: [0u8; 32],
+        pool: Pubkey::default(),
+        circuit_version: CIRCUIT_VERSION,
+        relayer: Pubkey::default(),
+        association_root: None,
+    };
+
+    let vk = VerificationKey::default();
+
+    assert!(verify_groth16_proof(&proof, &public_inputs, &vk).is_err());
+}
+
+fn hex_to_32(hex: &str) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    for (i, byte) in out.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).unwrap();
+    }
+    out
+}
+
+/// BN254 G1 generator (1, 2), big-endian
+fn g1_generator() -> [u8; 64] {
+    let mut point = [0u8; 64];
+    point[31] = 1;
+    point[63] = 2;
+    point
+}
+
+/// BN254 G2 generator in EIP-197 order (x_im, x_re, y_im, y_re), big-endian
+fn g2_generator() -> [u8; 128] {
+    let mut point = [0u8; 128];
+    point[..32].copy_from_slice(&hex_to_32("198e9393920d483a7260bfb731fb5d25f1aa493335a9e71297e485b7aef312c2"));
+    point[32..64].copy_from_slice(&hex_to_32("1800deef121f1e76426a00665e5c4479674322d4f75edadd46debd5cd992f6ed"));
+    point[64..96].copy_from_slice(&hex_to_32("090689d0585ff075ec9e99ad690c3395bc4b313370b38ef355acdadcd122975b"));
+    point[96..].copy_from_slice(&hex_to_32("12c85ea5db8c6deb4aab71808dcb408fe3d1e7690c43d37b4ce6cc0166fa7daa"));
+    point
+}
+
+#[test]
+fn test_compressed_proof_sizes() {
+    let proof = CompressedGroth16Proof {
+        a: [0u8; 32],
+        b: [0u8; 64],
+        c: [0u8; 32],
+    };
+
+    // Half the size of the uncompressed 256-byte proof
+    assert_eq!(proof.a.len() + proof.b.len() + proof.c.len(), 128);
+}
+
+#[test]
+fn test_compressed_proof_roundtrip() {
+    let proof = Groth16Proof {
+        a: g1_generator(),
+        b: g2_generator(),
+        c: g1_generator(),
+    };
+
+    let compressed = CompressedGroth16Proof {
+        a: alt_bn128_g1_compress(&proof.a).unwrap(),
+        b: alt_bn128_g2_compress(&proof.b).unwrap(),
+        c: alt_bn128_g1_compress(&proof.c).unwrap(),
+    };
+
+    let decompressed = compressed.decompress().unwrap();
+
+    assert_eq!(decompressed.a, proof.a);
+    assert_eq!(decompressed.b, proof.b);
+    assert_eq!(decompressed.c, proof.c);
+}
+
+#[test]
+fn test_compressed_proof_invalid_point() {
+    // All 0xff (ignoring the flag bits) is not a valid field element
+    let compressed = CompressedGroth16Proof {
+        a: [0xffu8; 32],
+        b: [0u8; 64],
+        c: [0u8; 32],
+    };
+
+    assert!(compressed.decompress().is_err());
+}
+
+#[test]
+fn test_proof_data_into_proof() {
+    let proof = Groth16Proof {
+        a: g1_generator(),
+        b: g2_generator(),
+        c: g1_generator(),
+    };
+
+    let uncompressed = ProofData::Uncompressed(proof.clone()).into_proof().unwrap();
+    assert_eq!(uncompressed.a, proof.a);
+    assert_eq!(uncompressed.b, proof.b);
+    assert_eq!(uncompressed.c, proof.c);
+
+    let compressed = ProofData::Compressed(CompressedGroth16Proof {
+        a: alt_bn128_g1_compress(&proof.a).unwrap(),
+        b: alt_bn128_g2_compress(&proof.b).unwrap(),
+        c: alt_bn128_g1_compress(&proof.c).unwrap(),
+    })
+    .into_proof()
+    .unwrap();
+    assert_eq!(compressed.a, proof.a);
+    assert_eq!(compressed.b, proof.b);
+    assert_eq!(compressed.c, proof.c);
+}
+
+/// Build a verification key with a known trapdoor and a proof that satisfies
+/// the Groth16 pairing equation for `public_inputs`
+///
+/// With the trapdoor we can pick A = a*G1 and B = b*G2 freely and solve
+/// C = (a*b - alpha*beta - vk_x*gamma) / delta * G1, which yields a real,
+/// verifiable proof without needing a circuit or prover.
+fn proof_fixture(public_inputs: &PublicInputs, seed: u64) -> (Groth16Proof, VerificationKey) {
+    let scalar = |n: u64| Fr::from(seed * 1_000 + n);
+    let g1 = |k: Fr| (G1Affine::generator() * k).into_affine();
+    let g2 = |k: Fr| (G2Affine::generator() * k).into_affine();
+
+    let (alpha, beta, gamma, delta) = (scalar(1), scalar(2), scalar(3), scalar(4));
+    let num_inputs = public_inputs.to_field_elements().len() as u64;
+    let ic: Vec<Fr> = (0..=num_inputs).map(|i| scalar(10 + i)).collect();
+
+    let vk_x = public_inputs
+        .to_field_elements()
+        .iter()
+        .zip(&ic[1..])
+        .fold(ic[0], |acc, (input, u)| acc + fr_from_be_bytes(input) * u);
+
+    let (mut a, mut b) = (scalar(5), scalar(6));
+
+    // Negating both A and B preserves a*b; pick the canonical A
+    if !is_canonical_g1(&g1_to_be(&g1(a))) {
+        a = -a;
+        b = -b;
+    }
+
+    let c = (a * b - alpha * beta - vk_x * gamma) * delta.inverse().unwrap();
+
+    let proof = Groth16Proof {
+        a: g1_to_be(&g1(a)),
+        b: g2_to_be(&g2(b)),
+        c: g1_to_be(&g1(c)),
+    };
+
+    let vk = VerificationKey {
+        alpha_g1: g1_to_be(&g1(alpha)),
+        beta_g2: g2_to_be(&g2(beta)),
+        gamma_g2: g2_to_be(&g2(gamma)),
+        delta_g2: g2_to_be(&g2(delta)),
+        ic: ic.iter().map(|u| g1_to_be(&g1(*u))).collect(),
+    };
+
+    (proof, vk)
+}
+
+fn fixture_inputs() -> PublicInputs {
+    PublicInputs {
+        root: [11u8; 32],
+        nullifier_hash: [22u8; 32],
+        deposit_timestamp: 1_700_000_000,
+        context_hash: compute_context_hash(&Pubkey::new_unique(), &Pubkey::new_unique(), &[22u8; 32]),
+        pool: Pubkey::default(),
+        circuit_version: CIRCUIT_VERSION,
+        relayer: Pubkey::default(),
+        association_root: None,
+    }
+}
+
+#[test]
+fn test_fixture_verification_key_is_valid() {
+    let (_, vk) = proof_fixture(&fixture_inputs(), 3);
+
+    assert!(vk.is_valid());
+    assert_eq!(vk.ic.len(), NUM_PUBLIC_INPUTS + 1);
+}
+
+#[test]
+fn test_verify_real_proof() {
+    let inputs = fixture_inputs();
+    let (proof, vk) = proof_fixture(&inputs, 3);
+
+    assert!(verify_groth16_proof(&proof, &inputs, &vk).unwrap());
+}
+
+#[test]
+fn test_verify_rejects_each_tampered_input() {
+    let inputs = fixture_inputs();
+    let (proof, vk) = proof_fixture(&inputs, 4);
+
+    let mut tampered = inputs.clone();
+    tampered.root[0] ^= 1;
+    assert!(!verify_groth16_proof(&proof, &tampered, &vk).unwrap());
+
+    let mut tampered = inputs.clone();
+    tampered.nullifier_hash[0] ^= 1;
+    assert!(!verify_groth16_proof(&proof, &tampered, &vk).unwrap());
+
+    let mut tampered = inputs.clone();
+    tampered.deposit_timestamp += 1;
+    assert!(!verify_groth16_proof(&proof, &tampered, &vk).unwrap());
+
+    let mut tampered = inputs.clone();
+    tampered.context_hash[0] ^= 1;
+    assert!(!verify_groth16_proof(&proof, &tampered, &vk).unwrap());
+
+    let mut tampered = inputs.clone();
+    tampered.pool = Pubkey::new_unique();
+    assert!(!verify_groth16_proof(&proof, &tampered, &vk).unwrap());
+
+    let mut tampered = inputs.clone();
+    tampered.circuit_version += 1;
+    assert!(!verify_groth16_proof(&proof, &tampered, &vk).unwrap());
+
+    let mut tampered = inputs.clone();
+    tampered.relayer = Pubkey::new_unique();
+    assert!(!verify_groth16_proof(&proof, &tampered, &vk).unwrap());
+}
+
+#[test]
+fn test_verify_rejects_tampered_proof() {
+    let inputs = fixture_inputs();
+    let (proof, vk) = proof_fixture(&inputs, 5);
+
+    // Valid curve point, wrong value
+    let mut tampered = proof.clone();
+    tampered.c = g1_generator();
+    assert!(!verify_groth16_proof(&tampered, &inputs, &vk).unwrap());
+}
+
+#[test]
+fn test_verify_rejects_proof_for_other_key() {
+    let inputs = fixture_inputs();
+    let (proof, _) = proof_fixture(&inputs, 6);
+    let (_, other_vk) = proof_fixture(&inputs, 7);
+
+    assert!(!verify_groth16_proof(&proof, &inputs, &other_vk).unwrap());
+}
+
+#[test]
+fn test_verify_rejects_negated_real_proof() {
+    let inputs = fixture_inputs();
+    let (proof, vk) = proof_fixture(&inputs, 8);
+
+    // (-A, -B, C) satisfies the pairing equation but is not canonical
+    let negated = Groth16Proof {
+        a: crate::bn254::negate_g1(&proof.a).unwrap(),
+        b: g2_to_be(&-crate::bn254::g2_from_be(&proof.b).unwrap()),
+        c: proof.c,
+    };
+    assert!(verify_groth16_proof(&negated, &inputs, &vk).is_err());
+}
+
+#[test]
+fn test_verify_rejects_wrong_ic_length() {
+    let inputs = fixture_inputs();
+    let (proof, mut vk) = proof_fixture(&inputs, 9);
+
+    vk.ic.pop();
+    assert!(verify_groth16_proof(&proof, &inputs, &vk).is_err());
+}
+
+#[test]
+fn test_verify_real_compressed_proof() {
+    let inputs = fixture_inputs();
+    let (proof, vk) = proof_fixture(&inputs, 10);
+
+    let compressed = ProofData::Compressed(CompressedGroth16Proof {
+        a: alt_bn128_g1_compress(&proof.a).unwrap(),
+        b: alt_bn128_g2_compress(&proof.b).unwrap(),
+        c: alt_bn128_g1_compress(&proof.c).unwrap(),
+    });
+
+    let decompressed = compressed.into_proof().unwrap();
+    assert!(verify_groth16_proof(&decompressed, &inputs, &vk).unwrap());
+}
+
+#[test]
+fn test_association_public_inputs() {
+    let inputs = fixture_inputs();
+    let associated = PublicInputs {
+        association_root: Some([33u8; 32]),
+        ..inputs.clone()
+    };
+
+    let elements = associated.to_field_elements();
+    assert_eq!(elements.len(), NUM_ASSOCIATION_PUBLIC_INPUTS);
+    assert_eq!(elements[..NUM_PUBLIC_INPUTS], inputs.to_field_elements()[..]);
+    assert_eq!(elements[NUM_PUBLIC_INPUTS], [33u8; 32]);
+    assert_eq!(
+        VerificationKey::ASSOCIATION_LEN,
+        VerificationKey::LEN + 64
+    );
+}
+
+#[test]
+fn test_verify_real_association_proof() {
+    let inputs = PublicInputs {
+        association_root: Some([33u8; 32]),
+        ..fixture_inputs()
+    };
+    let (proof, vk) = proof_fixture(&inputs, 11);
+
+    assert_eq!(vk.ic.len(), NUM_ASSOCIATION_PUBLIC_INPUTS + 1);
+    assert!(verify_groth16_proof(&proof, &inputs, &vk).unwrap());
+
+    // Another association set's root
+    let mut tampered = inputs.clone();
+    tampered.association_root = Some([34u8; 32]);
+    assert!(!verify_groth16_proof(&proof, &tampered, &vk).unwrap());
+
+    // Dropping the association root leaves the key one input short
+    let mut plain = inputs.clone();
+    plain.association_root = None;
+    assert!(verify_groth16_proof(&proof, &plain, &vk).is_err());
+}
+
+#[test]
+fn test_verify_plain_proof_rejects_association_root() {
+    let inputs = fixture_inputs();
+    let (proof, vk) = proof_fixture(&inputs, 12);
+
+    let associated = PublicInputs {
+        association_root: Some([33u8; 32]),
+        ..inputs
+    };
+    assert!(verify_groth16_proof(&proof, &associated, &vk).is_err());
+}
@@ -1,33 +1,127 @@
 use anchor_lang::prelude::*;
-
-mod merkle;
-mod merkle_poseidon;
-mod groth16;
+use anchor_lang::solana_program::program::set_return_data;
+use anchor_lang::system_program;
+use anchor_lang::Discriminator;
+use anchor_spl::associated_token::{self, AssociatedToken};
+use anchor_spl::token::{spl_token, Token};
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface};
+
+pub mod admin_log;
+pub mod association;
+pub mod bloom;
+pub mod bn254;
+pub mod compression;
+pub mod disclosure;
+pub mod exclusion;
+pub mod merkle;
+pub mod merkle_poseidon;
+pub mod merkle_keccak;
+pub mod mining;
+pub mod relayer;
+pub mod screening;
+pub mod sparse_merkle;
+pub mod groth16;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
+pub mod token_extensions;
 use merkle::*;
+use admin_log::{AdminAction, AdminLog, AdminLogEntry};
+use association::AssociationSet;
+use bloom::BLOOM_FILTER_BYTES;
+use disclosure::{ComplianceReport, DisclosedNote, DisclosureRegistry};
+use exclusion::{
+    ExclusionPublicInputs, InnocenceProof, TaintedSet, EXCLUSION_VERIFICATION_KEY_LEN,
+    NUM_EXCLUSION_PUBLIC_INPUTS,
+};
+use mining::{AnonymityMining, AnonymityPoints, RewardHookPayload};
+use relayer::{
+    PoolRelayers, RelayerFeeSchedule, RelayerInfo, RelayerPolicy, RelayerReport, RelayerStatus,
+    MIN_RELAYER_STAKE, RELAYER_REPORT_BOND,
+};
+use screening::ScreeningPayload;
+use std::cell::RefMut;
+use groth16::{
+    CeremonyAttestation, ProofData, PublicInputs, VerificationKey, CIRCUIT_VERSION,
+    NUM_ASSOCIATION_PUBLIC_INPUTS, NUM_PUBLIC_INPUTS,
+};
 
 // MAINNET-READY: Using SHA256 for commitments (Phase 1)
 // SHA256 is the production standard for privacy mixers (used by Tornado Cash)
 // Poseidon will be used in Phase 2 when ZK-SNARK circuits are integrated
 // This is NOT a workaround - it's the proper engineering approach for phased rollout
 use merkle::compute_commitment as commitment_hash;
-use merkle::verify_merkle_proof as verify_proof;
 
 declare_id!("Hhhwt7AydrCSWE5EN9xTrTkj6JXbot37FzgckJVdam4f");
 
 // Constants
 pub const MIN_TIME_DELAY: i64 = 60; // 1 minute in seconds
+pub const MAX_TIME_DELAY: i64 = 30 * 24 * 60 * 60; // 30 days in seconds
 pub const FEE_BASIS_POINTS: u64 = 10; // 0.1% = 10 basis points
 pub const BASIS_POINTS_DIVISOR: u64 = 10000;
 
+/// Relayer fee cap a new Config starts with (5% of the denomination)
+pub const DEFAULT_MAX_RELAYER_FEE_BPS: u16 = 500;
+
 // Fixed denominations in lamports (1 SOL = 1_000_000_000 lamports)
 pub const DENOMINATION_01_SOL: u64 = 100_000_000; // 0.1 SOL
 pub const DENOMINATION_1_SOL: u64 = 1_000_000_000;
 pub const DENOMINATION_10_SOL: u64 = 10_000_000_000;
 pub const DENOMINATION_100_SOL: u64 = 100_000_000_000;
 
+// Mint recorded for native SOL pools; pools are PDAs of [b"pool", mint, denomination]
+pub const SOL_POOL_MINT: Pubkey = Pubkey::new_from_array([0u8; 32]);
+
+// Smallest token pool size in whole tokens; anything less is dust
+pub const MIN_TOKEN_DENOMINATION_UNITS: u64 = 10;
+
+// Leading digits of a round token pool size (1, 2 or 5 times a power of ten)
+pub const ROUND_DENOMINATION_DIGITS: [u64; 3] = [1, 2, 5];
+
+// An NFT pool holds one token of a 0-decimal, supply-1 mint per note; it is
+// below the token pool dust floor
+pub const NFT_DENOMINATION: u64 = 1;
+
+// Stablecoin presets in base units (USDC / USDT, 6 decimals)
+pub const DENOMINATION_10_USDC: u64 = 10_000_000;
+pub const DENOMINATION_100_USDC: u64 = 100_000_000;
+pub const DENOMINATION_1000_USDC: u64 = 1_000_000_000;
+pub const DENOMINATION_10000_USDC: u64 = 10_000_000_000;
+
 // Maximum nullifiers per registry account (reduced to prevent stack overflow)
 pub const MAX_NULLIFIERS_PER_ACCOUNT: usize = 100;
 
+// Maximum mints on the token pool allowlist
+pub const MAX_ALLOWED_MINTS: usize = 32;
+
+// Maximum attestors that can co-sign a committed root
+pub const MAX_ATTESTORS: usize = 5;
+/// Most members of the authority multisig
+pub const MAX_AUTHORITY_MEMBERS: usize = 5;
+
+// Maximum commitments flagged per pool
+pub const MAX_BLOCKED_COMMITMENTS: usize = 64;
+
+// Time between requesting a commitment's unflag and executing it (7 days)
+pub const COMMITMENT_UNFLAG_DELAY: i64 = 7 * 24 * 60 * 60;
+
+// Time after close_pool before its nullifier accounts can be reclaimed (30 days)
+pub const NULLIFIER_RECLAIM_DELAY: i64 = 30 * 24 * 60 * 60;
+
+/// Layout version of Config written by this program, see migrate_config
+pub const CONFIG_VERSION: u8 = 6;
+
+/// Authority of a renounced Config: the all-zero key, which nobody can sign for
+pub const RENOUNCED_AUTHORITY: Pubkey = Pubkey::new_from_array([0u8; 32]);
+
+/// How long the mixer must stay paused before emergency withdrawals open (90 days)
+pub const EMERGENCY_PAUSE_DELAY: i64 = 90 * 24 * 60 * 60;
+
+/// How long a permissionless pool's creation bond stays locked (30 days)
+pub const POOL_BOND_LOCKUP: i64 = 30 * 24 * 60 * 60;
+
+// Time between scheduling a force close and executing it (7 days)
+pub const FORCE_CLOSE_DELAY: i64 = 7 * 24 * 60 * 60;
+
 #[program]
 pub mod nullifier {
     use super::*;
@@ -39,12 +133,43 @@ pub mod nullifier {
         config.paused = false;
         config.fee_collector = authority;
         config.bump = ctx.bumps.config;
+        config.attestors = [Pubkey::default(); MAX_ATTESTORS];
+        config.attestor_count = 0;
+        config.attestor_threshold = 0;
+        config.ata_reimbursement_bps = 0;
+        config.relayer_rebate_lamports = 0;
+        config.max_relayer_fee_bps = DEFAULT_MAX_RELAYER_FEE_BPS;
+        config.staker_vault = Pubkey::default();
+        config.staker_share_bps = 0;
+        config.reward_hook = Pubkey::default();
+        config.authority_members = [Pubkey::default(); MAX_AUTHORITY_MEMBERS];
+        config.authority_member_count = 0;
+        config.authority_threshold = 0;
+        config.guardian = Pubkey::default();
+        config.deposit_rate_limit = RateLimit::default();
+        config.withdrawal_rate_limit = RateLimit::default();
+        config.version = CONFIG_VERSION;
+        config.upgrade_authority = Pubkey::default();
+        config.last_deploy_slot = 0;
+        config.upgrade_info_recorded_at = 0;
+        config.paused_at = 0;
+        config.emergency_mode = false;
+        config.renounced = false;
+        config.permissionless_pools = false;
+        config.pool_creation_bond = 0;
+        config.screening_program = Pubkey::default();
+
+        let admin_log = &mut ctx.accounts.admin_log;
+        admin_log.count = 0;
+        admin_log.bump = ctx.bumps.admin_log;
 
         msg!("Mixer initialized with authority: {:?}", authority);
         Ok(())
     }
 
     /// Create a new mixing pool with a specific denomination
+    /// Anyone posting a PoolBond may create one while pools are permissionless;
+    /// min_delay is capped at MAX_TIME_DELAY so a creator can't lock deposits in
     pub fn create_pool(
         ctx: Context<CreatePool>,
         denomination: u64,
@@ -52,519 +177,10159 @@ pub mod nullifier {
     ) -> Result<()> {
         // Validate denomination
         require!(
-            denomination == DENOMINATION_01_SOL
-            || denomination == DENOMINATION_1_SOL
-            || denomination == DENOMINATION_10_SOL
-            || denomination == DENOMINATION_100_SOL,
+            is_valid_denomination(denomination),
             MixerError::InvalidDenomination
         );
 
         // Validate minimum delay
         require!(
-            min_delay >= MIN_TIME_DELAY,
+            is_valid_time_delay(min_delay),
             MixerError::InvalidTimeDelay
         );
 
+        // Without a bond this is the authority's pool, so it needs the multisig
+        if ctx.accounts.pool_bond.is_none() {
+            let signers = admin_signers(&ctx.accounts.authority.key(), ctx.remaining_accounts);
+            ctx.accounts.config.check_admin_approval(&signers)?;
+            log_admin_action(
+                &mut ctx.accounts.admin_log,
+                &ctx.accounts.authority,
+                &ctx.accounts.system_program,
+                AdminAction::CreatePool,
+                &(denomination, min_delay),
+            )?;
+        }
+
+        post_pool_bond(
+            &ctx.accounts.config,
+            ctx.accounts.pool.key(),
+            ctx.accounts.authority.key(),
+            ctx.accounts.pool_bond.as_mut(),
+            ctx.bumps.pool_bond,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+        )?;
+
         let pool = &mut ctx.accounts.pool;
-        pool.denomination = denomination;
-        pool.min_delay = min_delay;
-        pool.total_deposits = 0;
-        pool.total_withdrawals = 0;
-        pool.merkle_root = [0u8; 32]; // Not computed on-chain
-        pool.next_leaf_index = 0;
-        pool.creation_timestamp = Clock::get()?.unix_timestamp;
-        pool.bump = ctx.bumps.pool;
+        pool.initialize(
+            SOL_POOL_MINT,
+            denomination,
+            min_delay,
+            Clock::get()?.unix_timestamp,
+            ctx.bumps.pool,
+        );
+
+        let merkle_tree = &mut ctx.accounts.merkle_tree.load_init()?;
+        merkle_tree.initialize(pool.key(), 0, pool.version, pool.tree_hash, ctx.bumps.merkle_tree);
+        pool.merkle_root = merkle_tree.root();
+
+        let fee_vault = &mut ctx.accounts.fee_vault;
+        fee_vault.pool = pool.key();
+        fee_vault.total_fees_collected = 0;
+        fee_vault.total_fees_withdrawn = 0;
+        fee_vault.bump = ctx.bumps.fee_vault;
 
         msg!("Pool created with denomination: {} lamports", denomination);
         Ok(())
     }
 
-    /// Deposit SOL into a mixing pool with a commitment
-    /// commitment = SHA256(secret || nullifier)
-    /// encrypted_data = encrypted note data for cross-device recovery
-    pub fn deposit(ctx: Context<Deposit>, commitment: [u8; 32], encrypted_data: Vec<u8>) -> Result<()> {
-        let config = &ctx.accounts.config;
-        let pool = &mut ctx.accounts.pool;
-        let commitment_record = &mut ctx.accounts.commitment_record;
+    /// Create the fee vault of a SOL pool created before fee vaults existed
+    pub fn initialize_fee_vault(ctx: Context<InitializeFeeVault>) -> Result<()> {
+        let fee_vault = &mut ctx.accounts.fee_vault;
+        fee_vault.pool = ctx.accounts.pool.key();
+        fee_vault.total_fees_collected = 0;
+        fee_vault.total_fees_withdrawn = 0;
+        fee_vault.bump = ctx.bumps.fee_vault;
 
-        // Check if mixer is paused
-        require!(!config.paused, MixerError::MixerPaused);
+        msg!("Fee vault initialized for pool {:?}", fee_vault.pool);
+        Ok(())
+    }
 
-        // Validate commitment is not all zeros
-        require!(
-            commitment != [0u8; 32],
-            MixerError::InvalidCommitment
+    /// Sweep a SOL pool's accrued fees from its fee vault, staker_share_bps
+    /// to the staker vault and the rest to the fee collector (treasury)
+    /// Permissionless, the destinations come from config; the vault keeps its
+    /// rent-exempt minimum
+    /// Never sweeps more than the fees the vault has recorded, so even a
+    /// vault holding stray lamports can't pay out anything but fees
+    pub fn distribute_fees(ctx: Context<DistributeFees>) -> Result<()> {
+        let surplus = {
+            let fee_vault = ctx.accounts.fee_vault.to_account_info();
+            let rent_exempt = Rent::get()?.minimum_balance(fee_vault.data_len());
+            fee_vault.lamports().saturating_sub(rent_exempt)
+        };
+        let fees = surplus.min(ctx.accounts.fee_vault.unswept_fees());
+        ctx.accounts.fee_vault.record_sweep(fees)?;
+
+        let fee_vault = ctx.accounts.fee_vault.to_account_info();
+        let (treasury_share, staker_share) = ctx.accounts.config.split_fees(fees)?;
+
+        **fee_vault.try_borrow_mut_lamports()? -= fees;
+        **ctx.accounts.fee_collector.try_borrow_mut_lamports()? = ctx
+            .accounts
+            .fee_collector
+            .lamports()
+            .checked_add(treasury_share)
+            .ok_or(MixerError::ArithmeticOverflow)?;
+
+        if staker_share > 0 {
+            let staker_vault = ctx
+                .accounts
+                .staker_vault
+                .as_ref()
+                .ok_or(MixerError::StakerVaultMissing)?;
+            **staker_vault.try_borrow_mut_lamports()? = staker_vault
+                .lamports()
+                .checked_add(staker_share)
+                .ok_or(MixerError::ArithmeticOverflow)?;
+        }
+
+        msg!(
+            "Distributed {} lamports of fees from pool {:?}: {} to the treasury, {} to stakers",
+            fees,
+            ctx.accounts.fee_vault.pool,
+            treasury_share,
+            staker_share
         );
+        Ok(())
+    }
 
-        // SECURITY FIX: Validate encrypted data size to prevent DoS
+    /// Create a pool for an SPL Token or Token-2022 mint, holding deposits in
+    /// a pool-owned vault
+    /// Denominations are in the mint's base units
+    /// Bonded creators are held to the same MAX_TIME_DELAY as create_pool
+    pub fn create_token_pool(
+        ctx: Context<CreateTokenPool>,
+        denomination: u64,
+        min_delay: i64,
+    ) -> Result<()> {
+        check_token_denomination(denomination, ctx.accounts.mint.decimals)?;
+
+        // Wrapped SOL deposits and withdrawals go through the SOL pools
         require!(
-            encrypted_data.len() <= 200,
-            MixerError::EncryptedDataTooLarge
+            ctx.accounts.mint.key() != spl_token::native_mint::ID,
+            MixerError::WrappedSolTokenPool
         );
 
-        // Validate we haven't exceeded max deposits
         require!(
-            pool.next_leaf_index < (1 << MERKLE_TREE_DEPTH),
-            MixerError::TreeFull
+            ctx.accounts.allowed_mints.contains(&ctx.accounts.mint.key()),
+            MixerError::MintNotAllowed
         );
 
-        let deposit_amount = pool.denomination;
+        token_extensions::check_mint_extensions(&ctx.accounts.mint.to_account_info())?;
 
-        // Transfer SOL from user to pool
-        let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
-            &ctx.accounts.depositor.key(),
-            &pool.key(),
-            deposit_amount,
+        require!(
+            is_valid_time_delay(min_delay),
+            MixerError::InvalidTimeDelay
         );
 
-        anchor_lang::solana_program::program::invoke(
-            &transfer_ix,
-            &[
-                ctx.accounts.depositor.to_account_info(),
-                pool.to_account_info(),
-                ctx.accounts.system_program.to_account_info(),
-            ],
+        // Without a bond this is the authority's pool, so it needs the multisig
+        if ctx.accounts.pool_bond.is_none() {
+            let signers = admin_signers(&ctx.accounts.authority.key(), ctx.remaining_accounts);
+            ctx.accounts.config.check_admin_approval(&signers)?;
+            log_admin_action(
+                &mut ctx.accounts.admin_log,
+                &ctx.accounts.authority,
+                &ctx.accounts.system_program,
+                AdminAction::CreateTokenPool,
+                &(ctx.accounts.mint.key(), denomination, min_delay),
+            )?;
+        }
+
+        post_pool_bond(
+            &ctx.accounts.config,
+            ctx.accounts.pool.key(),
+            ctx.accounts.authority.key(),
+            ctx.accounts.pool_bond.as_mut(),
+            ctx.bumps.pool_bond,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
         )?;
 
-        // Store commitment record
-        let leaf_index = pool.next_leaf_index;
-        commitment_record.pool = pool.key();
-        commitment_record.commitment = commitment;
-        commitment_record.leaf_index = leaf_index;
-        commitment_record.timestamp = Clock::get()?.unix_timestamp;
-        commitment_record.bump = ctx.bumps.commitment_record;
-
-        // Store encrypted note on-chain for easy recovery across devices
-        let encrypted_note = &mut ctx.accounts.encrypted_note;
-        encrypted_note.owner = ctx.accounts.depositor.key();
-        encrypted_note.encrypted_data = encrypted_data;
-        encrypted_note.pool = pool.key();
-        encrypted_note.leaf_index = leaf_index;
-        encrypted_note.timestamp = Clock::get()?.unix_timestamp;
-        encrypted_note.bump = ctx.bumps.encrypted_note;
+        let pool = &mut ctx.accounts.pool;
+        pool.initialize(
+            ctx.accounts.mint.key(),
+            denomination,
+            min_delay,
+            Clock::get()?.unix_timestamp,
+            ctx.bumps.pool,
+        );
 
-        // Update pool state
-        // Note: We don't compute the Merkle root on-chain to save compute
-        // The frontend computes it from all commitments during withdrawal
-        pool.next_leaf_index += 1;
-        pool.total_deposits += 1;
+        let merkle_tree = &mut ctx.accounts.merkle_tree.load_init()?;
+        merkle_tree.initialize(pool.key(), 0, pool.version, pool.tree_hash, ctx.bumps.merkle_tree);
+        pool.merkle_root = merkle_tree.root();
 
         msg!(
-            "Deposit recorded: {} lamports, commitment: {:?}, leaf_index: {}",
-            deposit_amount,
-            commitment,
-            leaf_index
+            "Token pool created for mint {:?} with denomination: {}",
+            pool.mint,
+            denomination
         );
-
         Ok(())
     }
 
-    /// Withdraw SOL using commitment proof (privacy-preserving)
-    /// User must prove knowledge of secret and nullifier without revealing which deposit
-    pub fn withdraw(
-        ctx: Context<Withdraw>,
-        nullifier: [u8; 32],
-        secret: [u8; 32],
-        merkle_root: [u8; 32],
-        merkle_proof: [[u8; 32]; MERKLE_TREE_DEPTH],
-        path_indices: [bool; MERKLE_TREE_DEPTH],
+    /// Create a pool for a liquid staking token such as mSOL or jitoSOL
+    /// Denominations are the SOL pool sizes expressed in the LST itself, so
+    /// notes keep earning staking yield while in the pool. lamports_per_token
+    /// seeds the exchange rate shown by UIs
+    pub fn create_lst_pool(
+        ctx: Context<CreateLstPool>,
+        denomination: u64,
+        min_delay: i64,
+        lamports_per_token: u64,
     ) -> Result<()> {
-        let config = &ctx.accounts.config;
-        let pool = &mut ctx.accounts.pool;
-        let nullifier_record = &mut ctx.accounts.nullifier_record;
-
-        // Check if mixer is paused
-        require!(!config.paused, MixerError::MixerPaused);
+        let signers = admin_signers(&ctx.accounts.authority.key(), ctx.remaining_accounts);
+        ctx.accounts.config.check_admin_approval(&signers)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::CreateLstPool,
+            &(ctx.accounts.mint.key(), denomination, min_delay, lamports_per_token),
+        )?;
 
-        // Verify nullifier is not all zeros
         require!(
-            nullifier != [0u8; 32],
-            MixerError::InvalidNullifier
+            is_valid_denomination(denomination),
+            MixerError::InvalidDenomination
         );
 
-        // Verify secret is not all zeros
         require!(
-            secret != [0u8; 32],
-            MixerError::InvalidSecret
+            ctx.accounts.allowed_mints.contains(&ctx.accounts.mint.key()),
+            MixerError::MintNotAllowed
         );
 
-        // Check nullifier hasn't been used
+        token_extensions::check_mint_extensions(&ctx.accounts.mint.to_account_info())?;
+
         require!(
-            !nullifier_record.is_used(&nullifier),
-            MixerError::NullifierAlreadyUsed
+            is_valid_time_delay(min_delay),
+            MixerError::InvalidTimeDelay
         );
 
-        // CRITICAL SECURITY FIX: Verify the Merkle proof (Phase 1)
-        // Compute commitment from secret and nullifier using SHA256
-        let commitment = commitment_hash(&secret, &nullifier);
+        let now = Clock::get()?.unix_timestamp;
+        let pool = &mut ctx.accounts.pool;
+        pool.initialize(
+            ctx.accounts.mint.key(),
+            denomination,
+            min_delay,
+            now,
+            ctx.bumps.pool,
+        );
 
-        // Verify the commitment is in the Merkle tree using the provided proof
-        let proof_valid = verify_proof(
-            &commitment,
-            &merkle_proof,
-            &path_indices,
-            &merkle_root
+        let merkle_tree = &mut ctx.accounts.merkle_tree.load_init()?;
+        merkle_tree.initialize(pool.key(), 0, pool.version, pool.tree_hash, ctx.bumps.merkle_tree);
+        pool.merkle_root = merkle_tree.root();
+
+        let lst_info = &mut ctx.accounts.lst_info;
+        lst_info.pool = pool.key();
+        lst_info.bump = ctx.bumps.lst_info;
+        lst_info.set_exchange_rate(lamports_per_token, now)?;
+
+        msg!(
+            "LST pool created for mint {:?} with denomination: {}",
+            pool.mint,
+            denomination
         );
+        Ok(())
+    }
+
+    /// Record the LST's current SOL exchange rate for UIs
+    /// Informational only: deposits and withdrawals move LST amounts
+    pub fn update_lst_exchange_rate(
+        ctx: Context<UpdateLstExchangeRate>,
+        lamports_per_token: u64,
+    ) -> Result<()> {
+        let signers = admin_signers(&ctx.accounts.authority.key(), ctx.remaining_accounts);
+        ctx.accounts.config.check_admin_approval(&signers)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::UpdateLstExchangeRate,
+            &(ctx.accounts.lst_info.pool, lamports_per_token),
+        )?;
+
+        ctx.accounts
+            .lst_info
+            .set_exchange_rate(lamports_per_token, Clock::get()?.unix_timestamp)?;
+
+        msg!("LST exchange rate updated: {} lamports per token", lamports_per_token);
+        Ok(())
+    }
 
-        require!(proof_valid, MixerError::InvalidMerkleProof);
+    /// Create a pool for a single NFT (0 decimals, supply 1)
+    /// The NFT is deposited with deposit_token and withdrawn with withdraw_token
+    /// to any wallet. Programmable NFTs stay frozen in their owner's token
+    /// account and can't be moved by a plain transfer, so they aren't supported
+    pub fn create_nft_pool(ctx: Context<CreateNftPool>, min_delay: i64) -> Result<()> {
+        let signers = admin_signers(&ctx.accounts.authority.key(), ctx.remaining_accounts);
+        ctx.accounts.config.check_admin_approval(&signers)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::CreateNftPool,
+            &(ctx.accounts.mint.key(), min_delay),
+        )?;
 
-        // CRITICAL SECURITY FIX: Verify pool has enough deposits to provide anonymity
-        // Require at least 2 deposits to prevent trivial deanonymization
+        let mint = &ctx.accounts.mint;
         require!(
-            pool.total_deposits >= 2,
-            MixerError::InsufficientAnonymitySet
+            is_nft_mint(mint.decimals, mint.supply),
+            MixerError::NotAnNft
         );
 
-        // CRITICAL SECURITY FIX: Enforce minimum time delay
-        // Check that sufficient time has passed since pool creation
-        // Note: This is a simplified check. In Phase 2 with ZK, we can prove
-        // individual deposit age without revealing which deposit.
-        let current_time = Clock::get()?.unix_timestamp;
-        let pool_age = current_time.checked_sub(pool.creation_timestamp)
-            .ok_or(MixerError::TimeCalculationError)?;
-
         require!(
-            pool_age >= pool.min_delay,
-            MixerError::TimeDelayNotMet
+            ctx.accounts.allowed_mints.contains(&mint.key()),
+            MixerError::MintNotAllowed
         );
 
-        // Calculate withdrawal amount after fee with proper error handling
-        let withdrawal_amount = pool.denomination;
-        let fee_amount = withdrawal_amount
-            .checked_mul(FEE_BASIS_POINTS)
-            .ok_or(MixerError::ArithmeticOverflow)?
-            .checked_div(BASIS_POINTS_DIVISOR)
-            .ok_or(MixerError::ArithmeticOverflow)?;
-        let net_withdrawal = withdrawal_amount
-            .checked_sub(fee_amount)
-            .ok_or(MixerError::ArithmeticOverflow)?;
+        token_extensions::check_mint_extensions(&mint.to_account_info())?;
 
-        // Verify pool has sufficient balance
-        let pool_balance = pool.to_account_info().lamports();
         require!(
-            pool_balance >= withdrawal_amount,
-            MixerError::InsufficientFunds
+            is_valid_time_delay(min_delay),
+            MixerError::InvalidTimeDelay
         );
 
-        // Transfer net amount to recipient (manual lamport transfer for PDA with data)
-        **pool.to_account_info().try_borrow_mut_lamports()? = pool
-            .to_account_info()
-            .lamports()
-            .checked_sub(net_withdrawal)
-            .ok_or(MixerError::InsufficientFunds)?;
+        let pool = &mut ctx.accounts.pool;
+        pool.initialize(
+            mint.key(),
+            NFT_DENOMINATION,
+            min_delay,
+            Clock::get()?.unix_timestamp,
+            ctx.bumps.pool,
+        );
 
-        **ctx.accounts.recipient.to_account_info().try_borrow_mut_lamports()? = ctx
-            .accounts
-            .recipient
-            .to_account_info()
-            .lamports()
-            .checked_add(net_withdrawal)
-            .ok_or(MixerError::ArithmeticOverflow)?;
+        let merkle_tree = &mut ctx.accounts.merkle_tree.load_init()?;
+        merkle_tree.initialize(pool.key(), 0, pool.version, pool.tree_hash, ctx.bumps.merkle_tree);
+        pool.merkle_root = merkle_tree.root();
 
-        // Transfer fee to fee collector
-        **pool.to_account_info().try_borrow_mut_lamports()? = pool
-            .to_account_info()
-            .lamports()
-            .checked_sub(fee_amount)
-            .ok_or(MixerError::InsufficientFunds)?;
+        msg!("NFT pool created for mint {:?}", pool.mint);
+        Ok(())
+    }
 
-        **ctx.accounts.fee_collector.to_account_info().try_borrow_mut_lamports()? = ctx
-            .accounts
-            .fee_collector
-            .to_account_info()
-            .lamports()
-            .checked_add(fee_amount)
-            .ok_or(MixerError::ArithmeticOverflow)?;
+    /// Deposit SOL into a mixing pool with a commitment
+    /// commitment = SHA256(secret || nullifier)
+    /// encrypted_data = encrypted note data for cross-device recovery
+    /// The funder pays the denomination; rent can be sponsored by a separate payer
+    pub fn deposit<'info>(
+        ctx: Context<'_, '_, '_, 'info, Deposit<'info>>,
+        commitment: [u8; 32],
+        encrypted_data: Vec<u8>,
+    ) -> Result<()> {
+        let leaf_index = record_deposit(
+            DepositAccounts {
+                config: &ctx.accounts.config,
+                pool: &mut ctx.accounts.pool,
+                merkle_tree: &ctx.accounts.merkle_tree,
+                poseidon_tree: &ctx.accounts.poseidon_tree,
+                commitment_record: &mut ctx.accounts.commitment_record,
+                commitment_record_bump: ctx.bumps.commitment_record,
+                encrypted_note: &mut ctx.accounts.encrypted_note,
+                encrypted_note_bump: ctx.bumps.encrypted_note,
+                depositor: ctx.accounts.funder.key(),
+            },
+            commitment,
+            encrypted_data,
+        )?;
 
-        // Mark nullifier as used
-        nullifier_record.add_nullifier(nullifier)?;
+        screen_deposit(
+            &ctx.accounts.config,
+            &ctx.accounts.pool,
+            &ctx.accounts.funder.to_account_info(),
+            ctx.accounts.screening_program.as_ref(),
+            ctx.remaining_accounts,
+            ctx.accounts.pool.denomination,
+        )?;
+
+        let deposit_amount = ctx.accounts.pool.denomination;
 
-        // Update pool statistics
-        pool.total_withdrawals += 1;
+        // Transfer SOL from the funder to pool
+        collect_deposit(
+            &ctx.accounts.funder.to_account_info(),
+            &ctx.accounts.pool.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            deposit_amount,
+        )?;
 
         msg!(
-            "Withdrawal completed: {} lamports (fee: {} lamports) to {:?}",
-            net_withdrawal,
-            fee_amount,
-            ctx.accounts.recipient.key()
+            "Deposit recorded: {} lamports, commitment: {:?}, leaf_index: {}",
+            deposit_amount,
+            commitment,
+            leaf_index
         );
 
         Ok(())
     }
 
-    /// Initialize nullifier registry for a pool
-    pub fn initialize_nullifier_registry(ctx: Context<InitializeNullifierRegistry>) -> Result<()> {
-        let registry = &mut ctx.accounts.nullifier_registry;
-        registry.pool = ctx.accounts.pool.key();
-        registry.bump = ctx.bumps.nullifier_registry;
-        registry.nullifiers = Vec::new();
-
-        msg!("Nullifier registry initialized for pool: {:?}", registry.pool);
-        Ok(())
-    }
+    /// Deposit a token pool's denomination from the depositor's token account
+    /// Same commitment and encrypted note handling as deposit
+    pub fn deposit_token<'info>(
+        ctx: Context<'_, '_, '_, 'info, DepositToken<'info>>,
+        commitment: [u8; 32],
+        encrypted_data: Vec<u8>,
+    ) -> Result<()> {
+        let leaf_index = record_deposit(
+            DepositAccounts {
+                config: &ctx.accounts.config,
+                pool: &mut ctx.accounts.pool,
+                merkle_tree: &ctx.accounts.merkle_tree,
+                poseidon_tree: &ctx.accounts.poseidon_tree,
+                commitment_record: &mut ctx.accounts.commitment_record,
+                commitment_record_bump: ctx.bumps.commitment_record,
+                encrypted_note: &mut ctx.accounts.encrypted_note,
+                encrypted_note_bump: ctx.bumps.encrypted_note,
+                depositor: ctx.accounts.depositor.key(),
+            },
+            commitment,
+            encrypted_data,
+        )?;
 
-    /// Pause the mixer (emergency function)
-    pub fn pause(ctx: Context<AdminControl>) -> Result<()> {
-        let config = &mut ctx.accounts.config;
-        config.paused = true;
+        screen_deposit(
+            &ctx.accounts.config,
+            &ctx.accounts.pool,
+            &ctx.accounts.depositor.to_account_info(),
+            ctx.accounts.screening_program.as_ref(),
+            ctx.remaining_accounts,
+            ctx.accounts.pool.denomination,
+        )?;
 
-        msg!("Mixer paused by authority");
-        Ok(())
-    }
+        let deposit_amount = ctx.accounts.pool.denomination;
+
+        // Gross up for a Token-2022 transfer fee so the vault still receives
+        // exactly one denomination per note
+        let mint = ctx.accounts.mint.to_account_info();
+        let fee = token_extensions::transfer_fee(&mint, Clock::get()?.epoch)?;
+        let gross_amount = token_extensions::gross_up(fee.as_ref(), deposit_amount)?;
+        let vault_before = ctx.accounts.vault.amount;
+
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token_interface::TransferChecked {
+                    from: ctx.accounts.depositor_token.to_account_info(),
+                    mint,
+                    to: ctx.accounts.vault.to_account_info(),
+                    authority: ctx.accounts.depositor.to_account_info(),
+                },
+            ),
+            gross_amount,
+            ctx.accounts.mint.decimals,
+        )?;
 
-    /// Unpause the mixer
-    pub fn unpause(ctx: Context<AdminControl>) -> Result<()> {
-        let config = &mut ctx.accounts.config;
-        config.paused = false;
+        ctx.accounts.vault.reload()?;
+        require!(
+            ctx.accounts.vault.amount.saturating_sub(vault_before) >= deposit_amount,
+            MixerError::DepositAmountMismatch
+        );
+        check_vault_backing(&ctx.accounts.pool, ctx.accounts.vault.amount)?;
 
-        msg!("Mixer unpaused by authority");
+        msg!(
+            "Token deposit recorded: {} (sent {}), commitment: {:?}, leaf_index: {}",
+            deposit_amount,
+            gross_amount,
+            commitment,
+            leaf_index
+        );
         Ok(())
     }
 
-    /// Update the authority (multi-sig functionality)
-    pub fn update_authority(
-        ctx: Context<AdminControl>,
-        new_authority: Pubkey,
+    /// Deposit into a SOL pool from a wrapped SOL token account
+    /// The wSOL is unwrapped into the pool, so the note is an ordinary SOL note
+    pub fn deposit_wsol<'info>(
+        ctx: Context<'_, '_, '_, 'info, DepositWsol<'info>>,
+        commitment: [u8; 32],
+        encrypted_data: Vec<u8>,
     ) -> Result<()> {
-        let config = &mut ctx.accounts.config;
-        config.authority = new_authority;
+        let leaf_index = record_deposit(
+            DepositAccounts {
+                config: &ctx.accounts.config,
+                pool: &mut ctx.accounts.pool,
+                merkle_tree: &ctx.accounts.merkle_tree,
+                poseidon_tree: &ctx.accounts.poseidon_tree,
+                commitment_record: &mut ctx.accounts.commitment_record,
+                commitment_record_bump: ctx.bumps.commitment_record,
+                encrypted_note: &mut ctx.accounts.encrypted_note,
+                encrypted_note_bump: ctx.bumps.encrypted_note,
+                depositor: ctx.accounts.depositor.key(),
+            },
+            commitment,
+            encrypted_data,
+        )?;
 
-        msg!("Authority updated to: {:?}", new_authority);
-        Ok(())
-    }
+        screen_deposit(
+            &ctx.accounts.config,
+            &ctx.accounts.pool,
+            &ctx.accounts.depositor.to_account_info(),
+            ctx.accounts.screening_program.as_ref(),
+            ctx.remaining_accounts,
+            ctx.accounts.pool.denomination,
+        )?;
 
-    /// Update the fee collector address
-    pub fn update_fee_collector(
-        ctx: Context<AdminControl>,
-        new_fee_collector: Pubkey,
-    ) -> Result<()> {
-        let config = &mut ctx.accounts.config;
-        config.fee_collector = new_fee_collector;
+        let deposit_amount = ctx.accounts.pool.denomination;
+
+        unwrap_into_pool(ctx.accounts, deposit_amount)?;
+
+        msg!(
+            "Wrapped SOL deposit recorded: {} lamports, commitment: {:?}, leaf_index: {}",
+            deposit_amount,
+            commitment,
+            leaf_index
+        );
 
-        msg!("Fee collector updated to: {:?}", new_fee_collector);
         Ok(())
     }
 
-    /// Close a pool account and return lamports to authority
-    /// SECURITY: Can only close if all deposits have been withdrawn
-    pub fn close_pool(ctx: Context<ClosePool>) -> Result<()> {
-        let pool = &ctx.accounts.pool;
+    /// Attach an spl-account-compression tree to a pool for deposit_compressed
+    /// The tree account must already be allocated (zeroed) and owned by the
+    /// compression program; the pool PDA becomes its authority
+    pub fn init_compressed_tree(
+        ctx: Context<InitCompressedTree>,
+        max_depth: u32,
+        max_buffer_size: u32,
+    ) -> Result<()> {
+        let signers = admin_signers(&ctx.accounts.authority.key(), ctx.remaining_accounts);
+        ctx.accounts.config.check_admin_approval(&signers)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::InitCompressedTree,
+            &(ctx.accounts.pool.key(), max_depth, max_buffer_size),
+        )?;
+
+        let pool = &mut ctx.accounts.pool;
 
-        // CRITICAL SECURITY FIX: Prevent closing pools with outstanding deposits
-        // Only allow closure if all deposits have been withdrawn
         require!(
-            pool.total_deposits == pool.total_withdrawals,
-            MixerError::PoolHasOutstandingDeposits
+            pool.compressed_tree == Pubkey::default(),
+            MixerError::CompressedTreeAlreadyInitialized
         );
 
-        let pool_lamports = pool.to_account_info().lamports();
+        // Leaf indices must line up with the on-chain MerkleTreeState
+        require!(
+            max_depth as usize == MERKLE_TREE_DEPTH,
+            MixerError::InvalidCompressedTreeDepth
+        );
 
-        msg!("Closing empty pool with {} lamports rent", pool_lamports);
+        let denomination = pool.denomination.to_le_bytes();
+        let signer_seeds: &[&[u8]] = &[b"pool", pool.mint.as_ref(), denomination.as_ref(), &[pool.bump]];
+
+        compression::init_empty_merkle_tree(
+            &ctx.accounts.compression_program,
+            &ctx.accounts.compressed_tree,
+            &pool.to_account_info(),
+            &ctx.accounts.noop_program,
+            signer_seeds,
+            max_depth,
+            max_buffer_size,
+        )?;
 
-        // Transfer remaining rent lamports to authority
-        **pool.to_account_info().try_borrow_mut_lamports()? = 0;
-        **ctx.accounts.authority.try_borrow_mut_lamports()? += pool_lamports;
+        pool.compressed_tree = ctx.accounts.compressed_tree.key();
 
+        msg!("Compressed tree initialized: {:?}", pool.compressed_tree);
         Ok(())
     }
 
-    /// Force close any account owned by this program (for migration purposes)
-    pub fn force_close_account(ctx: Context<ForceCloseAccount>) -> Result<()> {
-        let account_to_close = &ctx.accounts.account_to_close;
-        let account_lamports = account_to_close.lamports();
+    /// Deposit SOL and append the commitment to the pool's compressed tree
+    /// No per-deposit accounts are created: the commitment and encrypted note
+    /// are emitted in a CompressedDeposit event for wallets and indexers
+    pub fn deposit_compressed<'info>(
+        ctx: Context<'_, '_, '_, 'info, DepositCompressed<'info>>,
+        commitment: [u8; 32],
+        encrypted_data: Vec<u8>,
+    ) -> Result<()> {
+        let config = &ctx.accounts.config;
+        let pool = &mut ctx.accounts.pool;
 
-        msg!("Force closing account with {} lamports", account_lamports);
+        // Check if mixer is paused
+        require!(!config.paused, MixerError::MixerPaused);
 
-        // Transfer all lamports to authority
-        **account_to_close.try_borrow_mut_lamports()? = 0;
-        **ctx.accounts.authority.try_borrow_mut_lamports()? += account_lamports;
+        require!(
+            pool.compressed_tree != Pubkey::default(),
+            MixerError::CompressedTreeNotInitialized
+        );
 
-        Ok(())
-    }
-}
+        // Validate commitment is not all zeros
+        require!(
+            commitment != [0u8; 32],
+            MixerError::InvalidCommitment
+        );
 
-// Account Structures
+        // SECURITY FIX: Validate encrypted data size to prevent DoS
+        require!(
+            encrypted_data.len() <= 200,
+            MixerError::EncryptedDataTooLarge
+        );
 
-#[account]
-pub struct Config {
-    pub authority: Pubkey,          // 32
-    pub fee_collector: Pubkey,      // 32
-    pub paused: bool,               // 1
-    pub bump: u8,                   // 1
-}
+        screen_deposit(
+            config,
+            pool,
+            &ctx.accounts.depositor.to_account_info(),
+            ctx.accounts.screening_program.as_ref(),
+            ctx.remaining_accounts,
+            pool.denomination,
+        )?;
 
-impl Config {
-    pub const LEN: usize = 8 + 32 + 32 + 1 + 1;
-}
+        let deposit_amount = pool.denomination;
 
+        // Transfer SOL from user to pool
+        collect_deposit(
+            &ctx.accounts.depositor.to_account_info(),
+            &pool.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            deposit_amount,
+        )?;
+
+        // Append to the compressed tree, signing as the pool
+        let denomination = pool.denomination.to_le_bytes();
+        let signer_seeds: &[&[u8]] = &[b"pool", pool.mint.as_ref(), denomination.as_ref(), &[pool.bump]];
+
+        compression::append(
+            &ctx.accounts.compression_program,
+            &ctx.accounts.compressed_tree,
+            &pool.to_account_info(),
+            &ctx.accounts.noop_program,
+            signer_seeds,
+            &commitment,
+        )?;
+
+        // Withdrawals still check roots against the on-chain tree
+        let merkle_tree = &mut ctx.accounts.merkle_tree.load_mut()?;
+        merkle_tree.insert(commitment)?;
+        pool.merkle_root = merkle_tree.root();
+
+        insert_into_poseidon_tree(
+            pool,
+            &ctx.accounts.poseidon_tree,
+            &[commitment],
+            Clock::get()?.unix_timestamp,
+        )?;
+
+        // Update pool state
+        let leaf_index = pool.next_leaf_index;
+        pool.deposit_counter.record(
+            &ctx.accounts.config.deposit_rate_limit,
+            1,
+            Clock::get()?.unix_timestamp,
+        )?;
+        pool.next_leaf_index += 1;
+        pool.total_deposits += 1;
+
+        emit!(CompressedDeposit {
+            pool: pool.key(),
+            commitment,
+            leaf_index,
+            encrypted_data,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!(
+            "Compressed deposit recorded: {} lamports, commitment: {:?}, leaf_index: {}",
+            deposit_amount,
+            commitment,
+            leaf_index
+        );
+
+        Ok(())
+    }
+
+    /// Deposit up to MAX_BATCH_LEAVES notes at once with a single tree update
+    /// For bulk depositors; no per-note accounts are created, the commitments
+    /// are emitted in a LeavesInserted event
+    pub fn insert_leaves<'info>(
+        ctx: Context<'_, '_, '_, 'info, InsertLeaves<'info>>,
+        commitments: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let config = &ctx.accounts.config;
+        let pool = &mut ctx.accounts.pool;
+
+        // Check if mixer is paused
+        require!(!config.paused, MixerError::MixerPaused);
+
+        require!(
+            !commitments.is_empty() && commitments.len() <= MAX_BATCH_LEAVES,
+            MixerError::InvalidBatchSize
+        );
+
+        // Validate commitments are not all zeros
+        require!(
+            commitments.iter().all(|commitment| *commitment != [0u8; 32]),
+            MixerError::InvalidCommitment
+        );
+
+        let deposit_amount = pool
+            .denomination
+            .checked_mul(commitments.len() as u64)
+            .ok_or(MixerError::ArithmeticOverflow)?;
+
+        // The batch is screened once, for the total it deposits
+        screen_deposit(
+            config,
+            pool,
+            &ctx.accounts.depositor.to_account_info(),
+            ctx.accounts.screening_program.as_ref(),
+            ctx.remaining_accounts,
+            deposit_amount,
+        )?;
+
+        // Transfer SOL for every note from user to pool
+        collect_deposit(
+            &ctx.accounts.depositor.to_account_info(),
+            &pool.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            deposit_amount,
+        )?;
+
+        let merkle_tree = &mut ctx.accounts.merkle_tree.load_mut()?;
+        merkle_tree.insert_batch(&commitments)?;
+        pool.merkle_root = merkle_tree.root();
+
+        insert_into_poseidon_tree(
+            pool,
+            &ctx.accounts.poseidon_tree,
+            &commitments,
+            Clock::get()?.unix_timestamp,
+        )?;
+
+        // Update pool state
+        let start_index = pool.next_leaf_index;
+        pool.deposit_counter.record(
+            &config.deposit_rate_limit,
+            commitments.len() as u32,
+            Clock::get()?.unix_timestamp,
+        )?;
+        pool.next_leaf_index += commitments.len() as u32;
+        pool.total_deposits += commitments.len() as u32;
+
+        msg!(
+            "Batch deposit recorded: {} notes, {} lamports, start leaf_index: {}",
+            commitments.len(),
+            deposit_amount,
+            start_index
+        );
+
+        emit!(LeavesInserted {
+            pool: pool.key(),
+            start_index,
+            commitments,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Open the next tree generation once the current one is full
+    /// Permissionless: anyone can pay for the new tree. Earlier generations
+    /// are kept, so notes in them stay withdrawable against their roots.
+    /// The pool's compressed tree (if any) is not rolled over; a dual-tree
+    /// pool must pass new_poseidon_tree so its Poseidon tree rolls over too.
+    pub fn rollover_tree(ctx: Context<RolloverTree>) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+
+        let current_tree = ctx.accounts.current_tree.load()?;
+        require!(current_tree.is_full(), MixerError::TreeNotFull);
+        require!(!current_tree.is_frozen(), MixerError::TreeFrozen);
+
+        let generation = pool
+            .tree_generation
+            .checked_add(1)
+            .ok_or(MixerError::ArithmeticOverflow)?;
+
+        let new_tree = &mut ctx.accounts.new_tree.load_init()?;
+        new_tree.initialize(pool.key(), generation, pool.version, pool.tree_hash, ctx.bumps.new_tree);
+
+        // Dual-tree pools roll their Poseidon tree over in step
+        if pool.dual_tree {
+            let new_poseidon_tree = ctx
+                .accounts
+                .new_poseidon_tree
+                .as_ref()
+                .ok_or(MixerError::PoseidonTreeMissing)?;
+            let bump = ctx.bumps.new_poseidon_tree.ok_or(MixerError::PoseidonTreeMissing)?;
+            new_poseidon_tree.load_init()?.initialize(
+                pool.key(),
+                generation,
+                pool.version,
+                TREE_HASH_POSEIDON,
+                bump,
+            );
+        }
+
+        pool.tree_generation = generation;
+        pool.merkle_root = new_tree.root();
+
+        emit!(TreeRolledOver {
+            pool: pool.key(),
+            generation,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("Tree rolled over to generation {}", generation);
+        Ok(())
+    }
+
+    /// Record the root of a completed 2^CHECKPOINT_LEVEL-leaf subtree
+    /// Permissionless: the root is read from the canopy, so checkpoints can be
+    /// written at any time after the subtree fills, and wallets sync from
+    /// them instead of replaying every deposit
+    pub fn write_checkpoint(ctx: Context<WriteCheckpoint>, subtree_index: u32) -> Result<()> {
+        let merkle_tree = ctx.accounts.merkle_tree.load()?;
+        let subtree_root = merkle_tree.subtree_root(subtree_index)?;
+
+        // Leaf range in the pool's global leaf numbering
+        let start_leaf = merkle_tree
+            .generation
+            .checked_mul(1 << MERKLE_TREE_DEPTH)
+            .and_then(|base| base.checked_add(subtree_index << CHECKPOINT_LEVEL))
+            .ok_or(MixerError::ArithmeticOverflow)?;
+        let end_leaf = start_leaf
+            .checked_add(1 << CHECKPOINT_LEVEL)
+            .ok_or(MixerError::ArithmeticOverflow)?;
+
+        let checkpoint = &mut ctx.accounts.checkpoint;
+        checkpoint.pool = ctx.accounts.pool.key();
+        checkpoint.generation = merkle_tree.generation;
+        checkpoint.subtree_index = subtree_index;
+        checkpoint.start_leaf = start_leaf;
+        checkpoint.end_leaf = end_leaf;
+        checkpoint.subtree_root = subtree_root;
+        checkpoint.timestamp = Clock::get()?.unix_timestamp;
+        checkpoint.bump = ctx.bumps.checkpoint;
+
+        msg!("Checkpoint written for leaves {}..{}", start_leaf, end_leaf);
+        Ok(())
+    }
+
+    /// Open an audit of a tree generation, see audit_tree
+    /// Permissionless: the payer funds the cursor account
+    pub fn start_audit(ctx: Context<StartAudit>) -> Result<()> {
+        let merkle_tree = ctx.accounts.merkle_tree.load()?;
+
+        ctx.accounts.audit_cursor.initialize(
+            ctx.accounts.pool.key(),
+            merkle_tree.generation,
+            merkle_tree.hash,
+            merkle_tree.version,
+            ctx.bumps.audit_cursor,
+        );
+
+        msg!("Audit started for tree generation {}", merkle_tree.generation);
+        Ok(())
+    }
+
+    /// Replay the next CommitmentRecords into the audit cursor
+    /// Records are passed as remaining accounts in leaf order, starting at the
+    /// cursor position; call repeatedly until the cursor catches up with the
+    /// tree. The final call compares the replayed root with the tree's root
+    /// (and pool.merkle_root for the current generation) and emits TreeAudited.
+    /// Leaves without a record (insert_leaves, deposit_compressed) can't be
+    /// replayed, so the audit stops at the first of them.
+    pub fn audit_tree(ctx: Context<AuditTree>) -> Result<()> {
+        let pool = &ctx.accounts.pool;
+        let merkle_tree = ctx.accounts.merkle_tree.load()?;
+        let cursor = &mut ctx.accounts.audit_cursor;
+
+        for account in ctx.remaining_accounts {
+            require!(
+                cursor.next_index < merkle_tree.next_index,
+                MixerError::AuditRecordMismatch
+            );
+            require_keys_eq!(*account.owner, crate::ID, MixerError::AuditRecordMismatch);
+
+            let record = CommitmentRecord::try_deserialize(&mut &account.try_borrow_data()?[..])?;
+            cursor.replay(&record, merkle_tree.hash, merkle_tree.version)?;
+        }
+
+        if cursor.next_index < merkle_tree.next_index {
+            msg!("Audited {} of {} leaves", cursor.next_index, merkle_tree.next_index);
+            return Ok(());
+        }
+
+        let valid = cursor.root == merkle_tree.root()
+            && (merkle_tree.generation != pool.tree_generation || cursor.root == pool.merkle_root);
+
+        emit!(TreeAudited {
+            pool: pool.key(),
+            generation: merkle_tree.generation,
+            leaf_count: cursor.next_index,
+            root: cursor.root,
+            valid,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("Audit of {} leaves complete, root valid: {}", cursor.next_index, valid);
+        Ok(())
+    }
+
+    /// Return the on-chain part of a leaf's sibling path as a Borsh-encoded
+    /// MerklePath through set_return_data, see MerkleTreeState::known_path
+    /// leaf_index is the position within the given tree generation
+    pub fn get_merkle_path(ctx: Context<GetMerklePath>, leaf_index: u32) -> Result<()> {
+        let merkle_tree = ctx.accounts.merkle_tree.load()?;
+        let path = merkle_tree.known_path(leaf_index)?;
+
+        set_return_data(&path.try_to_vec()?);
+        Ok(())
+    }
+
+    /// Return whether a nullifier (hash) has been spent in the pool as a
+    /// Borsh-encoded bool through set_return_data
+    /// Checks the SpentNullifier PDA and the pool's pre-PDA registry
+    pub fn check_nullifier(ctx: Context<CheckNullifier>, nullifier_hash: [u8; 32]) -> Result<()> {
+        let spent = !ctx.accounts.spent_nullifier.data_is_empty()
+            || NullifierRegistry::is_used_in(&ctx.accounts.nullifier_registry, &nullifier_hash)?;
+
+        set_return_data(&spent.try_to_vec()?);
+        Ok(())
+    }
+
+    /// Register the hash of a viewing key for the note at `leaf_index`, so
+    /// its owner can later disclose it with disclose_note. Sent by the
+    /// depositor alongside the deposit
+    pub fn register_viewing_key(
+        ctx: Context<RegisterViewingKey>,
+        leaf_index: u32,
+        viewing_key_hash: [u8; 32],
+    ) -> Result<()> {
+        require!(
+            viewing_key_hash != [0u8; 32],
+            MixerError::InvalidViewingKey
+        );
+
+        let viewing_key = &mut ctx.accounts.viewing_key;
+        viewing_key.pool = ctx.accounts.pool.key();
+        viewing_key.leaf_index = leaf_index;
+        viewing_key.owner = ctx.accounts.owner.key();
+        viewing_key.viewing_key_hash = viewing_key_hash;
+        viewing_key.bump = ctx.bumps.viewing_key;
+
+        msg!("Viewing key registered for leaf {}", leaf_index);
+        Ok(())
+    }
+
+    /// Show that the withdrawal which created `spent_nullifier` spent the
+    /// deposit at `leaf_index`, e.g. to give an exchange the source of funds
+    /// Opens the note with its nullifier and secret, which exposes nothing
+    /// once it is spent, and is authorized by the note owner's signature or
+    /// the viewing key registered for it. Returns a Borsh-encoded
+    /// NoteDisclosure through set_return_data; simulating the call keeps
+    /// the disclosure off chain, sending it publishes it as NoteDisclosed
+    pub fn disclose_note(
+        ctx: Context<DiscloseNote>,
+        leaf_index: u32,
+        nullifier: [u8; 32],
+        secret: [u8; 32],
+        viewing_key: Option<[u8; 32]>,
+    ) -> Result<()> {
+        let registered = &ctx.accounts.viewing_key;
+        let signed_by_owner = ctx
+            .accounts
+            .owner
+            .as_ref()
+            .is_some_and(|owner| owner.key() == registered.owner);
+        let opened_with_key = viewing_key
+            .is_some_and(|key| ViewingKey::hash(&key) == registered.viewing_key_hash);
+        require!(
+            signed_by_owner || opened_with_key,
+            MixerError::DisclosureNotAuthorized
+        );
+
+        let pool = ctx.accounts.pool.key();
+        let record = &ctx.accounts.commitment_record;
+        require!(
+            note_opens(&record.commitment, &nullifier, &secret),
+            MixerError::InvalidCommitment
+        );
+
+        let spent = &ctx.accounts.spent_nullifier;
+        require!(
+            note_spent_as(&pool, &nullifier, &spent.nullifier_hash),
+            MixerError::WithdrawalNotOfNote
+        );
+
+        let disclosure = NoteDisclosure {
+            pool,
+            leaf_index,
+            commitment: record.commitment,
+            depositor: registered.owner,
+            deposited_at: record.timestamp,
+            spent_nullifier: spent.key(),
+            withdrawn_at: spent.spent_at,
+        };
+        emit!(NoteDisclosed {
+            disclosure: disclosure.clone(),
+        });
+
+        set_return_data(&disclosure.try_to_vec()?);
+        Ok(())
+    }
+
+    /// Publish a withdrawn note's contents as a DisclosedNote, a voluntary
+    /// record for tax or audit purposes, see disclosure
+    /// Opens the note with its nullifier and secret and checks the
+    /// withdrawal spent it. Signed by the withdrawal's recipient, whose
+    /// DisclosureRegistry counts it
+    pub fn publish_note_disclosure(
+        ctx: Context<PublishNoteDisclosure>,
+        leaf_index: u32,
+        nullifier: [u8; 32],
+        secret: [u8; 32],
+    ) -> Result<()> {
+        let pool = &ctx.accounts.pool;
+        let record = &ctx.accounts.commitment_record;
+        require!(
+            note_opens(&record.commitment, &nullifier, &secret),
+            MixerError::InvalidCommitment
+        );
+
+        let spent = &ctx.accounts.spent_nullifier;
+        require!(
+            note_spent_as(&pool.key(), &nullifier, &spent.nullifier_hash),
+            MixerError::WithdrawalNotOfNote
+        );
+
+        let recipient = ctx.accounts.recipient.key();
+        let registry = &mut ctx.accounts.disclosure_registry;
+        registry.recipient = recipient;
+        registry.bump = ctx.bumps.disclosure_registry;
+        registry.record()?;
+
+        let current_time = Clock::get()?.unix_timestamp;
+        let disclosed_note = &mut ctx.accounts.disclosed_note;
+        disclosed_note.pool = pool.key();
+        disclosed_note.mint = pool.mint;
+        disclosed_note.amount = pool.denomination;
+        disclosed_note.leaf_index = leaf_index;
+        disclosed_note.commitment = record.commitment;
+        disclosed_note.nullifier_hash = spent.nullifier_hash;
+        disclosed_note.deposited_at = record.timestamp;
+        disclosed_note.withdrawn_at = spent.spent_at;
+        disclosed_note.recipient = recipient;
+        disclosed_note.published_at = current_time;
+        disclosed_note.bump = ctx.bumps.disclosed_note;
+
+        emit!(NoteDisclosurePublished {
+            disclosed_note: disclosed_note.key(),
+            pool: pool.key(),
+            leaf_index,
+            recipient,
+            timestamp: current_time,
+        });
+
+        msg!("Note at leaf {} disclosed by {:?}", leaf_index, recipient);
+        Ok(())
+    }
+
+    /// Return a Borsh-encoded ComplianceReport of the DisclosedNotes passed
+    /// as remaining accounts through set_return_data
+    /// Every note must be disclosed to the registry's recipient; at most
+    /// MAX_REPORT_WITHDRAWALS fit in one report
+    pub fn get_compliance_report<'info>(
+        ctx: Context<'_, '_, 'info, 'info, GetComplianceReport<'info>>,
+    ) -> Result<()> {
+        let notes = ctx
+            .remaining_accounts
+            .iter()
+            .map(|account| Ok(Account::<DisclosedNote>::try_from(account)?.into_inner()))
+            .collect::<Result<Vec<_>>>()?;
+
+        let report = ComplianceReport::build(
+            &ctx.accounts.disclosure_registry,
+            &notes,
+            Clock::get()?.unix_timestamp,
+        )?;
+
+        set_return_data(&report.try_to_vec()?);
+        Ok(())
+    }
+
+    /// Prove a ZK withdrawal's deposit is not in the pool's tainted set,
+    /// without revealing which deposit it was
+    /// Records an InnocenceProof for the withdrawal against the current
+    /// tainted root; proving again after the set changes refreshes it
+    pub fn prove_innocence(
+        ctx: Context<ProveInnocence>,
+        proof: ProofData,
+        merkle_root: [u8; 32],
+        nullifier_hash: [u8; 32],
+    ) -> Result<()> {
+        let pool = &ctx.accounts.pool;
+        check_known_root(pool, ctx.accounts.poseidon_tree.as_ref(), &merkle_root)?;
+
+        let tainted_root = ctx.accounts.tainted_set.root;
+        let public_inputs = ExclusionPublicInputs {
+            root: merkle_root,
+            nullifier_hash,
+            pool: pool.key(),
+            circuit_version: CIRCUIT_VERSION,
+            tainted_root,
+        };
+        let proof_valid = exclusion::verify_exclusion_proof(
+            &proof.into_proof()?,
+            &public_inputs,
+            &ctx.accounts.exclusion_verification_key,
+        )?;
+        require!(proof_valid, MixerError::InvalidProof);
+
+        let current_time = Clock::get()?.unix_timestamp;
+        let innocence_proof = &mut ctx.accounts.innocence_proof;
+        innocence_proof.pool = pool.key();
+        innocence_proof.nullifier_hash = nullifier_hash;
+        innocence_proof.tainted_root = tainted_root;
+        innocence_proof.proven_at = current_time;
+        innocence_proof.bump = ctx.bumps.innocence_proof;
+
+        emit!(InnocenceProven {
+            pool: pool.key(),
+            nullifier_hash,
+            tainted_root,
+            timestamp: current_time,
+        });
+
+        msg!("Innocence proven for withdrawal {:?}", ctx.accounts.spent_nullifier.key());
+        Ok(())
+    }
+
+    /// Opt in to a receipt linking the depositor to the note at `leaf_index`,
+    /// e.g. for an institution's audit trail. Signed by the depositor
+    /// Only receipt_hash, DepositReceipt::hash(depositor, commitment, salt),
+    /// is stored; the depositor alone can reveal it with reveal_deposit_receipt
+    pub fn create_deposit_receipt(
+        ctx: Context<CreateDepositReceipt>,
+        leaf_index: u32,
+        receipt_hash: [u8; 32],
+    ) -> Result<()> {
+        require!(receipt_hash != [0u8; 32], MixerError::InvalidDepositReceipt);
+
+        let current_time = Clock::get()?.unix_timestamp;
+        let receipt = &mut ctx.accounts.deposit_receipt;
+        receipt.pool = ctx.accounts.pool.key();
+        receipt.leaf_index = leaf_index;
+        receipt.receipt_hash = receipt_hash;
+        receipt.created_at = current_time;
+        receipt.revealed_at = 0;
+        receipt.bump = ctx.bumps.deposit_receipt;
+
+        emit!(DepositReceiptCreated {
+            pool: receipt.pool,
+            leaf_index,
+            receipt_hash,
+            timestamp: current_time,
+        });
+
+        msg!("Deposit receipt created for leaf {}", leaf_index);
+        Ok(())
+    }
+
+    /// Reveal a deposit receipt: the signing depositor shows `salt`, making
+    /// the depositor-commitment link it hashes public
+    pub fn reveal_deposit_receipt(
+        ctx: Context<RevealDepositReceipt>,
+        leaf_index: u32,
+        salt: [u8; 32],
+    ) -> Result<()> {
+        let depositor = ctx.accounts.depositor.key();
+        let commitment = ctx.accounts.commitment_record.commitment;
+        let receipt = &mut ctx.accounts.deposit_receipt;
+        require!(receipt.revealed_at == 0, MixerError::DepositReceiptAlreadyRevealed);
+        require!(
+            DepositReceipt::hash(&depositor, &commitment, &salt) == receipt.receipt_hash,
+            MixerError::InvalidDepositReceipt
+        );
+
+        let current_time = Clock::get()?.unix_timestamp;
+        receipt.revealed_at = current_time;
+
+        emit!(DepositReceiptRevealed {
+            pool: receipt.pool,
+            leaf_index,
+            commitment,
+            depositor,
+            created_at: receipt.created_at,
+            timestamp: current_time,
+        });
+
+        msg!("Deposit receipt revealed for leaf {}", leaf_index);
+        Ok(())
+    }
+
+    /// Return the pool's FeeStats as of the current epoch as Borsh-encoded
+    /// FeeStats through set_return_data
+    pub fn get_fee_stats(ctx: Context<GetFeeStats>) -> Result<()> {
+        let fee_stats = ctx.accounts.pool.fee_stats.as_of(Clock::get()?.epoch);
+
+        set_return_data(&fee_stats.try_to_vec()?);
+        Ok(())
+    }
+
+    /// Withdraw SOL using commitment proof (privacy-preserving)
+    /// User must prove knowledge of secret and nullifier without revealing which deposit
+    /// merkle_proof holds pool.tree_depth siblings from the leaf up, and
+    /// path_indices packs their left/right flags (bit i = right child at level i)
+    pub fn withdraw(
+        ctx: Context<Withdraw>,
+        nullifier: [u8; 32],
+        secret: [u8; 32],
+        merkle_root: [u8; 32],
+        merkle_proof: Vec<[u8; 32]>,
+        path_indices: u32,
+    ) -> Result<()> {
+        // Bit i of path_indices is set when the node at level i is a right child
+        let path_indices = unpack_path_indices(path_indices, ctx.accounts.pool.tree_depth as usize)?;
+
+        let accounts = &mut *ctx.accounts;
+        process_legacy_withdrawal(
+            LegacyWithdrawAccounts {
+                config: &accounts.config,
+                pool: &mut accounts.pool,
+                merkle_tree: &accounts.merkle_tree,
+                nullifier_registry: &accounts.nullifier_registry,
+                nullifier_bloom: &accounts.nullifier_bloom,
+                commitment_blocklist: &accounts.commitment_blocklist,
+                spent_nullifier: &mut accounts.spent_nullifier,
+                spent_nullifier_bump: ctx.bumps.spent_nullifier,
+            },
+            LegacyNote { nullifier, secret },
+            merkle_root,
+            &merkle_proof,
+            &path_indices,
+            |pool| {
+                let split = pool.split_withdrawal(Clock::get()?.unix_timestamp)?;
+                let payout = match accounts.relayer_info {
+                    Some(_) => pay_out_relayed_withdrawal(
+                        &pool.to_account_info(),
+                        &accounts.recipient,
+                        &accounts.payer,
+                        &accounts.fee_vault.to_account_info(),
+                        split,
+                        0,
+                        accounts.config.relayer_rebate_lamports,
+                    )?,
+                    None => pay_out_withdrawal(
+                        &pool.to_account_info(),
+                        &accounts.recipient,
+                        &accounts.fee_vault.to_account_info(),
+                        split,
+                    )?,
+                };
+                accounts.fee_vault.record_fees(payout.1)?;
+                sync_wrapped_sol_recipient(&accounts.recipient, accounts.token_program.as_ref())?;
+                Ok(payout)
+            },
+        )?;
+
+        if let Some(relayer_info) = accounts.relayer_info.as_mut() {
+            relayer_info.record_withdrawal(0)?;
+        }
+        Ok(())
+    }
+
+    /// Withdraw with only the lower MERKLE_TREE_DEPTH - CANOPY_DEPTH siblings
+    /// The upper siblings come from the tree's on-chain canopy, so the proof
+    /// must be against the current root of the tree
+    pub fn withdraw_with_canopy(
+        ctx: Context<Withdraw>,
+        nullifier: [u8; 32],
+        secret: [u8; 32],
+        leaf_index: u32,
+        merkle_proof: [[u8; 32]; MERKLE_TREE_DEPTH - CANOPY_DEPTH],
+    ) -> Result<()> {
+        let (merkle_root, full_proof, path_indices) = {
+            let merkle_tree = ctx.accounts.merkle_tree.load()?;
+            let (full_proof, path_indices) =
+                merkle_tree.fill_proof_from_canopy(leaf_index, &merkle_proof)?;
+            (merkle_tree.root(), full_proof, path_indices)
+        };
+
+        let accounts = &mut *ctx.accounts;
+        process_legacy_withdrawal(
+            LegacyWithdrawAccounts {
+                config: &accounts.config,
+                pool: &mut accounts.pool,
+                merkle_tree: &accounts.merkle_tree,
+                nullifier_registry: &accounts.nullifier_registry,
+                nullifier_bloom: &accounts.nullifier_bloom,
+                commitment_blocklist: &accounts.commitment_blocklist,
+                spent_nullifier: &mut accounts.spent_nullifier,
+                spent_nullifier_bump: ctx.bumps.spent_nullifier,
+            },
+            LegacyNote { nullifier, secret },
+            merkle_root,
+            &full_proof,
+            &path_indices,
+            |pool| {
+                let split = pool.split_withdrawal(Clock::get()?.unix_timestamp)?;
+                let payout = match accounts.relayer_info {
+                    Some(_) => pay_out_relayed_withdrawal(
+                        &pool.to_account_info(),
+                        &accounts.recipient,
+                        &accounts.payer,
+                        &accounts.fee_vault.to_account_info(),
+                        split,
+                        0,
+                        accounts.config.relayer_rebate_lamports,
+                    )?,
+                    None => pay_out_withdrawal(
+                        &pool.to_account_info(),
+                        &accounts.recipient,
+                        &accounts.fee_vault.to_account_info(),
+                        split,
+                    )?,
+                };
+                accounts.fee_vault.record_fees(payout.1)?;
+                sync_wrapped_sol_recipient(&accounts.recipient, accounts.token_program.as_ref())?;
+                Ok(payout)
+            },
+        )?;
+
+        if let Some(relayer_info) = accounts.relayer_info.as_mut() {
+            relayer_info.record_withdrawal(0)?;
+        }
+        Ok(())
+    }
+
+    /// Withdraw a token pool's denomination by opening a note, like withdraw
+    /// The net amount goes to the recipient's token account for the pool's mint
+    /// A payer that creates the recipient's ATA can take ata_reimbursement
+    /// tokens out of the protocol fee, up to Config::max_ata_reimbursement
+    pub fn withdraw_token(
+        ctx: Context<WithdrawToken>,
+        nullifier: [u8; 32],
+        secret: [u8; 32],
+        merkle_root: [u8; 32],
+        merkle_proof: Vec<[u8; 32]>,
+        path_indices: u32,
+        ata_reimbursement: u64,
+    ) -> Result<()> {
+        let path_indices = unpack_path_indices(path_indices, ctx.accounts.pool.tree_depth as usize)?;
+
+        let created_ata = create_recipient_token_account(ctx.accounts)?;
+        if ata_reimbursement > 0 {
+            require!(created_ata, MixerError::ReimbursementNotAllowed);
+            require!(
+                ctx.accounts.payer_token.is_some(),
+                MixerError::ReimbursementNotAllowed
+            );
+
+            let (_, fee_amount) = ctx
+                .accounts
+                .pool
+                .split_withdrawal(Clock::get()?.unix_timestamp)?;
+            require!(
+                ata_reimbursement <= ctx.accounts.config.max_ata_reimbursement(fee_amount)?,
+                MixerError::ReimbursementTooHigh
+            );
+        }
+
+        let accounts = &mut *ctx.accounts;
+        process_legacy_withdrawal(
+            LegacyWithdrawAccounts {
+                config: &accounts.config,
+                pool: &mut accounts.pool,
+                merkle_tree: &accounts.merkle_tree,
+                nullifier_registry: &accounts.nullifier_registry,
+                nullifier_bloom: &accounts.nullifier_bloom,
+                commitment_blocklist: &accounts.commitment_blocklist,
+                spent_nullifier: &mut accounts.spent_nullifier,
+                spent_nullifier_bump: ctx.bumps.spent_nullifier,
+            },
+            LegacyNote { nullifier, secret },
+            merkle_root,
+            &merkle_proof,
+            &path_indices,
+            |pool| {
+                let (net_withdrawal, fee_amount) = pay_out_token_withdrawal(
+                    pool,
+                    TokenPayoutAccounts {
+                        mint: &accounts.mint,
+                        vault: &accounts.vault,
+                        vault_authority: &accounts.vault_authority,
+                        vault_authority_bump: ctx.bumps.vault_authority,
+                        recipient: accounts.recipient_token.to_account_info(),
+                        fee_collector: &accounts.fee_collector_token,
+                        payer: accounts.payer_token.as_ref().map(|token| token.to_account_info()),
+                        ata_reimbursement,
+                        token_program: &accounts.token_program,
+                    },
+                )?;
+                accounts.fee_totals.record(pool.mint, fee_amount, ctx.bumps.fee_totals)?;
+                Ok((net_withdrawal, fee_amount))
+            },
+        )?;
+
+        if let Some(relayer_info) = accounts.relayer_info.as_mut() {
+            relayer_info.record_withdrawal(0)?;
+        }
+        Ok(())
+    }
+
+    /// Reclaim a SOL note once the mixer is sunset or has been paused for
+    /// EMERGENCY_PAUSE_DELAY, by revealing its nullifier and secret
+    /// The note is matched against its CommitmentRecord instead of a proof,
+    /// so this links the withdrawal to the deposit: privacy is given up to
+    /// get funds out without the admin keys. The full denomination is paid
+    /// out, no protocol fee
+    pub fn emergency_withdraw(
+        ctx: Context<EmergencyWithdraw>,
+        nullifier: [u8; 32],
+        secret: [u8; 32],
+        leaf_index: u32,
+    ) -> Result<()> {
+        let accounts = &mut *ctx.accounts;
+        let amount = process_emergency_withdrawal(
+            EmergencyWithdrawAccounts {
+                config: &mut accounts.config,
+                pool: &mut accounts.pool,
+                commitment_record: &accounts.commitment_record,
+                nullifier_registry: &accounts.nullifier_registry,
+                commitment_blocklist: &accounts.commitment_blocklist,
+                spent_nullifier: &mut accounts.spent_nullifier,
+                spent_nullifier_bump: ctx.bumps.spent_nullifier,
+                zk_spent_nullifier: &accounts.zk_spent_nullifier,
+            },
+            LegacyNote { nullifier, secret },
+            leaf_index,
+        )?;
+
+        let pool = accounts.pool.to_account_info();
+        let recipient = accounts.recipient.to_account_info();
+        **pool.try_borrow_mut_lamports()? = pool
+            .lamports()
+            .checked_sub(amount)
+            .ok_or(MixerError::InsufficientFunds)?;
+        **recipient.try_borrow_mut_lamports()? = recipient
+            .lamports()
+            .checked_add(amount)
+            .ok_or(MixerError::ArithmeticOverflow)?;
+
+        Ok(())
+    }
+
+    /// Token pool counterpart of emergency_withdraw
+    pub fn emergency_withdraw_token(
+        ctx: Context<EmergencyWithdrawToken>,
+        nullifier: [u8; 32],
+        secret: [u8; 32],
+        leaf_index: u32,
+    ) -> Result<()> {
+        let accounts = &mut *ctx.accounts;
+
+        // The note being reclaimed is still counted as outstanding here
+        check_vault_backing(&accounts.pool, accounts.vault.amount)?;
+
+        let amount = process_emergency_withdrawal(
+            EmergencyWithdrawAccounts {
+                config: &mut accounts.config,
+                pool: &mut accounts.pool,
+                commitment_record: &accounts.commitment_record,
+                nullifier_registry: &accounts.nullifier_registry,
+                commitment_blocklist: &accounts.commitment_blocklist,
+                spent_nullifier: &mut accounts.spent_nullifier,
+                spent_nullifier_bump: ctx.bumps.spent_nullifier,
+                zk_spent_nullifier: &accounts.zk_spent_nullifier,
+            },
+            LegacyNote { nullifier, secret },
+            leaf_index,
+        )?;
+
+        let pool_key = accounts.pool.key();
+        let seeds: &[&[u8]] = &[
+            b"vault_authority",
+            pool_key.as_ref(),
+            &[ctx.bumps.vault_authority],
+        ];
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                accounts.token_program.to_account_info(),
+                token_interface::TransferChecked {
+                    from: accounts.vault.to_account_info(),
+                    mint: accounts.mint.to_account_info(),
+                    to: accounts.recipient_token.to_account_info(),
+                    authority: accounts.vault_authority.to_account_info(),
+                },
+                &[seeds],
+            ),
+            amount,
+            accounts.mint.decimals,
+        )?;
+
+        Ok(())
+    }
+
+    /// Withdraw SOL using a Groth16 proof (Phase 2)
+    /// The proof shows knowledge of a leaf in the tree whose committed deposit
+    /// timestamp is <= deposit_timestamp, so the per-note delay is enforced
+    /// without revealing which deposit is being spent
+    /// The proof may be submitted compressed to save instruction data
+    /// The signing payer is a public input, so only the relayer the proof
+    /// was made out to can submit it
+    pub fn withdraw_with_proof<'info>(
+        ctx: Context<'_, '_, '_, 'info, WithdrawWithProof<'info>>,
+        proof: ProofData,
+        merkle_root: [u8; 32],
+        nullifier_hash: [u8; 32],
+        deposit_timestamp: i64,
+    ) -> Result<()> {
+        let payload = WithdrawProofPayload {
+            proof,
+            merkle_root,
+            nullifier_hash,
+            deposit_timestamp,
+        };
+
+        process_zk_withdrawal(
+            ZkWithdrawAccounts {
+                config: &ctx.accounts.config,
+                pool: &mut ctx.accounts.pool,
+                verification_key: &ctx.accounts.verification_key,
+                nullifier_registry: &ctx.accounts.nullifier_registry,
+                nullifier_bloom: &ctx.accounts.nullifier_bloom,
+                spent_nullifier: &mut ctx.accounts.spent_nullifier,
+                spent_nullifier_bump: ctx.bumps.spent_nullifier,
+                poseidon_tree: ctx.accounts.poseidon_tree.as_ref(),
+                recipient: &ctx.accounts.recipient,
+                fee_vault: &mut ctx.accounts.fee_vault,
+                token_program: ctx.accounts.token_program.as_ref(),
+                relayer: ctx.accounts.payer.key(),
+                relayer_account: &ctx.accounts.payer,
+                relayer_fee: 0,
+                relayer_info: ctx.accounts.relayer_info.as_mut(),
+                anonymity_mining: ctx.accounts.anonymity_mining.as_mut(),
+                anonymity_points: ctx.accounts.anonymity_points.as_mut(),
+                reward_hook: ctx.accounts.reward_hook.as_ref(),
+                reward_hook_accounts: ctx.remaining_accounts,
+                association_set: ctx.accounts.association_set.as_deref(),
+                association_verification_key: ctx.accounts.association_verification_key.as_deref(),
+            },
+            payload,
+        )
+    }
+
+    /// Create a buffer for staging a withdrawal proof across transactions
+    /// Used by relayers when proof data exceeds the 1232-byte transaction limit
+    pub fn create_proof_buffer(ctx: Context<CreateProofBuffer>, len: u32) -> Result<()> {
+        require!(
+            len as usize <= MAX_PROOF_BUFFER_SIZE,
+            MixerError::ProofBufferOverflow
+        );
+
+        let buffer = &mut ctx.accounts.proof_buffer;
+        buffer.owner = ctx.accounts.owner.key();
+        buffer.pool = ctx.accounts.pool.key();
+        buffer.data = vec![0u8; len as usize];
+        buffer.bump = ctx.bumps.proof_buffer;
+
+        msg!("Proof buffer created: {} bytes", len);
+        Ok(())
+    }
+
+    /// Write a chunk of the serialized WithdrawProofPayload into the buffer
+    pub fn write_proof_buffer(
+        ctx: Context<WriteProofBuffer>,
+        offset: u32,
+        bytes: Vec<u8>,
+    ) -> Result<()> {
+        let buffer = &mut ctx.accounts.proof_buffer;
+
+        let start = offset as usize;
+        let end = start
+            .checked_add(bytes.len())
+            .ok_or(MixerError::ArithmeticOverflow)?;
+        require!(
+            end <= buffer.data.len(),
+            MixerError::ProofBufferOverflow
+        );
+
+        buffer.data[start..end].copy_from_slice(&bytes);
+
+        msg!("Proof buffer written: {} bytes at offset {}", bytes.len(), offset);
+        Ok(())
+    }
+
+    /// Withdraw using the proof staged in a proof buffer, then close the buffer
+    /// nullifier_hash repeats the staged one so the SpentNullifier PDA can be derived
+    pub fn withdraw_from_buffer<'info>(
+        ctx: Context<'_, '_, '_, 'info, WithdrawFromBuffer<'info>>,
+        nullifier_hash: [u8; 32],
+    ) -> Result<()> {
+        let payload = WithdrawProofPayload::deserialize(
+            &mut ctx.accounts.proof_buffer.data.as_slice()
+        ).map_err(|_| MixerError::InvalidProofBuffer)?;
+
+        require!(
+            payload.nullifier_hash == nullifier_hash,
+            MixerError::InvalidProofBuffer
+        );
+
+        process_zk_withdrawal(
+            ZkWithdrawAccounts {
+                config: &ctx.accounts.config,
+                pool: &mut ctx.accounts.pool,
+                verification_key: &ctx.accounts.verification_key,
+                nullifier_registry: &ctx.accounts.nullifier_registry,
+                nullifier_bloom: &ctx.accounts.nullifier_bloom,
+                spent_nullifier: &mut ctx.accounts.spent_nullifier,
+                spent_nullifier_bump: ctx.bumps.spent_nullifier,
+                poseidon_tree: ctx.accounts.poseidon_tree.as_ref(),
+                recipient: &ctx.accounts.recipient,
+                fee_vault: &mut ctx.accounts.fee_vault,
+                token_program: ctx.accounts.token_program.as_ref(),
+                relayer: ctx.accounts.owner.key(),
+                relayer_account: &ctx.accounts.owner,
+                relayer_fee: 0,
+                relayer_info: ctx.accounts.relayer_info.as_mut(),
+                anonymity_mining: ctx.accounts.anonymity_mining.as_mut(),
+                anonymity_points: ctx.accounts.anonymity_points.as_mut(),
+                reward_hook: ctx.accounts.reward_hook.as_ref(),
+                reward_hook_accounts: ctx.remaining_accounts,
+                association_set: ctx.accounts.association_set.as_deref(),
+                association_verification_key: ctx.accounts.association_verification_key.as_deref(),
+            },
+            payload,
+        )
+    }
+
+    /// Queue a ZK withdrawal for any relayer to execute
+    /// The proof must name the intent's address as its relayer. The payload
+    /// itself is published encrypted to relayers and committed to by
+    /// payload_hash; `fee` goes to whichever relayer executes it. Intents
+    /// stay on-chain until executed, so censorship is visible
+    /// Passing the pool's IntentQueue enqueues the intent for in-order execution
+    pub fn post_withdrawal_intent(
+        ctx: Context<PostWithdrawalIntent>,
+        nullifier_hash: [u8; 32],
+        fee: u64,
+        recipient: Pubkey,
+        payload_hash: [u8; 32],
+        ciphertext: Vec<u8>,
+    ) -> Result<()> {
+        let pool = &ctx.accounts.pool;
+        require!(!ctx.accounts.config.paused, MixerError::MixerPaused);
+        require!(pool.withdraw_mode.allows_zk(), MixerError::WithdrawModeNotAllowed);
+        require!(nullifier_hash != [0u8; 32], MixerError::InvalidNullifier);
+        require!(
+            ciphertext.len() <= MAX_INTENT_CIPHERTEXT_SIZE,
+            MixerError::EncryptedDataTooLarge
+        );
+
+        // The curve or the end of a fee holiday may raise the protocol fee
+        // before execution, which re-checks the relayer fee against the net
+        // amount then
+        let (net_withdrawal, _) = pool.split_withdrawal(Clock::get()?.unix_timestamp)?;
+        require!(fee <= net_withdrawal, MixerError::RelayerFeeTooHigh);
+        ctx.accounts.config.check_relayer_fee(pool.denomination, fee)?;
+
+        let intent = &mut ctx.accounts.intent;
+        intent.pool = pool.key();
+        intent.poster = ctx.accounts.poster.key();
+        intent.nullifier_hash = nullifier_hash;
+        intent.fee = fee;
+        intent.recipient = recipient;
+        intent.payload_hash = payload_hash;
+        intent.ciphertext = ciphertext;
+        intent.posted_at = Clock::get()?.unix_timestamp;
+        intent.bump = ctx.bumps.intent;
+        intent.queue_position = match ctx.accounts.intent_queue.as_mut() {
+            Some(intent_queue) => Some(intent_queue.enqueue(intent.posted_at)),
+            None => None,
+        };
+
+        msg!("Withdrawal intent posted: {:?} (fee: {} lamports)", intent.key(), fee);
+        Ok(())
+    }
+
+    /// Execute a posted intent with its decrypted payload, collecting its fee
+    /// Queued intents must wait for those ahead of them
+    /// The intent is closed back to its poster
+    pub fn execute_intent<'info>(
+        ctx: Context<'_, '_, '_, 'info, ExecuteIntent<'info>>,
+        payload: WithdrawProofPayload,
+    ) -> Result<()> {
+        let intent = &ctx.accounts.intent;
+        require!(
+            payload.hash()? == intent.payload_hash,
+            MixerError::InvalidIntentPayload
+        );
+        require!(
+            payload.nullifier_hash == intent.nullifier_hash,
+            MixerError::InvalidIntentPayload
+        );
+
+        if let Some(position) = intent.queue_position {
+            let intent_queue = ctx
+                .accounts
+                .intent_queue
+                .as_mut()
+                .ok_or(MixerError::IntentQueueMissing)?;
+            require!(
+                intent_queue.can_execute(position),
+                MixerError::IntentOutOfOrder
+            );
+            intent_queue.complete(position, Clock::get()?.unix_timestamp)?;
+        }
+
+        process_zk_withdrawal(
+            ZkWithdrawAccounts {
+                config: &ctx.accounts.config,
+                pool: &mut ctx.accounts.pool,
+                verification_key: &ctx.accounts.verification_key,
+                nullifier_registry: &ctx.accounts.nullifier_registry,
+                nullifier_bloom: &ctx.accounts.nullifier_bloom,
+                spent_nullifier: &mut ctx.accounts.spent_nullifier,
+                spent_nullifier_bump: ctx.bumps.spent_nullifier,
+                poseidon_tree: ctx.accounts.poseidon_tree.as_ref(),
+                recipient: &ctx.accounts.recipient,
+                fee_vault: &mut ctx.accounts.fee_vault,
+                token_program: ctx.accounts.token_program.as_ref(),
+                relayer: intent.key(),
+                relayer_account: &ctx.accounts.executor,
+                relayer_fee: intent.fee,
+                relayer_info: ctx.accounts.relayer_info.as_mut(),
+                anonymity_mining: ctx.accounts.anonymity_mining.as_mut(),
+                anonymity_points: ctx.accounts.anonymity_points.as_mut(),
+                reward_hook: ctx.accounts.reward_hook.as_ref(),
+                reward_hook_accounts: ctx.remaining_accounts,
+                association_set: ctx.accounts.association_set.as_deref(),
+                association_verification_key: ctx.accounts.association_verification_key.as_deref(),
+            },
+            payload,
+        )
+    }
+
+    /// Create a pool's FIFO queue of withdrawal intents
+    pub fn initialize_intent_queue(ctx: Context<InitializeIntentQueue>) -> Result<()> {
+        let intent_queue = &mut ctx.accounts.intent_queue;
+        intent_queue.pool = ctx.accounts.pool.key();
+        intent_queue.head = 0;
+        intent_queue.tail = 0;
+        intent_queue.head_since = Clock::get()?.unix_timestamp;
+        intent_queue.bump = ctx.bumps.intent_queue;
+        intent_queue.cancelled = 0;
+
+        msg!("Intent queue initialized for pool {:?}", intent_queue.pool);
+        Ok(())
+    }
+
+    /// Move past a queued intent nobody has executed within
+    /// INTENT_QUEUE_TIMEOUT, so it can't hold up the ones behind it
+    /// The skipped intent can still be executed or cancelled later
+    pub fn skip_stale_intent(ctx: Context<SkipStaleIntent>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let intent_queue = &mut ctx.accounts.intent_queue;
+        require!(intent_queue.is_stale(now), MixerError::IntentQueueNotStale);
+
+        intent_queue.advance(now);
+
+        msg!("Skipped stale intent, queue head now {}", intent_queue.head);
+        Ok(())
+    }
+
+    /// Withdraw an unexecuted intent, refunding its rent to the poster
+    /// A queued intent can be cancelled up to INTENT_QUEUE_CANCEL_WINDOW
+    /// positions behind the head
+    pub fn cancel_withdrawal_intent(ctx: Context<CancelWithdrawalIntent>) -> Result<()> {
+        if let Some(position) = ctx.accounts.intent.queue_position {
+            ctx.accounts
+                .intent_queue
+                .as_mut()
+                .ok_or(MixerError::IntentQueueMissing)?
+                .complete(position, Clock::get()?.unix_timestamp)?;
+        }
+
+        msg!("Withdrawal intent cancelled: {:?}", ctx.accounts.intent.key());
+        Ok(())
+    }
+
+    /// Record the trusted setup ceremony output the verification key must match
+    pub fn register_ceremony_attestation(
+        ctx: Context<RegisterCeremonyAttestation>,
+        transcript_hash: [u8; 32],
+        vk_hash: [u8; 32],
+    ) -> Result<()> {
+        let signers = admin_signers(&ctx.accounts.authority.key(), ctx.remaining_accounts);
+        ctx.accounts.config.check_admin_approval(&signers)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::RegisterCeremonyAttestation,
+            &(transcript_hash, vk_hash),
+        )?;
+
+        record_ceremony_attestation(
+            &mut ctx.accounts.ceremony_attestation,
+            transcript_hash,
+            vk_hash,
+            ctx.accounts.authority.key(),
+            ctx.bumps.ceremony_attestation,
+        )?;
+
+        msg!(
+            "Ceremony attestation registered: transcript {:?}, vk {:?}",
+            transcript_hash,
+            vk_hash
+        );
+        Ok(())
+    }
+
+    /// Store the Groth16 verification key used by withdraw_with_proof
+    /// SECURITY: The key must hash to the attested ceremony output
+    pub fn initialize_verification_key(
+        ctx: Context<InitializeVerificationKey>,
+        alpha_g1: [u8; 64],
+        beta_g2: [u8; 128],
+        gamma_g2: [u8; 128],
+        delta_g2: [u8; 128],
+        ic: Vec<[u8; 64]>,
+    ) -> Result<()> {
+        let signers = admin_signers(&ctx.accounts.authority.key(), ctx.remaining_accounts);
+        ctx.accounts.config.check_admin_approval(&signers)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::InitializeVerificationKey,
+            &(alpha_g1, beta_g2, gamma_g2, delta_g2, &ic),
+        )?;
+
+        store_verification_key(
+            &mut ctx.accounts.verification_key,
+            VerificationKey {
+                alpha_g1,
+                beta_g2,
+                gamma_g2,
+                delta_g2,
+                ic,
+            },
+            NUM_PUBLIC_INPUTS,
+            &ctx.accounts.ceremony_attestation,
+        )?;
+
+        msg!("Verification key initialized");
+        Ok(())
+    }
+
+    /// Record the trusted setup ceremony output of the association set circuit
+    pub fn register_association_ceremony_attestation(
+        ctx: Context<RegisterAssociationCeremonyAttestation>,
+        transcript_hash: [u8; 32],
+        vk_hash: [u8; 32],
+    ) -> Result<()> {
+        let signers = admin_signers(&ctx.accounts.authority.key(), ctx.remaining_accounts);
+        ctx.accounts.config.check_admin_approval(&signers)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::RegisterAssociationCeremonyAttestation,
+            &(transcript_hash, vk_hash),
+        )?;
+
+        record_ceremony_attestation(
+            &mut ctx.accounts.ceremony_attestation,
+            transcript_hash,
+            vk_hash,
+            ctx.accounts.authority.key(),
+            ctx.bumps.ceremony_attestation,
+        )?;
+
+        msg!(
+            "Association ceremony attestation registered: transcript {:?}, vk {:?}",
+            transcript_hash,
+            vk_hash
+        );
+        Ok(())
+    }
+
+    /// Store the verification key of the association set circuit, used by
+    /// ZK withdrawals that prove their note is in the pool's association set
+    /// SECURITY: The key must hash to the attested association ceremony output
+    pub fn initialize_association_verification_key(
+        ctx: Context<InitializeAssociationVerificationKey>,
+        alpha_g1: [u8; 64],
+        beta_g2: [u8; 128],
+        gamma_g2: [u8; 128],
+        delta_g2: [u8; 128],
+        ic: Vec<[u8; 64]>,
+    ) -> Result<()> {
+        let signers = admin_signers(&ctx.accounts.authority.key(), ctx.remaining_accounts);
+        ctx.accounts.config.check_admin_approval(&signers)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::InitializeAssociationVerificationKey,
+            &(alpha_g1, beta_g2, gamma_g2, delta_g2, &ic),
+        )?;
+
+        store_verification_key(
+            &mut ctx.accounts.verification_key,
+            VerificationKey {
+                alpha_g1,
+                beta_g2,
+                gamma_g2,
+                delta_g2,
+                ic,
+            },
+            NUM_ASSOCIATION_PUBLIC_INPUTS,
+            &ctx.accounts.ceremony_attestation,
+        )?;
+
+        msg!("Association verification key initialized");
+        Ok(())
+    }
+
+    /// Record the trusted setup ceremony output of the exclusion circuit
+    pub fn register_exclusion_ceremony_attestation(
+        ctx: Context<RegisterExclusionCeremonyAttestation>,
+        transcript_hash: [u8; 32],
+        vk_hash: [u8; 32],
+    ) -> Result<()> {
+        let signers = admin_signers(&ctx.accounts.authority.key(), ctx.remaining_accounts);
+        ctx.accounts.config.check_admin_approval(&signers)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::RegisterExclusionCeremonyAttestation,
+            &(transcript_hash, vk_hash),
+        )?;
+
+        record_ceremony_attestation(
+            &mut ctx.accounts.ceremony_attestation,
+            transcript_hash,
+            vk_hash,
+            ctx.accounts.authority.key(),
+            ctx.bumps.ceremony_attestation,
+        )?;
+
+        msg!(
+            "Exclusion ceremony attestation registered: transcript {:?}, vk {:?}",
+            transcript_hash,
+            vk_hash
+        );
+        Ok(())
+    }
+
+    /// Store the verification key of the exclusion circuit, used by
+    /// prove_innocence
+    /// SECURITY: The key must hash to the attested exclusion ceremony output
+    pub fn initialize_exclusion_verification_key(
+        ctx: Context<InitializeExclusionVerificationKey>,
+        alpha_g1: [u8; 64],
+        beta_g2: [u8; 128],
+        gamma_g2: [u8; 128],
+        delta_g2: [u8; 128],
+        ic: Vec<[u8; 64]>,
+    ) -> Result<()> {
+        let signers = admin_signers(&ctx.accounts.authority.key(), ctx.remaining_accounts);
+        ctx.accounts.config.check_admin_approval(&signers)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::InitializeExclusionVerificationKey,
+            &(alpha_g1, beta_g2, gamma_g2, delta_g2, &ic),
+        )?;
+
+        store_verification_key(
+            &mut ctx.accounts.verification_key,
+            VerificationKey {
+                alpha_g1,
+                beta_g2,
+                gamma_g2,
+                delta_g2,
+                ic,
+            },
+            NUM_EXCLUSION_PUBLIC_INPUTS,
+            &ctx.accounts.ceremony_attestation,
+        )?;
+
+        msg!("Exclusion verification key initialized");
+        Ok(())
+    }
+
+    /// Create a pool's spent-nullifier bloom filter
+    /// Seeded with every entry of the pool's pre-PDA registry; nullifiers spent
+    /// after that are added by each withdrawal. The filter covers every spend
+    /// once `inserted` equals the pool's total_withdrawals
+    pub fn initialize_nullifier_bloom(ctx: Context<InitializeNullifierBloom>) -> Result<()> {
+        let bloom = &mut ctx.accounts.nullifier_bloom.load_init()?;
+        bloom.pool = ctx.accounts.pool.key();
+
+        let registry = &ctx.accounts.nullifier_registry;
+        if !registry.data_is_empty() {
+            let registry = NullifierRegistry::try_deserialize(&mut &registry.data.borrow()[..])?;
+            for nullifier in &registry.nullifiers {
+                bloom.insert(nullifier);
+            }
+        }
+
+        msg!(
+            "Nullifier bloom filter initialized for pool {:?} with {} nullifiers",
+            bloom.pool,
+            bloom.inserted
+        );
+        Ok(())
+    }
+
+    /// Pause the mixer (emergency function)
+    pub fn pause(ctx: Context<AdminControl>) -> Result<()> {
+        require_admin_approval(&ctx)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::Pause,
+            &(),
+        )?;
+
+        let config = &mut ctx.accounts.config;
+        config.pause(Clock::get()?.unix_timestamp);
+
+        msg!("Mixer paused by authority");
+        Ok(())
+    }
+
+    /// Pause the mixer as the guardian, e.g. a monitoring bot reacting to
+    /// an anomaly; only the authority can unpause
+    pub fn guardian_pause(ctx: Context<GuardianPause>) -> Result<()> {
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.guardian,
+            &ctx.accounts.system_program,
+            AdminAction::GuardianPause,
+            &(),
+        )?;
+
+        let config = &mut ctx.accounts.config;
+        config.pause(Clock::get()?.unix_timestamp);
+
+        msg!("Mixer paused by guardian");
+        Ok(())
+    }
+
+    /// Set how many deposits and withdrawals each pool accepts per window
+    pub fn set_rate_limits(
+        ctx: Context<AdminControl>,
+        deposit_rate_limit: RateLimit,
+        withdrawal_rate_limit: RateLimit,
+    ) -> Result<()> {
+        require_admin_approval(&ctx)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::SetRateLimits,
+            &(deposit_rate_limit, withdrawal_rate_limit),
+        )?;
+
+        deposit_rate_limit.validate()?;
+        withdrawal_rate_limit.validate()?;
+
+        let config = &mut ctx.accounts.config;
+        config.deposit_rate_limit = deposit_rate_limit;
+        config.withdrawal_rate_limit = withdrawal_rate_limit;
+
+        msg!(
+            "Rate limits: {:?} deposits, {:?} withdrawals",
+            deposit_rate_limit,
+            withdrawal_rate_limit
+        );
+        Ok(())
+    }
+
+    /// Set the guardian key that can pause (and do nothing else),
+    /// Pubkey::default() to remove it
+    pub fn set_guardian(ctx: Context<AdminControl>, guardian: Pubkey) -> Result<()> {
+        require_admin_approval(&ctx)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::SetGuardian,
+            &guardian,
+        )?;
+
+        ctx.accounts.config.guardian = guardian;
+
+        msg!("Guardian set to {:?}", guardian);
+        Ok(())
+    }
+
+    /// Unpause the mixer
+    pub fn unpause(ctx: Context<AdminControl>) -> Result<()> {
+        require_admin_approval(&ctx)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::Unpause,
+            &(),
+        )?;
+
+        let config = &mut ctx.accounts.config;
+        require!(!config.emergency_mode, MixerError::EmergencyModeActive);
+        config.paused = false;
+        config.paused_at = 0;
+
+        msg!("Mixer unpaused by authority");
+        Ok(())
+    }
+
+    /// Shut the mixer down for good: pause it and open emergency
+    /// withdrawals right away instead of after EMERGENCY_PAUSE_DELAY
+    pub fn sunset(ctx: Context<AdminControl>) -> Result<()> {
+        require_admin_approval(&ctx)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::Sunset,
+            &(),
+        )?;
+
+        let config = &mut ctx.accounts.config;
+        let current_time = Clock::get()?.unix_timestamp;
+        config.pause(current_time);
+        enter_emergency_mode(config, current_time);
+
+        msg!("Mixer sunset by authority");
+        Ok(())
+    }
+
+    /// Bring a Config written by an older program up to Config::LEN and
+    /// CONFIG_VERSION: grow the account (the payer covers the rent), zero
+    /// the new fields and run Config::migrate. Creates the admin log if
+    /// there isn't one yet. New fields go at the end of
+    /// Config so older layouts stay a prefix of the current one
+    /// Works on layouts Account<Config> can't deserialize yet, so the
+    /// authority is read straight from the account data
+    pub fn migrate_config(ctx: Context<MigrateConfig>) -> Result<()> {
+        let config = &ctx.accounts.config;
+        {
+            let data = config.try_borrow_data()?;
+            require!(
+                data.len() >= 8 + 32 && data[..8] == Config::DISCRIMINATOR,
+                ErrorCode::AccountDiscriminatorMismatch
+            );
+            let authority = Pubkey::try_from(&data[8..40]).unwrap();
+            require_keys_eq!(authority, ctx.accounts.authority.key(), MixerError::NotAuthority);
+        }
+
+        let old_len = config.data_len();
+        if old_len < Config::LEN {
+            let rent = Rent::get()?.minimum_balance(Config::LEN);
+            let top_up = rent.saturating_sub(config.lamports());
+            if top_up > 0 {
+                system_program::transfer(
+                    CpiContext::new(
+                        ctx.accounts.system_program.to_account_info(),
+                        system_program::Transfer {
+                            from: ctx.accounts.payer.to_account_info(),
+                            to: config.to_account_info(),
+                        },
+                    ),
+                    top_up,
+                )?;
+            }
+            config.realloc(Config::LEN, true)?;
+        }
+
+        let mut state = Config::try_deserialize(&mut &config.try_borrow_data()?[..])?;
+        let old_version = state.version;
+        state.migrate(Clock::get()?.unix_timestamp);
+
+        // Layouts before the multisig have no members, so this only binds
+        // once one is configured
+        let signers = admin_signers(&ctx.accounts.authority.key(), ctx.remaining_accounts);
+        state.check_admin_approval(&signers)?;
+        state.try_serialize(&mut &mut config.try_borrow_mut_data()?[..])?;
+
+        // Deployments from before the admin log also predate the current
+        // Config layout, so they get the log here before anything needs it
+        ctx.accounts.admin_log.bump = ctx.bumps.admin_log;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::MigrateConfig,
+            &(),
+        )?;
+
+        msg!(
+            "Config migrated from version {} ({} bytes) to {} ({} bytes)",
+            old_version,
+            old_len,
+            state.version,
+            Config::LEN
+        );
+        Ok(())
+    }
+
+    /// Copy the program's upgrade authority and last deploy slot from its
+    /// ProgramData into Config, so anyone can check on chain whether the
+    /// mixer can still be upgraded and by whom. Permissionless; call again
+    /// after an upgrade or authority change to refresh it
+    pub fn record_upgrade_authority(ctx: Context<RecordUpgradeAuthority>) -> Result<()> {
+        let program_data = &ctx.accounts.program_data;
+        let config = &mut ctx.accounts.config;
+        let current_time = Clock::get()?.unix_timestamp;
+
+        config.upgrade_authority = program_data.upgrade_authority_address.unwrap_or_default();
+        config.last_deploy_slot = program_data.slot;
+        config.upgrade_info_recorded_at = current_time;
+
+        emit!(UpgradeAuthorityRecorded {
+            upgrade_authority: program_data.upgrade_authority_address,
+            last_deploy_slot: program_data.slot,
+            timestamp: current_time,
+        });
+
+        msg!(
+            "Upgrade authority: {:?}, last deployed at slot {}",
+            program_data.upgrade_authority_address,
+            program_data.slot
+        );
+        Ok(())
+    }
+
+    /// Create the empty admin log that admin instructions append to, for
+    /// deployments from before initialize and migrate_config created it.
+    /// Permissionless, the payer covers the rent
+    pub fn initialize_admin_log(ctx: Context<InitializeAdminLog>) -> Result<()> {
+        let admin_log = &mut ctx.accounts.admin_log;
+        admin_log.count = 0;
+        admin_log.bump = ctx.bumps.admin_log;
+
+        msg!("Admin log initialized");
+        Ok(())
+    }
+
+    /// Update the authority
+    /// With an authority multisig set, this needs its threshold like any other
+    /// admin instruction
+    pub fn update_authority(
+        ctx: Context<AdminControl>,
+        new_authority: Pubkey,
+    ) -> Result<()> {
+        require_admin_approval(&ctx)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::UpdateAuthority,
+            &new_authority,
+        )?;
+
+        let config = &mut ctx.accounts.config;
+        config.authority = new_authority;
+
+        msg!("Authority updated to: {:?}", new_authority);
+        Ok(())
+    }
+
+    /// Give up admin control for good: the authority becomes
+    /// RENOUNCED_AUTHORITY and the multisig and guardian are cleared, so
+    /// every admin-mutable setting (fees, fee collector, verification key,
+    /// pool parameters) is frozen as it stands. There is no way back
+    pub fn renounce_authority(ctx: Context<AdminControl>) -> Result<()> {
+        require_admin_approval(&ctx)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::RenounceAuthority,
+            &(),
+        )?;
+
+        let config = &mut ctx.accounts.config;
+        let former_authority = config.authority;
+        config.renounce();
+
+        emit!(AuthorityRenounced {
+            former_authority,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("Authority renounced by {:?}", former_authority);
+        Ok(())
+    }
+
+    /// Set the share of a token withdrawal's fee a relayer can claim for
+    /// creating the recipient's associated token account
+    pub fn set_ata_reimbursement_bps(ctx: Context<AdminControl>, bps: u16) -> Result<()> {
+        require_admin_approval(&ctx)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::SetAtaReimbursementBps,
+            &bps,
+        )?;
+
+        require!(
+            bps as u64 <= BASIS_POINTS_DIVISOR,
+            MixerError::InvalidReimbursementBps
+        );
+
+        ctx.accounts.config.ata_reimbursement_bps = bps;
+
+        msg!("ATA reimbursement set to {} bps of the withdrawal fee", bps);
+        Ok(())
+    }
+
+    /// Set the flat rebate registered relayers get on SOL withdrawals to cover
+    /// priority fees, paid out of the protocol fee (never more than it)
+    pub fn set_relayer_rebate(ctx: Context<AdminControl>, lamports: u64) -> Result<()> {
+        require_admin_approval(&ctx)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::SetRelayerRebate,
+            &lamports,
+        )?;
+
+        ctx.accounts.config.relayer_rebate_lamports = lamports;
+
+        msg!("Relayer rebate set to {} lamports", lamports);
+        Ok(())
+    }
+
+    /// Cap the relayer fee a withdrawal can pay, in basis points of the
+    /// denomination
+    pub fn set_max_relayer_fee_bps(ctx: Context<AdminControl>, bps: u16) -> Result<()> {
+        require_admin_approval(&ctx)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::SetMaxRelayerFeeBps,
+            &bps,
+        )?;
+
+        require!(
+            bps as u64 <= BASIS_POINTS_DIVISOR,
+            MixerError::InvalidRelayerFee
+        );
+
+        ctx.accounts.config.max_relayer_fee_bps = bps;
+
+        msg!("Relayer fee capped at {} bps", bps);
+        Ok(())
+    }
+
+    /// Route `staker_share_bps` of distributed SOL fees to `staker_vault`,
+    /// e.g. a staking program's reward account, and the rest to the treasury
+    pub fn set_fee_split(
+        ctx: Context<AdminControl>,
+        staker_vault: Pubkey,
+        staker_share_bps: u16,
+    ) -> Result<()> {
+        require_admin_approval(&ctx)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::SetFeeSplit,
+            &(staker_vault, staker_share_bps),
+        )?;
+
+        require!(
+            staker_share_bps as u64 <= BASIS_POINTS_DIVISOR,
+            MixerError::InvalidFeeSplit
+        );
+        require!(
+            staker_share_bps == 0 || staker_vault != Pubkey::default(),
+            MixerError::StakerVaultMissing
+        );
+
+        let config = &mut ctx.accounts.config;
+        config.staker_vault = staker_vault;
+        config.staker_share_bps = staker_share_bps;
+
+        msg!("{} bps of fees go to staker vault {:?}", staker_share_bps, staker_vault);
+        Ok(())
+    }
+
+    /// Set the program CPI'd with each withdrawal's anonymity points to
+    /// distribute reward tokens, Pubkey::default() to turn it off
+    pub fn set_reward_hook(ctx: Context<AdminControl>, reward_hook: Pubkey) -> Result<()> {
+        require_admin_approval(&ctx)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::SetRewardHook,
+            &reward_hook,
+        )?;
+
+        ctx.accounts.config.reward_hook = reward_hook;
+
+        msg!("Reward hook set to {:?}", reward_hook);
+        Ok(())
+    }
+
+    /// Set the program CPI'd to screen every deposit, e.g. against a
+    /// sanctions list, Pubkey::default() to turn it off
+    pub fn set_screening_program(
+        ctx: Context<AdminControl>,
+        screening_program: Pubkey,
+    ) -> Result<()> {
+        require_admin_approval(&ctx)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::SetScreeningProgram,
+            &screening_program,
+        )?;
+
+        ctx.accounts.config.screening_program = screening_program;
+
+        msg!("Screening program set to {:?}", screening_program);
+        Ok(())
+    }
+
+    /// Create the empty allowlist of mints token pools can be created for
+    pub fn initialize_allowed_mints(ctx: Context<InitializeAllowedMints>) -> Result<()> {
+        let signers = admin_signers(&ctx.accounts.authority.key(), ctx.remaining_accounts);
+        ctx.accounts.config.check_admin_approval(&signers)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::InitializeAllowedMints,
+            &(),
+        )?;
+
+        let allowed_mints = &mut ctx.accounts.allowed_mints;
+        allowed_mints.mints = Vec::new();
+        allowed_mints.bump = ctx.bumps.allowed_mints;
+
+        msg!("Mint allowlist initialized");
+        Ok(())
+    }
+
+    /// Allow token and NFT pools to be created for `mint`
+    pub fn add_allowed_mint(ctx: Context<UpdateAllowedMints>, mint: Pubkey) -> Result<()> {
+        let signers = admin_signers(&ctx.accounts.authority.key(), ctx.remaining_accounts);
+        ctx.accounts.config.check_admin_approval(&signers)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::AddAllowedMint,
+            &mint,
+        )?;
+
+        ctx.accounts.allowed_mints.add(mint)?;
+
+        msg!("Mint allowed: {:?}", mint);
+        Ok(())
+    }
+
+    /// Stop new pools being created for `mint`; existing pools keep working
+    pub fn remove_allowed_mint(ctx: Context<UpdateAllowedMints>, mint: Pubkey) -> Result<()> {
+        let signers = admin_signers(&ctx.accounts.authority.key(), ctx.remaining_accounts);
+        ctx.accounts.config.check_admin_approval(&signers)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::RemoveAllowedMint,
+            &mint,
+        )?;
+
+        ctx.accounts.allowed_mints.remove(&mint)?;
+
+        msg!("Mint removed from allowlist: {:?}", mint);
+        Ok(())
+    }
+
+    /// Let anyone submit a pool's withdrawals, or only its allowlisted relayers
+    pub fn set_relayer_policy(ctx: Context<UpdatePool>, policy: RelayerPolicy) -> Result<()> {
+        let signers = admin_signers(&ctx.accounts.authority.key(), ctx.remaining_accounts);
+        ctx.accounts.config.check_admin_approval(&signers)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::SetRelayerPolicy,
+            &(ctx.accounts.pool.key(), policy),
+        )?;
+
+        ctx.accounts.pool.relayer_policy = policy;
+
+        msg!("Pool relayer policy: {:?}", policy);
+        Ok(())
+    }
+
+    /// Set the pool's anonymity-set fee curve, or disable it to charge the
+    /// flat FEE_BASIS_POINTS again
+    pub fn set_fee_curve(ctx: Context<UpdatePool>, fee_curve: FeeCurve) -> Result<()> {
+        let signers = admin_signers(&ctx.accounts.authority.key(), ctx.remaining_accounts);
+        ctx.accounts.config.check_admin_approval(&signers)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::SetFeeCurve,
+            &(ctx.accounts.pool.key(), fee_curve),
+        )?;
+
+        fee_curve.validate()?;
+        ctx.accounts.pool.fee_curve = fee_curve;
+
+        msg!("Pool fee curve: {:?}", fee_curve);
+        Ok(())
+    }
+
+    /// Start rewarding a pool's notes with `points_per_day` anonymity points
+    /// per day in the pool, claimed by ZK withdrawals
+    pub fn enable_anonymity_mining(
+        ctx: Context<EnableAnonymityMining>,
+        points_per_day: u64,
+    ) -> Result<()> {
+        let signers = admin_signers(&ctx.accounts.authority.key(), ctx.remaining_accounts);
+        ctx.accounts.config.check_admin_approval(&signers)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::EnableAnonymityMining,
+            &(ctx.accounts.pool.key(), points_per_day),
+        )?;
+
+        let anonymity_mining = &mut ctx.accounts.anonymity_mining;
+        anonymity_mining.pool = ctx.accounts.pool.key();
+        anonymity_mining.points_per_day = points_per_day;
+        anonymity_mining.total_points = 0;
+        anonymity_mining.bump = ctx.bumps.anonymity_mining;
+
+        msg!("Anonymity mining enabled at {} points per day", points_per_day);
+        Ok(())
+    }
+
+    /// Change a pool's anonymity mining rate, 0 pauses mining
+    /// Applies to the whole time in the pool of notes withdrawn afterwards
+    pub fn set_anonymity_mining_rate(
+        ctx: Context<UpdateAnonymityMining>,
+        points_per_day: u64,
+    ) -> Result<()> {
+        let signers = admin_signers(&ctx.accounts.authority.key(), ctx.remaining_accounts);
+        ctx.accounts.config.check_admin_approval(&signers)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::SetAnonymityMiningRate,
+            &(ctx.accounts.anonymity_mining.pool, points_per_day),
+        )?;
+
+        ctx.accounts.anonymity_mining.points_per_day = points_per_day;
+
+        msg!("Anonymity mining rate set to {} points per day", points_per_day);
+        Ok(())
+    }
+
+    /// Open the AnonymityPoints account withdrawals to `owner` credit
+    /// Anyone can pay for it, so recipients needn't hold SOL beforehand
+    pub fn initialize_anonymity_points(
+        ctx: Context<InitializeAnonymityPoints>,
+        owner: Pubkey,
+    ) -> Result<()> {
+        let anonymity_points = &mut ctx.accounts.anonymity_points;
+        anonymity_points.owner = owner;
+        anonymity_points.points = 0;
+        anonymity_points.bump = ctx.bumps.anonymity_points;
+
+        msg!("Anonymity points account opened for {:?}", owner);
+        Ok(())
+    }
+
+    /// Waive the pool's protocol fee for withdrawals from `start` until `end`
+    /// start == end turns the holiday off
+    pub fn set_fee_holiday(ctx: Context<UpdatePool>, start: i64, end: i64) -> Result<()> {
+        let signers = admin_signers(&ctx.accounts.authority.key(), ctx.remaining_accounts);
+        ctx.accounts.config.check_admin_approval(&signers)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::SetFeeHoliday,
+            &(ctx.accounts.pool.key(), start, end),
+        )?;
+
+        require!(start <= end, MixerError::InvalidFeeHoliday);
+
+        let pool = &mut ctx.accounts.pool;
+        pool.fee_holiday_start = start;
+        pool.fee_holiday_end = end;
+
+        msg!("Pool fee holiday from {} until {}", start, end);
+        Ok(())
+    }
+
+    /// Create a pool's empty relayer allowlist
+    pub fn initialize_pool_relayers(ctx: Context<InitializePoolRelayers>) -> Result<()> {
+        let signers = admin_signers(&ctx.accounts.authority.key(), ctx.remaining_accounts);
+        ctx.accounts.config.check_admin_approval(&signers)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::InitializePoolRelayers,
+            &ctx.accounts.pool.key(),
+        )?;
+
+        let pool_relayers = &mut ctx.accounts.pool_relayers;
+        pool_relayers.pool = ctx.accounts.pool.key();
+        pool_relayers.relayers = Vec::new();
+        pool_relayers.bump = ctx.bumps.pool_relayers;
+
+        msg!("Relayer allowlist initialized for pool {:?}", pool_relayers.pool);
+        Ok(())
+    }
+
+    /// Allow `relayer` to submit withdrawals from an Allowlisted pool
+    pub fn add_pool_relayer(ctx: Context<UpdatePoolRelayers>, relayer: Pubkey) -> Result<()> {
+        let signers = admin_signers(&ctx.accounts.authority.key(), ctx.remaining_accounts);
+        ctx.accounts.config.check_admin_approval(&signers)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::AddPoolRelayer,
+            &(ctx.accounts.pool_relayers.pool, relayer),
+        )?;
+
+        ctx.accounts.pool_relayers.add(relayer)?;
+
+        msg!("Relayer allowed: {:?}", relayer);
+        Ok(())
+    }
+
+    /// Remove `relayer` from a pool's allowlist
+    pub fn remove_pool_relayer(ctx: Context<UpdatePoolRelayers>, relayer: Pubkey) -> Result<()> {
+        let signers = admin_signers(&ctx.accounts.authority.key(), ctx.remaining_accounts);
+        ctx.accounts.config.check_admin_approval(&signers)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::RemovePoolRelayer,
+            &(ctx.accounts.pool_relayers.pool, relayer),
+        )?;
+
+        ctx.accounts.pool_relayers.remove(&relayer)?;
+
+        msg!("Relayer removed from allowlist: {:?}", relayer);
+        Ok(())
+    }
+
+    /// Raise or lower a pool's min_delay within MIN_TIME_DELAY..=MAX_TIME_DELAY
+    /// Applies to every withdrawal from then on, including notes already
+    /// deposited
+    pub fn update_min_delay(ctx: Context<UpdatePool>, min_delay: i64) -> Result<()> {
+        let signers = admin_signers(&ctx.accounts.authority.key(), ctx.remaining_accounts);
+        ctx.accounts.config.check_admin_approval(&signers)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::UpdateMinDelay,
+            &(ctx.accounts.pool.key(), min_delay),
+        )?;
+
+        require!(
+            is_valid_time_delay(min_delay),
+            MixerError::InvalidTimeDelay
+        );
+
+        let pool = &mut ctx.accounts.pool;
+        let old_delay = pool.min_delay;
+        pool.min_delay = min_delay;
+
+        emit!(MinDelayUpdated {
+            pool: pool.key(),
+            old_delay,
+            new_delay: min_delay,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("Minimum delay changed from {} to {} seconds", old_delay, min_delay);
+        Ok(())
+    }
+
+    /// Transition a pool between SHA256 (Legacy) and ZK withdrawals
+    /// Both keeps SHA256-phase notes redeemable while ZK withdrawals are live
+    pub fn set_withdraw_mode(
+        ctx: Context<UpdatePool>,
+        new_mode: WithdrawMode,
+    ) -> Result<()> {
+        let signers = admin_signers(&ctx.accounts.authority.key(), ctx.remaining_accounts);
+        ctx.accounts.config.check_admin_approval(&signers)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::SetWithdrawMode,
+            &(ctx.accounts.pool.key(), new_mode),
+        )?;
+
+        let pool = &mut ctx.accounts.pool;
+        let old_mode = pool.withdraw_mode;
+
+        require!(
+            old_mode != new_mode,
+            MixerError::WithdrawModeUnchanged
+        );
+
+        pool.withdraw_mode = new_mode;
+
+        emit!(WithdrawModeChanged {
+            pool: pool.key(),
+            old_mode,
+            new_mode,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("Withdraw mode changed from {:?} to {:?}", old_mode, new_mode);
+        Ok(())
+    }
+
+    /// Propose a new root for the pool's curated association set
+    /// It can be activated ASSOCIATION_ROOT_DELAY later, see association
+    pub fn propose_association_root(
+        ctx: Context<ProposeAssociationRoot>,
+        root: [u8; 32],
+    ) -> Result<()> {
+        let signers = admin_signers(&ctx.accounts.authority.key(), ctx.remaining_accounts);
+        ctx.accounts.config.check_admin_approval(&signers)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::ProposeAssociationRoot,
+            &(ctx.accounts.pool.key(), root),
+        )?;
+
+        let current_time = Clock::get()?.unix_timestamp;
+        let association_set = &mut ctx.accounts.association_set;
+        association_set.pool = ctx.accounts.pool.key();
+        association_set.bump = ctx.bumps.association_set;
+        let activates_at = association_set.propose(root, current_time)?;
+
+        emit!(AssociationRootProposed {
+            pool: association_set.pool,
+            root,
+            activates_at,
+            timestamp: current_time,
+        });
+
+        msg!(
+            "Association set root {:?} proposed for pool {:?}, activates at {}",
+            root,
+            association_set.pool,
+            activates_at
+        );
+        Ok(())
+    }
+
+    /// Make the pending association set root current once its timelock has
+    /// passed. Anyone can call this
+    pub fn activate_association_root(ctx: Context<ActivateAssociationRoot>) -> Result<()> {
+        let current_time = Clock::get()?.unix_timestamp;
+        let association_set = &mut ctx.accounts.association_set;
+        let activated = association_set.activate(current_time)?;
+
+        emit!(AssociationRootActivated {
+            pool: association_set.pool,
+            root: activated.root,
+            version: activated.version,
+            timestamp: current_time,
+        });
+
+        msg!(
+            "Association set root {:?} of pool {:?} active as version {}",
+            activated.root,
+            association_set.pool,
+            activated.version
+        );
+        Ok(())
+    }
+
+    /// Publish the root of the pool's tainted commitments, see exclusion
+    /// ZK withdrawals can then prove their deposit is not among them
+    pub fn set_tainted_root(ctx: Context<SetTaintedRoot>, root: [u8; 32]) -> Result<()> {
+        let signers = admin_signers(&ctx.accounts.authority.key(), ctx.remaining_accounts);
+        ctx.accounts.config.check_admin_approval(&signers)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::SetTaintedRoot,
+            &(ctx.accounts.pool.key(), root),
+        )?;
+
+        require!(root != [0u8; 32], MixerError::InvalidTaintedRoot);
+
+        let tainted_set = &mut ctx.accounts.tainted_set;
+        tainted_set.pool = ctx.accounts.pool.key();
+        tainted_set.root = root;
+        tainted_set.updated_at = Clock::get()?.unix_timestamp;
+        tainted_set.bump = ctx.bumps.tainted_set;
+
+        msg!("Tainted set root of pool {:?} set to {:?}", tainted_set.pool, root);
+        Ok(())
+    }
+
+    /// Start maintaining a Poseidon tree next to the SHA256 tree during the
+    /// Phase 1 -> Phase 2 transition, so ZK proofs check against on-chain
+    /// Poseidon roots of the same deposits. The current generation must still
+    /// be empty so both trees hold exactly the same leaves.
+    pub fn enable_dual_tree(ctx: Context<EnableDualTree>) -> Result<()> {
+        let signers = admin_signers(&ctx.accounts.authority.key(), ctx.remaining_accounts);
+        ctx.accounts.config.check_admin_approval(&signers)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::EnableDualTree,
+            &ctx.accounts.pool.key(),
+        )?;
+
+        let pool = &mut ctx.accounts.pool;
+
+        require!(!pool.dual_tree, MixerError::DualTreeAlreadyEnabled);
+        require!(
+            ctx.accounts.merkle_tree.load()?.next_index == 0,
+            MixerError::DualTreeRequiresEmptyTree
+        );
+
+        let poseidon_tree = &mut ctx.accounts.poseidon_tree.load_init()?;
+        poseidon_tree.initialize(
+            pool.key(),
+            pool.tree_generation,
+            pool.version,
+            TREE_HASH_POSEIDON,
+            ctx.bumps.poseidon_tree,
+        );
+
+        pool.dual_tree = true;
+
+        msg!("Dual SHA256/Poseidon trees enabled from generation {}", pool.tree_generation);
+        Ok(())
+    }
+
+    /// Switch the hash of the pool's main tree, e.g. to TREE_HASH_KECCAK so
+    /// EVM contracts can verify its roots and proofs
+    /// Only while the current tree is empty; later generations keep the hash
+    pub fn set_tree_hash(ctx: Context<SetTreeHash>, tree_hash: u8) -> Result<()> {
+        let signers = admin_signers(&ctx.accounts.authority.key(), ctx.remaining_accounts);
+        ctx.accounts.config.check_admin_approval(&signers)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::SetTreeHash,
+            &(ctx.accounts.pool.key(), tree_hash),
+        )?;
+
+        let pool = &mut ctx.accounts.pool;
+
+        require!(
+            tree_hash == TREE_HASH_SHA256 || tree_hash == TREE_HASH_KECCAK,
+            MixerError::InvalidTreeHash
+        );
+
+        let merkle_tree = &mut ctx.accounts.merkle_tree.load_mut()?;
+        require!(merkle_tree.next_index == 0, MixerError::TreeHashRequiresEmptyTree);
+
+        let bump = merkle_tree.bump;
+        merkle_tree.initialize(pool.key(), pool.tree_generation, pool.version, tree_hash, bump);
+
+        pool.tree_hash = tree_hash;
+        pool.merkle_root = merkle_tree.root();
+
+        msg!("Tree hash set to {} from generation {}", tree_hash, pool.tree_generation);
+        Ok(())
+    }
+
+    /// Freeze the pool's current tree and emit its state for migration
+    /// Deposits into the tree stop, but its roots stay valid so existing
+    /// notes can still be withdrawn. Can be repeated to re-emit the state.
+    pub fn export_tree_state(ctx: Context<ExportTreeState>) -> Result<()> {
+        let signers = admin_signers(&ctx.accounts.authority.key(), ctx.remaining_accounts);
+        ctx.accounts.config.check_admin_approval(&signers)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::ExportTreeState,
+            &ctx.accounts.pool.key(),
+        )?;
+
+        let merkle_tree = &mut ctx.accounts.merkle_tree.load_mut()?;
+        merkle_tree.freeze();
+
+        emit!(TreeStateExported {
+            pool: ctx.accounts.pool.key(),
+            generation: merkle_tree.generation,
+            next_index: merkle_tree.next_index,
+            current_root_index: merkle_tree.current_root_index,
+            filled_subtrees: merkle_tree.filled_subtrees.to_vec(),
+            roots: merkle_tree.roots.to_vec(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!(
+            "Tree generation {} frozen and exported at {} leaves",
+            merkle_tree.generation,
+            merkle_tree.next_index
+        );
+        Ok(())
+    }
+
+    /// Replace the authority multisig: `threshold` of `members` must sign
+    /// every admin instruction, the signer plus the rest as signer remaining
+    /// accounts. An empty set with threshold 0 goes back to the
+    /// single authority key
+    pub fn set_authority_members(
+        ctx: Context<AdminControl>,
+        members: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<()> {
+        require_admin_approval(&ctx)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::SetAuthorityMembers,
+            &(&members, threshold),
+        )?;
+
+        require!(
+            members.len() <= MAX_AUTHORITY_MEMBERS
+                && threshold as usize <= members.len()
+                && (threshold >= 1 || members.is_empty()),
+            MixerError::InvalidAuthoritySet
+        );
+
+        for (i, member) in members.iter().enumerate() {
+            require!(
+                *member != Pubkey::default() && !members[..i].contains(member),
+                MixerError::InvalidAuthoritySet
+            );
+        }
+
+        let config = &mut ctx.accounts.config;
+        config.authority_members = [Pubkey::default(); MAX_AUTHORITY_MEMBERS];
+        config.authority_members[..members.len()].copy_from_slice(&members);
+        config.authority_member_count = members.len() as u8;
+        config.authority_threshold = threshold;
+
+        msg!("Authority multisig updated: {}-of-{}", threshold, members.len());
+        Ok(())
+    }
+
+    /// Replace the attestor set allowed to commit Poseidon roots
+    /// `threshold` of them must co-sign each commit_root
+    pub fn set_attestors(
+        ctx: Context<AdminControl>,
+        attestors: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<()> {
+        require_admin_approval(&ctx)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::SetAttestors,
+            &(&attestors, threshold),
+        )?;
+
+        require!(
+            attestors.len() <= MAX_ATTESTORS
+                && threshold >= 1
+                && threshold as usize <= attestors.len(),
+            MixerError::InvalidAttestorSet
+        );
+
+        for (i, attestor) in attestors.iter().enumerate() {
+            require!(
+                *attestor != Pubkey::default() && !attestors[..i].contains(attestor),
+                MixerError::InvalidAttestorSet
+            );
+        }
+
+        let config = &mut ctx.accounts.config;
+        config.attestors = [Pubkey::default(); MAX_ATTESTORS];
+        config.attestors[..attestors.len()].copy_from_slice(&attestors);
+        config.attestor_count = attestors.len() as u8;
+        config.attestor_threshold = threshold;
+
+        msg!("Attestor set updated: {}-of-{}", threshold, attestors.len());
+        Ok(())
+    }
+
+    /// Commit the pool's Poseidon root, co-signed by at least attestor_threshold
+    /// attestors passed as signer remaining accounts
+    /// ZK withdrawals are checked against this root until the Poseidon tree
+    /// is maintained on-chain
+    pub fn commit_root(
+        ctx: Context<CommitRoot>,
+        root: [u8; 32],
+        leaf_count: u32,
+    ) -> Result<()> {
+        let config = &ctx.accounts.config;
+        let pool = &mut ctx.accounts.pool;
+
+        let signers: Vec<Pubkey> = ctx
+            .remaining_accounts
+            .iter()
+            .filter(|account| account.is_signer)
+            .map(|account| account.key())
+            .collect();
+
+        require!(
+            config.attestor_threshold > 0
+                && config.count_attestations(&signers) >= config.attestor_threshold as usize,
+            MixerError::InsufficientAttestations
+        );
+
+        require!(root != [0u8; 32], MixerError::InvalidAttestedRoot);
+
+        // The root can only cover deposits that exist, and never fewer than
+        // the previously attested root did
+        require!(
+            leaf_count <= pool.next_leaf_index && leaf_count >= pool.attested_leaf_count,
+            MixerError::InvalidAttestedRoot
+        );
+
+        pool.attested_root = root;
+        pool.attested_leaf_count = leaf_count;
+
+        emit!(RootCommitted {
+            pool: pool.key(),
+            root,
+            leaf_count,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("Attested root committed covering {} leaves", leaf_count);
+        Ok(())
+    }
+
+    /// Update the fee collector address
+    pub fn update_fee_collector(
+        ctx: Context<AdminControl>,
+        new_fee_collector: Pubkey,
+    ) -> Result<()> {
+        require_admin_approval(&ctx)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::UpdateFeeCollector,
+            &new_fee_collector,
+        )?;
+
+        let config = &mut ctx.accounts.config;
+        config.fee_collector = new_fee_collector;
+
+        msg!("Fee collector updated to: {:?}", new_fee_collector);
+        Ok(())
+    }
+
+    /// Let anyone create SOL and token pools for the allowed denominations
+    /// and mints by locking `pool_creation_bond` lamports in a PoolBond
+    pub fn set_permissionless_pools(
+        ctx: Context<AdminControl>,
+        enabled: bool,
+        pool_creation_bond: u64,
+    ) -> Result<()> {
+        require_admin_approval(&ctx)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::SetPermissionlessPools,
+            &(enabled, pool_creation_bond),
+        )?;
+
+        require!(
+            !enabled || pool_creation_bond > 0,
+            MixerError::InvalidPoolBond
+        );
+
+        let config = &mut ctx.accounts.config;
+        config.permissionless_pools = enabled;
+        config.pool_creation_bond = pool_creation_bond;
+
+        msg!(
+            "Permissionless pools {}, bond {} lamports",
+            if enabled { "enabled" } else { "disabled" },
+            pool_creation_bond
+        );
+        Ok(())
+    }
+
+    /// Refund a pool creation bond to its creator once POOL_BOND_LOCKUP has
+    /// passed without governance forfeiting it
+    /// Not while the pool is flagged for review
+    pub fn reclaim_pool_bond(ctx: Context<ReclaimPoolBond>) -> Result<()> {
+        let pool_bond = &ctx.accounts.pool_bond;
+        let current_time = Clock::get()?.unix_timestamp;
+        require!(!pool_bond.is_flagged(), MixerError::PoolBondFlagged);
+        require!(
+            pool_bond.is_reclaimable(current_time),
+            MixerError::PoolBondLocked
+        );
+
+        emit!(PoolBondReclaimed {
+            pool: pool_bond.pool,
+            creator: pool_bond.creator,
+            amount: pool_bond.amount,
+            timestamp: current_time,
+        });
+
+        msg!("Pool bond of {} lamports reclaimed", pool_bond.amount);
+        Ok(())
+    }
+
+    /// Flag a bonded pool for review, freezing its creation bond
+    /// The creator can't reclaim the bond until the pool is unflagged
+    pub fn flag_pool(ctx: Context<UpdatePoolFlag>) -> Result<()> {
+        let signers = admin_signers(&ctx.accounts.authority.key(), ctx.remaining_accounts);
+        ctx.accounts.config.check_admin_approval(&signers)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::FlagPool,
+            &ctx.accounts.pool_bond.pool,
+        )?;
+
+        let current_time = Clock::get()?.unix_timestamp;
+        let pool_bond = &mut ctx.accounts.pool_bond;
+        require!(!pool_bond.is_flagged(), MixerError::PoolBondFlagged);
+        pool_bond.flagged_at = current_time;
+
+        emit!(PoolFlagged {
+            pool: pool_bond.pool,
+            timestamp: current_time,
+        });
+
+        msg!("Pool {:?} flagged for review", pool_bond.pool);
+        Ok(())
+    }
+
+    /// Clear a pool's review flag, letting its creator reclaim the bond again
+    pub fn unflag_pool(ctx: Context<UpdatePoolFlag>) -> Result<()> {
+        let signers = admin_signers(&ctx.accounts.authority.key(), ctx.remaining_accounts);
+        ctx.accounts.config.check_admin_approval(&signers)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::UnflagPool,
+            &ctx.accounts.pool_bond.pool,
+        )?;
+
+        let pool_bond = &mut ctx.accounts.pool_bond;
+        require!(pool_bond.is_flagged(), MixerError::PoolBondNotFlagged);
+        pool_bond.flagged_at = 0;
+
+        emit!(PoolUnflagged {
+            pool: pool_bond.pool,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("Pool {:?} unflagged", pool_bond.pool);
+        Ok(())
+    }
+
+    /// Forfeit a flagged pool's creation bond to the fee collector
+    /// The pool must have been closed with close_pool, which needs it empty
+    pub fn forfeit_pool_bond(ctx: Context<ForfeitPoolBond>) -> Result<()> {
+        let signers = admin_signers(&ctx.accounts.authority.key(), ctx.remaining_accounts);
+        ctx.accounts.config.check_admin_approval(&signers)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::ForfeitPoolBond,
+            &ctx.accounts.pool_bond.pool,
+        )?;
+
+        let pool_bond = &ctx.accounts.pool_bond;
+        require!(pool_bond.is_flagged(), MixerError::PoolBondNotFlagged);
+        emit!(PoolBondForfeited {
+            pool: pool_bond.pool,
+            creator: pool_bond.creator,
+            amount: pool_bond.amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("Pool bond of {} lamports forfeited", pool_bond.amount);
+        Ok(())
+    }
+
+    /// Flag a pool's commitment as non-withdrawable, e.g. a deposit of
+    /// exploited funds. The guardian can flag on its own; the authority
+    /// needs its multisig threshold. With the threshold met, flagging a
+    /// commitment with an unflag pending cancels the unflag; the guardian
+    /// alone can only flag commitments not yet listed
+    /// Only withdrawals that reveal the commitment can be stopped: SHA256-phase
+    /// and emergency withdrawals. ZK withdrawals hide which commitment they
+    /// spend, so they are unaffected
+    pub fn flag_commitment(ctx: Context<FlagCommitment>, commitment: [u8; 32]) -> Result<()> {
+        let signer = ctx.accounts.signer.key();
+        let signers = admin_signers(&signer, ctx.remaining_accounts);
+        let approved = if signer == ctx.accounts.config.guardian {
+            ctx.accounts.config.check_admin_approval(&signers).is_ok()
+        } else {
+            ctx.accounts.config.check_admin_approval(&signers)?;
+            true
+        };
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.signer,
+            &ctx.accounts.system_program,
+            AdminAction::FlagCommitment,
+            &(ctx.accounts.pool.key(), commitment),
+        )?;
+
+        let pool = ctx.accounts.pool.key();
+        let current_time = Clock::get()?.unix_timestamp;
+        let blocklist = &mut ctx.accounts.commitment_blocklist;
+        blocklist.pool = pool;
+        blocklist.bump = ctx.bumps.commitment_blocklist;
+        blocklist.flag(commitment, current_time, approved)?;
+
+        emit!(CommitmentFlagged {
+            pool,
+            commitment,
+            flagged_by: signer,
+            timestamp: current_time,
+        });
+
+        msg!("Commitment flagged in pool {:?}", pool);
+        Ok(())
+    }
+
+    /// Start the COMMITMENT_UNFLAG_DELAY timelock on unflagging a
+    /// commitment, after which anyone can run unflag_commitment
+    pub fn request_commitment_unflag(
+        ctx: Context<RequestCommitmentUnflag>,
+        commitment: [u8; 32],
+    ) -> Result<()> {
+        let signers = admin_signers(&ctx.accounts.authority.key(), ctx.remaining_accounts);
+        ctx.accounts.config.check_admin_approval(&signers)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::RequestCommitmentUnflag,
+            &(ctx.accounts.pool.key(), commitment),
+        )?;
+
+        let current_time = Clock::get()?.unix_timestamp;
+        let unflag_at = ctx
+            .accounts
+            .commitment_blocklist
+            .request_unflag(&commitment, current_time)?;
+
+        emit!(CommitmentUnflagRequested {
+            pool: ctx.accounts.pool.key(),
+            commitment,
+            unflag_at,
+            timestamp: current_time,
+        });
+
+        msg!("Commitment unflag executable at {}", unflag_at);
+        Ok(())
+    }
+
+    /// Unflag a commitment whose unflag timelock has passed. Permissionless
+    pub fn unflag_commitment(ctx: Context<UnflagCommitment>, commitment: [u8; 32]) -> Result<()> {
+        let current_time = Clock::get()?.unix_timestamp;
+        let blocklist = &mut ctx.accounts.commitment_blocklist;
+        blocklist.unflag(&commitment, current_time)?;
+
+        emit!(CommitmentUnflagged {
+            pool: blocklist.pool,
+            commitment,
+            timestamp: current_time,
+        });
+
+        msg!("Commitment unflagged in pool {:?}", blocklist.pool);
+        Ok(())
+    }
+
+    /// Close a pool account and return lamports to authority
+    /// SECURITY: Can only close if all deposits have been withdrawn
+    /// A token pool's vault must be empty and is closed along with it
+    pub fn close_pool(ctx: Context<ClosePool>) -> Result<()> {
+        let signers = admin_signers(&ctx.accounts.authority.key(), ctx.remaining_accounts);
+        ctx.accounts.config.check_admin_approval(&signers)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::ClosePool,
+            &ctx.accounts.pool.key(),
+        )?;
+
+        let pool = &ctx.accounts.pool;
+
+        // CRITICAL SECURITY FIX: Prevent closing pools with outstanding deposits
+        // Only allow closure if all deposits have been withdrawn
+        require!(
+            pool.total_deposits == pool.total_withdrawals,
+            MixerError::PoolHasOutstandingDeposits
+        );
+
+        if pool.is_token_pool() {
+            close_vault(ctx.accounts, ctx.bumps.vault_authority)?;
+        }
+
+        let pool = &ctx.accounts.pool;
+        let pool_lamports = pool.to_account_info().lamports();
+
+        // Remember the closure so nullifier rent can be reclaimed later
+        let retired_pool = &mut ctx.accounts.retired_pool;
+        retired_pool.pool = pool.key();
+        retired_pool.closed_at = Clock::get()?.unix_timestamp;
+        retired_pool.bump = ctx.bumps.retired_pool;
+
+        msg!("Closing empty pool with {} lamports rent", pool_lamports);
+
+        // Transfer remaining rent lamports to authority
+        **pool.to_account_info().try_borrow_mut_lamports()? = 0;
+        **ctx.accounts.authority.try_borrow_mut_lamports()? += pool_lamports;
+
+        Ok(())
+    }
+
+    /// Move a pool's pre-PDA NullifierRegistry into SpentNullifier PDAs
+    /// Remaining accounts are the SpentNullifier addresses of the registry's
+    /// last entries, last entry first; each call migrates that many and
+    /// shrinks the registry, so withdrawals stay protected between calls.
+    /// The call that empties the registry closes it, sending its rent to the
+    /// fee collector as close_nullifier_registry does
+    pub fn migrate_nullifiers<'info>(
+        ctx: Context<'_, '_, '_, 'info, MigrateNullifiers<'info>>,
+    ) -> Result<()> {
+        let pool = ctx.accounts.pool.key();
+        let registry = &mut ctx.accounts.nullifier_registry;
+        let now = Clock::get()?.unix_timestamp;
+
+        for account in ctx.remaining_accounts {
+            let nullifier = registry
+                .nullifiers
+                .pop()
+                .ok_or(MixerError::NullifierMigrationMismatch)?;
+            let key = compute_pool_nullifier(&nullifier, &pool);
+            let (address, bump) = Pubkey::find_program_address(
+                &[b"nullifier", pool.as_ref(), key.as_ref()],
+                &crate::ID,
+            );
+            require_keys_eq!(account.key(), address, MixerError::NullifierMigrationMismatch);
+
+            create_spent_nullifier(
+                account,
+                &ctx.accounts.payer,
+                &ctx.accounts.system_program,
+                SpentNullifier {
+                    pool,
+                    nullifier_hash: key,
+                    spent_at: now,
+                    bump,
+                },
+            )?;
+        }
+
+        let remaining = registry.nullifiers.len();
+        if remaining == 0 {
+            registry.close(ctx.accounts.fee_collector.to_account_info())?;
+        }
+
+        msg!(
+            "Migrated {} nullifiers of pool {:?}, {} left in registry",
+            ctx.remaining_accounts.len(),
+            pool,
+            remaining
+        );
+        Ok(())
+    }
+
+    /// Close a SpentNullifier of a retired pool, sending its rent to the fee collector
+    /// Only once NULLIFIER_RECLAIM_DELAY has passed since close_pool
+    pub fn close_spent_nullifier(ctx: Context<CloseSpentNullifier>) -> Result<()> {
+        check_reclaimable(&ctx.accounts.retired_pool, &ctx.accounts.pool)?;
+
+        msg!(
+            "Spent nullifier {:?} of retired pool {:?} closed",
+            ctx.accounts.spent_nullifier.nullifier_hash,
+            ctx.accounts.retired_pool.pool
+        );
+        Ok(())
+    }
+
+    /// Close the pre-PDA NullifierRegistry of a retired pool, sending its rent
+    /// to the fee collector, under the same timelock as close_spent_nullifier
+    pub fn close_nullifier_registry(ctx: Context<CloseNullifierRegistry>) -> Result<()> {
+        check_reclaimable(&ctx.accounts.retired_pool, &ctx.accounts.pool)?;
+
+        msg!(
+            "Nullifier registry of retired pool {:?} closed",
+            ctx.accounts.retired_pool.pool
+        );
+        Ok(())
+    }
+
+    /// Announce a force close of a program account (for migration purposes)
+    /// It can be executed with force_close_account once FORCE_CLOSE_DELAY
+    /// has passed, giving users time to see the ForceCloseScheduled event.
+    /// Only stale proof buffers, audit cursors and force close records can be
+    /// force closed
+    pub fn schedule_force_close(ctx: Context<ScheduleForceClose>) -> Result<()> {
+        let signers = admin_signers(&ctx.accounts.authority.key(), ctx.remaining_accounts);
+        ctx.accounts.config.check_admin_approval(&signers)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::ScheduleForceClose,
+            &ctx.accounts.account_to_close.key(),
+        )?;
+
+        check_force_closable(&ctx.accounts.account_to_close)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let pending = &mut ctx.accounts.pending_force_close;
+        pending.account = ctx.accounts.account_to_close.key();
+        pending.scheduled_at = now;
+        pending.executable_at = now.saturating_add(FORCE_CLOSE_DELAY);
+        pending.bump = ctx.bumps.pending_force_close;
+
+        emit!(ForceCloseScheduled {
+            account: pending.account,
+            lamports: ctx.accounts.account_to_close.lamports(),
+            executable_at: pending.executable_at,
+            timestamp: now,
+        });
+
+        msg!("Force close of {:?} scheduled for {}", pending.account, pending.executable_at);
+        Ok(())
+    }
+
+    /// Drop a scheduled force close
+    pub fn cancel_force_close(ctx: Context<CancelForceClose>) -> Result<()> {
+        let signers = admin_signers(&ctx.accounts.authority.key(), ctx.remaining_accounts);
+        ctx.accounts.config.check_admin_approval(&signers)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::CancelForceClose,
+            &ctx.accounts.pending_force_close.account,
+        )?;
+
+        msg!("Force close of {:?} cancelled", ctx.accounts.pending_force_close.account);
+        Ok(())
+    }
+
+    /// Force close a program account scheduled with schedule_force_close,
+    /// sending its lamports to the authority
+    pub fn force_close_account(ctx: Context<ForceCloseAccount>) -> Result<()> {
+        let signers = admin_signers(&ctx.accounts.authority.key(), ctx.remaining_accounts);
+        ctx.accounts.config.check_admin_approval(&signers)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::ForceCloseAccount,
+            &ctx.accounts.pending_force_close.account,
+        )?;
+
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            now >= ctx.accounts.pending_force_close.executable_at,
+            MixerError::ForceCloseTimelockNotElapsed
+        );
+
+        // The account may have been re-created as something else since
+        let account_to_close = &ctx.accounts.account_to_close;
+        check_force_closable(account_to_close)?;
+        let account_lamports = account_to_close.lamports();
+
+        msg!("Force closing account with {} lamports", account_lamports);
+
+        // Transfer all lamports to authority
+        **account_to_close.try_borrow_mut_lamports()? = 0;
+        **ctx.accounts.authority.try_borrow_mut_lamports()? += account_lamports;
+
+        emit!(AccountForceClosed {
+            account: account_to_close.key(),
+            lamports: account_lamports,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Publish a RelayerInfo so wallets can discover the signing relayer
+    /// endpoint_hash is SHA256 of the relayer's endpoint URL; `stake`
+    /// lamports, at least MIN_RELAYER_STAKE, are locked in the account
+    pub fn register_relayer(
+        ctx: Context<RegisterRelayer>,
+        fee_schedule: RelayerFeeSchedule,
+        endpoint_hash: [u8; 32],
+        stake: u64,
+    ) -> Result<()> {
+        fee_schedule.validate()?;
+        require!(stake >= MIN_RELAYER_STAKE, MixerError::InsufficientRelayerStake);
+
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.relayer.to_account_info(),
+                    to: ctx.accounts.relayer_info.to_account_info(),
+                },
+            ),
+            stake,
+        )?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let relayer_info = &mut ctx.accounts.relayer_info;
+        relayer_info.relayer = ctx.accounts.relayer.key();
+        relayer_info.fee_schedule = fee_schedule;
+        relayer_info.endpoint_hash = endpoint_hash;
+        relayer_info.status = RelayerStatus::Active;
+        relayer_info.registered_at = now;
+        relayer_info.updated_at = now;
+        relayer_info.bump = ctx.bumps.relayer_info;
+        relayer_info.stake = stake;
+        relayer_info.open_report = false;
+        relayer_info.withdrawals_executed = 0;
+        relayer_info.fees_earned = 0;
+        relayer_info.failures = 0;
+
+        msg!("Relayer registered: {:?}", relayer_info.relayer);
+        Ok(())
+    }
+
+    /// Change a relayer's fee schedule, endpoint or status
+    pub fn update_relayer(
+        ctx: Context<UpdateRelayer>,
+        fee_schedule: RelayerFeeSchedule,
+        endpoint_hash: [u8; 32],
+        status: RelayerStatus,
+    ) -> Result<()> {
+        fee_schedule.validate()?;
+
+        let relayer_info = &mut ctx.accounts.relayer_info;
+        relayer_info.fee_schedule = fee_schedule;
+        relayer_info.endpoint_hash = endpoint_hash;
+        relayer_info.set_status(status)?;
+        relayer_info.updated_at = Clock::get()?.unix_timestamp;
+
+        msg!("Relayer updated: {:?} ({:?})", relayer_info.relayer, status);
+        Ok(())
+    }
+
+    /// Lock more lamports in a relayer's stake
+    pub fn add_relayer_stake(ctx: Context<UpdateRelayer>, amount: u64) -> Result<()> {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.relayer.to_account_info(),
+                    to: ctx.accounts.relayer_info.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let relayer_info = &mut ctx.accounts.relayer_info;
+        relayer_info.stake = relayer_info
+            .stake
+            .checked_add(amount)
+            .ok_or(MixerError::ArithmeticOverflow)?;
+
+        msg!("Relayer stake: {} lamports", relayer_info.stake);
+        Ok(())
+    }
+
+    /// Remove a relayer from the registry, returning its stake and rent
+    /// The relayer must have been paused for RELAYER_UNBONDING_PERIOD with
+    /// no open report
+    pub fn deregister_relayer(ctx: Context<DeregisterRelayer>) -> Result<()> {
+        require!(
+            ctx.accounts.relayer_info.can_deregister(Clock::get()?.unix_timestamp),
+            MixerError::RelayerStillBonded
+        );
+
+        msg!("Relayer deregistered: {:?}", ctx.accounts.relayer.key());
+        Ok(())
+    }
+
+    /// Report a relayer's misbehavior for the authority to adjudicate
+    /// evidence_hash commits to the off-chain evidence, e.g. a withheld
+    /// signed withdrawal intent. The reporter locks RELAYER_REPORT_BOND in
+    /// the report until it is resolved
+    pub fn report_relayer(ctx: Context<ReportRelayer>, evidence_hash: [u8; 32]) -> Result<()> {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.reporter.to_account_info(),
+                    to: ctx.accounts.report.to_account_info(),
+                },
+            ),
+            RELAYER_REPORT_BOND,
+        )?;
+
+        let report = &mut ctx.accounts.report;
+        report.relayer = ctx.accounts.relayer_info.relayer;
+        report.reporter = ctx.accounts.reporter.key();
+        report.evidence_hash = evidence_hash;
+        report.reported_at = Clock::get()?.unix_timestamp;
+        report.bump = ctx.bumps.report;
+        report.bond = RELAYER_REPORT_BOND;
+
+        ctx.accounts.relayer_info.open_report = true;
+
+        msg!("Relayer reported: {:?}", report.relayer);
+        Ok(())
+    }
+
+    /// Uphold a report: move up to `amount` of the relayer's stake to the
+    /// treasury and close the report, refunding the reporter's bond
+    pub fn slash_relayer(ctx: Context<AdjudicateRelayerReport>, amount: u64) -> Result<()> {
+        let signers = admin_signers(&ctx.accounts.authority.key(), ctx.remaining_accounts);
+        ctx.accounts.config.check_admin_approval(&signers)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::SlashRelayer,
+            &(ctx.accounts.relayer_info.relayer, amount),
+        )?;
+
+        let relayer_info = &mut ctx.accounts.relayer_info;
+        let slashed = relayer_info.slash(amount);
+        relayer_info.open_report = false;
+        relayer_info.failures = relayer_info.failures.saturating_add(1);
+
+        let relayer_account = relayer_info.to_account_info();
+        **relayer_account.try_borrow_mut_lamports()? = relayer_account
+            .lamports()
+            .checked_sub(slashed)
+            .ok_or(MixerError::InsufficientFunds)?;
+        **ctx.accounts.fee_collector.try_borrow_mut_lamports()? = ctx
+            .accounts
+            .fee_collector
+            .lamports()
+            .checked_add(slashed)
+            .ok_or(MixerError::ArithmeticOverflow)?;
+
+        msg!("Relayer {:?} slashed {} lamports", relayer_info.relayer, slashed);
+        Ok(())
+    }
+
+    /// Reject a report without slashing; the reporter's bond goes to the
+    /// treasury
+    pub fn dismiss_relayer_report(ctx: Context<AdjudicateRelayerReport>) -> Result<()> {
+        let signers = admin_signers(&ctx.accounts.authority.key(), ctx.remaining_accounts);
+        ctx.accounts.config.check_admin_approval(&signers)?;
+        log_admin_action(
+            &mut ctx.accounts.admin_log,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            AdminAction::DismissRelayerReport,
+            &ctx.accounts.relayer_info.relayer,
+        )?;
+
+        ctx.accounts.relayer_info.open_report = false;
+
+        let bond = ctx.accounts.report.bond;
+        let report_account = ctx.accounts.report.to_account_info();
+        **report_account.try_borrow_mut_lamports()? = report_account
+            .lamports()
+            .checked_sub(bond)
+            .ok_or(MixerError::InsufficientFunds)?;
+        **ctx.accounts.fee_collector.try_borrow_mut_lamports()? = ctx
+            .accounts
+            .fee_collector
+            .lamports()
+            .checked_add(bond)
+            .ok_or(MixerError::ArithmeticOverflow)?;
+
+        msg!("Report against relayer {:?} dismissed", ctx.accounts.relayer_info.relayer);
+        Ok(())
+    }
+}
+
+// Helpers
+
+/// Whether `denomination` is one of the fixed pool sizes
+pub fn is_valid_denomination(denomination: u64) -> bool {
+    denomination == DENOMINATION_01_SOL
+        || denomination == DENOMINATION_1_SOL
+        || denomination == DENOMINATION_10_SOL
+        || denomination == DENOMINATION_100_SOL
+}
+
+/// Whether `min_delay` is within MIN_TIME_DELAY..=MAX_TIME_DELAY
+/// The cap keeps a pool's notes from being locked up indefinitely
+pub fn is_valid_time_delay(min_delay: i64) -> bool {
+    (MIN_TIME_DELAY..=MAX_TIME_DELAY).contains(&min_delay)
+}
+
+/// Check a token pool size for a mint with `decimals`: a whole number of
+/// tokens, at least MIN_TOKEN_DENOMINATION_UNITS, with a single leading digit
+/// from ROUND_DENOMINATION_DIGITS (10, 20, 50, 100, ... tokens)
+pub fn check_token_denomination(denomination: u64, decimals: u8) -> Result<()> {
+    let one_token = 10u64
+        .checked_pow(decimals as u32)
+        .ok_or(MixerError::InvalidDenomination)?;
+    let min_denomination = MIN_TOKEN_DENOMINATION_UNITS
+        .checked_mul(one_token)
+        .ok_or(MixerError::InvalidDenomination)?;
+
+    require!(
+        denomination >= min_denomination,
+        MixerError::DenominationBelowDustFloor
+    );
+    // No fractional tokens
+    let fraction = denomination % one_token;
+    require!(fraction == 0, MixerError::DenominationNotRound);
+
+    // Strip trailing zeros down to the leading digit(s)
+    let mut leading = denomination / one_token;
+    loop {
+        let last_digit = leading % 10;
+        if last_digit != 0 {
+            break;
+        }
+        leading /= 10;
+    }
+    require!(
+        ROUND_DENOMINATION_DIGITS.contains(&leading),
+        MixerError::DenominationNotRound
+    );
+
+    Ok(())
+}
+
+/// Whether a mint with `decimals` and `supply` is a non-fungible token
+pub fn is_nft_mint(decimals: u8, supply: u64) -> bool {
+    decimals == 0 && supply == 1
+}
+
+/// Accounts used by record_deposit
+struct DepositAccounts<'a, 'info> {
+    config: &'a Config,
+    pool: &'a mut Account<'info, MixerPool>,
+    merkle_tree: &'a AccountLoader<'info, MerkleTreeState>,
+    poseidon_tree: &'a Option<AccountLoader<'info, MerkleTreeState>>,
+    commitment_record: &'a mut Account<'info, CommitmentRecord>,
+    commitment_record_bump: u8,
+    encrypted_note: &'a mut Account<'info, EncryptedNote>,
+    encrypted_note_bump: u8,
+    depositor: Pubkey,
+}
+
+/// Shared body of deposit and deposit_token: store the commitment and its
+/// encrypted note and insert it into the pool's trees
+/// The caller moves the denomination into the pool. Returns the leaf index
+fn record_deposit(
+    accounts: DepositAccounts,
+    commitment: [u8; 32],
+    encrypted_data: Vec<u8>,
+) -> Result<u32> {
+    let DepositAccounts {
+        config,
+        pool,
+        merkle_tree,
+        poseidon_tree,
+        commitment_record,
+        commitment_record_bump,
+        encrypted_note,
+        encrypted_note_bump,
+        depositor,
+    } = accounts;
+
+    // Check if mixer is paused
+    require!(!config.paused, MixerError::MixerPaused);
+
+    // Validate commitment is not all zeros
+    require!(
+        commitment != [0u8; 32],
+        MixerError::InvalidCommitment
+    );
+
+    // SECURITY FIX: Validate encrypted data size to prevent DoS
+    require!(
+        encrypted_data.len() <= 200,
+        MixerError::EncryptedDataTooLarge
+    );
+
+    // Store commitment record
+    // The deposit timestamp is committed in the ZK tree leaf as
+    // poseidon_leaf(commitment, timestamp) so withdraw_with_proof can
+    // enforce the delay per note
+    let leaf_index = pool.next_leaf_index;
+    commitment_record.pool = pool.key();
+    commitment_record.commitment = commitment;
+    commitment_record.leaf_index = leaf_index;
+    commitment_record.timestamp = Clock::get()?.unix_timestamp;
+    commitment_record.bump = commitment_record_bump;
+
+    // Store encrypted note on-chain for easy recovery across devices
+    encrypted_note.owner = depositor;
+    encrypted_note.encrypted_data = encrypted_data;
+    encrypted_note.pool = pool.key();
+    encrypted_note.leaf_index = leaf_index;
+    encrypted_note.timestamp = Clock::get()?.unix_timestamp;
+    encrypted_note.bump = encrypted_note_bump;
+
+    // Insert the commitment into the current tree and publish the new root
+    // Fails with TreeFull once the generation is full, see rollover_tree
+    let merkle_tree = &mut merkle_tree.load_mut()?;
+    merkle_tree.insert(commitment)?;
+    pool.merkle_root = merkle_tree.root();
+
+    insert_into_poseidon_tree(
+        pool,
+        poseidon_tree,
+        &[commitment],
+        commitment_record.timestamp,
+    )?;
+
+    // Update pool state
+    pool.deposit_counter.record(
+        &config.deposit_rate_limit,
+        1,
+        commitment_record.timestamp,
+    )?;
+    pool.next_leaf_index += 1;
+    pool.total_deposits += 1;
+
+    Ok(leaf_index)
+}
+
+/// Create and write a SpentNullifier PDA outside of an init constraint
+/// Like init, tolerates lamports already sent to the address
+fn create_spent_nullifier<'info>(
+    account: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    spent: SpentNullifier,
+) -> Result<()> {
+    let bump = [spent.bump];
+    let seeds: &[&[u8]] = &[
+        b"nullifier",
+        spent.pool.as_ref(),
+        spent.nullifier_hash.as_ref(),
+        &bump,
+    ];
+    let signer_seeds = &[seeds];
+    let program = system_program.to_account_info();
+    let rent = Rent::get()?.minimum_balance(SpentNullifier::LEN);
+
+    if account.lamports() == 0 {
+        system_program::create_account(
+            CpiContext::new_with_signer(
+                program,
+                system_program::CreateAccount {
+                    from: payer.to_account_info(),
+                    to: account.clone(),
+                },
+                signer_seeds,
+            ),
+            rent,
+            SpentNullifier::LEN as u64,
+            &crate::ID,
+        )?;
+    } else {
+        let top_up = rent.saturating_sub(account.lamports());
+        if top_up > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    program.clone(),
+                    system_program::Transfer {
+                        from: payer.to_account_info(),
+                        to: account.clone(),
+                    },
+                ),
+                top_up,
+            )?;
+        }
+        system_program::allocate(
+            CpiContext::new_with_signer(
+                program.clone(),
+                system_program::Allocate { account_to_allocate: account.clone() },
+                signer_seeds,
+            ),
+            SpentNullifier::LEN as u64,
+        )?;
+        system_program::assign(
+            CpiContext::new_with_signer(
+                program,
+                system_program::Assign { account_to_assign: account.clone() },
+                signer_seeds,
+            ),
+            &crate::ID,
+        )?;
+    }
+
+    spent.try_serialize(&mut &mut account.try_borrow_mut_data()?[..])
+}
+
+/// Whether a program account with `data` may be force closed: only scratch
+/// accounts that hold no funds and guard no invariant (spent nullifiers,
+/// bonds, the admin log, ...) can be
+pub fn is_force_closable(data: &[u8]) -> bool {
+    const CLOSABLE: [[u8; 8]; 3] = [
+        ProofBuffer::DISCRIMINATOR,
+        PendingForceClose::DISCRIMINATOR,
+        AuditCursor::DISCRIMINATOR,
+    ];
+
+    data.len() >= 8 && CLOSABLE.iter().any(|discriminator| data[..8] == *discriminator)
+}
+
+/// Reject force closing `account` unless is_force_closable
+fn check_force_closable(account: &AccountInfo) -> Result<()> {
+    require!(
+        is_force_closable(&account.try_borrow_data()?),
+        MixerError::AccountNotForceClosable
+    );
+    Ok(())
+}
+
+/// The pool must still be closed and its reclaim timelock must have passed
+fn check_reclaimable(retired_pool: &RetiredPool, pool: &AccountInfo) -> Result<()> {
+    // A pool recreated at the same address keeps its nullifiers
+    require!(pool.data_is_empty(), MixerError::PoolNotRetired);
+
+    require!(
+        retired_pool.is_reclaimable(Clock::get()?.unix_timestamp),
+        MixerError::ReclaimTimelockNotElapsed
+    );
+
+    Ok(())
+}
+
+/// Check that a note deposited at deposit_timestamp has aged at least min_delay
+pub fn check_deposit_age(current_time: i64, deposit_timestamp: i64, min_delay: i64) -> Result<()> {
+    let deposit_age = current_time
+        .checked_sub(deposit_timestamp)
+        .ok_or(MixerError::TimeCalculationError)?;
+
+    require!(
+        deposit_age >= min_delay,
+        MixerError::TimeDelayNotMet
+    );
+
+    Ok(())
+}
+
+/// Accounts used by process_legacy_withdrawal
+struct LegacyWithdrawAccounts<'a, 'info> {
+    config: &'a Config,
+    pool: &'a mut Account<'info, MixerPool>,
+    merkle_tree: &'a AccountLoader<'info, MerkleTreeState>,
+    nullifier_registry: &'a AccountInfo<'info>,
+    nullifier_bloom: &'a AccountInfo<'info>,
+    commitment_blocklist: &'a AccountInfo<'info>,
+    spent_nullifier: &'a mut Account<'info, SpentNullifier>,
+    spent_nullifier_bump: u8,
+}
+
+/// Note opened by a SHA256-phase withdrawal
+struct LegacyNote {
+    nullifier: [u8; 32],
+    secret: [u8; 32],
+}
+
+/// Accounts used by process_emergency_withdrawal
+struct EmergencyWithdrawAccounts<'a, 'info> {
+    config: &'a mut Account<'info, Config>,
+    pool: &'a mut Account<'info, MixerPool>,
+    commitment_record: &'a Account<'info, CommitmentRecord>,
+    nullifier_registry: &'a AccountInfo<'info>,
+    commitment_blocklist: &'a AccountInfo<'info>,
+    spent_nullifier: &'a mut Account<'info, SpentNullifier>,
+    spent_nullifier_bump: u8,
+    zk_spent_nullifier: &'a AccountInfo<'info>,
+}
+
+/// Shared body of emergency_withdraw and emergency_withdraw_token: check
+/// emergency withdrawals are open and that the note is the deposit at
+/// `leaf_index` and unspent, then spend it under both its SHA256-phase and
+/// ZK nullifiers. The caller pays out the returned denomination
+fn process_emergency_withdrawal(
+    accounts: EmergencyWithdrawAccounts,
+    note: LegacyNote,
+    leaf_index: u32,
+) -> Result<u64> {
+    let EmergencyWithdrawAccounts {
+        config,
+        pool,
+        commitment_record,
+        nullifier_registry,
+        commitment_blocklist,
+        spent_nullifier,
+        spent_nullifier_bump,
+        zk_spent_nullifier,
+    } = accounts;
+    let LegacyNote { nullifier, secret } = note;
+
+    let current_time = Clock::get()?.unix_timestamp;
+    require!(
+        config.emergency_withdrawals_open(current_time),
+        MixerError::EmergencyWithdrawalsNotOpen
+    );
+    enter_emergency_mode(config, current_time);
+
+    // Notes deposited for either withdrawal phase can be reclaimed
+    require!(
+        note_opens(&commitment_record.commitment, &nullifier, &secret),
+        MixerError::InvalidCommitment
+    );
+
+    // Flagged notes stay locked even now
+    require!(
+        !CommitmentBlocklist::is_blocked_in(commitment_blocklist, &commitment_record.commitment)?,
+        MixerError::CommitmentBlocked
+    );
+
+    // A note already withdrawn through either phase is spent; the legacy
+    // form is rejected by the spent_nullifier init itself
+    let zk_nullifier_hash =
+        merkle_poseidon::poseidon_nullifier_hash(&nullifier, &pool.key(), CIRCUIT_VERSION);
+    require!(
+        zk_spent_nullifier.data_is_empty()
+            && !NullifierRegistry::is_used_in(nullifier_registry, &nullifier)?
+            && !NullifierRegistry::is_used_in(nullifier_registry, &zk_nullifier_hash)?,
+        MixerError::NullifierAlreadyUsed
+    );
+
+    spent_nullifier.record(
+        pool.key(),
+        compute_pool_nullifier(&nullifier, &pool.key()),
+        current_time,
+        spent_nullifier_bump,
+    );
+    pool.total_withdrawals += 1;
+
+    emit!(EmergencyWithdrawal {
+        pool: pool.key(),
+        leaf_index,
+        commitment: commitment_record.commitment,
+        amount: pool.denomination,
+        timestamp: current_time,
+    });
+
+    msg!("Emergency withdrawal of leaf {}: {}", leaf_index, pool.denomination);
+    Ok(pool.denomination)
+}
+
+/// Whether `nullifier` and `secret` open `commitment`, hashed for either
+/// withdrawal phase
+pub fn note_opens(commitment: &[u8; 32], nullifier: &[u8; 32], secret: &[u8; 32]) -> bool {
+    commitment_hash(secret, nullifier) == *commitment
+        || merkle_poseidon::poseidon_commitment(secret, nullifier) == *commitment
+}
+
+/// Whether a SpentNullifier keyed `spent_nullifier_hash` spent the note
+/// with `nullifier` in `pool`, as a SHA256-phase or ZK withdrawal
+pub fn note_spent_as(pool: &Pubkey, nullifier: &[u8; 32], spent_nullifier_hash: &[u8; 32]) -> bool {
+    let zk_nullifier_hash =
+        merkle_poseidon::poseidon_nullifier_hash(nullifier, pool, CIRCUIT_VERSION);
+    *spent_nullifier_hash == compute_pool_nullifier(nullifier, pool)
+        || *spent_nullifier_hash == compute_pool_nullifier(&zk_nullifier_hash, pool)
+}
+
+/// Latch emergency mode, announcing it the first time
+fn enter_emergency_mode(config: &mut Config, now: i64) {
+    if !config.emergency_mode {
+        config.emergency_mode = true;
+        emit!(EmergencyModeEntered {
+            paused_at: config.paused_at,
+            timestamp: now,
+        });
+    }
+}
+
+/// Shared body of withdraw, withdraw_with_canopy and withdraw_token
+/// pay_out moves the denomination out of the pool and returns (net, fee)
+fn process_legacy_withdrawal<'info>(
+    accounts: LegacyWithdrawAccounts<'_, 'info>,
+    note: LegacyNote,
+    merkle_root: [u8; 32],
+    merkle_proof: &[[u8; 32]],
+    path_indices: &[bool],
+    pay_out: impl FnOnce(&Account<'info, MixerPool>) -> Result<(u64, u64)>,
+) -> Result<()> {
+    let LegacyWithdrawAccounts {
+        config,
+        pool,
+        merkle_tree,
+        nullifier_registry,
+        nullifier_bloom,
+        commitment_blocklist,
+        spent_nullifier,
+        spent_nullifier_bump,
+    } = accounts;
+    let LegacyNote { nullifier, secret } = note;
+
+    // Check if mixer is paused
+    require!(!config.paused, MixerError::MixerPaused);
+
+    // SHA256-phase withdrawals must still be enabled for this pool
+    require!(
+        pool.withdraw_mode.allows_legacy(),
+        MixerError::WithdrawModeNotAllowed
+    );
+
+    // Verify nullifier is not all zeros
+    require!(
+        nullifier != [0u8; 32],
+        MixerError::InvalidNullifier
+    );
+
+    // Verify secret is not all zeros
+    require!(
+        secret != [0u8; 32],
+        MixerError::InvalidSecret
+    );
+
+    // Nullifiers spent before SpentNullifier PDAs are still in the old registry;
+    // later ones are rejected by the spent_nullifier init itself
+    check_and_record_nullifier(nullifier_registry, nullifier_bloom, &nullifier)?;
+
+    // The root must be one the pool's tree actually had
+    let merkle_tree = merkle_tree.load()?;
+    require!(
+        merkle_tree.is_known_root(&merkle_root),
+        MixerError::UnknownMerkleRoot
+    );
+
+    // CRITICAL SECURITY FIX: Verify the Merkle proof (Phase 1)
+    // Compute commitment from secret and nullifier using SHA256
+    let commitment = commitment_hash(&secret, &nullifier);
+    require!(
+        !CommitmentBlocklist::is_blocked_in(commitment_blocklist, &commitment)?,
+        MixerError::CommitmentBlocked
+    );
+
+    // Verify the commitment is in the Merkle tree using the provided proof,
+    // hashed the way this tree's hash and pool version hash it
+    // The proof must be exactly as deep as the pool's tree
+    let proof_valid = tree_hasher(merkle_tree.hash).verify_proof(
+        merkle_tree.version,
+        &commitment,
+        merkle_proof,
+        path_indices,
+        pool.tree_depth as usize,
+        &merkle_root
+    )?;
+
+    require!(proof_valid, MixerError::InvalidMerkleProof);
+
+    // CRITICAL SECURITY FIX: Verify pool has enough deposits to provide anonymity
+    // Require at least 2 deposits to prevent trivial deanonymization
+    require!(
+        pool.total_deposits >= 2,
+        MixerError::InsufficientAnonymitySet
+    );
+
+    // CRITICAL SECURITY FIX: Enforce minimum time delay
+    // Check that sufficient time has passed since pool creation
+    // Note: This is a simplified check. In Phase 2 with ZK, we can prove
+    // individual deposit age without revealing which deposit.
+    let current_time = Clock::get()?.unix_timestamp;
+    let pool_age = current_time.checked_sub(pool.creation_timestamp)
+        .ok_or(MixerError::TimeCalculationError)?;
+
+    require!(
+        pool_age >= pool.min_delay,
+        MixerError::TimeDelayNotMet
+    );
+
+    // Transfer the denomination out of the pool, minus the protocol fee
+    let (net_withdrawal, fee_amount) = pay_out(pool)?;
+
+    // Mark nullifier as used
+    spent_nullifier.record(
+        pool.key(),
+        compute_pool_nullifier(&nullifier, &pool.key()),
+        current_time,
+        spent_nullifier_bump,
+    );
+
+    // Update pool statistics
+    pool.withdrawal_counter.record(&config.withdrawal_rate_limit, 1, current_time)?;
+    pool.total_withdrawals += 1;
+    pool.fee_stats.record(fee_amount, Clock::get()?.epoch)?;
+    emit_fee_holiday(pool, current_time)?;
+
+    msg!(
+        "Withdrawal completed: {} (fee: {})",
+        net_withdrawal,
+        fee_amount
+    );
+
+    Ok(())
+}
+
+/// Reject a nullifier recorded in the pool's pre-PDA registry, then add it to
+/// the pool's bloom filter if it has one
+/// The filter holds every registry entry, so a miss skips the registry scan
+fn check_and_record_nullifier(
+    registry: &AccountInfo,
+    bloom: &AccountInfo,
+    nullifier: &[u8; 32],
+) -> Result<()> {
+    let mut bloom = NullifierBloom::load_mut(bloom)?;
+    let maybe_spent = match &bloom {
+        Some(bloom) => bloom.might_contain(nullifier),
+        None => true,
+    };
+
+    if maybe_spent {
+        require!(
+            !NullifierRegistry::is_used_in(registry, nullifier)?,
+            MixerError::NullifierAlreadyUsed
+        );
+    }
+
+    if let Some(bloom) = bloom.as_mut() {
+        bloom.insert(nullifier);
+    }
+
+    Ok(())
+}
+
+/// Accounts used by process_zk_withdrawal
+struct ZkWithdrawAccounts<'a, 'info> {
+    config: &'a Config,
+    pool: &'a mut Account<'info, MixerPool>,
+    verification_key: &'a VerificationKey,
+    nullifier_registry: &'a AccountInfo<'info>,
+    nullifier_bloom: &'a AccountInfo<'info>,
+    spent_nullifier: &'a mut Account<'info, SpentNullifier>,
+    spent_nullifier_bump: u8,
+    poseidon_tree: Option<&'a AccountLoader<'info, MerkleTreeState>>,
+    recipient: &'a AccountInfo<'info>,
+    fee_vault: &'a mut Account<'info, FeeVault>,
+    token_program: Option<&'a Program<'info, Token>>,
+    /// Signer submitting the withdrawal, bound into the proof
+    relayer: Pubkey,
+    /// Account paid the relayer fee and any rebate
+    relayer_account: &'a AccountInfo<'info>,
+    /// Lamports of the recipient's share paid to relayer_account
+    relayer_fee: u64,
+    /// Submitting relayer's RelayerInfo, when registered
+    relayer_info: Option<&'a mut Account<'info, RelayerInfo>>,
+    /// Pool's AnonymityMining and the recipient's AnonymityPoints, when
+    /// claiming anonymity points
+    anonymity_mining: Option<&'a mut Account<'info, AnonymityMining>>,
+    anonymity_points: Option<&'a mut Account<'info, AnonymityPoints>>,
+    /// Config's reward hook program and the accounts it takes
+    reward_hook: Option<&'a AccountInfo<'info>>,
+    reward_hook_accounts: &'a [AccountInfo<'info>],
+    /// Pool's AssociationSet and the association circuit's key, when the
+    /// proof shows the note is in the association set
+    association_set: Option<&'a Account<'info, AssociationSet>>,
+    association_verification_key: Option<&'a Account<'info, VerificationKey>>,
+}
+
+/// Shared body of withdraw_with_proof and withdraw_from_buffer
+fn process_zk_withdrawal(
+    accounts: ZkWithdrawAccounts,
+    payload: WithdrawProofPayload,
+) -> Result<()> {
+    let ZkWithdrawAccounts {
+        config,
+        pool,
+        verification_key,
+        nullifier_registry,
+        nullifier_bloom,
+        spent_nullifier,
+        spent_nullifier_bump,
+        poseidon_tree,
+        recipient,
+        fee_vault,
+        token_program,
+        relayer,
+        relayer_account,
+        relayer_fee,
+        relayer_info,
+        anonymity_mining,
+        anonymity_points,
+        reward_hook,
+        reward_hook_accounts,
+        association_set,
+        association_verification_key,
+    } = accounts;
+    let WithdrawProofPayload {
+        proof,
+        merkle_root,
+        nullifier_hash,
+        deposit_timestamp,
+    } = payload;
+
+    // Check if mixer is paused
+    require!(!config.paused, MixerError::MixerPaused);
+
+    // ZK withdrawals must be enabled for this pool
+    require!(
+        pool.withdraw_mode.allows_zk(),
+        MixerError::WithdrawModeNotAllowed
+    );
+
+    // Verify nullifier hash is not all zeros
+    require!(
+        nullifier_hash != [0u8; 32],
+        MixerError::InvalidNullifier
+    );
+
+    // Check nullifier hash hasn't been used; the spent_nullifier init already
+    // rejects hashes spent since the switch to per-nullifier PDAs
+    check_and_record_nullifier(nullifier_registry, nullifier_bloom, &nullifier_hash)?;
+
+    // Enforce the minimum delay against the spent note's own deposit time
+    let current_time = Clock::get()?.unix_timestamp;
+    check_deposit_age(current_time, deposit_timestamp, pool.min_delay)?;
+
+    check_known_root(pool, poseidon_tree, &merkle_root)?;
+
+    // Bind the proof to this pool and recipient
+    let context_hash = groth16::compute_context_hash(
+        &pool.key(),
+        &recipient.key(),
+        &nullifier_hash,
+    );
+
+    // Association set proofs are checked against the set's current root with
+    // the association circuit's key
+    let (verification_key, association) =
+        match (association_set, association_verification_key) {
+            (Some(association_set), Some(association_verification_key)) => {
+                require!(
+                    association_set.version > 0,
+                    MixerError::AssociationRootNotSet
+                );
+                (
+                    &**association_verification_key,
+                    Some((association_set.root, association_set.version)),
+                )
+            }
+            (None, None) => (verification_key, None),
+            _ => return err!(MixerError::AssociationSetMissing),
+        };
+    let association_root = association.map(|(root, _)| root);
+
+    let public_inputs = PublicInputs {
+        root: merkle_root,
+        nullifier_hash,
+        deposit_timestamp,
+        context_hash,
+        pool: pool.key(),
+        circuit_version: CIRCUIT_VERSION,
+        relayer,
+        association_root,
+    };
+
+    let proof = proof.into_proof()?;
+    let proof_valid = groth16::verify_groth16_proof(
+        &proof,
+        &public_inputs,
+        verification_key,
+    )?;
+
+    require!(proof_valid, MixerError::InvalidProof);
+
+    // Transfer the denomination out of the pool, minus the protocol fee
+    // The cap may have been lowered since an intent was posted
+    config.check_relayer_fee(pool.denomination, relayer_fee)?;
+
+    // Registered relayers also get the rebate out of the protocol fee
+    let rebate = match relayer_info {
+        Some(_) => config.relayer_rebate_lamports,
+        None => 0,
+    };
+    let (net_withdrawal, fee_amount) = pay_out_relayed_withdrawal(
+        &pool.to_account_info(),
+        recipient,
+        relayer_account,
+        &fee_vault.to_account_info(),
+        pool.split_withdrawal(current_time)?,
+        relayer_fee,
+        rebate,
+    )?;
+    fee_vault.record_fees(fee_amount)?;
+    sync_wrapped_sol_recipient(recipient, token_program)?;
+
+    // Mark nullifier hash as used
+    spent_nullifier.record(
+        pool.key(),
+        compute_pool_nullifier(&nullifier_hash, &pool.key()),
+        current_time,
+        spent_nullifier_bump,
+    );
+
+    // Update pool statistics
+    pool.withdrawal_counter.record(&config.withdrawal_rate_limit, 1, current_time)?;
+    pool.total_withdrawals += 1;
+    pool.fee_stats.record(fee_amount, Clock::get()?.epoch)?;
+    emit_fee_holiday(pool, current_time)?;
+
+    if let Some(relayer_info) = relayer_info {
+        relayer_info.record_withdrawal(relayer_fee)?;
+    }
+
+    // The proven deposit_timestamp bound is as far back as the note can
+    // claim to have been in the pool
+    if let (Some(anonymity_mining), Some(anonymity_points)) = (anonymity_mining, anonymity_points) {
+        let points = anonymity_mining.award(anonymity_points, deposit_timestamp, current_time)?;
+        msg!("Awarded {} anonymity points", points);
+
+        if points > 0 && config.reward_hook != Pubkey::default() {
+            let reward_hook = reward_hook.ok_or(MixerError::RewardHookMissing)?;
+            let denomination = pool.denomination.to_le_bytes();
+            let seeds: &[&[u8]] = &[b"pool", pool.mint.as_ref(), denomination.as_ref(), &[pool.bump]];
+            mining::invoke_reward_hook(
+                reward_hook,
+                &pool.to_account_info(),
+                reward_hook_accounts,
+                seeds,
+                &RewardHookPayload {
+                    pool: pool.key(),
+                    recipient: recipient.key(),
+                    relayer: relayer_account.key(),
+                    points,
+                },
+            )?;
+        }
+    }
+
+    if let Some((association_root, association_version)) = association {
+        emit!(AssociatedWithdrawal {
+            pool: pool.key(),
+            nullifier_hash,
+            association_root,
+            association_version,
+            timestamp: current_time,
+        });
+    }
+
+    msg!(
+        "ZK withdrawal completed: {} lamports (fee: {} lamports) to {:?}",
+        net_withdrawal,
+        fee_amount,
+        recipient.key()
+    );
+
+    Ok(())
+}
+
+/// Check a ZK proof's Merkle root is one of the pool's
+/// Dual-tree pools check the root against their on-chain Poseidon tree;
+/// otherwise it must be the one last committed by the attestors
+fn check_known_root(
+    pool: &MixerPool,
+    poseidon_tree: Option<&AccountLoader<MerkleTreeState>>,
+    merkle_root: &[u8; 32],
+) -> Result<()> {
+    match poseidon_tree {
+        Some(poseidon_tree) => require!(
+            poseidon_tree.load()?.is_known_root(merkle_root),
+            MixerError::UnknownMerkleRoot
+        ),
+        None => require!(
+            pool.attested_root != [0u8; 32] && *merkle_root == pool.attested_root,
+            MixerError::RootNotAttested
+        ),
+    }
+    Ok(())
+}
+
+/// Mirror deposited commitments into a dual-tree pool's Poseidon tree
+/// Leaves commit to the deposit time as poseidon_leaf(commitment, timestamp),
+/// the form the withdrawal circuit opens
+fn insert_into_poseidon_tree(
+    pool: &MixerPool,
+    poseidon_tree: &Option<AccountLoader<MerkleTreeState>>,
+    commitments: &[[u8; 32]],
+    timestamp: i64,
+) -> Result<()> {
+    if !pool.dual_tree {
+        return Ok(());
+    }
+
+    let poseidon_tree = poseidon_tree.as_ref().ok_or(MixerError::PoseidonTreeMissing)?;
+    let leaves: Vec<[u8; 32]> = commitments
+        .iter()
+        .map(|commitment| merkle_poseidon::poseidon_leaf(commitment, timestamp))
+        .collect();
+
+    poseidon_tree.load_mut()?.insert_batch(&leaves)?;
+    Ok(())
+}
+
+/// CPI config's screening program, when one is set, for a deposit of
+/// `amount` by `depositor`; the deposit fails with it
+fn screen_deposit<'info>(
+    config: &Config,
+    pool: &Account<'info, MixerPool>,
+    depositor: &AccountInfo<'info>,
+    screening_program: Option<&AccountInfo<'info>>,
+    screening_accounts: &[AccountInfo<'info>],
+    amount: u64,
+) -> Result<()> {
+    if config.screening_program == Pubkey::default() {
+        return Ok(());
+    }
+
+    let screening_program = screening_program.ok_or(MixerError::ScreeningProgramMissing)?;
+    let denomination = pool.denomination.to_le_bytes();
+    let seeds: &[&[u8]] = &[b"pool", pool.mint.as_ref(), denomination.as_ref(), &[pool.bump]];
+    screening::invoke_screening(
+        screening_program,
+        &pool.to_account_info(),
+        depositor,
+        screening_accounts,
+        seeds,
+        &ScreeningPayload {
+            pool: pool.key(),
+            mint: pool.mint,
+            depositor: depositor.key(),
+            amount,
+        },
+    )
+}
+
+/// Transfer `amount` lamports from the depositor into the pool
+fn collect_deposit<'info>(
+    depositor: &AccountInfo<'info>,
+    pool: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
+        depositor.key,
+        pool.key,
+        amount,
+    );
+
+    anchor_lang::solana_program::program::invoke(
+        &transfer_ix,
+        &[depositor.clone(), pool.clone(), system_program.clone()],
+    )?;
+
+    Ok(())
+}
+
+/// Move a withdrawal split into (net, protocol fee) out of the pool: the net
+/// amount to the recipient and the fee to the pool's fee vault.
+/// Returns (net, fee).
+fn pay_out_withdrawal<'info>(
+    pool: &AccountInfo<'info>,
+    recipient: &AccountInfo<'info>,
+    fee_vault: &AccountInfo<'info>,
+    (net_withdrawal, fee_amount): (u64, u64),
+) -> Result<(u64, u64)> {
+    let amount = net_withdrawal
+        .checked_add(fee_amount)
+        .ok_or(MixerError::ArithmeticOverflow)?;
+
+    // Verify pool has sufficient balance
+    require!(
+        pool.lamports() >= amount,
+        MixerError::InsufficientFunds
+    );
+
+    // Transfer net amount to recipient (manual lamport transfer for PDA with data)
+    **pool.try_borrow_mut_lamports()? = pool
+        .lamports()
+        .checked_sub(net_withdrawal)
+        .ok_or(MixerError::InsufficientFunds)?;
+
+    **recipient.try_borrow_mut_lamports()? = recipient
+        .lamports()
+        .checked_add(net_withdrawal)
+        .ok_or(MixerError::ArithmeticOverflow)?;
+
+    // Transfer fee to the fee vault
+    **pool.try_borrow_mut_lamports()? = pool
+        .lamports()
+        .checked_sub(fee_amount)
+        .ok_or(MixerError::InsufficientFunds)?;
+
+    **fee_vault.try_borrow_mut_lamports()? = fee_vault
+        .lamports()
+        .checked_add(fee_amount)
+        .ok_or(MixerError::ArithmeticOverflow)?;
+
+    Ok((net_withdrawal, fee_amount))
+}
+
+/// pay_out_withdrawal with `relayer_fee` of the recipient's share, and a
+/// `rebate` of up to the whole protocol fee, paid to `relayer`
+/// Returns what the recipient received and the fee collected
+fn pay_out_relayed_withdrawal<'info>(
+    pool: &AccountInfo<'info>,
+    recipient: &AccountInfo<'info>,
+    relayer: &AccountInfo<'info>,
+    fee_vault: &AccountInfo<'info>,
+    (net_withdrawal, fee_amount): (u64, u64),
+    relayer_fee: u64,
+    rebate: u64,
+) -> Result<(u64, u64)> {
+    let amount = net_withdrawal
+        .checked_add(fee_amount)
+        .ok_or(MixerError::ArithmeticOverflow)?;
+    let recipient_amount = net_withdrawal
+        .checked_sub(relayer_fee)
+        .ok_or(MixerError::RelayerFeeTooHigh)?;
+    let rebate = rebate.min(fee_amount);
+    let fee_amount = fee_amount - rebate;
+
+    require!(
+        pool.lamports() >= amount,
+        MixerError::InsufficientFunds
+    );
+
+    for (account, lamports) in [
+        (recipient, recipient_amount),
+        (relayer, relayer_fee + rebate),
+        (fee_vault, fee_amount),
+    ] {
+        **pool.try_borrow_mut_lamports()? = pool
+            .lamports()
+            .checked_sub(lamports)
+            .ok_or(MixerError::InsufficientFunds)?;
+
+        **account.try_borrow_mut_lamports()? = account
+            .lamports()
+            .checked_add(lamports)
+            .ok_or(MixerError::ArithmeticOverflow)?;
+    }
+
+    Ok((recipient_amount, fee_amount))
+}
+
+/// Unwrap a deposit_wsol depositor's `amount` wSOL into the pool's lamports
+/// The wSOL passes through a pool-owned unwrap account that is closed to the
+/// pool; its rent goes back to the depositor
+fn unwrap_into_pool(accounts: &DepositWsol, amount: u64) -> Result<()> {
+    let pool = accounts.pool.to_account_info();
+    let token_program = accounts.token_program.to_account_info();
+
+    token_interface::transfer_checked(
+        CpiContext::new(
+            token_program.clone(),
+            token_interface::TransferChecked {
+                from: accounts.depositor_token.to_account_info(),
+                mint: accounts.native_mint.to_account_info(),
+                to: accounts.unwrap_account.to_account_info(),
+                authority: accounts.depositor.to_account_info(),
+            },
+        ),
+        amount,
+        accounts.native_mint.decimals,
+    )?;
+
+    let denomination = accounts.pool.denomination.to_le_bytes();
+    let seeds: &[&[u8]] = &[b"pool", accounts.pool.mint.as_ref(), denomination.as_ref(), &[accounts.pool.bump]];
+    let pool_before = pool.lamports();
+
+    token_interface::close_account(CpiContext::new_with_signer(
+        token_program,
+        token_interface::CloseAccount {
+            account: accounts.unwrap_account.to_account_info(),
+            destination: pool.clone(),
+            authority: pool.clone(),
+        },
+        &[seeds],
+    ))?;
+
+    // Everything above `amount` is the unwrap account's rent
+    let rent = pool
+        .lamports()
+        .checked_sub(pool_before)
+        .and_then(|received| received.checked_sub(amount))
+        .ok_or(MixerError::DepositAmountMismatch)?;
+
+    let depositor = accounts.depositor.to_account_info();
+    **pool.try_borrow_mut_lamports()? -= rent;
+    **depositor.try_borrow_mut_lamports()? = depositor
+        .lamports()
+        .checked_add(rent)
+        .ok_or(MixerError::ArithmeticOverflow)?;
+
+    Ok(())
+}
+
+/// Complete a wrapped SOL payout: when the recipient is a wSOL token account,
+/// sync its token amount with the lamports just paid into it
+/// Other recipients are left as they are
+fn sync_wrapped_sol_recipient<'info>(
+    recipient: &AccountInfo<'info>,
+    token_program: Option<&Program<'info, Token>>,
+) -> Result<()> {
+    if *recipient.owner != spl_token::ID {
+        return Ok(());
+    }
+
+    require!(
+        is_wrapped_sol_account(recipient)?,
+        MixerError::NotWrappedSolAccount
+    );
+    let token_program = token_program.ok_or(MixerError::TokenProgramMissing)?;
+
+    token_interface::sync_native(CpiContext::new(
+        token_program.to_account_info(),
+        token_interface::SyncNative {
+            account: recipient.clone(),
+        },
+    ))
+}
+
+/// Whether an SPL Token account holds the native mint
+fn is_wrapped_sol_account(account: &AccountInfo) -> Result<bool> {
+    use anchor_lang::solana_program::program_pack::Pack;
+
+    let data = account.try_borrow_data()?;
+    let token_account = spl_token::state::Account::unpack(&data)
+        .map_err(|_| MixerError::NotWrappedSolAccount)?;
+    Ok(token_account.mint == spl_token::native_mint::ID)
+}
+
+/// Create the recipient's associated token account when withdraw_token is
+/// given one that doesn't exist yet, so a fresh wallet holding no SOL can
+/// receive tokens. The payer covers the rent. Returns whether it was created
+/// An existing recipient account must hold the pool's mint and not be frozen,
+/// and the mint must still be transferable, so a transfer that can't succeed
+/// is reported before the note is opened
+fn create_recipient_token_account(accounts: &WithdrawToken) -> Result<bool> {
+    require!(
+        !token_extensions::is_non_transferable(&accounts.mint.to_account_info())?,
+        MixerError::MintNotTransferable
+    );
+
+    let recipient_token = accounts.recipient_token.to_account_info();
+    let created = recipient_token.data_is_empty();
+
+    if created {
+        let recipient = accounts
+            .recipient
+            .as_ref()
+            .ok_or(MixerError::RecipientTokenAccountMissing)?;
+
+        // The associated token program rejects any other address
+        associated_token::create(CpiContext::new(
+            accounts.associated_token_program.to_account_info(),
+            associated_token::Create {
+                payer: accounts.payer.to_account_info(),
+                associated_token: recipient_token.clone(),
+                authority: recipient.to_account_info(),
+                mint: accounts.mint.to_account_info(),
+                system_program: accounts.system_program.to_account_info(),
+                token_program: accounts.token_program.to_account_info(),
+            },
+        ))?;
+    }
+
+    require_keys_eq!(
+        *recipient_token.owner,
+        accounts.token_program.key(),
+        MixerError::InvalidRecipientTokenAccount
+    );
+    let data = recipient_token.try_borrow_data()?;
+    let token_account = TokenAccount::try_deserialize(&mut &data[..])
+        .map_err(|_| MixerError::InvalidRecipientTokenAccount)?;
+    require_keys_eq!(
+        token_account.mint,
+        accounts.pool.mint,
+        MixerError::InvalidRecipientTokenAccount
+    );
+    require!(!token_account.is_frozen(), MixerError::RecipientAccountFrozen);
+
+    Ok(created)
+}
+
+/// Close an emptied token pool's vault, returning its rent to the authority
+fn close_vault(accounts: &ClosePool, vault_authority_bump: Option<u8>) -> Result<()> {
+    let (Some(vault), Some(vault_authority), Some(vault_authority_bump), Some(token_program)) = (
+        &accounts.vault,
+        &accounts.vault_authority,
+        vault_authority_bump,
+        &accounts.token_program,
+    ) else {
+        return err!(MixerError::VaultAccountsMissing);
+    };
+
+    require!(vault.amount == 0, MixerError::VaultNotEmpty);
+
+    let pool_key = accounts.pool.key();
+    let seeds: &[&[u8]] = &[b"vault_authority", pool_key.as_ref(), &[vault_authority_bump]];
+
+    token_interface::close_account(CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        token_interface::CloseAccount {
+            account: vault.to_account_info(),
+            destination: accounts.authority.to_account_info(),
+            authority: vault_authority.to_account_info(),
+        },
+        &[seeds],
+    ))
+}
+
+/// Emit FeeHolidayApplied for a withdrawal at `now` that paid no protocol
+/// fee because of the pool's fee holiday
+fn emit_fee_holiday(pool: &Account<MixerPool>, now: i64) -> Result<()> {
+    if pool.in_fee_holiday(now) {
+        let (_, waived_fee) = split_fee(pool.denomination, pool.standard_fee_bps())?;
+        emit!(FeeHolidayApplied {
+            pool: pool.key(),
+            fee_bps: 0,
+            waived_fee,
+            holiday_end: pool.fee_holiday_end,
+            timestamp: now,
+        });
+    }
+    Ok(())
+}
+
+/// Check an AdminControl instruction's signer and signer remaining accounts
+/// against the authority multisig
+fn require_admin_approval(ctx: &Context<AdminControl>) -> Result<()> {
+    let signers = admin_signers(&ctx.accounts.authority.key(), ctx.remaining_accounts);
+    ctx.accounts.config.check_admin_approval(&signers)
+}
+
+/// An admin instruction's signer followed by its signer remaining accounts
+fn admin_signers(authority: &Pubkey, remaining_accounts: &[AccountInfo]) -> Vec<Pubkey> {
+    std::iter::once(*authority)
+        .chain(
+            remaining_accounts
+                .iter()
+                .filter(|account| account.is_signer)
+                .map(|account| account.key()),
+        )
+        .collect()
+}
+
+/// Lock the creation bond in `pool_bond` if one is passed; pools created
+/// without one must be the authority's
+fn post_pool_bond<'info>(
+    config: &Config,
+    pool: Pubkey,
+    creator: Pubkey,
+    pool_bond: Option<&mut Account<'info, PoolBond>>,
+    pool_bond_bump: Option<u8>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    let (Some(pool_bond), Some(bump)) = (pool_bond, pool_bond_bump) else {
+        require_keys_eq!(creator, config.authority, MixerError::PoolBondMissing);
+        return Ok(());
+    };
+
+    system_program::transfer(
+        CpiContext::new(
+            system_program.to_account_info(),
+            system_program::Transfer {
+                from: payer.to_account_info(),
+                to: pool_bond.to_account_info(),
+            },
+        ),
+        config.pool_creation_bond,
+    )?;
+
+    let current_time = Clock::get()?.unix_timestamp;
+    pool_bond.pool = pool;
+    pool_bond.creator = creator;
+    pool_bond.amount = config.pool_creation_bond;
+    pool_bond.created_at = current_time;
+    pool_bond.bump = bump;
+    pool_bond.flagged_at = 0;
+
+    emit!(PoolBondPosted {
+        pool,
+        creator,
+        amount: config.pool_creation_bond,
+        timestamp: current_time,
+    });
+    Ok(())
+}
+
+/// Fill in a trusted setup ceremony attestation
+fn record_ceremony_attestation(
+    attestation: &mut CeremonyAttestation,
+    transcript_hash: [u8; 32],
+    vk_hash: [u8; 32],
+    authority: Pubkey,
+    bump: u8,
+) -> Result<()> {
+    require!(
+        transcript_hash != [0u8; 32] && vk_hash != [0u8; 32],
+        MixerError::InvalidCeremonyAttestation
+    );
+
+    attestation.transcript_hash = transcript_hash;
+    attestation.vk_hash = vk_hash;
+    attestation.attested_by = authority;
+    attestation.timestamp = Clock::get()?.unix_timestamp;
+    attestation.bump = bump;
+    Ok(())
+}
+
+/// Store `key` for a circuit with `num_public_inputs` public inputs
+/// SECURITY: The key must hash to the attested ceremony output
+fn store_verification_key(
+    verification_key: &mut VerificationKey,
+    key: VerificationKey,
+    num_public_inputs: usize,
+    ceremony_attestation: &CeremonyAttestation,
+) -> Result<()> {
+    // One IC point per public input plus the constant term
+    require!(
+        key.ic.len() == num_public_inputs + 1,
+        MixerError::InvalidVerificationKey
+    );
+    require!(key.is_valid(), MixerError::InvalidVerificationKey);
+
+    // Refuse any key that isn't the attested ceremony output
+    require!(
+        key.hash() == ceremony_attestation.vk_hash,
+        MixerError::VerificationKeyHashMismatch
+    );
+
+    *verification_key = key;
+    Ok(())
+}
+
+/// Record an admin action with arguments `params` in the admin log, the
+/// signing authority paying for the entry
+fn log_admin_action<'info>(
+    admin_log: &mut Account<'info, AdminLog>,
+    authority: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    action: AdminAction,
+    params: &impl AnchorSerialize,
+) -> Result<()> {
+    let entry = AdminLogEntry::new(action, params, Clock::get()?.slot, authority.key())?;
+    admin_log::append(
+        admin_log,
+        &authority.to_account_info(),
+        &system_program.to_account_info(),
+        &entry,
+    )
+}
+
+/// Split a withdrawal into (net, protocol fee) at `fee_bps`
+fn split_fee(amount: u64, fee_bps: u64) -> Result<(u64, u64)> {
+    // Calculate withdrawal amount after fee with proper error handling
+    let fee_amount = amount
+        .checked_mul(fee_bps)
+        .ok_or(MixerError::ArithmeticOverflow)?
+        .checked_div(BASIS_POINTS_DIVISOR)
+        .ok_or(MixerError::ArithmeticOverflow)?;
+    let net_withdrawal = amount
+        .checked_sub(fee_amount)
+        .ok_or(MixerError::ArithmeticOverflow)?;
+
+    Ok((net_withdrawal, fee_amount))
+}
+
+/// Token accounts used by pay_out_token_withdrawal
+struct TokenPayoutAccounts<'a, 'info> {
+    mint: &'a InterfaceAccount<'info, Mint>,
+    vault: &'a InterfaceAccount<'info, TokenAccount>,
+    vault_authority: &'a AccountInfo<'info>,
+    vault_authority_bump: u8,
+    recipient: AccountInfo<'info>,
+    fee_collector: &'a InterfaceAccount<'info, TokenAccount>,
+    payer: Option<AccountInfo<'info>>,
+    ata_reimbursement: u64,
+    token_program: &'a Interface<'info, TokenInterface>,
+}
+
+/// Token counterpart of pay_out_withdrawal: move the denomination out of the
+/// pool's vault, the net amount to the recipient and the fee to the fee
+/// collector's token account, less any ATA reimbursement to the payer.
+/// Returns (net, fee collected).
+/// A Token-2022 transfer fee is withheld from what each side receives
+fn pay_out_token_withdrawal(
+    pool: &Account<MixerPool>,
+    accounts: TokenPayoutAccounts,
+) -> Result<(u64, u64)> {
+    let TokenPayoutAccounts {
+        mint,
+        vault,
+        vault_authority,
+        vault_authority_bump,
+        recipient,
+        fee_collector,
+        payer,
+        ata_reimbursement,
+        token_program,
+    } = accounts;
+    let (net_withdrawal, fee_amount) = pool.split_withdrawal(Clock::get()?.unix_timestamp)?;
+    let collected_fee = fee_amount
+        .checked_sub(ata_reimbursement)
+        .ok_or(MixerError::ReimbursementTooHigh)?;
+
+    // The note being spent is still counted as outstanding here
+    check_vault_backing(pool, vault.amount)?;
+
+    let pool_key = pool.key();
+    let seeds: &[&[u8]] = &[b"vault_authority", pool_key.as_ref(), &[vault_authority_bump]];
+    let signer_seeds = &[seeds];
+
+    let mut transfers = vec![
+        (recipient, net_withdrawal),
+        (fee_collector.to_account_info(), collected_fee),
+    ];
+    if let Some(payer) = payer {
+        transfers.push((payer, ata_reimbursement));
+    }
+
+    for (to, amount) in transfers {
+        if amount == 0 {
+            continue;
+        }
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                token_program.to_account_info(),
+                token_interface::TransferChecked {
+                    from: vault.to_account_info(),
+                    mint: mint.to_account_info(),
+                    to,
+                    authority: vault_authority.clone(),
+                },
+                signer_seeds,
+            ),
+            amount,
+            mint.decimals,
+        )?;
+    }
+
+    Ok((net_withdrawal, collected_fee))
+}
+
+/// Require a token pool's vault to hold at least one denomination for every
+/// note not yet withdrawn
+fn check_vault_backing(pool: &MixerPool, vault_amount: u64) -> Result<()> {
+    let required = pool
+        .outstanding_notes()
+        .checked_mul(pool.denomination)
+        .ok_or(MixerError::ArithmeticOverflow)?;
+
+    require!(
+        vault_amount >= required,
+        MixerError::VaultUndercollateralized
+    );
+    Ok(())
+}
+
+// Account Structures
+
+#[account]
+pub struct Config {
+    pub authority: Pubkey,          // 32
+    pub fee_collector: Pubkey,      // 32
+    pub paused: bool,               // 1
+    pub bump: u8,                   // 1
+    pub attestors: [Pubkey; MAX_ATTESTORS], // 32 * 5 - Root attestors, first attestor_count used
+    pub attestor_count: u8,         // 1
+    pub attestor_threshold: u8,     // 1 - Signatures required by commit_root
+    pub ata_reimbursement_bps: u16, // 2 - Share of a token withdrawal's fee a relayer can claim for creating the recipient's ATA
+    pub relayer_rebate_lamports: u64, // 8 - Flat rebate out of the protocol fee for registered relayers
+    pub max_relayer_fee_bps: u16,   // 2 - Most a withdrawal's relayer fee can be, in bps of the denomination
+    pub staker_vault: Pubkey,       // 32 - Receives staker_share_bps of distributed fees
+    pub staker_share_bps: u16,      // 2 - Stakers' share of SOL fees, the treasury gets the rest
+    pub reward_hook: Pubkey,        // 32 - Program CPI'd with each withdrawal's anonymity points, default if none
+    pub authority_members: [Pubkey; MAX_AUTHORITY_MEMBERS], // 32 * 5 - Authority multisig, first authority_member_count used
+    pub authority_member_count: u8, // 1
+    pub authority_threshold: u8,    // 1 - Member signatures admin instructions need, 0 for the single authority key
+    pub guardian: Pubkey,           // 32 - Can pause but nothing else, default if none
+    pub deposit_rate_limit: RateLimit, // 12 - Most deposits per pool per window
+    pub withdrawal_rate_limit: RateLimit, // 12 - Most withdrawals per pool per window
+    pub version: u8,                // 1 - Layout version, CONFIG_VERSION once migrated; add new fields after it
+    pub upgrade_authority: Pubkey,  // 32 - Program upgrade authority as last recorded, default if immutable
+    pub last_deploy_slot: u64,      // 8 - Slot the program was last deployed at, as last recorded
+    pub upgrade_info_recorded_at: i64, // 8 - When record_upgrade_authority last ran, 0 if never
+    pub paused_at: i64,             // 8 - When the current pause started, 0 while unpaused
+    pub emergency_mode: bool,       // 1 - Emergency withdrawals are open for good, see sunset
+    pub renounced: bool,            // 1 - Admin control given up for good, see renounce_authority
+    pub permissionless_pools: bool, // 1 - Anyone can create SOL and token pools by posting a bond
+    pub pool_creation_bond: u64,    // 8 - Lamports a creator other than the authority locks per pool
+    pub screening_program: Pubkey,  // 32 - Program CPI'd to screen every deposit, Pubkey::default() when off
+}
+
+impl Config {
+    pub const LEN: usize = 8 + 32 + 32 + 1 + 1 + (32 * MAX_ATTESTORS) + 1 + 1 + 2 + 8 + 2 + 32 + 2 + 32
+        + (32 * MAX_AUTHORITY_MEMBERS) + 1 + 1 + 32 + RateLimit::LEN * 2 + 1 + 32 + 8 + 8 + 8 + 1 + 1 + 1 + 8 + 32;
+
+    /// Set fields added since self.version to their defaults and stamp
+    /// CONFIG_VERSION. migrate_config zeroes new fields first, so each step
+    /// only sets the ones whose zero isn't their off state
+    pub fn migrate(&mut self, now: i64) {
+        // Version 0 predates the relayer fee cap, where 0 would block every
+        // relayed withdrawal
+        if self.version < 1 && self.max_relayer_fee_bps == 0 {
+            self.max_relayer_fee_bps = DEFAULT_MAX_RELAYER_FEE_BPS;
+        }
+        // Version 2 added the recorded upgrade info, unset until
+        // record_upgrade_authority runs
+        // Version 3 started tracking pauses; one already under way counts
+        // from the migration
+        if self.version < 3 && self.paused && self.paused_at == 0 {
+            self.paused_at = now;
+        }
+        // Version 4 added renounced, false until renounce_authority
+        // Version 5 added permissionless pool creation, off until enabled
+        // Version 6 added deposit screening, off until set
+        self.version = CONFIG_VERSION;
+    }
+
+    /// Hand the authority to RENOUNCED_AUTHORITY and drop every other key
+    /// that could act for it
+    pub fn renounce(&mut self) {
+        self.authority = RENOUNCED_AUTHORITY;
+        self.authority_members = [Pubkey::default(); MAX_AUTHORITY_MEMBERS];
+        self.authority_member_count = 0;
+        self.authority_threshold = 0;
+        self.guardian = Pubkey::default();
+        self.renounced = true;
+    }
+
+    /// Pause at `now`, keeping the start of a pause already under way
+    pub fn pause(&mut self, now: i64) {
+        if !self.paused {
+            self.paused_at = now;
+        }
+        self.paused = true;
+    }
+
+    /// Whether emergency_withdraw is allowed at `now`: the mixer was sunset
+    /// or has been paused for EMERGENCY_PAUSE_DELAY
+    pub fn emergency_withdrawals_open(&self, now: i64) -> bool {
+        self.emergency_mode
+            || (self.paused
+                && self.paused_at != 0
+                && now.saturating_sub(self.paused_at) >= EMERGENCY_PAUSE_DELAY)
+    }
+
+    /// Split distributed `fees` into (treasury, stakers) shares
+    pub fn split_fees(&self, fees: u64) -> Result<(u64, u64)> {
+        let staker_share = (fees as u128)
+            .checked_mul(self.staker_share_bps as u128)
+            .ok_or(MixerError::ArithmeticOverflow)?
+            / BASIS_POINTS_DIVISOR as u128;
+        let staker_share = staker_share as u64;
+        Ok((fees - staker_share, staker_share))
+    }
+
+    /// Reject a relayer fee above max_relayer_fee_bps of `denomination`
+    pub fn check_relayer_fee(&self, denomination: u64, fee: u64) -> Result<()> {
+        let max = (denomination as u128)
+            .checked_mul(self.max_relayer_fee_bps as u128)
+            .ok_or(MixerError::ArithmeticOverflow)?
+            / BASIS_POINTS_DIVISOR as u128;
+        require!(fee as u128 <= max, MixerError::RelayerFeeAboveCap);
+        Ok(())
+    }
+
+    /// Most a relayer can be reimbursed out of a withdrawal's `fee` for
+    /// creating the recipient's associated token account
+    pub fn max_ata_reimbursement(&self, fee: u64) -> Result<u64> {
+        let max = (fee as u128)
+            .checked_mul(self.ata_reimbursement_bps as u128)
+            .ok_or(MixerError::ArithmeticOverflow)?
+            / BASIS_POINTS_DIVISOR as u128;
+        Ok(max as u64)
+    }
+
+    /// Whether `creator` may create a pool: the authority, or anyone while
+    /// permissionless_pools is on (posting a bond)
+    pub fn may_create_pool(&self, creator: &Pubkey) -> bool {
+        *creator == self.authority || self.permissionless_pools
+    }
+
+    /// Whether `signer` can submit admin instructions: the authority, or any
+    /// member once there is a multisig
+    pub fn is_admin_signer(&self, signer: &Pubkey) -> bool {
+        !self.renounced
+            && (*signer == self.authority
+                || (self.authority_threshold > 0 && self.authority_members().contains(signer)))
+    }
+
+    /// Configured authority multisig members
+    pub fn authority_members(&self) -> &[Pubkey] {
+        &self.authority_members[..self.authority_member_count as usize]
+    }
+
+    /// Reject an admin instruction signed by fewer than authority_threshold
+    /// distinct members among `signers`
+    pub fn check_admin_approval(&self, signers: &[Pubkey]) -> Result<()> {
+        let approvals = self
+            .authority_members()
+            .iter()
+            .filter(|member| signers.contains(member))
+            .count();
+        require!(
+            approvals >= self.authority_threshold as usize,
+            MixerError::InsufficientAuthorityApprovals
+        );
+        Ok(())
+    }
+
+    /// Number of distinct configured attestors among `signers`
+    pub fn count_attestations(&self, signers: &[Pubkey]) -> usize {
+        self.attestors[..self.attestor_count as usize]
+            .iter()
+            .filter(|attestor| signers.contains(attestor))
+            .count()
+    }
+}
+
+#[account]
+pub struct MixerPool {
+    pub denomination: u64,          // 8
+    pub min_delay: i64,             // 8
+    pub total_deposits: u32,        // 4
+    pub total_withdrawals: u32,     // 4
+    pub merkle_root: [u8; 32],      // 32 - Privacy: stores root of commitment tree
+    pub next_leaf_index: u32,       // 4 - Next available leaf position, counted across generations
+    pub creation_timestamp: i64,    // 8 - SECURITY: Track pool creation time
+    pub bump: u8,                   // 1
+    pub withdraw_mode: WithdrawMode, // 1 - Which withdrawal paths are enabled
+    pub compressed_tree: Pubkey,    // 32 - spl-account-compression tree, default if unused
+    pub tree_generation: u32,       // 4 - Current MerkleTreeState generation
+    pub attested_root: [u8; 32],    // 32 - Poseidon root committed by attestors
+    pub attested_leaf_count: u32,   // 4 - Leaves covered by attested_root
+    pub version: u8,                // 1 - Tree hashing scheme, see merkle::POOL_VERSION_*
+    pub dual_tree: bool,            // 1 - Also maintains a Poseidon tree over the same leaves
+    pub tree_depth: u8,             // 1 - Levels in the pool's trees, proofs must match
+    pub tree_hash: u8,              // 1 - Hash of the main tree, TREE_HASH_SHA256 or TREE_HASH_KECCAK
+    pub mint: Pubkey,               // 32 - SPL mint of a token pool, default for native SOL
+    pub relayer_policy: RelayerPolicy, // 1 - Who may submit withdrawals
+    pub fee_curve: FeeCurve,        // 9 - Anonymity-set-based protocol fee, if enabled
+    pub fee_stats: FeeStats,        // 32 - Protocol fees collected, for dashboards
+    pub fee_holiday_start: i64,     // 8 - Protocol fee is waived from here...
+    pub fee_holiday_end: i64,       // 8 - ...until here, exclusive
+    pub deposit_counter: RateCounter, // 16 - Recent deposits, against Config::deposit_rate_limit
+    pub withdrawal_counter: RateCounter, // 16 - Recent withdrawals, against Config::withdrawal_rate_limit
+}
+
+impl MixerPool {
+    pub const LEN: usize = 8 + 8 + 8 + 4 + 4 + 32 + 4 + 8 + 1 + 1 + 32 + 4 + 32 + 4 + 1 + 1 + 1 + 1 + 32 + 1 + FeeCurve::LEN + FeeStats::LEN + 8 + 8
+        + RateCounter::LEN * 2;
+
+    /// Fresh pool state shared by create_pool and create_token_pool
+    pub fn initialize(&mut self, mint: Pubkey, denomination: u64, min_delay: i64, now: i64, bump: u8) {
+        self.denomination = denomination;
+        self.min_delay = min_delay;
+        self.total_deposits = 0;
+        self.total_withdrawals = 0;
+        self.next_leaf_index = 0;
+        self.tree_generation = 0;
+        self.creation_timestamp = now;
+        self.bump = bump;
+        self.withdraw_mode = WithdrawMode::Legacy;
+        self.compressed_tree = Pubkey::default();
+        self.attested_root = [0u8; 32];
+        self.attested_leaf_count = 0;
+        self.version = CURRENT_POOL_VERSION;
+        self.dual_tree = false;
+        self.tree_depth = MERKLE_TREE_DEPTH as u8;
+        self.tree_hash = TREE_HASH_SHA256;
+        self.mint = mint;
+        self.relayer_policy = RelayerPolicy::Permissionless;
+        self.fee_curve = FeeCurve::default();
+        self.fee_stats = FeeStats::default();
+        self.fee_holiday_start = 0;
+        self.fee_holiday_end = 0;
+        self.deposit_counter = RateCounter::default();
+        self.withdrawal_counter = RateCounter::default();
+    }
+
+    /// Protocol fee in basis points a withdrawal pays at `now`
+    pub fn withdrawal_fee_bps(&self, now: i64) -> u64 {
+        if self.in_fee_holiday(now) {
+            0
+        } else {
+            self.standard_fee_bps()
+        }
+    }
+
+    /// Protocol fee in basis points outside a fee holiday
+    pub fn standard_fee_bps(&self) -> u64 {
+        if self.fee_curve.enabled {
+            self.fee_curve.fee_bps(self.total_deposits as u64)
+        } else {
+            FEE_BASIS_POINTS
+        }
+    }
+
+    /// Whether `now` falls in the pool's fee holiday, [start, end)
+    pub fn in_fee_holiday(&self, now: i64) -> bool {
+        self.fee_holiday_start <= now && now < self.fee_holiday_end
+    }
+
+    /// Split the denomination into (net, protocol fee) at the fee at `now`
+    pub fn split_withdrawal(&self, now: i64) -> Result<(u64, u64)> {
+        split_fee(self.denomination, self.withdrawal_fee_bps(now))
+    }
+
+    /// Whether `relayer` may submit withdrawals, given the pool's allowlist
+    pub fn permits_relayer(&self, pool_relayers: Option<&PoolRelayers>, relayer: &Pubkey) -> bool {
+        match self.relayer_policy {
+            RelayerPolicy::Permissionless => true,
+            RelayerPolicy::Allowlisted => {
+                pool_relayers.is_some_and(|pool_relayers| pool_relayers.contains(relayer))
+            }
+        }
+    }
+
+    /// Deposits not yet withdrawn
+    pub fn outstanding_notes(&self) -> u64 {
+        (self.total_deposits as u64).saturating_sub(self.total_withdrawals as u64)
+    }
+
+    pub fn is_token_pool(&self) -> bool {
+        self.mint != SOL_POOL_MINT
+    }
+}
+
+/// Protocol fee that ramps up with a pool's anonymity set (every deposit it
+/// has taken), so young pools can charge little or nothing to bootstrap
+/// Rises linearly from floor_bps with no deposits to ceiling_bps at
+/// ramp_deposits, and stays there
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct FeeCurve {
+    pub enabled: bool,
+    pub floor_bps: u16,
+    pub ceiling_bps: u16,
+    pub ramp_deposits: u32,
+}
+
+impl FeeCurve {
+    pub const LEN: usize = 1 + 2 + 2 + 4;
+
+    /// A curve only ever discounts the flat FEE_BASIS_POINTS
+    pub fn validate(&self) -> Result<()> {
+        if self.enabled {
+            require!(
+                self.floor_bps <= self.ceiling_bps
+                    && self.ceiling_bps as u64 <= FEE_BASIS_POINTS
+                    && self.ramp_deposits > 0,
+                MixerError::InvalidFeeCurve
+            );
+        }
+        Ok(())
+    }
+
+    /// Fee in basis points for an anonymity set of `deposits`
+    pub fn fee_bps(&self, deposits: u64) -> u64 {
+        let floor = self.floor_bps as u64;
+        let ceiling = self.ceiling_bps as u64;
+        let ramp = self.ramp_deposits as u64;
+        if deposits >= ramp {
+            return ceiling;
+        }
+        floor + ceiling.saturating_sub(floor) * deposits / ramp
+    }
+}
+
+/// Protocol fees a pool's withdrawals have paid, in the pool's base units
+/// Epochs are Solana epochs; the stats roll over on the first withdrawal of
+/// an epoch
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct FeeStats {
+    pub fees_collected: u64,
+    pub fees_collected_last_epoch: u64,
+    pub fees_collected_this_epoch: u64,
+    pub epoch: u64,
+}
+
+impl FeeStats {
+    pub const LEN: usize = 8 + 8 + 8 + 8;
+
+    /// The stats as of `epoch`, with any epochs since the last withdrawal
+    /// rolled over
+    pub fn as_of(&self, epoch: u64) -> FeeStats {
+        if epoch == self.epoch {
+            return *self;
+        }
+        let fees_collected_last_epoch = if epoch == self.epoch + 1 {
+            self.fees_collected_this_epoch
+        } else {
+            0
+        };
+        FeeStats {
+            fees_collected: self.fees_collected,
+            fees_collected_last_epoch,
+            fees_collected_this_epoch: 0,
+            epoch,
+        }
+    }
+
+    /// Add a withdrawal's protocol fee, paid in `epoch`
+    pub fn record(&mut self, fee: u64, epoch: u64) -> Result<()> {
+        *self = self.as_of(epoch);
+        self.fees_collected = self
+            .fees_collected
+            .checked_add(fee)
+            .ok_or(MixerError::ArithmeticOverflow)?;
+        self.fees_collected_this_epoch = self
+            .fees_collected_this_epoch
+            .checked_add(fee)
+            .ok_or(MixerError::ArithmeticOverflow)?;
+        Ok(())
+    }
+}
+
+/// Most operations a pool accepts per `window` seconds, max 0 for no limit
+/// Throttles a draining exploit or wash deposits until humans step in
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct RateLimit {
+    pub max: u32,
+    pub window: i64,
+}
+
+impl RateLimit {
+    pub const LEN: usize = 4 + 8;
+
+    pub fn validate(&self) -> Result<()> {
+        require!(self.max == 0 || self.window > 0, MixerError::InvalidRateLimit);
+        Ok(())
+    }
+}
+
+/// Sliding-window count of a pool's operations
+/// Approximated from fixed windows: the previous window's count, weighted by
+/// how much of it the sliding window still overlaps, plus the current one's
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct RateCounter {
+    pub window_start: i64,
+    pub current: u32,
+    pub previous: u32,
+}
+
+impl RateCounter {
+    pub const LEN: usize = 8 + 4 + 4;
+
+    /// Count `count` operations at `now`, failing if that takes the
+    /// sliding window over `limit`
+    pub fn record(&mut self, limit: &RateLimit, count: u32, now: i64) -> Result<()> {
+        if limit.max == 0 {
+            return Ok(());
+        }
+
+        let elapsed = now.saturating_sub(self.window_start);
+        if elapsed >= limit.window.saturating_mul(2) {
+            self.previous = 0;
+            self.current = 0;
+            self.window_start = now;
+        } else if elapsed >= limit.window {
+            self.previous = self.current;
+            self.current = 0;
+            self.window_start += limit.window;
+        }
+
+        let overlap = limit.window - now.saturating_sub(self.window_start).min(limit.window);
+        let carried = self.previous as u64 * overlap as u64 / limit.window as u64;
+        let total = carried + self.current as u64 + count as u64;
+        require!(total <= limit.max as u64, MixerError::RateLimitExceeded);
+
+        self.current += count;
+        Ok(())
+    }
+}
+
+/// Withdrawal paths enabled for a pool during the SHA256 -> ZK migration
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WithdrawMode {
+    /// Only SHA256 commitment reveal (Phase 1)
+    Legacy,
+    /// Only Groth16 proofs (Phase 2)
+    Zk,
+    /// Both paths, while SHA256-phase notes are redeemed
+    Both,
+}
+
+impl WithdrawMode {
+    pub fn allows_legacy(&self) -> bool {
+        matches!(self, WithdrawMode::Legacy | WithdrawMode::Both)
+    }
+
+    pub fn allows_zk(&self) -> bool {
+        matches!(self, WithdrawMode::Zk | WithdrawMode::Both)
+    }
+}
+
+/// Maximum commitments accepted by one insert_leaves call
+pub const MAX_BATCH_LEAVES: usize = 16;
+
+/// Number of upper tree levels cached on-chain (below the root)
+pub const CANOPY_DEPTH: usize = 6;
+
+/// Nodes in the canopy: 2 + 4 + ... + 2^CANOPY_DEPTH
+pub const CANOPY_NODES: usize = (1 << (CANOPY_DEPTH + 1)) - 2;
+
+/// MerkleTreeState hashed with SHA256, checked by withdraw
+pub const TREE_HASH_SHA256: u8 = 0;
+
+/// MerkleTreeState hashed with Poseidon, checked by ZK withdrawals
+pub const TREE_HASH_POSEIDON: u8 = 1;
+
+/// MerkleTreeState hashed with Keccak256, so EVM contracts can check its proofs
+pub const TREE_HASH_KECCAK: u8 = 2;
+
+/// Level of the subtrees recorded by write_checkpoint (2^14 leaves each)
+/// The lowest canopy level, so every subtree root is already on-chain
+pub const CHECKPOINT_LEVEL: usize = MERKLE_TREE_DEPTH - CANOPY_DEPTH;
+
+/// Number of recent roots accepted at withdrawal, so a deposit landing between
+/// proof generation and submission doesn't invalidate the proof
+pub const ROOT_HISTORY_SIZE: usize = 30;
+
+/// Incremental SHA256 Merkle tree of a pool's commitments (Tornado-style)
+/// Only the rightmost filled node per level is kept, so each insert costs
+/// MERKLE_TREE_DEPTH hashes and the root is always known on-chain
+/// A pool has one tree per generation; since a generation only rolls over
+/// when full, a note's global leaf_index splits into
+/// (leaf_index >> MERKLE_TREE_DEPTH, leaf_index % 2^MERKLE_TREE_DEPTH)
+/// Zero-copy: accessed in place through AccountLoader instead of being
+/// Borsh-deserialized onto the stack
+/// Leaves and nodes are hashed per the pool version the tree was created for
+/// Dual-tree pools keep a Poseidon-hashed copy over the same leaves
+#[account(zero_copy)]
+pub struct MerkleTreeState {
+    pub pool: Pubkey,                                       // 32
+    pub filled_subtrees: [[u8; 32]; MERKLE_TREE_DEPTH],     // 32 * 20 - Last left node per level
+    pub roots: [[u8; 32]; ROOT_HISTORY_SIZE],               // 32 * 30 - Ring buffer of recent roots
+    pub canopy: [[u8; 32]; CANOPY_NODES],                   // 32 * 126 - Top levels, root's children first
+    pub current_root_index: u32,                            // 4
+    pub next_index: u32,                                    // 4 - Next leaf to insert
+    pub generation: u32,                                    // 4 - Position in the pool's tree sequence
+    pub bump: u8,                                           // 1
+    pub frozen: u8,                                         // 1 - Non-zero once exported for migration
+    pub version: u8,                                        // 1 - Pool version the tree hashes for
+    pub hash: u8,                                           // 1 - TREE_HASH_SHA256 or TREE_HASH_POSEIDON
+}
+
+impl MerkleTreeState {
+    pub const LEN: usize = 8 + 32 + (32 * MERKLE_TREE_DEPTH) + (32 * ROOT_HISTORY_SIZE) +
+        (32 * CANOPY_NODES) + 4 + 4 + 4 + 1 + 1 + 1 + 1;
+
+    /// Reset to the empty tree
+    pub fn initialize(&mut self, pool: Pubkey, generation: u32, version: u8, hash: u8, bump: u8) {
+        let zeros = tree_hasher(hash).zero_values(version);
+        self.pool = pool;
+        self.generation = generation;
+        self.version = version;
+        self.hash = hash;
+        self.filled_subtrees.copy_from_slice(&zeros[..MERKLE_TREE_DEPTH]);
+        self.roots = [[0u8; 32]; ROOT_HISTORY_SIZE];
+        self.roots[0] = zeros[MERKLE_TREE_DEPTH];
+        let canopy_levels = zeros[..MERKLE_TREE_DEPTH]
+            .iter()
+            .enumerate()
+            .skip(MERKLE_TREE_DEPTH - CANOPY_DEPTH);
+        for (level, zero) in canopy_levels {
+            let width = 1 << (MERKLE_TREE_DEPTH - level);
+            let offset = canopy_offset(level);
+            self.canopy[offset..offset + width].fill(*zero);
+        }
+        self.current_root_index = 0;
+        self.next_index = 0;
+        self.bump = bump;
+        self.frozen = 0;
+    }
+
+    /// Current root of the tree
+    pub fn root(&self) -> [u8; 32] {
+        self.roots[self.current_root_index as usize]
+    }
+
+    /// Whether every leaf position is taken
+    pub fn is_full(&self) -> bool {
+        self.next_index as usize >= (1 << MERKLE_TREE_DEPTH)
+    }
+
+    /// Whether the tree has been exported and no longer accepts leaves
+    pub fn is_frozen(&self) -> bool {
+        self.frozen != 0
+    }
+
+    /// Stop accepting leaves; roots stay valid for withdrawals
+    pub fn freeze(&mut self) {
+        self.frozen = 1;
+    }
+
+    /// Append a commitment, hashed into a leaf as the tree's version requires,
+    /// and record the new root. Returns the leaf index.
+    pub fn insert(&mut self, leaf: [u8; 32]) -> Result<u32> {
+        require!(!self.is_frozen(), MixerError::TreeFrozen);
+
+        let leaf_index = self.next_index;
+        require!(
+            (leaf_index as usize) < (1 << MERKLE_TREE_DEPTH),
+            MixerError::TreeFull
+        );
+
+        let canopy = &mut self.canopy;
+        let root = append_leaf(
+            &mut self.filled_subtrees,
+            leaf_index,
+            &leaf,
+            self.hash,
+            self.version,
+            |level, index, node| set_canopy_node(canopy, level, index, node),
+        );
+
+        self.current_root_index = (self.current_root_index + 1) % ROOT_HISTORY_SIZE as u32;
+        self.roots[self.current_root_index as usize] = root;
+        self.next_index += 1;
+
+        Ok(leaf_index)
+    }
+
+    /// Append several leaves and record a single new root
+    /// Each level is hashed once for the whole batch, so inserting n leaves
+    /// costs about 2n + MERKLE_TREE_DEPTH hashes instead of n * MERKLE_TREE_DEPTH.
+    /// Returns the index of the first leaf.
+    pub fn insert_batch(&mut self, leaves: &[[u8; 32]]) -> Result<u32> {
+        require!(!self.is_frozen(), MixerError::TreeFrozen);
+
+        let start_index = self.next_index;
+        require!(!leaves.is_empty(), MixerError::InvalidBatchSize);
+
+        let end_index = (start_index as usize)
+            .checked_add(leaves.len())
+            .ok_or(MixerError::ArithmeticOverflow)?;
+        require!(
+            end_index <= (1 << MERKLE_TREE_DEPTH),
+            MixerError::TreeFull
+        );
+
+        // Nodes of the current level, the first one sitting at first_index
+        let (hasher, version) = (tree_hasher(self.hash), self.version);
+        let mut nodes: Vec<[u8; 32]> = leaves
+            .iter()
+            .zip(start_index..)
+            .map(|(leaf, leaf_index)| hasher.leaf_hash(version, leaf, leaf_index))
+            .collect();
+        let mut first_index = start_index;
+
+        for (level, (filled_subtree, zero)) in
+            self.filled_subtrees.iter_mut().zip(hasher.zero_values(version)).enumerate()
+        {
+            // An odd first node pairs with the last filled subtree on its left
+            if !first_index.is_multiple_of(2) {
+                nodes.insert(0, *filled_subtree);
+                first_index -= 1;
+            }
+
+            // Remember the last left child, as insert would have
+            if nodes.len().is_multiple_of(2) {
+                *filled_subtree = nodes[nodes.len() - 2];
+            } else {
+                *filled_subtree = nodes[nodes.len() - 1];
+                // The last left child's right sibling is still empty
+                nodes.push(*zero);
+            }
+
+            nodes = nodes
+                .chunks_exact(2)
+                .map(|pair| hasher.node_hash(version, &pair[0], &pair[1]))
+                .collect();
+            first_index /= 2;
+
+            for (i, node) in nodes.iter().enumerate() {
+                set_canopy_node(&mut self.canopy, level + 1, first_index + i as u32, *node);
+            }
+        }
+
+        self.current_root_index = (self.current_root_index + 1) % ROOT_HISTORY_SIZE as u32;
+        self.roots[self.current_root_index as usize] = nodes[0];
+        self.next_index = end_index as u32;
+
+        Ok(start_index)
+    }
+
+    /// Check the root is the current root or one of the recent ones
+    pub fn is_known_root(&self, root: &[u8; 32]) -> bool {
+        *root != [0u8; 32] && self.roots.contains(root)
+    }
+
+    /// Root of a completed subtree at CHECKPOINT_LEVEL
+    pub fn subtree_root(&self, subtree_index: u32) -> Result<[u8; 32]> {
+        require!(
+            (subtree_index as usize) < (1 << CANOPY_DEPTH)
+                && ((subtree_index as u64 + 1) << CHECKPOINT_LEVEL) <= self.next_index as u64,
+            MixerError::SubtreeNotComplete
+        );
+
+        Ok(self.canopy[canopy_offset(CHECKPOINT_LEVEL) + subtree_index as usize])
+    }
+
+    /// Complete a proof holding only the lower siblings with the canopy
+    /// Returns the full sibling path and the path indices of leaf_index
+    pub fn fill_proof_from_canopy(
+        &self,
+        leaf_index: u32,
+        lower_proof: &[[u8; 32]; MERKLE_TREE_DEPTH - CANOPY_DEPTH],
+    ) -> Result<([[u8; 32]; MERKLE_TREE_DEPTH], [bool; MERKLE_TREE_DEPTH])> {
+        require!(
+            leaf_index < self.next_index,
+            MixerError::InvalidMerkleProof
+        );
+
+        let mut proof = [[0u8; 32]; MERKLE_TREE_DEPTH];
+        let mut path_indices = [false; MERKLE_TREE_DEPTH];
+        proof[..MERKLE_TREE_DEPTH - CANOPY_DEPTH].copy_from_slice(lower_proof);
+
+        for (level, (sibling, is_right)) in proof.iter_mut().zip(&mut path_indices).enumerate() {
+            let index = leaf_index >> level;
+            *is_right = !index.is_multiple_of(2);
+
+            if level >= MERKLE_TREE_DEPTH - CANOPY_DEPTH {
+                *sibling = self.canopy[canopy_offset(level) + (index ^ 1) as usize];
+            }
+        }
+
+        Ok((proof, path_indices))
+    }
+
+    /// Siblings of leaf_index that can be read from the account alone
+    /// Canopy levels are always known. Below them, a sibling is known when its
+    /// subtree is still empty (a zero value) or it is the frontier node kept
+    /// in filled_subtrees; the rest are None and must come from an indexer.
+    pub fn known_path(&self, leaf_index: u32) -> Result<MerklePath> {
+        require!(
+            leaf_index < self.next_index,
+            MixerError::InvalidMerkleProof
+        );
+
+        let zeros = tree_hasher(self.hash).zero_values(self.version);
+        let last_index = self.next_index - 1;
+
+        let siblings = (0..MERKLE_TREE_DEPTH)
+            .map(|level| {
+                let sibling = (leaf_index >> level) ^ 1;
+
+                if level >= MERKLE_TREE_DEPTH - CANOPY_DEPTH {
+                    Some(self.canopy[canopy_offset(level) + sibling as usize])
+                } else if (sibling as u64) << level > last_index as u64 {
+                    Some(zeros[level])
+                } else if sibling == (last_index >> level) & !1 {
+                    Some(self.filled_subtrees[level])
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        Ok(MerklePath {
+            leaf_index,
+            root: self.root(),
+            siblings,
+        })
+    }
+}
+
+/// Sibling path returned by get_merkle_path
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct MerklePath {
+    pub leaf_index: u32,
+    /// Root the siblings lead to
+    pub root: [u8; 32],
+    /// Sibling per level from the leaf up, None where it isn't stored on-chain
+    pub siblings: Vec<Option<[u8; 32]>>,
+}
+
+/// Append `data` at `leaf_index` to an incremental tree given by its filled
+/// subtrees, hashed per the tree's hash and pool version
+/// `on_node(level, index, node)` sees every recomputed node. Returns the new root.
+pub fn append_leaf(
+    filled_subtrees: &mut [[u8; 32]; MERKLE_TREE_DEPTH],
+    leaf_index: u32,
+    data: &[u8; 32],
+    hash: u8,
+    version: u8,
+    mut on_node: impl FnMut(usize, u32, [u8; 32]),
+) -> [u8; 32] {
+    let hasher = tree_hasher(hash);
+    let mut current_index = leaf_index;
+    let mut current = hasher.leaf_hash(version, data, leaf_index);
+
+    for (level, (filled_subtree, zero)) in
+        filled_subtrees.iter_mut().zip(hasher.zero_values(version)).enumerate()
+    {
+        let (left, right) = if current_index.is_multiple_of(2) {
+            // Left child: the right sibling is still empty
+            *filled_subtree = current;
+            (current, *zero)
+        } else {
+            // Right child: the left sibling is the last filled subtree
+            (*filled_subtree, current)
+        };
+
+        current = hasher.node_hash(version, &left, &right);
+        current_index /= 2;
+        on_node(level + 1, current_index, current);
+    }
+
+    current
+}
+
+/// Hasher of a tree with the given TREE_HASH_* kind
+pub fn tree_hasher(hash: u8) -> &'static dyn MerkleHasher {
+    match hash {
+        TREE_HASH_POSEIDON => &merkle_poseidon::PoseidonHasher,
+        TREE_HASH_KECCAK => &merkle_keccak::KeccakHasher,
+        _ => &Sha256Hasher,
+    }
+}
+
+/// Position of a level's first node in the canopy
+/// Canopy levels run from MERKLE_TREE_DEPTH - 1 (root's children) down to
+/// MERKLE_TREE_DEPTH - CANOPY_DEPTH
+fn canopy_offset(level: usize) -> usize {
+    (1 << (MERKLE_TREE_DEPTH - level)) - 2
+}
+
+/// Record a freshly computed node if its level is cached in the canopy
+fn set_canopy_node(canopy: &mut [[u8; 32]; CANOPY_NODES], level: usize, index: u32, node: [u8; 32]) {
+    if (MERKLE_TREE_DEPTH - CANOPY_DEPTH..MERKLE_TREE_DEPTH).contains(&level) {
+        canopy[canopy_offset(level) + index as usize] = node;
+    }
+}
+
+/// Root of a filled subtree, written once per 2^CHECKPOINT_LEVEL leaves
+#[account]
+pub struct TreeCheckpoint {
+    pub pool: Pubkey,               // 32
+    pub generation: u32,            // 4 - Tree generation the subtree belongs to
+    pub subtree_index: u32,         // 4 - Position among the generation's subtrees
+    pub start_leaf: u32,            // 4 - First global leaf index covered
+    pub end_leaf: u32,              // 4 - One past the last global leaf index covered
+    pub subtree_root: [u8; 32],     // 32
+    pub timestamp: i64,             // 8
+    pub bump: u8,                   // 1
+}
+
+impl TreeCheckpoint {
+    pub const LEN: usize = 8 + 32 + 4 + 4 + 4 + 4 + 32 + 8 + 1;
+}
+
+/// Progress of an audit_tree replay of one main tree generation
+/// Rebuilds the tree from CommitmentRecords alongside the real one
+#[account]
+pub struct AuditCursor {
+    pub pool: Pubkey,                                       // 32
+    pub generation: u32,                                    // 4 - Tree generation being replayed
+    pub next_index: u32,                                    // 4 - Leaves replayed so far
+    pub filled_subtrees: [[u8; 32]; MERKLE_TREE_DEPTH],     // 32 * 20 - Frontier of the replayed tree
+    pub root: [u8; 32],                                     // 32 - Root after the replayed leaves
+    pub bump: u8,                                           // 1
+}
+
+impl AuditCursor {
+    pub const LEN: usize = 8 + 32 + 4 + 4 + (32 * MERKLE_TREE_DEPTH) + 32 + 1;
+
+    /// Start an empty replay of the given tree generation
+    pub fn initialize(&mut self, pool: Pubkey, generation: u32, hash: u8, version: u8, bump: u8) {
+        let zeros = tree_hasher(hash).zero_values(version);
+        self.pool = pool;
+        self.generation = generation;
+        self.next_index = 0;
+        self.filled_subtrees.copy_from_slice(&zeros[..MERKLE_TREE_DEPTH]);
+        self.root = zeros[MERKLE_TREE_DEPTH];
+        self.bump = bump;
+    }
+
+    /// Replay the next leaf from its record
+    /// The record must belong to the pool and sit at the next global leaf
+    /// index of the generation
+    pub fn replay(&mut self, record: &CommitmentRecord, hash: u8, version: u8) -> Result<()> {
+        let expected_index = self
+            .generation
+            .checked_mul(1 << MERKLE_TREE_DEPTH)
+            .and_then(|base| base.checked_add(self.next_index))
+            .ok_or(MixerError::ArithmeticOverflow)?;
+        require!(
+            record.pool == self.pool && record.leaf_index == expected_index,
+            MixerError::AuditRecordMismatch
+        );
+
+        self.root = append_leaf(
+            &mut self.filled_subtrees,
+            self.next_index,
+            &record.commitment,
+            hash,
+            version,
+            |_, _, _| {},
+        );
+        self.next_index += 1;
+
+        Ok(())
+    }
+}
+
+#[account]
+pub struct CommitmentRecord {
+    pub pool: Pubkey,               // 32
+    pub commitment: [u8; 32],       // 32 - Privacy: hash instead of user address
+    pub leaf_index: u32,            // 4
+    pub timestamp: i64,             // 8
+    pub bump: u8,                   // 1
+}
+
+impl CommitmentRecord {
+    pub const LEN: usize = 8 + 32 + 32 + 4 + 8 + 1;
+}
+
+#[account]
+pub struct EncryptedNote {
+    pub owner: Pubkey,              // 32 - Wallet that owns this note
+    pub encrypted_data: Vec<u8>,    // Variable - Encrypted note data (secret, nullifier, etc.)
+    pub pool: Pubkey,               // 32 - Pool this note belongs to
+    pub leaf_index: u32,            // 4 - Leaf index in Merkle tree
+    pub timestamp: i64,             // 8 - When note was created
+    pub bump: u8,                   // 1 - PDA bump
+}
+
+impl EncryptedNote {
+    // Max encrypted note size: ~200 bytes encrypted data + overhead
+    pub const MAX_SIZE: usize = 8 + 32 + 4 + 200 + 32 + 4 + 8 + 1;
+}
+
+/// Maximum size of a staged withdrawal payload
+pub const MAX_PROOF_BUFFER_SIZE: usize = 1024;
+
+/// Everything withdraw_with_proof takes as instruction data, staged in a ProofBuffer
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct WithdrawProofPayload {
+    pub proof: ProofData,
+    pub merkle_root: [u8; 32],
+    pub nullifier_hash: [u8; 32],
+    pub deposit_timestamp: i64,
+}
+
+impl WithdrawProofPayload {
+    /// SHA256 of the serialized payload, as committed to by a WithdrawalIntent
+    pub fn hash(&self) -> Result<[u8; 32]> {
+        let bytes = self.try_to_vec().map_err(|_| MixerError::InvalidIntentPayload)?;
+        Ok(anchor_lang::solana_program::hash::hash(&bytes).to_bytes())
+    }
+}
+
+#[account]
+pub struct ProofBuffer {
+    pub owner: Pubkey,              // 32 - Relayer staging the proof
+    pub pool: Pubkey,               // 32 - Pool the withdrawal targets
+    pub data: Vec<u8>,              // 4 + len - Serialized WithdrawProofPayload
+    pub bump: u8,                   // 1
+}
+
+impl ProofBuffer {
+    pub const MAX_SIZE: usize = 8 + 32 + 32 + 4 + MAX_PROOF_BUFFER_SIZE + 1;
+}
+
+/// Maximum size of an intent's encrypted payload
+pub const MAX_INTENT_CIPHERTEXT_SIZE: usize = 512;
+
+/// A ZK withdrawal waiting for a relayer, see post_withdrawal_intent
+#[account]
+pub struct WithdrawalIntent {
+    pub pool: Pubkey,               // 32 - Pool the withdrawal targets
+    pub poster: Pubkey,             // 32 - Refunded the rent when executed or cancelled
+    pub nullifier_hash: [u8; 32],   // 32 - Nullifier hash the proof spends
+    pub fee: u64,                   // 8 - Lamports paid to the executing relayer
+    pub recipient: Pubkey,          // 32 - Bound into the proof's context hash
+    pub payload_hash: [u8; 32],     // 32 - SHA256 of the WithdrawProofPayload
+    pub ciphertext: Vec<u8>,        // 4 + len - Payload encrypted to relayers
+    pub posted_at: i64,             // 8
+    pub bump: u8,                   // 1
+    pub queue_position: Option<u64>, // 1 + 8 - Position in the pool's IntentQueue, if queued
+}
+
+impl WithdrawalIntent {
+    pub const MAX_SIZE: usize =
+        8 + 32 + 32 + 32 + 8 + 32 + 32 + 4 + MAX_INTENT_CIPHERTEXT_SIZE + 8 + 1 + (1 + 8);
+}
+
+/// How long the head of an IntentQueue can go unexecuted before it may be skipped
+pub const INTENT_QUEUE_TIMEOUT: i64 = 10 * 60;
+
+/// Positions behind the head of an IntentQueue that can be cancelled
+pub const INTENT_QUEUE_CANCEL_WINDOW: u64 = 64;
+
+/// FIFO order for a pool's queued withdrawal intents
+/// Positions are handed out from `tail`; an intent can only be executed once
+/// everything ahead of it has been executed, cancelled or skipped, so
+/// relayers can't reorder or sandwich queued withdrawals
+#[account]
+pub struct IntentQueue {
+    pub pool: Pubkey,               // 32
+    pub head: u64,                  // 8 - Next position allowed to execute
+    pub tail: u64,                  // 8 - Position the next intent gets
+    pub head_since: i64,            // 8 - When `head` became the head
+    pub bump: u8,                   // 1
+    pub cancelled: u64,             // 8 - Bit i set when position head + 1 + i was cancelled
+}
+
+impl IntentQueue {
+    pub const LEN: usize = 8 + 32 + 8 + 8 + 8 + 1 + 8;
+
+    /// Hand out the next position
+    pub fn enqueue(&mut self, now: i64) -> u64 {
+        if self.head == self.tail {
+            self.head_since = now;
+        }
+        let position = self.tail;
+        self.tail += 1;
+        position
+    }
+
+    /// Intents at the head, or already skipped, may execute
+    pub fn can_execute(&self, position: u64) -> bool {
+        position <= self.head
+    }
+
+    /// Record that the intent at `position` left the queue
+    /// A position behind the head is remembered, so the head moves straight
+    /// past it instead of waiting out INTENT_QUEUE_TIMEOUT there
+    pub fn complete(&mut self, position: u64, now: i64) -> Result<()> {
+        if position == self.head {
+            self.advance(now);
+        } else if position > self.head {
+            let offset = position - self.head - 1;
+            require!(
+                offset < INTENT_QUEUE_CANCEL_WINDOW,
+                MixerError::IntentTooFarBehindHead
+            );
+            self.cancelled |= 1 << offset;
+        }
+        Ok(())
+    }
+
+    /// Move the head to the next position, skipping cancelled ones
+    pub fn advance(&mut self, now: i64) {
+        loop {
+            self.head += 1;
+            let was_cancelled = self.cancelled & 1 == 1;
+            self.cancelled >>= 1;
+            if !was_cancelled {
+                break;
+            }
+        }
+        self.head_since = now;
+    }
+
+    /// The head has waited out INTENT_QUEUE_TIMEOUT
+    pub fn is_stale(&self, now: i64) -> bool {
+        self.head < self.tail && now >= self.head_since.saturating_add(INTENT_QUEUE_TIMEOUT)
+    }
+}
+
+#[account]
+pub struct NullifierRegistry {
+    pub pool: Pubkey,                       // 32
+    pub bump: u8,                           // 1
+    pub nullifiers: Vec<[u8; 32]>,          // 4 (vec len) + 32 * count (dynamic)
+}
+
+impl NullifierRegistry {
+    // Base size + space for initial nullifiers
+    pub const LEN: usize = 8 + 32 + 1 + 4 + (32 * MAX_NULLIFIERS_PER_ACCOUNT);
+
+    /// Check a pool's registry account, which is empty for pools created
+    /// after nullifiers moved to SpentNullifier PDAs
+    pub fn is_used_in(registry: &AccountInfo, nullifier: &[u8; 32]) -> Result<bool> {
+        if registry.data_is_empty() {
+            return Ok(false);
+        }
+
+        let registry = NullifierRegistry::try_deserialize(&mut &registry.data.borrow()[..])?;
+        Ok(registry.is_used(nullifier))
+    }
+
+    pub fn is_used(&self, nullifier: &[u8; 32]) -> bool {
+        self.nullifiers.contains(nullifier)
+    }
+
+    pub fn add_nullifier(&mut self, nullifier: [u8; 32]) -> Result<()> {
+        require!(
+            self.nullifiers.len() < MAX_NULLIFIERS_PER_ACCOUNT,
+            MixerError::NullifierRegistryFull
+        );
+
+        self.nullifiers.push(nullifier);
+        Ok(())
+    }
+}
+
+/// Commitments of a pool that can't be withdrawn, [b"commitment_blocklist", pool]
+#[account]
+pub struct CommitmentBlocklist {
+    pub pool: Pubkey,                       // 32
+    pub entries: Vec<BlockedCommitment>,    // 4 + BlockedCommitment::LEN * MAX_BLOCKED_COMMITMENTS
+    pub bump: u8,                           // 1 - PDA bump
+}
+
+/// A flagged commitment
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct BlockedCommitment {
+    pub commitment: [u8; 32],       // 32
+    pub flagged_at: i64,            // 8
+    pub unflag_at: i64,             // 8 - When unflag_commitment may run, 0 if not requested
+}
+
+impl BlockedCommitment {
+    pub const LEN: usize = 32 + 8 + 8;
+}
+
+impl CommitmentBlocklist {
+    pub const LEN: usize = 8 + 32 + 4 + BlockedCommitment::LEN * MAX_BLOCKED_COMMITMENTS + 1;
+
+    /// Whether the blocklist in `account` flags `commitment`; an account
+    /// never initialized flags nothing
+    pub fn is_blocked_in(account: &AccountInfo, commitment: &[u8; 32]) -> Result<bool> {
+        if account.data_is_empty() {
+            return Ok(false);
+        }
+
+        let blocklist = CommitmentBlocklist::try_deserialize(&mut &account.data.borrow()[..])?;
+        Ok(blocklist.is_blocked(commitment))
+    }
+
+    /// Flagged commitments stay blocked until unflagged, pending unflag or not
+    pub fn is_blocked(&self, commitment: &[u8; 32]) -> bool {
+        self.position(commitment).is_some()
+    }
+
+    fn position(&self, commitment: &[u8; 32]) -> Option<usize> {
+        self.entries
+            .iter()
+            .position(|entry| entry.commitment == *commitment)
+    }
+
+    /// Flag `commitment`, or cancel its pending unflag if `approved` by the
+    /// authority's multisig
+    pub fn flag(&mut self, commitment: [u8; 32], now: i64, approved: bool) -> Result<()> {
+        if let Some(position) = self.position(&commitment) {
+            let entry = &mut self.entries[position];
+            require!(
+                approved && entry.unflag_at != 0,
+                MixerError::CommitmentAlreadyFlagged
+            );
+            entry.unflag_at = 0;
+            return Ok(());
+        }
+
+        require!(
+            self.entries.len() < MAX_BLOCKED_COMMITMENTS,
+            MixerError::CommitmentBlocklistFull
+        );
+        self.entries.push(BlockedCommitment {
+            commitment,
+            flagged_at: now,
+            unflag_at: 0,
+        });
+        Ok(())
+    }
+
+    /// Schedule unflagging `commitment` COMMITMENT_UNFLAG_DELAY from `now`.
+    /// Returns when it can run
+    pub fn request_unflag(&mut self, commitment: &[u8; 32], now: i64) -> Result<i64> {
+        let position = self
+            .position(commitment)
+            .ok_or(MixerError::CommitmentNotFlagged)?;
+        let unflag_at = now
+            .checked_add(COMMITMENT_UNFLAG_DELAY)
+            .ok_or(MixerError::TimeCalculationError)?;
+        self.entries[position].unflag_at = unflag_at;
+        Ok(unflag_at)
+    }
+
+    /// Remove `commitment` once its requested unflag is due
+    pub fn unflag(&mut self, commitment: &[u8; 32], now: i64) -> Result<()> {
+        let position = self
+            .position(commitment)
+            .ok_or(MixerError::CommitmentNotFlagged)?;
+        let unflag_at = self.entries[position].unflag_at;
+        require!(
+            unflag_at != 0 && now >= unflag_at,
+            MixerError::UnflagTimelockNotElapsed
+        );
+        self.entries.swap_remove(position);
+        Ok(())
+    }
+}
+
+/// Viewing key registered for one note, [b"viewing_key", pool, leaf_index]
 #[account]
-pub struct MixerPool {
-    pub denomination: u64,          // 8
-    pub min_delay: i64,             // 8
-    pub total_deposits: u32,        // 4
-    pub total_withdrawals: u32,     // 4
-    pub merkle_root: [u8; 32],      // 32 - Privacy: stores root of commitment tree
-    pub next_leaf_index: u32,       // 4 - Next available leaf position
-    pub creation_timestamp: i64,    // 8 - SECURITY: Track pool creation time
-    pub bump: u8,                   // 1
+pub struct ViewingKey {
+    pub pool: Pubkey,               // 32
+    pub leaf_index: u32,            // 4
+    pub owner: Pubkey,              // 32 - Note owner, who can also disclose by signing
+    pub viewing_key_hash: [u8; 32], // 32 - ViewingKey::hash of the viewing key
+    pub bump: u8,                   // 1 - PDA bump
+}
+
+impl ViewingKey {
+    pub const LEN: usize = 8 + 32 + 4 + 32 + 32 + 1;
+
+    /// SHA256 of a viewing key, as registered
+    pub fn hash(viewing_key: &[u8; 32]) -> [u8; 32] {
+        anchor_lang::solana_program::hash::hash(viewing_key).to_bytes()
+    }
+}
+
+/// What disclose_note shows: which deposit a withdrawal spent
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Debug)]
+pub struct NoteDisclosure {
+    pub pool: Pubkey,
+    pub leaf_index: u32,
+    pub commitment: [u8; 32],
+    pub depositor: Pubkey,
+    pub deposited_at: i64,
+    pub spent_nullifier: Pubkey,
+    pub withdrawn_at: i64,
+}
+
+/// Depositor's receipt for one note, [b"deposit_receipt", pool, leaf_index]
+#[account]
+pub struct DepositReceipt {
+    pub pool: Pubkey,               // 32
+    pub leaf_index: u32,            // 4
+    pub receipt_hash: [u8; 32],     // 32 - DepositReceipt::hash of depositor, commitment and salt
+    pub created_at: i64,            // 8
+    pub revealed_at: i64,           // 8 - 0 until the depositor reveals it
+    pub bump: u8,                   // 1 - PDA bump
+}
+
+impl DepositReceipt {
+    pub const LEN: usize = 8 + 32 + 4 + 32 + 8 + 8 + 1;
+
+    /// SHA256 linking `depositor` to `commitment`, blinded by `salt`
+    pub fn hash(depositor: &Pubkey, commitment: &[u8; 32], salt: &[u8; 32]) -> [u8; 32] {
+        anchor_lang::solana_program::hash::hashv(&[
+            b"nullifier-deposit-receipt",
+            depositor.as_ref(),
+            commitment,
+            salt,
+        ])
+        .to_bytes()
+    }
+}
+
+/// Marks one nullifier (or nullifier hash) as spent
+/// Created by the withdrawal itself, so a second spend fails on init
+/// Keyed by merkle::compute_pool_nullifier, so the same value spent in
+/// another pool maps to a different account
+#[account]
+pub struct SpentNullifier {
+    pub pool: Pubkey,               // 32 - Pool the nullifier was spent in
+    pub nullifier_hash: [u8; 32],   // 32 - compute_pool_nullifier of the revealed nullifier (hash)
+    pub spent_at: i64,              // 8 - Withdrawal timestamp
+    pub bump: u8,                   // 1 - PDA bump
+}
+
+impl SpentNullifier {
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 1;
+
+    pub fn record(&mut self, pool: Pubkey, nullifier_hash: [u8; 32], spent_at: i64, bump: u8) {
+        self.pool = pool;
+        self.nullifier_hash = nullifier_hash;
+        self.spent_at = spent_at;
+        self.bump = bump;
+    }
+}
+
+/// Mints the authority has vetted for token and NFT pools
+#[account]
+pub struct AllowedMints {
+    pub mints: Vec<Pubkey>,         // 4 + 32 * MAX_ALLOWED_MINTS
+    pub bump: u8,                   // 1 - PDA bump
+}
+
+impl AllowedMints {
+    pub const LEN: usize = 8 + 4 + 32 * MAX_ALLOWED_MINTS + 1;
+
+    pub fn contains(&self, mint: &Pubkey) -> bool {
+        self.mints.contains(mint)
+    }
+
+    pub fn add(&mut self, mint: Pubkey) -> Result<()> {
+        require!(!self.contains(&mint), MixerError::MintAlreadyAllowed);
+        require!(
+            self.mints.len() < MAX_ALLOWED_MINTS,
+            MixerError::AllowedMintsFull
+        );
+
+        self.mints.push(mint);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, mint: &Pubkey) -> Result<()> {
+        let position = self
+            .mints
+            .iter()
+            .position(|allowed| allowed == mint)
+            .ok_or(MixerError::MintNotAllowed)?;
+
+        self.mints.swap_remove(position);
+        Ok(())
+    }
+}
+
+/// Exchange-rate metadata of a liquid staking token pool, for UIs
+#[account]
+pub struct LstPoolInfo {
+    pub pool: Pubkey,               // 32 - LST pool
+    pub lamports_per_token: u64,    // 8 - Lamports one whole LST redeems for
+    pub updated_at: i64,            // 8 - When the rate was last set
+    pub bump: u8,                   // 1 - PDA bump
+}
+
+impl LstPoolInfo {
+    pub const LEN: usize = 8 + 32 + 8 + 8 + 1;
+
+    /// Base units in one whole LST (9 decimals)
+    pub const ONE_TOKEN: u64 = 1_000_000_000;
+
+    pub fn set_exchange_rate(&mut self, lamports_per_token: u64, now: i64) -> Result<()> {
+        require!(lamports_per_token > 0, MixerError::InvalidExchangeRate);
+
+        self.lamports_per_token = lamports_per_token;
+        self.updated_at = now;
+        Ok(())
+    }
+
+    /// Lamports `amount` LST base units redeem for at the recorded rate
+    pub fn lamports_for(&self, amount: u64) -> Option<u64> {
+        let lamports = amount as u128 * self.lamports_per_token as u128 / Self::ONE_TOKEN as u128;
+        u64::try_from(lamports).ok()
+    }
+}
+
+/// Holds a SOL pool's withdrawal fees until distribute_fees sweeps them
+/// Keeps the global fee collector out of every withdrawal's write set
+#[account]
+pub struct FeeVault {
+    pub pool: Pubkey,               // 32 - SOL pool whose fees accrue here
+    pub total_fees_collected: u64,  // 8 - Lamports of fees paid in by withdrawals
+    pub total_fees_withdrawn: u64,  // 8 - Lamports swept out by distribute_fees
+    pub bump: u8,                   // 1 - PDA bump
+}
+
+impl FeeVault {
+    pub const LEN: usize = 8 + 32 + 8 + 8 + 1;
+
+    /// Add a withdrawal's protocol fee
+    pub fn record_fees(&mut self, fee: u64) -> Result<()> {
+        self.total_fees_collected = self
+            .total_fees_collected
+            .checked_add(fee)
+            .ok_or(MixerError::ArithmeticOverflow)?;
+        Ok(())
+    }
+
+    /// Fees collected and not yet swept
+    pub fn unswept_fees(&self) -> u64 {
+        self.total_fees_collected.saturating_sub(self.total_fees_withdrawn)
+    }
+
+    /// Record a sweep of `amount`, which can't exceed the unswept fees
+    pub fn record_sweep(&mut self, amount: u64) -> Result<()> {
+        let withdrawn = self
+            .total_fees_withdrawn
+            .checked_add(amount)
+            .ok_or(MixerError::ArithmeticOverflow)?;
+        require!(
+            withdrawn <= self.total_fees_collected,
+            MixerError::FeeSweepExceedsAccrued
+        );
+        self.total_fees_withdrawn = withdrawn;
+        Ok(())
+    }
+}
+
+/// Withdrawal fees collected in one mint across all of its token pools
+#[account]
+pub struct FeeTotals {
+    pub mint: Pubkey,               // 32 - Mint the fees were paid in
+    pub total_fees: u64,            // 8 - Base units sent to the fee collector
+    pub withdrawals: u64,           // 8 - Withdrawals that paid a fee
+    pub bump: u8,                   // 1 - PDA bump
+}
+
+impl FeeTotals {
+    pub const LEN: usize = 8 + 32 + 8 + 8 + 1;
+
+    /// Add one withdrawal's fee, filling in the account on first use
+    pub fn record(&mut self, mint: Pubkey, fee: u64, bump: u8) -> Result<()> {
+        self.mint = mint;
+        self.bump = bump;
+        self.total_fees = self
+            .total_fees
+            .checked_add(fee)
+            .ok_or(MixerError::ArithmeticOverflow)?;
+        self.withdrawals = self
+            .withdrawals
+            .checked_add(1)
+            .ok_or(MixerError::ArithmeticOverflow)?;
+        Ok(())
+    }
+}
+
+/// Left behind by close_pool so the pool's nullifier accounts can be reclaimed
+#[account]
+pub struct RetiredPool {
+    pub pool: Pubkey,               // 32 - Closed pool
+    pub closed_at: i64,             // 8 - close_pool timestamp
+    pub bump: u8,                   // 1 - PDA bump
+}
+
+impl RetiredPool {
+    pub const LEN: usize = 8 + 32 + 8 + 1;
+
+    pub fn is_reclaimable(&self, now: i64) -> bool {
+        now >= self.closed_at.saturating_add(NULLIFIER_RECLAIM_DELAY)
+    }
+}
+
+/// Bond locked by whoever created `pool` without being the authority,
+/// [b"pool_bond", pool]. Holds `amount` lamports on top of its rent
+#[account]
+pub struct PoolBond {
+    pub pool: Pubkey,               // 32 - Pool the bond was posted for
+    pub creator: Pubkey,            // 32 - Refunded by reclaim_pool_bond
+    pub amount: u64,                // 8 - Bond in lamports
+    pub created_at: i64,            // 8
+    pub bump: u8,                   // 1 - PDA bump
+    pub flagged_at: i64,            // 8 - When governance flagged the pool for review, 0 if not
+}
+
+impl PoolBond {
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 1 + 8;
+
+    pub fn is_reclaimable(&self, now: i64) -> bool {
+        now >= self.created_at.saturating_add(POOL_BOND_LOCKUP)
+    }
+
+    pub fn is_flagged(&self) -> bool {
+        self.flagged_at != 0
+    }
+}
+
+/// Force close announced by schedule_force_close, [b"force_close", account]
+#[account]
+pub struct PendingForceClose {
+    pub account: Pubkey,            // 32 - Account to close
+    pub scheduled_at: i64,          // 8
+    pub executable_at: i64,         // 8 - scheduled_at + FORCE_CLOSE_DELAY
+    pub bump: u8,                   // 1 - PDA bump
+}
+
+impl PendingForceClose {
+    pub const LEN: usize = 8 + 32 + 8 + 8 + 1;
+}
+
+/// Per-pool bloom filter over spent nullifiers, see bloom.rs
+#[account(zero_copy)]
+pub struct NullifierBloom {
+    pub pool: Pubkey,                       // 32
+    pub inserted: u64,                      // 8 - Nullifiers added so far
+    pub bits: [u8; BLOOM_FILTER_BYTES],     // 8192
+}
+
+impl NullifierBloom {
+    pub const LEN: usize = 8 + 32 + 8 + BLOOM_FILTER_BYTES;
+
+    pub fn might_contain(&self, nullifier: &[u8; 32]) -> bool {
+        bloom::might_contain(&self.bits, nullifier)
+    }
+
+    pub fn insert(&mut self, nullifier: &[u8; 32]) {
+        bloom::insert(&mut self.bits, nullifier);
+        self.inserted += 1;
+    }
+
+    /// Borrow an initialized filter in place, or None for a pool without one
+    pub fn load_mut<'a>(account: &'a AccountInfo) -> Result<Option<RefMut<'a, NullifierBloom>>> {
+        if account.data_is_empty() {
+            return Ok(None);
+        }
+
+        require_keys_eq!(*account.owner, crate::ID, ErrorCode::AccountOwnedByWrongProgram);
+        let data = account.try_borrow_mut_data()?;
+        require!(
+            data.len() >= NullifierBloom::LEN
+                && data[..8] == NullifierBloom::DISCRIMINATOR,
+            ErrorCode::AccountDiscriminatorMismatch
+        );
+
+        Ok(Some(RefMut::map(data, |data| {
+            bytemuck::from_bytes_mut(&mut data[8..NullifierBloom::LEN])
+        })))
+    }
+}
+
+// Context Structures
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = Config::LEN,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = AdminLog::LEN,
+        seeds = [b"admin_log"],
+        bump
+    )]
+    pub admin_log: Account<'info, AdminLog>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(denomination: u64)]
+pub struct CreatePool<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.may_create_pool(&authority.key()) @ MixerError::NotAuthority
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = MixerPool::LEN,
+        seeds = [b"pool", SOL_POOL_MINT.as_ref(), denomination.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub pool: Account<'info, MixerPool>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = MerkleTreeState::LEN,
+        seeds = [b"merkle_tree", pool.key().as_ref(), 0u32.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub merkle_tree: AccountLoader<'info, MerkleTreeState>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = FeeVault::LEN,
+        seeds = [b"fee_vault", pool.key().as_ref()],
+        bump
+    )]
+    pub fee_vault: Account<'info, FeeVault>,
+
+    /// Append-only record of admin actions
+    #[account(
+        mut,
+        seeds = [b"admin_log"],
+        bump = admin_log.bump
+    )]
+    pub admin_log: Account<'info, AdminLog>,
+
+    /// The authority, or anyone while Config::permissionless_pools is on
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// Bond locked by a creator other than the authority
+    #[account(
+        init,
+        payer = payer,
+        space = PoolBond::LEN,
+        seeds = [b"pool_bond", pool.key().as_ref()],
+        bump
+    )]
+    pub pool_bond: Option<Account<'info, PoolBond>>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeFeeVault<'info> {
+    #[account(
+        seeds = [b"pool", pool.mint.as_ref(), pool.denomination.to_le_bytes().as_ref()],
+        bump = pool.bump,
+        constraint = pool.mint == SOL_POOL_MINT @ MixerError::NotSolPool
+    )]
+    pub pool: Account<'info, MixerPool>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = FeeVault::LEN,
+        seeds = [b"fee_vault", pool.key().as_ref()],
+        bump
+    )]
+    pub fee_vault: Account<'info, FeeVault>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DistributeFees<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = fee_collector
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"fee_vault", fee_vault.pool.as_ref()],
+        bump = fee_vault.bump
+    )]
+    pub fee_vault: Account<'info, FeeVault>,
+
+    /// CHECK: Treasury, fee collector from config
+    #[account(mut)]
+    pub fee_collector: AccountInfo<'info>,
+
+    /// CHECK: Staker vault from config, required while staker_share_bps > 0
+    #[account(
+        mut,
+        address = config.staker_vault
+    )]
+    pub staker_vault: Option<AccountInfo<'info>>,
+}
+
+#[derive(Accounts)]
+#[instruction(denomination: u64)]
+pub struct CreateTokenPool<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.may_create_pool(&authority.key()) @ MixerError::NotAuthority
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(mint::token_program = token_program)]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"allowed_mints"],
+        bump = allowed_mints.bump
+    )]
+    pub allowed_mints: Account<'info, AllowedMints>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = MixerPool::LEN,
+        seeds = [b"pool", mint.key().as_ref(), denomination.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub pool: Account<'info, MixerPool>,
+
+    /// CHECK: PDA owning the pool's vault; holds no data or lamports
+    #[account(
+        seeds = [b"vault_authority", pool.key().as_ref()],
+        bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    /// Token account holding deposits
+    #[account(
+        init,
+        payer = payer,
+        token::mint = mint,
+        token::authority = vault_authority,
+        token::token_program = token_program,
+        seeds = [b"vault", pool.key().as_ref()],
+        bump
+    )]
+    pub vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = MerkleTreeState::LEN,
+        seeds = [b"merkle_tree", pool.key().as_ref(), 0u32.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub merkle_tree: AccountLoader<'info, MerkleTreeState>,
+
+    /// Append-only record of admin actions
+    #[account(
+        mut,
+        seeds = [b"admin_log"],
+        bump = admin_log.bump
+    )]
+    pub admin_log: Account<'info, AdminLog>,
+
+    /// The authority, or anyone while Config::permissionless_pools is on
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// Bond locked by a creator other than the authority
+    #[account(
+        init,
+        payer = payer,
+        space = PoolBond::LEN,
+        seeds = [b"pool_bond", pool.key().as_ref()],
+        bump
+    )]
+    pub pool_bond: Option<Account<'info, PoolBond>>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateNftPool<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.is_admin_signer(&authority.key()) @ MixerError::NotAuthority
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(mint::token_program = token_program)]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"allowed_mints"],
+        bump = allowed_mints.bump
+    )]
+    pub allowed_mints: Account<'info, AllowedMints>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = MixerPool::LEN,
+        seeds = [b"pool", mint.key().as_ref(), NFT_DENOMINATION.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub pool: Account<'info, MixerPool>,
+
+    /// CHECK: PDA owning the pool's vault; holds no data or lamports
+    #[account(
+        seeds = [b"vault_authority", pool.key().as_ref()],
+        bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    /// Token account holding the NFT while it's deposited
+    #[account(
+        init,
+        payer = payer,
+        token::mint = mint,
+        token::authority = vault_authority,
+        token::token_program = token_program,
+        seeds = [b"vault", pool.key().as_ref()],
+        bump
+    )]
+    pub vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = MerkleTreeState::LEN,
+        seeds = [b"merkle_tree", pool.key().as_ref(), 0u32.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub merkle_tree: AccountLoader<'info, MerkleTreeState>,
+
+    /// Append-only record of admin actions
+    #[account(
+        mut,
+        seeds = [b"admin_log"],
+        bump = admin_log.bump
+    )]
+    pub admin_log: Account<'info, AdminLog>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(denomination: u64)]
+pub struct CreateLstPool<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.is_admin_signer(&authority.key()) @ MixerError::NotAuthority
+    )]
+    pub config: Account<'info, Config>,
+
+    /// LSTs share SOL's 9 decimals so SOL denominations carry over
+    #[account(
+        mint::decimals = 9,
+        mint::token_program = token_program
+    )]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"allowed_mints"],
+        bump = allowed_mints.bump
+    )]
+    pub allowed_mints: Account<'info, AllowedMints>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = MixerPool::LEN,
+        seeds = [b"pool", mint.key().as_ref(), denomination.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub pool: Account<'info, MixerPool>,
+
+    /// CHECK: PDA owning the pool's vault; holds no data or lamports
+    #[account(
+        seeds = [b"vault_authority", pool.key().as_ref()],
+        bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    /// Token account holding deposits
+    #[account(
+        init,
+        payer = payer,
+        token::mint = mint,
+        token::authority = vault_authority,
+        token::token_program = token_program,
+        seeds = [b"vault", pool.key().as_ref()],
+        bump
+    )]
+    pub vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = MerkleTreeState::LEN,
+        seeds = [b"merkle_tree", pool.key().as_ref(), 0u32.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub merkle_tree: AccountLoader<'info, MerkleTreeState>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = LstPoolInfo::LEN,
+        seeds = [b"lst_info", pool.key().as_ref()],
+        bump
+    )]
+    pub lst_info: Account<'info, LstPoolInfo>,
+
+    /// Append-only record of admin actions
+    #[account(
+        mut,
+        seeds = [b"admin_log"],
+        bump = admin_log.bump
+    )]
+    pub admin_log: Account<'info, AdminLog>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateLstExchangeRate<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.is_admin_signer(&authority.key()) @ MixerError::NotAuthority
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"lst_info", lst_info.pool.as_ref()],
+        bump = lst_info.bump
+    )]
+    pub lst_info: Account<'info, LstPoolInfo>,
+
+    /// Append-only record of admin actions
+    #[account(
+        mut,
+        seeds = [b"admin_log"],
+        bump = admin_log.bump
+    )]
+    pub admin_log: Account<'info, AdminLog>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(commitment: [u8; 32], encrypted_data: Vec<u8>)]
+pub struct DepositToken<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", pool.mint.as_ref(), pool.denomination.to_le_bytes().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, MixerPool>,
+
+    #[account(
+        address = pool.mint,
+        mint::token_program = token_program
+    )]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// CHECK: PDA owning the pool's vault; holds no data or lamports
+    #[account(
+        seeds = [b"vault_authority", pool.key().as_ref()],
+        bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", pool.key().as_ref()],
+        bump,
+        token::authority = vault_authority
+    )]
+    pub vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = pool.mint,
+        token::authority = depositor
+    )]
+    pub depositor_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"merkle_tree",
+            pool.key().as_ref(),
+            pool.tree_generation.to_le_bytes().as_ref()
+        ],
+        bump = merkle_tree.load()?.bump
+    )]
+    pub merkle_tree: AccountLoader<'info, MerkleTreeState>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"poseidon_tree",
+            pool.key().as_ref(),
+            pool.tree_generation.to_le_bytes().as_ref()
+        ],
+        bump = poseidon_tree.load()?.bump
+    )]
+    pub poseidon_tree: Option<AccountLoader<'info, MerkleTreeState>>,
+
+    #[account(
+        init,
+        payer = depositor,
+        space = CommitmentRecord::LEN,
+        seeds = [
+            b"commitment",
+            pool.key().as_ref(),
+            pool.next_leaf_index.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub commitment_record: Account<'info, CommitmentRecord>,
+
+    #[account(
+        init,
+        payer = depositor,
+        space = EncryptedNote::MAX_SIZE,
+        seeds = [
+            b"encrypted_note",
+            depositor.key().as_ref(),
+            pool.key().as_ref(),
+            pool.next_leaf_index.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub encrypted_note: Account<'info, EncryptedNote>,
+
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    /// CHECK: Screening program from config, required while one is
+    /// configured; its accounts follow as remaining accounts
+    #[account(address = config.screening_program)]
+    pub screening_program: Option<AccountInfo<'info>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(commitment: [u8; 32], encrypted_data: Vec<u8>)]
+pub struct Deposit<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", pool.mint.as_ref(), pool.denomination.to_le_bytes().as_ref()],
+        bump = pool.bump,
+        constraint = pool.mint == SOL_POOL_MINT @ MixerError::NotSolPool
+    )]
+    pub pool: Account<'info, MixerPool>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"merkle_tree",
+            pool.key().as_ref(),
+            pool.tree_generation.to_le_bytes().as_ref()
+        ],
+        bump = merkle_tree.load()?.bump
+    )]
+    pub merkle_tree: AccountLoader<'info, MerkleTreeState>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"poseidon_tree",
+            pool.key().as_ref(),
+            pool.tree_generation.to_le_bytes().as_ref()
+        ],
+        bump = poseidon_tree.load()?.bump
+    )]
+    pub poseidon_tree: Option<AccountLoader<'info, MerkleTreeState>>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = CommitmentRecord::LEN,
+        seeds = [
+            b"commitment",
+            pool.key().as_ref(),
+            pool.next_leaf_index.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub commitment_record: Account<'info, CommitmentRecord>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = EncryptedNote::MAX_SIZE,
+        seeds = [
+            b"encrypted_note",
+            funder.key().as_ref(),
+            pool.key().as_ref(),
+            pool.next_leaf_index.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub encrypted_note: Account<'info, EncryptedNote>,
+
+    /// Pays the denomination and owns the encrypted note
+    #[account(mut)]
+    pub funder: Signer<'info>,
+
+    /// Pays rent for the deposit's accounts and the transaction fee; may be a
+    /// sponsor or the funder itself
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: Screening program from config, required while one is
+    /// configured; its accounts follow as remaining accounts
+    #[account(address = config.screening_program)]
+    pub screening_program: Option<AccountInfo<'info>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(commitment: [u8; 32], encrypted_data: Vec<u8>)]
+pub struct DepositWsol<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", pool.mint.as_ref(), pool.denomination.to_le_bytes().as_ref()],
+        bump = pool.bump,
+        constraint = pool.mint == SOL_POOL_MINT @ MixerError::NotSolPool
+    )]
+    pub pool: Account<'info, MixerPool>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"merkle_tree",
+            pool.key().as_ref(),
+            pool.tree_generation.to_le_bytes().as_ref()
+        ],
+        bump = merkle_tree.load()?.bump
+    )]
+    pub merkle_tree: AccountLoader<'info, MerkleTreeState>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"poseidon_tree",
+            pool.key().as_ref(),
+            pool.tree_generation.to_le_bytes().as_ref()
+        ],
+        bump = poseidon_tree.load()?.bump
+    )]
+    pub poseidon_tree: Option<AccountLoader<'info, MerkleTreeState>>,
+
+    #[account(
+        init,
+        payer = depositor,
+        space = CommitmentRecord::LEN,
+        seeds = [
+            b"commitment",
+            pool.key().as_ref(),
+            pool.next_leaf_index.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub commitment_record: Account<'info, CommitmentRecord>,
+
+    #[account(
+        init,
+        payer = depositor,
+        space = EncryptedNote::MAX_SIZE,
+        seeds = [
+            b"encrypted_note",
+            depositor.key().as_ref(),
+            pool.key().as_ref(),
+            pool.next_leaf_index.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub encrypted_note: Account<'info, EncryptedNote>,
+
+    #[account(address = spl_token::native_mint::ID)]
+    pub native_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        token::mint = native_mint,
+        token::authority = depositor,
+        token::token_program = token_program
+    )]
+    pub depositor_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Pool-owned wSOL account the deposit is unwrapped through, closed
+    /// again before the instruction returns
+    #[account(
+        init,
+        payer = depositor,
+        seeds = [b"wsol_unwrap", pool.key().as_ref()],
+        bump,
+        token::mint = native_mint,
+        token::authority = pool,
+        token::token_program = token_program
+    )]
+    pub unwrap_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    /// CHECK: Screening program from config, required while one is
+    /// configured; its accounts follow as remaining accounts
+    #[account(address = config.screening_program)]
+    pub screening_program: Option<AccountInfo<'info>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitCompressedTree<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.is_admin_signer(&authority.key()) @ MixerError::NotAuthority
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", pool.mint.as_ref(), pool.denomination.to_le_bytes().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, MixerPool>,
+
+    /// CHECK: Zeroed tree account owned by spl-account-compression, validated by the CPI
+    #[account(mut)]
+    pub compressed_tree: AccountInfo<'info>,
+
+    /// CHECK: spl-account-compression program
+    #[account(address = compression::SPL_ACCOUNT_COMPRESSION_ID)]
+    pub compression_program: AccountInfo<'info>,
+
+    /// CHECK: spl-noop program
+    #[account(address = compression::SPL_NOOP_ID)]
+    pub noop_program: AccountInfo<'info>,
+
+    /// Append-only record of admin actions
+    #[account(
+        mut,
+        seeds = [b"admin_log"],
+        bump = admin_log.bump
+    )]
+    pub admin_log: Account<'info, AdminLog>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositCompressed<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", pool.mint.as_ref(), pool.denomination.to_le_bytes().as_ref()],
+        bump = pool.bump,
+        constraint = pool.mint == SOL_POOL_MINT @ MixerError::NotSolPool
+    )]
+    pub pool: Account<'info, MixerPool>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"merkle_tree",
+            pool.key().as_ref(),
+            pool.tree_generation.to_le_bytes().as_ref()
+        ],
+        bump = merkle_tree.load()?.bump
+    )]
+    pub merkle_tree: AccountLoader<'info, MerkleTreeState>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"poseidon_tree",
+            pool.key().as_ref(),
+            pool.tree_generation.to_le_bytes().as_ref()
+        ],
+        bump = poseidon_tree.load()?.bump
+    )]
+    pub poseidon_tree: Option<AccountLoader<'info, MerkleTreeState>>,
+
+    /// CHECK: The pool's spl-account-compression tree
+    #[account(
+        mut,
+        address = pool.compressed_tree
+    )]
+    pub compressed_tree: AccountInfo<'info>,
+
+    /// CHECK: spl-account-compression program
+    #[account(address = compression::SPL_ACCOUNT_COMPRESSION_ID)]
+    pub compression_program: AccountInfo<'info>,
+
+    /// CHECK: spl-noop program
+    #[account(address = compression::SPL_NOOP_ID)]
+    pub noop_program: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+
+    /// CHECK: Screening program from config, required while one is
+    /// configured; its accounts follow as remaining accounts
+    #[account(address = config.screening_program)]
+    pub screening_program: Option<AccountInfo<'info>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InsertLeaves<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", pool.mint.as_ref(), pool.denomination.to_le_bytes().as_ref()],
+        bump = pool.bump,
+        constraint = pool.mint == SOL_POOL_MINT @ MixerError::NotSolPool
+    )]
+    pub pool: Account<'info, MixerPool>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"merkle_tree",
+            pool.key().as_ref(),
+            pool.tree_generation.to_le_bytes().as_ref()
+        ],
+        bump = merkle_tree.load()?.bump
+    )]
+    pub merkle_tree: AccountLoader<'info, MerkleTreeState>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"poseidon_tree",
+            pool.key().as_ref(),
+            pool.tree_generation.to_le_bytes().as_ref()
+        ],
+        bump = poseidon_tree.load()?.bump
+    )]
+    pub poseidon_tree: Option<AccountLoader<'info, MerkleTreeState>>,
+
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+
+    /// CHECK: Screening program from config, required while one is
+    /// configured; its accounts follow as remaining accounts
+    #[account(address = config.screening_program)]
+    pub screening_program: Option<AccountInfo<'info>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RolloverTree<'info> {
+    #[account(
+        mut,
+        seeds = [b"pool", pool.mint.as_ref(), pool.denomination.to_le_bytes().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, MixerPool>,
+
+    #[account(
+        seeds = [
+            b"merkle_tree",
+            pool.key().as_ref(),
+            pool.tree_generation.to_le_bytes().as_ref()
+        ],
+        bump = current_tree.load()?.bump
+    )]
+    pub current_tree: AccountLoader<'info, MerkleTreeState>,
+
+    /// Next Poseidon tree, required for dual-tree pools
+    #[account(
+        init,
+        payer = payer,
+        space = MerkleTreeState::LEN,
+        seeds = [
+            b"poseidon_tree",
+            pool.key().as_ref(),
+            (pool.tree_generation + 1).to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub new_poseidon_tree: Option<AccountLoader<'info, MerkleTreeState>>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = MerkleTreeState::LEN,
+        seeds = [
+            b"merkle_tree",
+            pool.key().as_ref(),
+            (pool.tree_generation + 1).to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub new_tree: AccountLoader<'info, MerkleTreeState>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(subtree_index: u32)]
+pub struct WriteCheckpoint<'info> {
+    #[account(
+        seeds = [b"pool", pool.mint.as_ref(), pool.denomination.to_le_bytes().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, MixerPool>,
+
+    #[account(
+        seeds = [
+            b"merkle_tree",
+            pool.key().as_ref(),
+            merkle_tree.load()?.generation.to_le_bytes().as_ref()
+        ],
+        bump = merkle_tree.load()?.bump
+    )]
+    pub merkle_tree: AccountLoader<'info, MerkleTreeState>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = TreeCheckpoint::LEN,
+        seeds = [
+            b"checkpoint",
+            merkle_tree.key().as_ref(),
+            subtree_index.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub checkpoint: Account<'info, TreeCheckpoint>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct StartAudit<'info> {
+    #[account(
+        seeds = [b"pool", pool.mint.as_ref(), pool.denomination.to_le_bytes().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, MixerPool>,
+
+    #[account(
+        seeds = [
+            b"merkle_tree",
+            pool.key().as_ref(),
+            merkle_tree.load()?.generation.to_le_bytes().as_ref()
+        ],
+        bump = merkle_tree.load()?.bump
+    )]
+    pub merkle_tree: AccountLoader<'info, MerkleTreeState>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = AuditCursor::LEN,
+        seeds = [b"audit", merkle_tree.key().as_ref()],
+        bump
+    )]
+    pub audit_cursor: Account<'info, AuditCursor>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AuditTree<'info> {
+    #[account(
+        seeds = [b"pool", pool.mint.as_ref(), pool.denomination.to_le_bytes().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, MixerPool>,
+
+    #[account(
+        seeds = [
+            b"merkle_tree",
+            pool.key().as_ref(),
+            merkle_tree.load()?.generation.to_le_bytes().as_ref()
+        ],
+        bump = merkle_tree.load()?.bump
+    )]
+    pub merkle_tree: AccountLoader<'info, MerkleTreeState>,
+
+    #[account(
+        mut,
+        seeds = [b"audit", merkle_tree.key().as_ref()],
+        bump = audit_cursor.bump
+    )]
+    pub audit_cursor: Account<'info, AuditCursor>,
+}
+
+#[derive(Accounts)]
+pub struct GetFeeStats<'info> {
+    #[account(
+        seeds = [b"pool", pool.mint.as_ref(), pool.denomination.to_le_bytes().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, MixerPool>,
+}
+
+#[derive(Accounts)]
+pub struct GetMerklePath<'info> {
+    #[account(
+        seeds = [b"pool", pool.mint.as_ref(), pool.denomination.to_le_bytes().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, MixerPool>,
+
+    #[account(
+        seeds = [
+            b"merkle_tree",
+            pool.key().as_ref(),
+            merkle_tree.load()?.generation.to_le_bytes().as_ref()
+        ],
+        bump = merkle_tree.load()?.bump
+    )]
+    pub merkle_tree: AccountLoader<'info, MerkleTreeState>,
+}
+
+#[derive(Accounts)]
+#[instruction(leaf_index: u32)]
+pub struct RegisterViewingKey<'info> {
+    #[account(
+        seeds = [b"pool", pool.mint.as_ref(), pool.denomination.to_le_bytes().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, MixerPool>,
+
+    /// The note's encrypted copy, which names its owner
+    #[account(
+        seeds = [
+            b"encrypted_note",
+            owner.key().as_ref(),
+            pool.key().as_ref(),
+            leaf_index.to_le_bytes().as_ref()
+        ],
+        bump = encrypted_note.bump,
+        has_one = owner
+    )]
+    pub encrypted_note: Account<'info, EncryptedNote>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = ViewingKey::LEN,
+        seeds = [b"viewing_key", pool.key().as_ref(), leaf_index.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub viewing_key: Account<'info, ViewingKey>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(leaf_index: u32)]
+pub struct DiscloseNote<'info> {
+    #[account(
+        seeds = [b"pool", pool.mint.as_ref(), pool.denomination.to_le_bytes().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, MixerPool>,
+
+    #[account(
+        seeds = [b"commitment", pool.key().as_ref(), leaf_index.to_le_bytes().as_ref()],
+        bump = commitment_record.bump
+    )]
+    pub commitment_record: Account<'info, CommitmentRecord>,
+
+    #[account(
+        seeds = [b"viewing_key", pool.key().as_ref(), leaf_index.to_le_bytes().as_ref()],
+        bump = viewing_key.bump
+    )]
+    pub viewing_key: Account<'info, ViewingKey>,
+
+    /// Created by the withdrawal being disclosed
+    #[account(
+        seeds = [b"nullifier", pool.key().as_ref(), spent_nullifier.nullifier_hash.as_ref()],
+        bump = spent_nullifier.bump
+    )]
+    pub spent_nullifier: Account<'info, SpentNullifier>,
+
+    /// The note owner, when disclosing by signature instead of viewing key
+    pub owner: Option<Signer<'info>>,
+}
+
+#[derive(Accounts)]
+#[instruction(leaf_index: u32)]
+pub struct PublishNoteDisclosure<'info> {
+    #[account(
+        seeds = [b"pool", pool.mint.as_ref(), pool.denomination.to_le_bytes().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, MixerPool>,
+
+    #[account(
+        seeds = [b"commitment", pool.key().as_ref(), leaf_index.to_le_bytes().as_ref()],
+        bump = commitment_record.bump
+    )]
+    pub commitment_record: Account<'info, CommitmentRecord>,
+
+    /// Created by the withdrawal being disclosed
+    #[account(
+        seeds = [b"nullifier", pool.key().as_ref(), spent_nullifier.nullifier_hash.as_ref()],
+        bump = spent_nullifier.bump
+    )]
+    pub spent_nullifier: Account<'info, SpentNullifier>,
+
+    #[account(
+        init,
+        payer = recipient,
+        space = DisclosedNote::LEN,
+        seeds = [b"disclosed_note", spent_nullifier.key().as_ref()],
+        bump
+    )]
+    pub disclosed_note: Account<'info, DisclosedNote>,
+
+    #[account(
+        init_if_needed,
+        payer = recipient,
+        space = DisclosureRegistry::LEN,
+        seeds = [b"disclosure_registry", recipient.key().as_ref()],
+        bump
+    )]
+    pub disclosure_registry: Account<'info, DisclosureRegistry>,
+
+    /// The withdrawal's recipient
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct GetComplianceReport<'info> {
+    #[account(
+        seeds = [b"disclosure_registry", disclosure_registry.recipient.as_ref()],
+        bump = disclosure_registry.bump
+    )]
+    pub disclosure_registry: Account<'info, DisclosureRegistry>,
+}
+
+#[derive(Accounts)]
+#[instruction(leaf_index: u32)]
+pub struct CreateDepositReceipt<'info> {
+    #[account(
+        seeds = [b"pool", pool.mint.as_ref(), pool.denomination.to_le_bytes().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, MixerPool>,
+
+    /// The note's encrypted copy, which names its depositor
+    #[account(
+        seeds = [
+            b"encrypted_note",
+            depositor.key().as_ref(),
+            pool.key().as_ref(),
+            leaf_index.to_le_bytes().as_ref()
+        ],
+        bump = encrypted_note.bump,
+        constraint = encrypted_note.owner == depositor.key() @ MixerError::NotAuthority
+    )]
+    pub encrypted_note: Account<'info, EncryptedNote>,
+
+    #[account(
+        init,
+        payer = depositor,
+        space = DepositReceipt::LEN,
+        seeds = [b"deposit_receipt", pool.key().as_ref(), leaf_index.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub deposit_receipt: Account<'info, DepositReceipt>,
+
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(leaf_index: u32)]
+pub struct RevealDepositReceipt<'info> {
+    #[account(
+        seeds = [b"pool", pool.mint.as_ref(), pool.denomination.to_le_bytes().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, MixerPool>,
+
+    #[account(
+        seeds = [b"commitment", pool.key().as_ref(), leaf_index.to_le_bytes().as_ref()],
+        bump = commitment_record.bump
+    )]
+    pub commitment_record: Account<'info, CommitmentRecord>,
+
+    #[account(
+        mut,
+        seeds = [b"deposit_receipt", pool.key().as_ref(), leaf_index.to_le_bytes().as_ref()],
+        bump = deposit_receipt.bump
+    )]
+    pub deposit_receipt: Account<'info, DepositReceipt>,
+
+    /// Must be the depositor the receipt hashes
+    pub depositor: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(proof: ProofData, merkle_root: [u8; 32], nullifier_hash: [u8; 32])]
+pub struct ProveInnocence<'info> {
+    #[account(
+        seeds = [b"pool", pool.mint.as_ref(), pool.denomination.to_le_bytes().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, MixerPool>,
+
+    #[account(
+        seeds = [b"tainted_set", pool.key().as_ref()],
+        bump = tainted_set.bump
+    )]
+    pub tainted_set: Account<'info, TaintedSet>,
+
+    #[account(
+        seeds = [b"exclusion_verification_key"],
+        bump
+    )]
+    pub exclusion_verification_key: Box<Account<'info, VerificationKey>>,
+
+    /// Created by the withdrawal that revealed nullifier_hash
+    #[account(
+        seeds = [
+            b"nullifier",
+            pool.key().as_ref(),
+            merkle::compute_pool_nullifier(&nullifier_hash, &pool.key()).as_ref()
+        ],
+        bump = spent_nullifier.bump
+    )]
+    pub spent_nullifier: Account<'info, SpentNullifier>,
+
+    /// Poseidon tree the proof's root belongs to, for dual-tree pools
+    /// Without it the root must match the attested root
+    #[account(
+        seeds = [
+            b"poseidon_tree",
+            pool.key().as_ref(),
+            poseidon_tree.load()?.generation.to_le_bytes().as_ref()
+        ],
+        bump = poseidon_tree.load()?.bump
+    )]
+    pub poseidon_tree: Option<AccountLoader<'info, MerkleTreeState>>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = InnocenceProof::LEN,
+        seeds = [b"innocence_proof", spent_nullifier.key().as_ref()],
+        bump
+    )]
+    pub innocence_proof: Account<'info, InnocenceProof>,
+
+    /// Anyone holding the proof; pays rent for the InnocenceProof
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(nullifier_hash: [u8; 32])]
+pub struct CheckNullifier<'info> {
+    #[account(
+        seeds = [b"pool", pool.mint.as_ref(), pool.denomination.to_le_bytes().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, MixerPool>,
+
+    /// CHECK: SpentNullifier PDA for nullifier_hash, empty while unspent
+    #[account(
+        seeds = [
+            b"nullifier",
+            pool.key().as_ref(),
+            merkle::compute_pool_nullifier(&nullifier_hash, &pool.key()).as_ref()
+        ],
+        bump
+    )]
+    pub spent_nullifier: UncheckedAccount<'info>,
+
+    /// CHECK: The pool's pre-PDA NullifierRegistry, empty for newer pools
+    #[account(
+        seeds = [b"nullifier_registry", pool.key().as_ref()],
+        bump
+    )]
+    pub nullifier_registry: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(nullifier: [u8; 32])]
+pub struct Withdraw<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", pool.mint.as_ref(), pool.denomination.to_le_bytes().as_ref()],
+        bump = pool.bump,
+        constraint = pool.mint == SOL_POOL_MINT @ MixerError::NotSolPool
+    )]
+    pub pool: Account<'info, MixerPool>,
+
+    #[account(
+        seeds = [
+            b"merkle_tree",
+            pool.key().as_ref(),
+            merkle_tree.load()?.generation.to_le_bytes().as_ref()
+        ],
+        bump = merkle_tree.load()?.bump
+    )]
+    pub merkle_tree: AccountLoader<'info, MerkleTreeState>,
+
+    /// CHECK: The pool's pre-PDA NullifierRegistry, read by NullifierRegistry::is_used_in
+    /// Pinned by seeds so it can't be swapped out; empty for newer pools
+    #[account(
+        seeds = [b"nullifier_registry", pool.key().as_ref()],
+        bump
+    )]
+    pub nullifier_registry: UncheckedAccount<'info>,
+
+    /// CHECK: The pool's CommitmentBlocklist, read by CommitmentBlocklist::is_blocked_in
+    /// Pinned by seeds so it can't be swapped out; empty until a commitment is flagged
+    #[account(
+        seeds = [b"commitment_blocklist", pool.key().as_ref()],
+        bump
+    )]
+    pub commitment_blocklist: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = SpentNullifier::LEN,
+        seeds = [
+            b"nullifier",
+            pool.key().as_ref(),
+            merkle::compute_pool_nullifier(&nullifier, &pool.key()).as_ref()
+        ],
+        bump
+    )]
+    pub spent_nullifier: Account<'info, SpentNullifier>,
+
+    /// CHECK: The pool's NullifierBloom, updated when it exists
+    /// Pinned by seeds so withdrawals can't skip updating it
+    #[account(
+        mut,
+        seeds = [b"nullifier_bloom", pool.key().as_ref()],
+        bump
+    )]
+    pub nullifier_bloom: UncheckedAccount<'info>,
+
+    /// CHECK: This is the recipient address, can be any address (PRIVACY)
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+
+    /// The pool's FeeVault, accrues the protocol fee until distribute_fees
+    #[account(
+        mut,
+        seeds = [b"fee_vault", pool.key().as_ref()],
+        bump = fee_vault.bump
+    )]
+    pub fee_vault: Account<'info, FeeVault>,
+
+    /// Withdrawer or relayer, pays rent for the SpentNullifier
+    #[account(
+        mut,
+        constraint = pool.permits_relayer(pool_relayers.as_deref(), &payer.key()) @ MixerError::RelayerNotAllowed
+    )]
+    pub payer: Signer<'info>,
+
+    /// Needed only when the recipient is a wrapped SOL account
+    pub token_program: Option<Program<'info, Token>>,
+
+    /// The pool's relayer allowlist, needed when its policy is Allowlisted
+    #[account(
+        seeds = [b"pool_relayers", pool.key().as_ref()],
+        bump = pool_relayers.bump
+    )]
+    pub pool_relayers: Option<Account<'info, PoolRelayers>>,
+
+    /// The submitting relayer's RelayerInfo, credited with the withdrawal
+    #[account(
+        mut,
+        seeds = [b"relayer", payer.key().as_ref()],
+        bump = relayer_info.bump
+    )]
+    pub relayer_info: Option<Account<'info, RelayerInfo>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(nullifier: [u8; 32])]
+pub struct WithdrawToken<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", pool.mint.as_ref(), pool.denomination.to_le_bytes().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, MixerPool>,
+
+    #[account(
+        seeds = [
+            b"merkle_tree",
+            pool.key().as_ref(),
+            merkle_tree.load()?.generation.to_le_bytes().as_ref()
+        ],
+        bump = merkle_tree.load()?.bump
+    )]
+    pub merkle_tree: AccountLoader<'info, MerkleTreeState>,
+
+    /// CHECK: The pool's pre-PDA NullifierRegistry, read by NullifierRegistry::is_used_in
+    /// Pinned by seeds so it can't be swapped out; empty for newer pools
+    #[account(
+        seeds = [b"nullifier_registry", pool.key().as_ref()],
+        bump
+    )]
+    pub nullifier_registry: UncheckedAccount<'info>,
+
+    /// CHECK: The pool's CommitmentBlocklist, read by CommitmentBlocklist::is_blocked_in
+    /// Pinned by seeds so it can't be swapped out; empty until a commitment is flagged
+    #[account(
+        seeds = [b"commitment_blocklist", pool.key().as_ref()],
+        bump
+    )]
+    pub commitment_blocklist: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = SpentNullifier::LEN,
+        seeds = [
+            b"nullifier",
+            pool.key().as_ref(),
+            merkle::compute_pool_nullifier(&nullifier, &pool.key()).as_ref()
+        ],
+        bump
+    )]
+    pub spent_nullifier: Account<'info, SpentNullifier>,
+
+    /// CHECK: The pool's NullifierBloom, updated when it exists
+    /// Pinned by seeds so withdrawals can't skip updating it
+    #[account(
+        mut,
+        seeds = [b"nullifier_bloom", pool.key().as_ref()],
+        bump
+    )]
+    pub nullifier_bloom: UncheckedAccount<'info>,
+
+    #[account(
+        address = pool.mint,
+        mint::token_program = token_program
+    )]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// CHECK: PDA owning the pool's vault; holds no data or lamports
+    #[account(
+        seeds = [b"vault_authority", pool.key().as_ref()],
+        bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", pool.key().as_ref()],
+        bump,
+        token::authority = vault_authority
+    )]
+    pub vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: Any token account for the pool's mint (PRIVACY), or the
+    /// recipient's not yet created associated token account; checked by
+    /// create_recipient_token_account
+    #[account(mut)]
+    pub recipient_token: UncheckedAccount<'info>,
+
+    /// CHECK: Wallet owning recipient_token, needed only to create it
+    pub recipient: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Fee collector from config
+    #[account(address = config.fee_collector)]
+    pub fee_collector: UncheckedAccount<'info>,
+
+    /// Fee collector's associated token account, created on first use
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = mint,
+        associated_token::authority = fee_collector,
+        associated_token::token_program = token_program
+    )]
+    pub fee_collector_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = FeeTotals::LEN,
+        seeds = [b"fee_totals", pool.mint.as_ref()],
+        bump
+    )]
+    pub fee_totals: Account<'info, FeeTotals>,
+
+    /// Withdrawer or relayer, pays rent for the SpentNullifier and any
+    /// token accounts created
+    #[account(
+        mut,
+        constraint = pool.permits_relayer(pool_relayers.as_deref(), &payer.key()) @ MixerError::RelayerNotAllowed
+    )]
+    pub payer: Signer<'info>,
+
+    /// Payer's token account for the pool's mint, receives any ATA reimbursement
+    #[account(
+        mut,
+        token::mint = pool.mint,
+        token::authority = payer
+    )]
+    pub payer_token: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    /// The pool's relayer allowlist, needed when its policy is Allowlisted
+    #[account(
+        seeds = [b"pool_relayers", pool.key().as_ref()],
+        bump = pool_relayers.bump
+    )]
+    pub pool_relayers: Option<Account<'info, PoolRelayers>>,
+
+    /// The submitting relayer's RelayerInfo, credited with the withdrawal
+    #[account(
+        mut,
+        seeds = [b"relayer", payer.key().as_ref()],
+        bump = relayer_info.bump
+    )]
+    pub relayer_info: Option<Account<'info, RelayerInfo>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(nullifier: [u8; 32], secret: [u8; 32], leaf_index: u32)]
+pub struct EmergencyWithdraw<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", pool.mint.as_ref(), pool.denomination.to_le_bytes().as_ref()],
+        bump = pool.bump,
+        constraint = pool.mint == SOL_POOL_MINT @ MixerError::NotSolPool
+    )]
+    pub pool: Account<'info, MixerPool>,
+
+    /// Record of the deposit being reclaimed
+    #[account(
+        seeds = [b"commitment", pool.key().as_ref(), leaf_index.to_le_bytes().as_ref()],
+        bump = commitment_record.bump
+    )]
+    pub commitment_record: Account<'info, CommitmentRecord>,
+
+    /// CHECK: The pool's pre-PDA NullifierRegistry, read by NullifierRegistry::is_used_in
+    #[account(
+        seeds = [b"nullifier_registry", pool.key().as_ref()],
+        bump
+    )]
+    pub nullifier_registry: UncheckedAccount<'info>,
+
+    /// CHECK: The pool's CommitmentBlocklist, read by CommitmentBlocklist::is_blocked_in
+    /// Pinned by seeds so it can't be swapped out; empty until a commitment is flagged
+    #[account(
+        seeds = [b"commitment_blocklist", pool.key().as_ref()],
+        bump
+    )]
+    pub commitment_blocklist: UncheckedAccount<'info>,
+
+    /// Spends the note as a SHA256-phase withdrawal would
+    #[account(
+        init,
+        payer = payer,
+        space = SpentNullifier::LEN,
+        seeds = [
+            b"nullifier",
+            pool.key().as_ref(),
+            merkle::compute_pool_nullifier(&nullifier, &pool.key()).as_ref()
+        ],
+        bump
+    )]
+    pub spent_nullifier: Account<'info, SpentNullifier>,
+
+    /// CHECK: SpentNullifier of the note's ZK nullifier hash, must be empty
+    #[account(
+        seeds = [
+            b"nullifier",
+            pool.key().as_ref(),
+            merkle::compute_pool_nullifier(
+                &merkle_poseidon::poseidon_nullifier_hash(&nullifier, &pool.key(), CIRCUIT_VERSION),
+                &pool.key()
+            ).as_ref()
+        ],
+        bump
+    )]
+    pub zk_spent_nullifier: UncheckedAccount<'info>,
+
+    /// CHECK: Any account, receives the denomination
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+
+    /// Pays rent for the SpentNullifier
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(nullifier: [u8; 32], secret: [u8; 32], leaf_index: u32)]
+pub struct EmergencyWithdrawToken<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", pool.mint.as_ref(), pool.denomination.to_le_bytes().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, MixerPool>,
+
+    /// Record of the deposit being reclaimed
+    #[account(
+        seeds = [b"commitment", pool.key().as_ref(), leaf_index.to_le_bytes().as_ref()],
+        bump = commitment_record.bump
+    )]
+    pub commitment_record: Account<'info, CommitmentRecord>,
+
+    /// CHECK: The pool's pre-PDA NullifierRegistry, read by NullifierRegistry::is_used_in
+    #[account(
+        seeds = [b"nullifier_registry", pool.key().as_ref()],
+        bump
+    )]
+    pub nullifier_registry: UncheckedAccount<'info>,
+
+    /// CHECK: The pool's CommitmentBlocklist, read by CommitmentBlocklist::is_blocked_in
+    /// Pinned by seeds so it can't be swapped out; empty until a commitment is flagged
+    #[account(
+        seeds = [b"commitment_blocklist", pool.key().as_ref()],
+        bump
+    )]
+    pub commitment_blocklist: UncheckedAccount<'info>,
+
+    /// Spends the note as a SHA256-phase withdrawal would
+    #[account(
+        init,
+        payer = payer,
+        space = SpentNullifier::LEN,
+        seeds = [
+            b"nullifier",
+            pool.key().as_ref(),
+            merkle::compute_pool_nullifier(&nullifier, &pool.key()).as_ref()
+        ],
+        bump
+    )]
+    pub spent_nullifier: Account<'info, SpentNullifier>,
+
+    /// CHECK: SpentNullifier of the note's ZK nullifier hash, must be empty
+    #[account(
+        seeds = [
+            b"nullifier",
+            pool.key().as_ref(),
+            merkle::compute_pool_nullifier(
+                &merkle_poseidon::poseidon_nullifier_hash(&nullifier, &pool.key(), CIRCUIT_VERSION),
+                &pool.key()
+            ).as_ref()
+        ],
+        bump
+    )]
+    pub zk_spent_nullifier: UncheckedAccount<'info>,
+
+    #[account(
+        address = pool.mint,
+        mint::token_program = token_program
+    )]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// CHECK: PDA owning the pool's vault; holds no data or lamports
+    #[account(
+        seeds = [b"vault_authority", pool.key().as_ref()],
+        bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", pool.key().as_ref()],
+        bump,
+        token::authority = vault_authority
+    )]
+    pub vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Any token account for the pool's mint, receives the denomination
+    #[account(
+        mut,
+        token::mint = pool.mint,
+        token::token_program = token_program
+    )]
+    pub recipient_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Pays rent for the SpentNullifier
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(proof: ProofData, merkle_root: [u8; 32], nullifier_hash: [u8; 32])]
+pub struct WithdrawWithProof<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", pool.mint.as_ref(), pool.denomination.to_le_bytes().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, MixerPool>,
+
+    #[account(
+        seeds = [b"verification_key"],
+        bump
+    )]
+    pub verification_key: Account<'info, VerificationKey>,
+
+    /// CHECK: The pool's pre-PDA NullifierRegistry, read by NullifierRegistry::is_used_in
+    /// Pinned by seeds so it can't be swapped out; empty for newer pools
+    #[account(
+        seeds = [b"nullifier_registry", pool.key().as_ref()],
+        bump
+    )]
+    pub nullifier_registry: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = SpentNullifier::LEN,
+        seeds = [
+            b"nullifier",
+            pool.key().as_ref(),
+            merkle::compute_pool_nullifier(&nullifier_hash, &pool.key()).as_ref()
+        ],
+        bump
+    )]
+    pub spent_nullifier: Account<'info, SpentNullifier>,
+
+    /// CHECK: The pool's NullifierBloom, updated when it exists
+    /// Pinned by seeds so withdrawals can't skip updating it
+    #[account(
+        mut,
+        seeds = [b"nullifier_bloom", pool.key().as_ref()],
+        bump
+    )]
+    pub nullifier_bloom: UncheckedAccount<'info>,
+
+    /// Poseidon tree the proof's root belongs to, for dual-tree pools
+    /// Without it the root must match the attested root
+    #[account(
+        seeds = [
+            b"poseidon_tree",
+            pool.key().as_ref(),
+            poseidon_tree.load()?.generation.to_le_bytes().as_ref()
+        ],
+        bump = poseidon_tree.load()?.bump
+    )]
+    pub poseidon_tree: Option<AccountLoader<'info, MerkleTreeState>>,
+
+    /// CHECK: This is the recipient address, can be any address (PRIVACY)
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+
+    /// The pool's FeeVault, accrues the protocol fee until distribute_fees
+    #[account(
+        mut,
+        seeds = [b"fee_vault", pool.key().as_ref()],
+        bump = fee_vault.bump
+    )]
+    pub fee_vault: Account<'info, FeeVault>,
+
+    /// Withdrawer or relayer, pays rent for the SpentNullifier
+    #[account(
+        mut,
+        constraint = pool.permits_relayer(pool_relayers.as_deref(), &payer.key()) @ MixerError::RelayerNotAllowed
+    )]
+    pub payer: Signer<'info>,
+
+    /// Needed only when the recipient is a wrapped SOL account
+    pub token_program: Option<Program<'info, Token>>,
+
+    /// The pool's relayer allowlist, needed when its policy is Allowlisted
+    #[account(
+        seeds = [b"pool_relayers", pool.key().as_ref()],
+        bump = pool_relayers.bump
+    )]
+    pub pool_relayers: Option<Account<'info, PoolRelayers>>,
+
+    /// The submitting relayer's RelayerInfo, credited with the withdrawal
+    #[account(
+        mut,
+        seeds = [b"relayer", payer.key().as_ref()],
+        bump = relayer_info.bump
+    )]
+    pub relayer_info: Option<Account<'info, RelayerInfo>>,
+
+    /// The pool's AnonymityMining, when the pool rewards time in the pool
+    #[account(
+        mut,
+        seeds = [b"anonymity_mining", pool.key().as_ref()],
+        bump = anonymity_mining.bump
+    )]
+    pub anonymity_mining: Option<Account<'info, AnonymityMining>>,
+
+    /// The recipient's AnonymityPoints, credited with the note's points
+    #[account(
+        mut,
+        seeds = [b"anonymity_points", recipient.key().as_ref()],
+        bump = anonymity_points.bump
+    )]
+    pub anonymity_points: Option<Account<'info, AnonymityPoints>>,
+
+    /// CHECK: Reward hook program from config, required to claim points
+    /// while one is configured; its accounts follow as remaining accounts
+    #[account(address = config.reward_hook)]
+    pub reward_hook: Option<AccountInfo<'info>>,
+
+    /// The pool's AssociationSet, when proving the note is in it
+    #[account(
+        seeds = [b"association_set", pool.key().as_ref()],
+        bump = association_set.bump
+    )]
+    pub association_set: Option<Box<Account<'info, AssociationSet>>>,
+
+    /// Key of the association set circuit, required with association_set
+    #[account(
+        seeds = [b"association_verification_key"],
+        bump
+    )]
+    pub association_verification_key: Option<Box<Account<'info, VerificationKey>>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(nullifier_hash: [u8; 32], fee: u64)]
+pub struct PostWithdrawalIntent<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        seeds = [b"pool", pool.mint.as_ref(), pool.denomination.to_le_bytes().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, MixerPool>,
+
+    /// The fee is in the seeds, so an intent re-posted with a higher fee
+    /// lands at an address the proof wasn't made out to
+    #[account(
+        init,
+        payer = poster,
+        space = WithdrawalIntent::MAX_SIZE,
+        seeds = [
+            b"intent",
+            pool.key().as_ref(),
+            nullifier_hash.as_ref(),
+            fee.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub intent: Account<'info, WithdrawalIntent>,
+
+    /// The pool's IntentQueue, for queued intents
+    #[account(
+        mut,
+        seeds = [b"intent_queue", pool.key().as_ref()],
+        bump = intent_queue.bump
+    )]
+    pub intent_queue: Option<Account<'info, IntentQueue>>,
+
+    #[account(mut)]
+    pub poster: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteIntent<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", pool.mint.as_ref(), pool.denomination.to_le_bytes().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, MixerPool>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"intent",
+            pool.key().as_ref(),
+            intent.nullifier_hash.as_ref(),
+            intent.fee.to_le_bytes().as_ref()
+        ],
+        bump = intent.bump,
+        has_one = pool,
+        has_one = poster,
+        has_one = recipient,
+        close = poster
+    )]
+    pub intent: Account<'info, WithdrawalIntent>,
+
+    /// CHECK: Refunded the intent's rent
+    #[account(mut)]
+    pub poster: UncheckedAccount<'info>,
+
+    /// The pool's IntentQueue, for queued intents
+    #[account(
+        mut,
+        seeds = [b"intent_queue", pool.key().as_ref()],
+        bump = intent_queue.bump
+    )]
+    pub intent_queue: Option<Account<'info, IntentQueue>>,
+
+    #[account(
+        seeds = [b"verification_key"],
+        bump
+    )]
+    pub verification_key: Account<'info, VerificationKey>,
+
+    /// CHECK: The pool's pre-PDA NullifierRegistry, read by NullifierRegistry::is_used_in
+    /// Pinned by seeds so it can't be swapped out; empty for newer pools
+    #[account(
+        seeds = [b"nullifier_registry", pool.key().as_ref()],
+        bump
+    )]
+    pub nullifier_registry: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = executor,
+        space = SpentNullifier::LEN,
+        seeds = [
+            b"nullifier",
+            pool.key().as_ref(),
+            merkle::compute_pool_nullifier(&intent.nullifier_hash, &pool.key()).as_ref()
+        ],
+        bump
+    )]
+    pub spent_nullifier: Account<'info, SpentNullifier>,
+
+    /// CHECK: The pool's NullifierBloom, updated when it exists
+    /// Pinned by seeds so withdrawals can't skip updating it
+    #[account(
+        mut,
+        seeds = [b"nullifier_bloom", pool.key().as_ref()],
+        bump
+    )]
+    pub nullifier_bloom: UncheckedAccount<'info>,
+
+    /// Poseidon tree the proof's root belongs to, for dual-tree pools
+    /// Without it the root must match the attested root
+    #[account(
+        seeds = [
+            b"poseidon_tree",
+            pool.key().as_ref(),
+            poseidon_tree.load()?.generation.to_le_bytes().as_ref()
+        ],
+        bump = poseidon_tree.load()?.bump
+    )]
+    pub poseidon_tree: Option<AccountLoader<'info, MerkleTreeState>>,
+
+    /// CHECK: The intent's recipient
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+
+    /// The pool's FeeVault, accrues the protocol fee until distribute_fees
+    #[account(
+        mut,
+        seeds = [b"fee_vault", pool.key().as_ref()],
+        bump = fee_vault.bump
+    )]
+    pub fee_vault: Account<'info, FeeVault>,
+
+    /// Any relayer; pays rent for the SpentNullifier and collects the fee
+    #[account(
+        mut,
+        constraint = pool.permits_relayer(pool_relayers.as_deref(), &executor.key()) @ MixerError::RelayerNotAllowed
+    )]
+    pub executor: Signer<'info>,
+
+    /// Needed only when the recipient is a wrapped SOL account
+    pub token_program: Option<Program<'info, Token>>,
+
+    /// The pool's relayer allowlist, needed when its policy is Allowlisted
+    #[account(
+        seeds = [b"pool_relayers", pool.key().as_ref()],
+        bump = pool_relayers.bump
+    )]
+    pub pool_relayers: Option<Account<'info, PoolRelayers>>,
+
+    /// The submitting relayer's RelayerInfo, credited with the withdrawal
+    #[account(
+        mut,
+        seeds = [b"relayer", executor.key().as_ref()],
+        bump = relayer_info.bump
+    )]
+    pub relayer_info: Option<Account<'info, RelayerInfo>>,
+
+    /// The pool's AnonymityMining, when the pool rewards time in the pool
+    #[account(
+        mut,
+        seeds = [b"anonymity_mining", pool.key().as_ref()],
+        bump = anonymity_mining.bump
+    )]
+    pub anonymity_mining: Option<Account<'info, AnonymityMining>>,
+
+    /// The recipient's AnonymityPoints, credited with the note's points
+    #[account(
+        mut,
+        seeds = [b"anonymity_points", recipient.key().as_ref()],
+        bump = anonymity_points.bump
+    )]
+    pub anonymity_points: Option<Account<'info, AnonymityPoints>>,
+
+    /// CHECK: Reward hook program from config, required to claim points
+    /// while one is configured; its accounts follow as remaining accounts
+    #[account(address = config.reward_hook)]
+    pub reward_hook: Option<AccountInfo<'info>>,
+
+    /// The pool's AssociationSet, when proving the note is in it
+    #[account(
+        seeds = [b"association_set", pool.key().as_ref()],
+        bump = association_set.bump
+    )]
+    pub association_set: Option<Box<Account<'info, AssociationSet>>>,
+
+    /// Key of the association set circuit, required with association_set
+    #[account(
+        seeds = [b"association_verification_key"],
+        bump
+    )]
+    pub association_verification_key: Option<Box<Account<'info, VerificationKey>>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelWithdrawalIntent<'info> {
+    #[account(
+        mut,
+        has_one = poster,
+        close = poster
+    )]
+    pub intent: Account<'info, WithdrawalIntent>,
+
+    /// The pool's IntentQueue, for queued intents
+    #[account(
+        mut,
+        seeds = [b"intent_queue", intent.pool.as_ref()],
+        bump = intent_queue.bump
+    )]
+    pub intent_queue: Option<Account<'info, IntentQueue>>,
+
+    #[account(mut)]
+    pub poster: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeIntentQueue<'info> {
+    #[account(
+        seeds = [b"pool", pool.mint.as_ref(), pool.denomination.to_le_bytes().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, MixerPool>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = IntentQueue::LEN,
+        seeds = [b"intent_queue", pool.key().as_ref()],
+        bump
+    )]
+    pub intent_queue: Account<'info, IntentQueue>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SkipStaleIntent<'info> {
+    #[account(
+        mut,
+        seeds = [b"intent_queue", intent_queue.pool.as_ref()],
+        bump = intent_queue.bump
+    )]
+    pub intent_queue: Account<'info, IntentQueue>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeNullifierBloom<'info> {
+    #[account(
+        seeds = [b"pool", pool.mint.as_ref(), pool.denomination.to_le_bytes().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, MixerPool>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = NullifierBloom::LEN,
+        seeds = [b"nullifier_bloom", pool.key().as_ref()],
+        bump
+    )]
+    pub nullifier_bloom: AccountLoader<'info, NullifierBloom>,
+
+    /// CHECK: The pool's pre-PDA NullifierRegistry, empty for newer pools
+    #[account(
+        seeds = [b"nullifier_registry", pool.key().as_ref()],
+        bump
+    )]
+    pub nullifier_registry: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateProofBuffer<'info> {
+    #[account(
+        seeds = [b"pool", pool.mint.as_ref(), pool.denomination.to_le_bytes().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, MixerPool>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = ProofBuffer::MAX_SIZE,
+        seeds = [b"proof_buffer", owner.key().as_ref()],
+        bump
+    )]
+    pub proof_buffer: Account<'info, ProofBuffer>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WriteProofBuffer<'info> {
+    #[account(
+        mut,
+        seeds = [b"proof_buffer", owner.key().as_ref()],
+        bump = proof_buffer.bump,
+        has_one = owner
+    )]
+    pub proof_buffer: Account<'info, ProofBuffer>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(nullifier_hash: [u8; 32])]
+pub struct WithdrawFromBuffer<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", pool.mint.as_ref(), pool.denomination.to_le_bytes().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, MixerPool>,
+
+    #[account(
+        seeds = [b"verification_key"],
+        bump
+    )]
+    pub verification_key: Account<'info, VerificationKey>,
+
+    /// CHECK: The pool's pre-PDA NullifierRegistry, read by NullifierRegistry::is_used_in
+    /// Pinned by seeds so it can't be swapped out; empty for newer pools
+    #[account(
+        seeds = [b"nullifier_registry", pool.key().as_ref()],
+        bump
+    )]
+    pub nullifier_registry: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = SpentNullifier::LEN,
+        seeds = [
+            b"nullifier",
+            pool.key().as_ref(),
+            merkle::compute_pool_nullifier(&nullifier_hash, &pool.key()).as_ref()
+        ],
+        bump
+    )]
+    pub spent_nullifier: Account<'info, SpentNullifier>,
+
+    /// CHECK: The pool's NullifierBloom, updated when it exists
+    /// Pinned by seeds so withdrawals can't skip updating it
+    #[account(
+        mut,
+        seeds = [b"nullifier_bloom", pool.key().as_ref()],
+        bump
+    )]
+    pub nullifier_bloom: UncheckedAccount<'info>,
+
+    /// Poseidon tree the proof's root belongs to, for dual-tree pools
+    /// Without it the root must match the attested root
+    #[account(
+        seeds = [
+            b"poseidon_tree",
+            pool.key().as_ref(),
+            poseidon_tree.load()?.generation.to_le_bytes().as_ref()
+        ],
+        bump = poseidon_tree.load()?.bump
+    )]
+    pub poseidon_tree: Option<AccountLoader<'info, MerkleTreeState>>,
+
+    #[account(
+        mut,
+        seeds = [b"proof_buffer", owner.key().as_ref()],
+        bump = proof_buffer.bump,
+        has_one = owner,
+        has_one = pool,
+        close = owner
+    )]
+    pub proof_buffer: Account<'info, ProofBuffer>,
+
+    #[account(
+        mut,
+        constraint = pool.permits_relayer(pool_relayers.as_deref(), &owner.key()) @ MixerError::RelayerNotAllowed
+    )]
+    pub owner: Signer<'info>,
+
+    /// CHECK: This is the recipient address, can be any address (PRIVACY)
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+
+    /// The pool's FeeVault, accrues the protocol fee until distribute_fees
+    #[account(
+        mut,
+        seeds = [b"fee_vault", pool.key().as_ref()],
+        bump = fee_vault.bump
+    )]
+    pub fee_vault: Account<'info, FeeVault>,
+
+    /// Needed only when the recipient is a wrapped SOL account
+    pub token_program: Option<Program<'info, Token>>,
+
+    /// The pool's relayer allowlist, needed when its policy is Allowlisted
+    #[account(
+        seeds = [b"pool_relayers", pool.key().as_ref()],
+        bump = pool_relayers.bump
+    )]
+    pub pool_relayers: Option<Account<'info, PoolRelayers>>,
+
+    /// The submitting relayer's RelayerInfo, credited with the withdrawal
+    #[account(
+        mut,
+        seeds = [b"relayer", owner.key().as_ref()],
+        bump = relayer_info.bump
+    )]
+    pub relayer_info: Option<Account<'info, RelayerInfo>>,
+
+    /// The pool's AnonymityMining, when the pool rewards time in the pool
+    #[account(
+        mut,
+        seeds = [b"anonymity_mining", pool.key().as_ref()],
+        bump = anonymity_mining.bump
+    )]
+    pub anonymity_mining: Option<Account<'info, AnonymityMining>>,
+
+    /// The recipient's AnonymityPoints, credited with the note's points
+    #[account(
+        mut,
+        seeds = [b"anonymity_points", recipient.key().as_ref()],
+        bump = anonymity_points.bump
+    )]
+    pub anonymity_points: Option<Account<'info, AnonymityPoints>>,
+
+    /// CHECK: Reward hook program from config, required to claim points
+    /// while one is configured; its accounts follow as remaining accounts
+    #[account(address = config.reward_hook)]
+    pub reward_hook: Option<AccountInfo<'info>>,
+
+    /// The pool's AssociationSet, when proving the note is in it
+    #[account(
+        seeds = [b"association_set", pool.key().as_ref()],
+        bump = association_set.bump
+    )]
+    pub association_set: Option<Box<Account<'info, AssociationSet>>>,
+
+    /// Key of the association set circuit, required with association_set
+    #[account(
+        seeds = [b"association_verification_key"],
+        bump
+    )]
+    pub association_verification_key: Option<Box<Account<'info, VerificationKey>>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterCeremonyAttestation<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.is_admin_signer(&authority.key()) @ MixerError::NotAuthority
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = CeremonyAttestation::LEN,
+        seeds = [b"ceremony_attestation"],
+        bump
+    )]
+    pub ceremony_attestation: Account<'info, CeremonyAttestation>,
+
+    /// Append-only record of admin actions
+    #[account(
+        mut,
+        seeds = [b"admin_log"],
+        bump = admin_log.bump
+    )]
+    pub admin_log: Account<'info, AdminLog>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeVerificationKey<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.is_admin_signer(&authority.key()) @ MixerError::NotAuthority
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        seeds = [b"ceremony_attestation"],
+        bump = ceremony_attestation.bump
+    )]
+    pub ceremony_attestation: Account<'info, CeremonyAttestation>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = VerificationKey::LEN,
+        seeds = [b"verification_key"],
+        bump
+    )]
+    pub verification_key: Account<'info, VerificationKey>,
+
+    /// Append-only record of admin actions
+    #[account(
+        mut,
+        seeds = [b"admin_log"],
+        bump = admin_log.bump
+    )]
+    pub admin_log: Account<'info, AdminLog>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterAssociationCeremonyAttestation<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.is_admin_signer(&authority.key()) @ MixerError::NotAuthority
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = CeremonyAttestation::LEN,
+        seeds = [b"association_ceremony_attestation"],
+        bump
+    )]
+    pub ceremony_attestation: Account<'info, CeremonyAttestation>,
+
+    /// Append-only record of admin actions
+    #[account(
+        mut,
+        seeds = [b"admin_log"],
+        bump = admin_log.bump
+    )]
+    pub admin_log: Account<'info, AdminLog>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeAssociationVerificationKey<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.is_admin_signer(&authority.key()) @ MixerError::NotAuthority
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        seeds = [b"association_ceremony_attestation"],
+        bump = ceremony_attestation.bump
+    )]
+    pub ceremony_attestation: Account<'info, CeremonyAttestation>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = VerificationKey::ASSOCIATION_LEN,
+        seeds = [b"association_verification_key"],
+        bump
+    )]
+    pub verification_key: Account<'info, VerificationKey>,
+
+    /// Append-only record of admin actions
+    #[account(
+        mut,
+        seeds = [b"admin_log"],
+        bump = admin_log.bump
+    )]
+    pub admin_log: Account<'info, AdminLog>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterExclusionCeremonyAttestation<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.is_admin_signer(&authority.key()) @ MixerError::NotAuthority
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = CeremonyAttestation::LEN,
+        seeds = [b"exclusion_ceremony_attestation"],
+        bump
+    )]
+    pub ceremony_attestation: Account<'info, CeremonyAttestation>,
+
+    /// Append-only record of admin actions
+    #[account(
+        mut,
+        seeds = [b"admin_log"],
+        bump = admin_log.bump
+    )]
+    pub admin_log: Account<'info, AdminLog>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeExclusionVerificationKey<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.is_admin_signer(&authority.key()) @ MixerError::NotAuthority
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        seeds = [b"exclusion_ceremony_attestation"],
+        bump = ceremony_attestation.bump
+    )]
+    pub ceremony_attestation: Account<'info, CeremonyAttestation>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = EXCLUSION_VERIFICATION_KEY_LEN,
+        seeds = [b"exclusion_verification_key"],
+        bump
+    )]
+    pub verification_key: Account<'info, VerificationKey>,
+
+    /// Append-only record of admin actions
+    #[account(
+        mut,
+        seeds = [b"admin_log"],
+        bump = admin_log.bump
+    )]
+    pub admin_log: Account<'info, AdminLog>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AdminControl<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.is_admin_signer(&authority.key()) @ MixerError::NotAuthority
+    )]
+    pub config: Account<'info, Config>,
+
+    /// Append-only record of admin actions
+    #[account(
+        mut,
+        seeds = [b"admin_log"],
+        bump = admin_log.bump
+    )]
+    pub admin_log: Account<'info, AdminLog>,
+
+    /// Pays for the admin log entry
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FlagCommitment<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.is_admin_signer(&signer.key())
+            || signer.key() == config.guardian @ MixerError::NotAuthority
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        seeds = [b"pool", pool.mint.as_ref(), pool.denomination.to_le_bytes().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, MixerPool>,
+
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = CommitmentBlocklist::LEN,
+        seeds = [b"commitment_blocklist", pool.key().as_ref()],
+        bump
+    )]
+    pub commitment_blocklist: Account<'info, CommitmentBlocklist>,
+
+    #[account(
+        mut,
+        seeds = [b"admin_log"],
+        bump = admin_log.bump
+    )]
+    pub admin_log: Account<'info, AdminLog>,
+
+    /// The guardian, or an authority (multisig) signer
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RequestCommitmentUnflag<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.is_admin_signer(&authority.key()) @ MixerError::NotAuthority
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        seeds = [b"pool", pool.mint.as_ref(), pool.denomination.to_le_bytes().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, MixerPool>,
+
+    #[account(
+        mut,
+        seeds = [b"commitment_blocklist", pool.key().as_ref()],
+        bump = commitment_blocklist.bump
+    )]
+    pub commitment_blocklist: Account<'info, CommitmentBlocklist>,
+
+    #[account(
+        mut,
+        seeds = [b"admin_log"],
+        bump = admin_log.bump
+    )]
+    pub admin_log: Account<'info, AdminLog>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UnflagCommitment<'info> {
+    #[account(
+        mut,
+        seeds = [b"commitment_blocklist", commitment_blocklist.pool.as_ref()],
+        bump = commitment_blocklist.bump
+    )]
+    pub commitment_blocklist: Account<'info, CommitmentBlocklist>,
+}
+
+#[derive(Accounts)]
+pub struct ReclaimPoolBond<'info> {
+    #[account(
+        mut,
+        seeds = [b"pool_bond", pool_bond.pool.as_ref()],
+        bump = pool_bond.bump,
+        has_one = creator,
+        close = creator
+    )]
+    pub pool_bond: Account<'info, PoolBond>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePoolFlag<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.is_admin_signer(&authority.key()) @ MixerError::NotAuthority
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"pool_bond", pool_bond.pool.as_ref()],
+        bump = pool_bond.bump
+    )]
+    pub pool_bond: Account<'info, PoolBond>,
+
+    #[account(
+        mut,
+        seeds = [b"admin_log"],
+        bump = admin_log.bump
+    )]
+    pub admin_log: Account<'info, AdminLog>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ForfeitPoolBond<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.is_admin_signer(&authority.key()) @ MixerError::NotAuthority
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"pool_bond", pool_bond.pool.as_ref()],
+        bump = pool_bond.bump,
+        close = fee_collector
+    )]
+    pub pool_bond: Account<'info, PoolBond>,
+
+    /// The bonded pool, closed by close_pool
+    #[account(
+        seeds = [b"retired_pool", pool_bond.pool.as_ref()],
+        bump = retired_pool.bump
+    )]
+    pub retired_pool: Account<'info, RetiredPool>,
+
+    /// CHECK: Fee collector from config, receives the bond
+    #[account(mut, address = config.fee_collector)]
+    pub fee_collector: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"admin_log"],
+        bump = admin_log.bump
+    )]
+    pub admin_log: Account<'info, AdminLog>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeAdminLog<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = AdminLog::LEN,
+        seeds = [b"admin_log"],
+        bump
+    )]
+    pub admin_log: Account<'info, AdminLog>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateConfig<'info> {
+    /// CHECK: Config in a possibly older layout, checked by discriminator
+    /// and authority in migrate_config
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump,
+        owner = crate::ID
+    )]
+    pub config: AccountInfo<'info>,
+
+    /// Append-only record of admin actions, created here for deployments
+    /// from before it existed
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = AdminLog::LEN,
+        seeds = [b"admin_log"],
+        bump
+    )]
+    pub admin_log: Account<'info, AdminLog>,
+
+    /// Pays for the admin log entry
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RecordUpgradeAuthority<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        constraint = program.programdata_address()? == Some(program_data.key()) @ MixerError::InvalidProgramData
+    )]
+    pub program: Program<'info, crate::program::Nullifier>,
+
+    pub program_data: Account<'info, ProgramData>,
+}
+
+#[derive(Accounts)]
+pub struct GuardianPause<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = guardian @ MixerError::NotGuardian
+    )]
+    pub config: Account<'info, Config>,
+
+    /// Append-only record of admin actions
+    #[account(
+        mut,
+        seeds = [b"admin_log"],
+        bump = admin_log.bump
+    )]
+    pub admin_log: Account<'info, AdminLog>,
+
+    #[account(mut)]
+    pub guardian: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeAllowedMints<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.is_admin_signer(&authority.key()) @ MixerError::NotAuthority
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = AllowedMints::LEN,
+        seeds = [b"allowed_mints"],
+        bump
+    )]
+    pub allowed_mints: Account<'info, AllowedMints>,
+
+    /// Append-only record of admin actions
+    #[account(
+        mut,
+        seeds = [b"admin_log"],
+        bump = admin_log.bump
+    )]
+    pub admin_log: Account<'info, AdminLog>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
 }
 
-impl MixerPool {
-    pub const LEN: usize = 8 + 8 + 8 + 4 + 4 + 32 + 4 + 8 + 1;
+#[derive(Accounts)]
+pub struct UpdateAllowedMints<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.is_admin_signer(&authority.key()) @ MixerError::NotAuthority
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"allowed_mints"],
+        bump = allowed_mints.bump
+    )]
+    pub allowed_mints: Account<'info, AllowedMints>,
+
+    /// Append-only record of admin actions
+    #[account(
+        mut,
+        seeds = [b"admin_log"],
+        bump = admin_log.bump
+    )]
+    pub admin_log: Account<'info, AdminLog>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
 }
 
-#[account]
-pub struct CommitmentRecord {
-    pub pool: Pubkey,               // 32
-    pub commitment: [u8; 32],       // 32 - Privacy: hash instead of user address
-    pub leaf_index: u32,            // 4
-    pub timestamp: i64,             // 8
-    pub bump: u8,                   // 1
+#[derive(Accounts)]
+pub struct EnableAnonymityMining<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.is_admin_signer(&authority.key()) @ MixerError::NotAuthority
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        seeds = [b"pool", pool.mint.as_ref(), pool.denomination.to_le_bytes().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, MixerPool>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = AnonymityMining::LEN,
+        seeds = [b"anonymity_mining", pool.key().as_ref()],
+        bump
+    )]
+    pub anonymity_mining: Account<'info, AnonymityMining>,
+
+    /// Append-only record of admin actions
+    #[account(
+        mut,
+        seeds = [b"admin_log"],
+        bump = admin_log.bump
+    )]
+    pub admin_log: Account<'info, AdminLog>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
 }
 
-impl CommitmentRecord {
-    pub const LEN: usize = 8 + 32 + 32 + 4 + 8 + 1;
+#[derive(Accounts)]
+pub struct UpdateAnonymityMining<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.is_admin_signer(&authority.key()) @ MixerError::NotAuthority
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"anonymity_mining", anonymity_mining.pool.as_ref()],
+        bump = anonymity_mining.bump
+    )]
+    pub anonymity_mining: Account<'info, AnonymityMining>,
+
+    /// Append-only record of admin actions
+    #[account(
+        mut,
+        seeds = [b"admin_log"],
+        bump = admin_log.bump
+    )]
+    pub admin_log: Account<'info, AdminLog>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(owner: Pubkey)]
+pub struct InitializeAnonymityPoints<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = AnonymityPoints::LEN,
+        seeds = [b"anonymity_points", owner.as_ref()],
+        bump
+    )]
+    pub anonymity_points: Account<'info, AnonymityPoints>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializePoolRelayers<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.is_admin_signer(&authority.key()) @ MixerError::NotAuthority
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        seeds = [b"pool", pool.mint.as_ref(), pool.denomination.to_le_bytes().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, MixerPool>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = PoolRelayers::LEN,
+        seeds = [b"pool_relayers", pool.key().as_ref()],
+        bump
+    )]
+    pub pool_relayers: Account<'info, PoolRelayers>,
+
+    /// Append-only record of admin actions
+    #[account(
+        mut,
+        seeds = [b"admin_log"],
+        bump = admin_log.bump
+    )]
+    pub admin_log: Account<'info, AdminLog>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePoolRelayers<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.is_admin_signer(&authority.key()) @ MixerError::NotAuthority
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"pool_relayers", pool_relayers.pool.as_ref()],
+        bump = pool_relayers.bump
+    )]
+    pub pool_relayers: Account<'info, PoolRelayers>,
+
+    /// Append-only record of admin actions
+    #[account(
+        mut,
+        seeds = [b"admin_log"],
+        bump = admin_log.bump
+    )]
+    pub admin_log: Account<'info, AdminLog>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterRelayer<'info> {
+    #[account(
+        init,
+        payer = relayer,
+        space = RelayerInfo::LEN,
+        seeds = [b"relayer", relayer.key().as_ref()],
+        bump
+    )]
+    pub relayer_info: Account<'info, RelayerInfo>,
+
+    #[account(mut)]
+    pub relayer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateRelayer<'info> {
+    #[account(
+        mut,
+        seeds = [b"relayer", relayer.key().as_ref()],
+        bump = relayer_info.bump,
+        has_one = relayer
+    )]
+    pub relayer_info: Account<'info, RelayerInfo>,
+
+    #[account(mut)]
+    pub relayer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReportRelayer<'info> {
+    #[account(
+        mut,
+        seeds = [b"relayer", relayer_info.relayer.as_ref()],
+        bump = relayer_info.bump
+    )]
+    pub relayer_info: Account<'info, RelayerInfo>,
+
+    /// One open report per relayer
+    #[account(
+        init,
+        payer = reporter,
+        space = RelayerReport::LEN,
+        seeds = [b"relayer_report", relayer_info.relayer.as_ref()],
+        bump
+    )]
+    pub report: Account<'info, RelayerReport>,
+
+    #[account(mut)]
+    pub reporter: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AdjudicateRelayerReport<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.is_admin_signer(&authority.key()) @ MixerError::NotAuthority,
+        has_one = fee_collector
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"relayer", relayer_info.relayer.as_ref()],
+        bump = relayer_info.bump
+    )]
+    pub relayer_info: Account<'info, RelayerInfo>,
+
+    /// Closed back to the reporter, less the bond on a dismissal
+    #[account(
+        mut,
+        seeds = [b"relayer_report", relayer_info.relayer.as_ref()],
+        bump = report.bump,
+        has_one = reporter,
+        close = reporter
+    )]
+    pub report: Account<'info, RelayerReport>,
+
+    /// CHECK: Refunded the report's rent
+    #[account(mut)]
+    pub reporter: UncheckedAccount<'info>,
+
+    /// CHECK: Treasury receiving slashed stake and dismissed reports' bonds
+    #[account(mut)]
+    pub fee_collector: UncheckedAccount<'info>,
+
+    /// Append-only record of admin actions
+    #[account(
+        mut,
+        seeds = [b"admin_log"],
+        bump = admin_log.bump
+    )]
+    pub admin_log: Account<'info, AdminLog>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DeregisterRelayer<'info> {
+    #[account(
+        mut,
+        seeds = [b"relayer", relayer.key().as_ref()],
+        bump = relayer_info.bump,
+        has_one = relayer,
+        close = relayer
+    )]
+    pub relayer_info: Account<'info, RelayerInfo>,
+
+    #[account(mut)]
+    pub relayer: Signer<'info>,
 }
 
-#[account]
-pub struct EncryptedNote {
-    pub owner: Pubkey,              // 32 - Wallet that owns this note
-    pub encrypted_data: Vec<u8>,    // Variable - Encrypted note data (secret, nullifier, etc.)
-    pub pool: Pubkey,               // 32 - Pool this note belongs to
-    pub leaf_index: u32,            // 4 - Leaf index in Merkle tree
-    pub timestamp: i64,             // 8 - When note was created
-    pub bump: u8,                   // 1 - PDA bump
+#[derive(Accounts)]
+pub struct UpdatePool<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.is_admin_signer(&authority.key()) @ MixerError::NotAuthority
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", pool.mint.as_ref(), pool.denomination.to_le_bytes().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, MixerPool>,
+
+    /// Append-only record of admin actions
+    #[account(
+        mut,
+        seeds = [b"admin_log"],
+        bump = admin_log.bump
+    )]
+    pub admin_log: Account<'info, AdminLog>,
+
+    /// Pays for the admin log entry
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
 }
 
-impl EncryptedNote {
-    // Max encrypted note size: ~200 bytes encrypted data + overhead
-    pub const MAX_SIZE: usize = 8 + 32 + 4 + 200 + 32 + 4 + 8 + 1;
-}
+#[derive(Accounts)]
+pub struct ProposeAssociationRoot<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.is_admin_signer(&authority.key()) @ MixerError::NotAuthority
+    )]
+    pub config: Account<'info, Config>,
 
-#[account]
-pub struct NullifierRegistry {
-    pub pool: Pubkey,                       // 32
-    pub bump: u8,                           // 1
-    pub nullifiers: Vec<[u8; 32]>,          // 4 (vec len) + 32 * count (dynamic)
-}
+    #[account(
+        seeds = [b"pool", pool.mint.as_ref(), pool.denomination.to_le_bytes().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, MixerPool>,
 
-impl NullifierRegistry {
-    // Base size + space for initial nullifiers
-    pub const LEN: usize = 8 + 32 + 1 + 4 + (32 * MAX_NULLIFIERS_PER_ACCOUNT);
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = AssociationSet::LEN,
+        seeds = [b"association_set", pool.key().as_ref()],
+        bump
+    )]
+    pub association_set: Box<Account<'info, AssociationSet>>,
 
-    pub fn is_used(&self, nullifier: &[u8; 32]) -> bool {
-        self.nullifiers.contains(nullifier)
-    }
+    /// Append-only record of admin actions
+    #[account(
+        mut,
+        seeds = [b"admin_log"],
+        bump = admin_log.bump
+    )]
+    pub admin_log: Account<'info, AdminLog>,
 
-    pub fn add_nullifier(&mut self, nullifier: [u8; 32]) -> Result<()> {
-        require!(
-            self.nullifiers.len() < MAX_NULLIFIERS_PER_ACCOUNT,
-            MixerError::NullifierRegistryFull
-        );
+    /// Pays for the association set and the admin log entry
+    #[account(mut)]
+    pub authority: Signer<'info>,
 
-        self.nullifiers.push(nullifier);
-        Ok(())
-    }
+    pub system_program: Program<'info, System>,
 }
 
-// Context Structures
+#[derive(Accounts)]
+pub struct ActivateAssociationRoot<'info> {
+    #[account(
+        seeds = [b"pool", pool.mint.as_ref(), pool.denomination.to_le_bytes().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, MixerPool>,
+
+    #[account(
+        mut,
+        seeds = [b"association_set", pool.key().as_ref()],
+        bump = association_set.bump
+    )]
+    pub association_set: Box<Account<'info, AssociationSet>>,
+}
 
 #[derive(Accounts)]
-pub struct Initialize<'info> {
+pub struct SetTaintedRoot<'info> {
     #[account(
-        init,
-        payer = payer,
-        space = Config::LEN,
         seeds = [b"config"],
-        bump
+        bump = config.bump,
+        constraint = config.is_admin_signer(&authority.key()) @ MixerError::NotAuthority
     )]
     pub config: Account<'info, Config>,
 
+    #[account(
+        seeds = [b"pool", pool.mint.as_ref(), pool.denomination.to_le_bytes().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, MixerPool>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = TaintedSet::LEN,
+        seeds = [b"tainted_set", pool.key().as_ref()],
+        bump
+    )]
+    pub tainted_set: Account<'info, TaintedSet>,
+
+    /// Append-only record of admin actions
+    #[account(
+        mut,
+        seeds = [b"admin_log"],
+        bump = admin_log.bump
+    )]
+    pub admin_log: Account<'info, AdminLog>,
+
+    /// Pays for the tainted set and the admin log entry
     #[account(mut)]
-    pub payer: Signer<'info>,
+    pub authority: Signer<'info>,
 
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-#[instruction(denomination: u64)]
-pub struct CreatePool<'info> {
+pub struct EnableDualTree<'info> {
     #[account(
         seeds = [b"config"],
         bump = config.bump,
-        has_one = authority
+        constraint = config.is_admin_signer(&authority.key()) @ MixerError::NotAuthority
     )]
     pub config: Account<'info, Config>,
 
+    #[account(
+        mut,
+        seeds = [b"pool", pool.mint.as_ref(), pool.denomination.to_le_bytes().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, MixerPool>,
+
+    #[account(
+        seeds = [
+            b"merkle_tree",
+            pool.key().as_ref(),
+            pool.tree_generation.to_le_bytes().as_ref()
+        ],
+        bump = merkle_tree.load()?.bump
+    )]
+    pub merkle_tree: AccountLoader<'info, MerkleTreeState>,
+
     #[account(
         init,
-        payer = payer,
-        space = MixerPool::LEN,
-        seeds = [b"pool", denomination.to_le_bytes().as_ref()],
+        payer = authority,
+        space = MerkleTreeState::LEN,
+        seeds = [
+            b"poseidon_tree",
+            pool.key().as_ref(),
+            pool.tree_generation.to_le_bytes().as_ref()
+        ],
         bump
     )]
-    pub pool: Account<'info, MixerPool>,
+    pub poseidon_tree: AccountLoader<'info, MerkleTreeState>,
 
-    pub authority: Signer<'info>,
+    /// Append-only record of admin actions
+    #[account(
+        mut,
+        seeds = [b"admin_log"],
+        bump = admin_log.bump
+    )]
+    pub admin_log: Account<'info, AdminLog>,
 
     #[account(mut)]
-    pub payer: Signer<'info>,
+    pub authority: Signer<'info>,
 
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-#[instruction(commitment: [u8; 32], encrypted_data: Vec<u8>)]
-pub struct Deposit<'info> {
+pub struct SetTreeHash<'info> {
     #[account(
         seeds = [b"config"],
-        bump = config.bump
+        bump = config.bump,
+        constraint = config.is_admin_signer(&authority.key()) @ MixerError::NotAuthority
     )]
     pub config: Account<'info, Config>,
 
     #[account(
         mut,
-        seeds = [b"pool", pool.denomination.to_le_bytes().as_ref()],
+        seeds = [b"pool", pool.mint.as_ref(), pool.denomination.to_le_bytes().as_ref()],
         bump = pool.bump
     )]
     pub pool: Account<'info, MixerPool>,
 
     #[account(
-        init,
-        payer = depositor,
-        space = CommitmentRecord::LEN,
+        mut,
         seeds = [
-            b"commitment",
+            b"merkle_tree",
             pool.key().as_ref(),
-            pool.next_leaf_index.to_le_bytes().as_ref()
+            pool.tree_generation.to_le_bytes().as_ref()
         ],
-        bump
+        bump = merkle_tree.load()?.bump
     )]
-    pub commitment_record: Account<'info, CommitmentRecord>,
+    pub merkle_tree: AccountLoader<'info, MerkleTreeState>,
 
+    /// Append-only record of admin actions
     #[account(
-        init,
-        payer = depositor,
-        space = EncryptedNote::MAX_SIZE,
+        mut,
+        seeds = [b"admin_log"],
+        bump = admin_log.bump
+    )]
+    pub admin_log: Account<'info, AdminLog>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExportTreeState<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.is_admin_signer(&authority.key()) @ MixerError::NotAuthority
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        seeds = [b"pool", pool.mint.as_ref(), pool.denomination.to_le_bytes().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, MixerPool>,
+
+    #[account(
+        mut,
         seeds = [
-            b"encrypted_note",
-            depositor.key().as_ref(),
+            b"merkle_tree",
             pool.key().as_ref(),
-            pool.next_leaf_index.to_le_bytes().as_ref()
+            pool.tree_generation.to_le_bytes().as_ref()
         ],
+        bump = merkle_tree.load()?.bump
+    )]
+    pub merkle_tree: AccountLoader<'info, MerkleTreeState>,
+
+    /// Append-only record of admin actions
+    #[account(
+        mut,
+        seeds = [b"admin_log"],
+        bump = admin_log.bump
+    )]
+    pub admin_log: Account<'info, AdminLog>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CommitRoot<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"pool", pool.mint.as_ref(), pool.denomination.to_le_bytes().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, MixerPool>,
+}
+
+#[derive(Accounts)]
+pub struct ClosePool<'info> {
+    #[account(
+        mut,
+        close = authority
+    )]
+    pub pool: Account<'info, MixerPool>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = RetiredPool::LEN,
+        seeds = [b"retired_pool", pool.key().as_ref()],
         bump
     )]
-    pub encrypted_note: Account<'info, EncryptedNote>,
+    pub retired_pool: Account<'info, RetiredPool>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.is_admin_signer(&authority.key()) @ MixerError::NotAuthority
+    )]
+    pub config: Account<'info, Config>,
+
+    /// CHECK: Token pools only, PDA owning the vault
+    #[account(
+        seeds = [b"vault_authority", pool.key().as_ref()],
+        bump
+    )]
+    pub vault_authority: Option<UncheckedAccount<'info>>,
+
+    /// Token pools only, closed with the pool
+    #[account(
+        mut,
+        seeds = [b"vault", pool.key().as_ref()],
+        bump,
+        token::authority = vault_authority
+    )]
+    pub vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Append-only record of admin actions
+    #[account(
+        mut,
+        seeds = [b"admin_log"],
+        bump = admin_log.bump
+    )]
+    pub admin_log: Account<'info, AdminLog>,
 
     #[account(mut)]
-    pub depositor: Signer<'info>,
+    pub authority: Signer<'info>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateNullifiers<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        seeds = [b"pool", pool.mint.as_ref(), pool.denomination.to_le_bytes().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, MixerPool>,
+
+    #[account(
+        mut,
+        seeds = [b"nullifier_registry", pool.key().as_ref()],
+        bump = nullifier_registry.bump
+    )]
+    pub nullifier_registry: Account<'info, NullifierRegistry>,
+
+    /// CHECK: Fee collector from config, receives the emptied registry's rent
+    #[account(
+        mut,
+        address = config.fee_collector
+    )]
+    pub fee_collector: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseSpentNullifier<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        seeds = [b"retired_pool", pool.key().as_ref()],
+        bump = retired_pool.bump
+    )]
+    pub retired_pool: Account<'info, RetiredPool>,
+
+    /// CHECK: Address of the closed pool, must still be empty
+    pub pool: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"nullifier", pool.key().as_ref(), spent_nullifier.nullifier_hash.as_ref()],
+        bump = spent_nullifier.bump,
+        close = fee_collector
+    )]
+    pub spent_nullifier: Account<'info, SpentNullifier>,
 
-    pub system_program: Program<'info, System>,
+    /// CHECK: Fee collector from config, receives the reclaimed rent
+    #[account(
+        mut,
+        address = config.fee_collector
+    )]
+    pub fee_collector: AccountInfo<'info>,
 }
 
 #[derive(Accounts)]
-#[instruction(nullifier: [u8; 32], secret: [u8; 32], merkle_root: [u8; 32])]
-pub struct Withdraw<'info> {
+pub struct CloseNullifierRegistry<'info> {
     #[account(
         seeds = [b"config"],
         bump = config.bump
@@ -572,103 +10337,381 @@ pub struct Withdraw<'info> {
     pub config: Account<'info, Config>,
 
     #[account(
-        mut,
-        seeds = [b"pool", pool.denomination.to_le_bytes().as_ref()],
-        bump = pool.bump
+        seeds = [b"retired_pool", pool.key().as_ref()],
+        bump = retired_pool.bump
     )]
-    pub pool: Account<'info, MixerPool>,
+    pub retired_pool: Account<'info, RetiredPool>,
+
+    /// CHECK: Address of the closed pool, must still be empty
+    pub pool: UncheckedAccount<'info>,
 
     #[account(
         mut,
         seeds = [b"nullifier_registry", pool.key().as_ref()],
-        bump = nullifier_record.bump
+        bump = nullifier_registry.bump,
+        close = fee_collector
     )]
-    pub nullifier_record: Account<'info, NullifierRegistry>,
-
-    /// CHECK: This is the recipient address, can be any address (PRIVACY)
-    #[account(mut)]
-    pub recipient: AccountInfo<'info>,
+    pub nullifier_registry: Account<'info, NullifierRegistry>,
 
-    /// CHECK: Fee collector from config
+    /// CHECK: Fee collector from config, receives the reclaimed rent
     #[account(
         mut,
         address = config.fee_collector
     )]
     pub fee_collector: AccountInfo<'info>,
-
-    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct InitializeNullifierRegistry<'info> {
-    #[account(
-        seeds = [b"pool", pool.denomination.to_le_bytes().as_ref()],
-        bump = pool.bump
-    )]
-    pub pool: Account<'info, MixerPool>,
+pub struct ScheduleForceClose<'info> {
+    /// CHECK: Checked against is_force_closable by discriminator
+    #[account(owner = crate::ID)]
+    pub account_to_close: AccountInfo<'info>,
 
     #[account(
         init,
-        payer = payer,
-        space = NullifierRegistry::LEN,
-        seeds = [b"nullifier_registry", pool.key().as_ref()],
+        payer = authority,
+        space = PendingForceClose::LEN,
+        seeds = [b"force_close", account_to_close.key().as_ref()],
         bump
     )]
-    pub nullifier_registry: Account<'info, NullifierRegistry>,
-
-    #[account(mut)]
-    pub payer: Signer<'info>,
-
-    pub system_program: Program<'info, System>,
-}
+    pub pending_force_close: Account<'info, PendingForceClose>,
 
-#[derive(Accounts)]
-pub struct AdminControl<'info> {
     #[account(
-        mut,
         seeds = [b"config"],
         bump = config.bump,
-        has_one = authority
+        constraint = config.is_admin_signer(&authority.key()) @ MixerError::NotAuthority
     )]
     pub config: Account<'info, Config>,
 
+    /// Append-only record of admin actions
+    #[account(
+        mut,
+        seeds = [b"admin_log"],
+        bump = admin_log.bump
+    )]
+    pub admin_log: Account<'info, AdminLog>,
+
+    #[account(mut)]
     pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct ClosePool<'info> {
+pub struct CancelForceClose<'info> {
     #[account(
         mut,
+        seeds = [b"force_close", pending_force_close.account.as_ref()],
+        bump = pending_force_close.bump,
         close = authority
     )]
-    pub pool: Account<'info, MixerPool>,
+    pub pending_force_close: Account<'info, PendingForceClose>,
 
     #[account(
         seeds = [b"config"],
         bump = config.bump,
-        has_one = authority
+        constraint = config.is_admin_signer(&authority.key()) @ MixerError::NotAuthority
     )]
     pub config: Account<'info, Config>,
 
+    /// Append-only record of admin actions
+    #[account(
+        mut,
+        seeds = [b"admin_log"],
+        bump = admin_log.bump
+    )]
+    pub admin_log: Account<'info, AdminLog>,
+
     #[account(mut)]
     pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
 pub struct ForceCloseAccount<'info> {
     /// CHECK: This account will be closed without deserialization (for migration)
-    #[account(mut)]
+    /// Checked against is_force_closable by discriminator
+    #[account(
+        mut,
+        owner = crate::ID
+    )]
     pub account_to_close: AccountInfo<'info>,
 
+    #[account(
+        mut,
+        seeds = [b"force_close", account_to_close.key().as_ref()],
+        bump = pending_force_close.bump,
+        close = authority
+    )]
+    pub pending_force_close: Account<'info, PendingForceClose>,
+
     #[account(
         seeds = [b"config"],
         bump = config.bump,
-        has_one = authority
+        constraint = config.is_admin_signer(&authority.key()) @ MixerError::NotAuthority
     )]
     pub config: Account<'info, Config>,
 
+    /// Append-only record of admin actions
+    #[account(
+        mut,
+        seeds = [b"admin_log"],
+        bump = admin_log.bump
+    )]
+    pub admin_log: Account<'info, AdminLog>,
+
     #[account(mut)]
     pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+// Events
+
+#[event]
+pub struct ForceCloseScheduled {
+    pub account: Pubkey,
+    pub lamports: u64,
+    pub executable_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct NoteDisclosed {
+    pub disclosure: NoteDisclosure,
+}
+
+#[event]
+pub struct AssociatedWithdrawal {
+    pub pool: Pubkey,
+    pub nullifier_hash: [u8; 32],
+    pub association_root: [u8; 32],
+    pub association_version: u32,       // Version of the association set the root belongs to
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AssociationRootProposed {
+    pub pool: Pubkey,
+    pub root: [u8; 32],
+    pub activates_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AssociationRootActivated {
+    pub pool: Pubkey,
+    pub root: [u8; 32],
+    pub version: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct NoteDisclosurePublished {
+    pub disclosed_note: Pubkey,
+    pub pool: Pubkey,
+    pub leaf_index: u32,
+    pub recipient: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DepositReceiptCreated {
+    pub pool: Pubkey,
+    pub leaf_index: u32,
+    pub receipt_hash: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DepositReceiptRevealed {
+    pub pool: Pubkey,
+    pub leaf_index: u32,
+    pub commitment: [u8; 32],
+    pub depositor: Pubkey,
+    pub created_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct InnocenceProven {
+    pub pool: Pubkey,
+    pub nullifier_hash: [u8; 32],
+    pub tainted_root: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CommitmentFlagged {
+    pub pool: Pubkey,
+    pub commitment: [u8; 32],
+    pub flagged_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CommitmentUnflagRequested {
+    pub pool: Pubkey,
+    pub commitment: [u8; 32],
+    pub unflag_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CommitmentUnflagged {
+    pub pool: Pubkey,
+    pub commitment: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolBondPosted {
+    pub pool: Pubkey,
+    pub creator: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolBondReclaimed {
+    pub pool: Pubkey,
+    pub creator: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolBondForfeited {
+    pub pool: Pubkey,
+    pub creator: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolFlagged {
+    pub pool: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolUnflagged {
+    pub pool: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuthorityRenounced {
+    pub former_authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EmergencyModeEntered {
+    pub paused_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EmergencyWithdrawal {
+    pub pool: Pubkey,
+    pub leaf_index: u32,
+    pub commitment: [u8; 32],
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AccountForceClosed {
+    pub account: Pubkey,
+    pub lamports: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FeeHolidayApplied {
+    pub pool: Pubkey,
+    pub fee_bps: u64,
+    pub waived_fee: u64,
+    pub holiday_end: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct UpgradeAuthorityRecorded {
+    pub upgrade_authority: Option<Pubkey>,
+    pub last_deploy_slot: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MinDelayUpdated {
+    pub pool: Pubkey,
+    pub old_delay: i64,
+    pub new_delay: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct WithdrawModeChanged {
+    pub pool: Pubkey,
+    pub old_mode: WithdrawMode,
+    pub new_mode: WithdrawMode,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CompressedDeposit {
+    pub pool: Pubkey,
+    pub commitment: [u8; 32],
+    pub leaf_index: u32,
+    pub encrypted_data: Vec<u8>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LeavesInserted {
+    pub pool: Pubkey,
+    pub start_index: u32,
+    pub commitments: Vec<[u8; 32]>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TreeRolledOver {
+    pub pool: Pubkey,
+    pub generation: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TreeStateExported {
+    pub pool: Pubkey,
+    pub generation: u32,
+    pub next_index: u32,
+    pub current_root_index: u32,
+    pub filled_subtrees: Vec<[u8; 32]>,
+    pub roots: Vec<[u8; 32]>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RootCommitted {
+    pub pool: Pubkey,
+    pub root: [u8; 32],
+    pub leaf_count: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TreeAudited {
+    pub pool: Pubkey,
+    pub generation: u32,
+    pub leaf_count: u32,
+    pub root: [u8; 32],
+    pub valid: bool,
+    pub timestamp: i64,
 }
 
 // Error Codes
@@ -678,7 +10721,7 @@ pub enum MixerError {
     #[msg("Invalid denomination. Must be 0.1, 1, 10, or 100 SOL.")]
     InvalidDenomination,
 
-    #[msg("Time delay must be at least 1 minute.")]
+    #[msg("Time delay must be between 1 minute and 30 days.")]
     InvalidTimeDelay,
 
     #[msg("Mixer is currently paused.")]
@@ -728,14 +10771,378 @@ pub enum MixerError {
 
     #[msg("Encrypted data exceeds maximum size of 200 bytes.")]
     EncryptedDataTooLarge,
+
+    #[msg("Invalid zero-knowledge proof.")]
+    InvalidProof,
+
+    #[msg("Invalid verification key. IC length must match public inputs and points must be on curve.")]
+    InvalidVerificationKey,
+
+    #[msg("Invalid ceremony attestation. Hashes must not be all zeros.")]
+    InvalidCeremonyAttestation,
+
+    #[msg("Verification key does not match the attested ceremony output.")]
+    VerificationKeyHashMismatch,
+
+    #[msg("Invalid proof point. Not on the BN254 curve.")]
+    InvalidProofPoint,
+
+    #[msg("Proof is not in canonical form. Negated proofs are rejected.")]
+    MalleableProof,
+
+    #[msg("Invalid proof encoding. Compressed point could not be decompressed.")]
+    InvalidProofEncoding,
+
+    #[msg("This withdrawal method is not enabled for the pool.")]
+    WithdrawModeNotAllowed,
+
+    #[msg("Pool is already in the requested withdraw mode.")]
+    WithdrawModeUnchanged,
+
+    #[msg("Write exceeds the proof buffer size.")]
+    ProofBufferOverflow,
+
+    #[msg("Proof buffer does not contain a valid withdrawal payload.")]
+    InvalidProofBuffer,
+
+    #[msg("Merkle root is not a recent root of the pool's tree.")]
+    UnknownMerkleRoot,
+
+    #[msg("Pool has no compressed tree. Call init_compressed_tree first.")]
+    CompressedTreeNotInitialized,
+
+    #[msg("Pool already has a compressed tree.")]
+    CompressedTreeAlreadyInitialized,
+
+    #[msg("Compressed tree depth must match MERKLE_TREE_DEPTH.")]
+    InvalidCompressedTreeDepth,
+
+    #[msg("Batch must contain between 1 and 16 commitments.")]
+    InvalidBatchSize,
+
+    #[msg("Current Merkle tree still has free leaves. Cannot roll over yet.")]
+    TreeNotFull,
+
+    #[msg("Nullifier non-membership proof does not match the current root.")]
+    InvalidNonMembershipProof,
+
+    #[msg("Invalid attestor set. Need 1 to 5 distinct attestors and a threshold between 1 and their count.")]
+    InvalidAttestorSet,
+
+    #[msg("Not enough attestor signatures to commit a root.")]
+    InsufficientAttestations,
+
+    #[msg("Invalid attested root. Must be non-zero and cover between the last attested and current leaf counts.")]
+    InvalidAttestedRoot,
+
+    #[msg("Merkle root does not match the pool's attested root.")]
+    RootNotAttested,
+
+    #[msg("Subtree is not complete yet. Cannot write its checkpoint.")]
+    SubtreeNotComplete,
+
+    #[msg("Merkle tree has been exported for migration and no longer accepts deposits.")]
+    TreeFrozen,
+
+    #[msg("Pool already maintains a Poseidon tree.")]
+    DualTreeAlreadyEnabled,
+
+    #[msg("Dual trees can only be enabled while the current tree is empty.")]
+    DualTreeRequiresEmptyTree,
+
+    #[msg("Dual-tree pool requires its Poseidon tree account.")]
+    PoseidonTreeMissing,
+
+    #[msg("Proof path length does not match the tree depth.")]
+    InvalidProofLength,
+
+    #[msg("Commitment record is not the next leaf of the audited tree.")]
+    AuditRecordMismatch,
+
+    #[msg("Unsupported tree hash for the pool's main tree.")]
+    InvalidTreeHash,
+
+    #[msg("The tree hash can only be changed while the current tree is empty.")]
+    TreeHashRequiresEmptyTree,
+
+    #[msg("Pool is still open. Nullifier accounts can only be reclaimed after close_pool.")]
+    PoolNotRetired,
+
+    #[msg("Nullifier accounts of a closed pool can't be reclaimed until the timelock has elapsed.")]
+    ReclaimTimelockNotElapsed,
+
+    #[msg("Account is not the SpentNullifier PDA of the registry's next entry to migrate.")]
+    NullifierMigrationMismatch,
+
+    #[msg("Mint has an extension token pools can't hold (permanent delegate, transfer hook or non-transferable).")]
+    UnsupportedMintExtension,
+
+    #[msg("Vault received less than the pool denomination.")]
+    DepositAmountMismatch,
+
+    #[msg("Wrapped SOL uses the SOL pools; token pools can't be created for the native mint.")]
+    WrappedSolTokenPool,
+
+    #[msg("Token account recipients must be wrapped SOL accounts.")]
+    NotWrappedSolAccount,
+
+    #[msg("Token program account is required to pay out as wrapped SOL.")]
+    TokenProgramMissing,
+
+    #[msg("Only SOL pools accept this instruction.")]
+    NotSolPool,
+
+    #[msg("NFT pools need a mint with 0 decimals and a supply of 1.")]
+    NotAnNft,
+
+    #[msg("Recipient token account doesn't exist; pass the recipient wallet to create it.")]
+    RecipientTokenAccountMissing,
+
+    #[msg("Recipient token account isn't a token account for the pool's mint.")]
+    InvalidRecipientTokenAccount,
+
+    #[msg("Mint is not on the allowlist.")]
+    MintNotAllowed,
+
+    #[msg("Mint is already on the allowlist.")]
+    MintAlreadyAllowed,
+
+    #[msg("Mint allowlist is full.")]
+    AllowedMintsFull,
+
+    #[msg("Exchange rate must be greater than zero.")]
+    InvalidExchangeRate,
+
+    #[msg("Vault holds less than one denomination per outstanding note.")]
+    VaultUndercollateralized,
+
+    #[msg("Token pool denomination is below the dust floor of 10 whole tokens.")]
+    DenominationBelowDustFloor,
+
+    #[msg("Token pool denomination must be 1, 2 or 5 times a power of ten whole tokens.")]
+    DenominationNotRound,
+
+    #[msg("Reimbursement basis points can't exceed 10000.")]
+    InvalidReimbursementBps,
+
+    #[msg("ATA reimbursement needs the recipient's ATA created in this withdrawal and the payer's token account.")]
+    ReimbursementNotAllowed,
+
+    #[msg("ATA reimbursement exceeds the configured share of the withdrawal fee.")]
+    ReimbursementTooHigh,
+
+    #[msg("Closing a token pool needs its vault, vault authority and token program.")]
+    VaultAccountsMissing,
+
+    #[msg("Token pool vault still holds tokens.")]
+    VaultNotEmpty,
+
+    #[msg("Recipient token account is frozen.")]
+    RecipientAccountFrozen,
+
+    #[msg("Mint is non-transferable.")]
+    MintNotTransferable,
+
+    #[msg("Relayer fee can't exceed 10000 basis points.")]
+    InvalidRelayerFee,
+
+    #[msg("Relayer stake is below the minimum.")]
+    InsufficientRelayerStake,
+
+    #[msg("Relayer must be paused for the unbonding period with no open report to deregister.")]
+    RelayerStillBonded,
+
+    #[msg("Relayer fee exceeds the withdrawal amount.")]
+    RelayerFeeTooHigh,
+
+    #[msg("Payload doesn't match the withdrawal intent.")]
+    InvalidIntentPayload,
+
+    #[msg("Relayer is not allowed to submit withdrawals from this pool.")]
+    RelayerNotAllowed,
+
+    #[msg("Relayer is already allowed.")]
+    RelayerAlreadyAllowed,
+
+    #[msg("Pool relayer allowlist is full.")]
+    PoolRelayersFull,
+
+    #[msg("Queued intent needs the pool's intent queue.")]
+    IntentQueueMissing,
+
+    #[msg("Intents ahead in the queue must be executed first.")]
+    IntentOutOfOrder,
+
+    #[msg("Queue head hasn't timed out.")]
+    IntentQueueNotStale,
+
+    #[msg("Queued intent is too far behind the head to cancel yet.")]
+    IntentTooFarBehindHead,
+
+    #[msg("Relayer fee is above the configured cap.")]
+    RelayerFeeAboveCap,
+
+    #[msg("Fee curve must rise to at most the flat fee over a non-empty ramp.")]
+    InvalidFeeCurve,
+
+    #[msg("Staker share can't exceed 10000 basis points.")]
+    InvalidFeeSplit,
+
+    #[msg("A staker share needs a staker vault.")]
+    StakerVaultMissing,
+
+    #[msg("Fee sweep exceeds the fees collected.")]
+    FeeSweepExceedsAccrued,
+
+    #[msg("Claiming anonymity points requires the configured reward hook.")]
+    RewardHookMissing,
+
+    #[msg("Fee holiday must end no earlier than it starts.")]
+    InvalidFeeHoliday,
+
+    #[msg("Signer is neither the authority nor an authority member.")]
+    NotAuthority,
+
+    #[msg("Invalid authority members or threshold.")]
+    InvalidAuthoritySet,
+
+    #[msg("Not enough authority members signed.")]
+    InsufficientAuthorityApprovals,
+
+    #[msg("Signer is not the guardian.")]
+    NotGuardian,
+
+    #[msg("Rate limit needs a positive window.")]
+    InvalidRateLimit,
+
+    #[msg("Pool rate limit exceeded, try again later.")]
+    RateLimitExceeded,
+
+    #[msg("Pools, fee vaults, relayer stakes and the config can't be force closed.")]
+    AccountNotForceClosable,
+
+    #[msg("Force close can't be executed until its timelock has elapsed.")]
+    ForceCloseTimelockNotElapsed,
+
+    #[msg("Program data account is not this program's.")]
+    InvalidProgramData,
+
+    #[msg("Emergency withdrawals open only after a sunset or a long pause.")]
+    EmergencyWithdrawalsNotOpen,
+
+    #[msg("The mixer is in emergency mode and can't be unpaused.")]
+    EmergencyModeActive,
+
+    #[msg("Pools created by anyone but the authority need a creation bond.")]
+    PoolBondMissing,
+
+    #[msg("Pool creation bond must be nonzero while permissionless pools are on.")]
+    InvalidPoolBond,
+
+    #[msg("Pool creation bond is still locked.")]
+    PoolBondLocked,
+
+    #[msg("Pool is flagged for review.")]
+    PoolBondFlagged,
+
+    #[msg("Pool is not flagged for review.")]
+    PoolBondNotFlagged,
+
+    #[msg("Commitment is flagged and can't be withdrawn.")]
+    CommitmentBlocked,
+
+    #[msg("Commitment is already flagged.")]
+    CommitmentAlreadyFlagged,
+
+    #[msg("Commitment is not flagged.")]
+    CommitmentNotFlagged,
+
+    #[msg("Commitment blocklist is full.")]
+    CommitmentBlocklistFull,
+
+    #[msg("Commitment can't be unflagged until its unflag timelock has elapsed.")]
+    UnflagTimelockNotElapsed,
+
+    #[msg("Viewing key hash must not be all zeros.")]
+    InvalidViewingKey,
+
+    #[msg("Disclosure needs the note owner's signature or its viewing key.")]
+    DisclosureNotAuthorized,
+
+    #[msg("Withdrawal did not spend this note.")]
+    WithdrawalNotOfNote,
+
+    #[msg("Association set root must be non-zero.")]
+    InvalidAssociationRoot,
+
+    #[msg("Association set proofs need both the pool's association set and the association verification key.")]
+    AssociationSetMissing,
+
+    #[msg("Tainted set root must be non-zero.")]
+    InvalidTaintedRoot,
+
+    #[msg("Deposit receipt hash is zero or doesn't match the depositor, commitment and salt.")]
+    InvalidDepositReceipt,
+
+    #[msg("Deposit receipt already revealed.")]
+    DepositReceiptAlreadyRevealed,
+
+    #[msg("A screening program is configured but was not passed.")]
+    ScreeningProgramMissing,
+
+    #[msg("Too many disclosed notes for one compliance report.")]
+    ComplianceReportTooLarge,
+
+    #[msg("Disclosed note was published by another recipient.")]
+    DisclosureNotOfRecipient,
+
+    #[msg("Disclosed note passed more than once.")]
+    DuplicateDisclosure,
+
+    #[msg("No association set root is pending.")]
+    NoPendingAssociationRoot,
+
+    #[msg("Association set root timelock has not elapsed.")]
+    AssociationRootTimelockNotElapsed,
+
+    #[msg("Association set has no active root yet.")]
+    AssociationRootNotSet,
 }
 
 // Unit tests modules
 #[cfg(test)]
 mod lib_test;
 #[cfg(test)]
+mod admin_log_test;
+#[cfg(test)]
+mod association_test;
+#[cfg(test)]
+mod bloom_test;
+#[cfg(test)]
+mod bn254_test;
+#[cfg(test)]
+mod compression_test;
+#[cfg(test)]
+mod disclosure_test;
+#[cfg(test)]
 mod merkle_test;
 #[cfg(test)]
 mod merkle_poseidon_test;
 #[cfg(test)]
+mod merkle_keccak_test;
+#[cfg(test)]
+mod mining_test;
+#[cfg(test)]
+mod relayer_test;
+#[cfg(test)]
+mod screening_test;
+#[cfg(test)]
+mod sparse_merkle_test;
+#[cfg(test)]
 mod groth16_test;
+#[cfg(test)]
+mod exclusion_test;
+#[cfg(test)]
+mod test_utils_test;
+#[cfg(test)]
+mod token_extensions_test;
@@ -72,20 +72,209 @@ fn test_max_nullifiers_per_account() {
 
 #[test]
 fn test_config_account_size() {
-    // Config: authority (32) + fee_collector (32) + paused (1) + bump (1) + discriminator (8)
-    let expected_size = 8 + 32 + 32 + 1 + 1;
+    // Config: authority (32) + fee_collector (32) + paused (1) + bump (1) + discriminator (8) +
+    // attestors (32 * 5) + attestor_count (1) + attestor_threshold (1) +
+    // ata_reimbursement_bps (2) + relayer_rebate_lamports (8) + max_relayer_fee_bps (2) +
+    // staker_vault (32) + staker_share_bps (2) + reward_hook (32) +
+    // authority_members (32 * 5) + authority_member_count (1) + authority_threshold (1) +
+    // guardian (32) + deposit_rate_limit (4 + 8) + withdrawal_rate_limit (4 + 8) + version (1) +
+    // upgrade_authority (32) + last_deploy_slot (8) + upgrade_info_recorded_at (8) +
+    // paused_at (8) + emergency_mode (1) + renounced (1) + permissionless_pools (1) +
+    // pool_creation_bond (8) + screening_program (32)
+    let expected_size = 8 + 32 + 32 + 1 + 1 + 32 * MAX_ATTESTORS + 1 + 1 + 2 + 8 + 2 + 32 + 2 + 32
+        + 32 * MAX_AUTHORITY_MEMBERS + 1 + 1 + 32 + 12 + 12 + 1 + 32 + 8 + 8 + 8 + 1 + 1 + 1 + 8 + 32;
     assert_eq!(Config::LEN, expected_size);
-    assert_eq!(Config::LEN, 74);
+    assert_eq!(Config::LEN, 632);
 }
 
 #[test]
 fn test_mixer_pool_account_size() {
     // MixerPool: discriminator (8) + denomination (8) + min_delay (8) +
     // total_deposits (4) + total_withdrawals (4) + merkle_root (32) +
-    // next_leaf_index (4) + creation_timestamp (8) + bump (1)
-    let expected_size = 8 + 8 + 8 + 4 + 4 + 32 + 4 + 8 + 1;
+    // next_leaf_index (4) + creation_timestamp (8) + bump (1) + withdraw_mode (1) +
+    // compressed_tree (32) + tree_generation (4) + attested_root (32) + attested_leaf_count (4)
+    // + version (1) + dual_tree (1) + tree_depth (1) + tree_hash (1) + mint (32) +
+    // relayer_policy (1) + fee_curve (1 + 2 + 2 + 4) + fee_stats (8 * 4) +
+    // fee_holiday_start (8) + fee_holiday_end (8) + deposit_counter (16) + withdrawal_counter (16)
+    let expected_size = 8 + 8 + 8 + 4 + 4 + 32 + 4 + 8 + 1 + 1 + 32 + 4 + 32 + 4 + 1 + 1 + 1 + 1 + 32 + 1 + 9 + 32 + 8 + 8
+        + 16 + 16;
     assert_eq!(MixerPool::LEN, expected_size);
-    assert_eq!(MixerPool::LEN, 77);
+    assert_eq!(MixerPool::LEN, 276);
+}
+
+#[test]
+fn test_split_fee() {
+    // Same split for lamports and token base units
+    assert_eq!(split_fee(DENOMINATION_1_SOL, FEE_BASIS_POINTS).unwrap(), (999_000_000, 1_000_000));
+    assert_eq!(split_fee(999, FEE_BASIS_POINTS).unwrap(), (999, 0));
+    assert!(split_fee(u64::MAX, FEE_BASIS_POINTS).is_err());
+}
+
+#[test]
+fn test_valid_token_denominations() {
+    for denomination in [
+        DENOMINATION_10_USDC,
+        DENOMINATION_100_USDC,
+        DENOMINATION_1000_USDC,
+        DENOMINATION_10000_USDC,
+        20_000_000,
+        50_000_000,
+        500_000_000,
+    ] {
+        assert!(check_token_denomination(denomination, 6).is_ok());
+    }
+
+    // Sizes follow the mint's decimals, not lamports: 1 SOL worth of base
+    // units is a single token of a 9-decimal mint
+    assert!(check_token_denomination(DENOMINATION_1_SOL, 9).is_err());
+    assert!(check_token_denomination(10_000_000_000, 9).is_ok());
+
+    // Decimals too large for the floor to fit in a u64
+    assert!(check_token_denomination(10, 20).is_err());
+}
+
+#[test]
+fn test_token_denomination_errors() {
+    let error_of = |denomination, decimals| match check_token_denomination(denomination, decimals) {
+        Err(Error::AnchorError(error)) => error.error_code_number,
+        other => panic!("expected an error, got {:?}", other),
+    };
+    let code = |error: MixerError| anchor_lang::error::ERROR_CODE_OFFSET + error as u32;
+
+    // 0.000001 USDC and 9.99 USDC pools would only fragment liquidity
+    assert_eq!(error_of(1, 6), code(MixerError::DenominationBelowDustFloor));
+    assert_eq!(error_of(9_990_000, 6), code(MixerError::DenominationBelowDustFloor));
+
+    assert_eq!(error_of(DENOMINATION_10_USDC + 1, 6), code(MixerError::DenominationNotRound));
+    assert_eq!(error_of(30_000_000, 6), code(MixerError::DenominationNotRound));
+    assert_eq!(error_of(150_000_000, 6), code(MixerError::DenominationNotRound));
+}
+
+#[test]
+fn test_nft_pool_denomination() {
+    assert!(is_nft_mint(0, 1));
+    assert!(!is_nft_mint(0, 2));
+    assert!(!is_nft_mint(6, 1));
+
+    // Only create_nft_pool makes single-unit pools, and the NFT is paid out whole
+    assert!(check_token_denomination(NFT_DENOMINATION, 0).is_err());
+    assert_eq!(split_fee(NFT_DENOMINATION, FEE_BASIS_POINTS).unwrap(), (1, 0));
+}
+
+/// Packed SPL Token account data holding `mint`
+fn token_account_data(mint: Pubkey) -> Vec<u8> {
+    use anchor_lang::solana_program::program_pack::Pack;
+
+    let account = spl_token::state::Account {
+        mint,
+        owner: Pubkey::new_unique(),
+        state: spl_token::state::AccountState::Initialized,
+        ..Default::default()
+    };
+    let mut data = vec![0u8; spl_token::state::Account::LEN];
+    account.pack_into_slice(&mut data);
+    data
+}
+
+#[test]
+fn test_wrapped_sol_recipient() {
+    let key = Pubkey::new_unique();
+    let mut lamports = 0;
+    let mut wsol = token_account_data(spl_token::native_mint::ID);
+    let recipient = AccountInfo::new(&key, false, true, &mut lamports, &mut wsol, &spl_token::ID, false, 0);
+    assert!(is_wrapped_sol_account(&recipient).unwrap());
+
+    // Lamports paid into another mint's account would only be stranded rent
+    let mut other_lamports = 0;
+    let mut other = token_account_data(Pubkey::new_unique());
+    let other = AccountInfo::new(&key, false, true, &mut other_lamports, &mut other, &spl_token::ID, false, 0);
+    assert!(!is_wrapped_sol_account(&other).unwrap());
+    assert!(sync_wrapped_sol_recipient(&other, None).is_err());
+
+    // Plain system accounts are paid in lamports without a token program
+    let owner = Pubkey::default();
+    let mut system_lamports = 0;
+    let mut empty = [];
+    let system = AccountInfo::new(&key, false, true, &mut system_lamports, &mut empty, &owner, false, 0);
+    assert!(sync_wrapped_sol_recipient(&system, None).is_ok());
+}
+
+#[test]
+fn test_token_pool_rejects_sol_deposits() {
+    let owner = crate::ID;
+    let system_id = anchor_lang::system_program::ID;
+    let mint = Pubkey::new_unique();
+    let denomination: u64 = 10_000_000;
+
+    let (config_key, config_bump) = Pubkey::find_program_address(&[b"config"], &owner);
+    let mut config = config_with_attestors(&[], 0);
+    config.bump = config_bump;
+    let (pool_key, pool_bump) = Pubkey::find_program_address(
+        &[b"pool", mint.as_ref(), denomination.to_le_bytes().as_ref()],
+        &owner,
+    );
+    let mut pool = MixerPool::try_from_slice(&[0u8; MixerPool::LEN - 8]).unwrap();
+    pool.mint = mint;
+    pool.denomination = denomination;
+    pool.bump = pool_bump;
+
+    let (mut config_data, mut pool_data) = (Vec::new(), Vec::new());
+    config.try_serialize(&mut config_data).unwrap();
+    pool.try_serialize(&mut pool_data).unwrap();
+    let mut tree_data = vec![0u8; MerkleTreeState::LEN];
+    tree_data[..8].copy_from_slice(&MerkleTreeState::DISCRIMINATOR);
+    let keys: Vec<Pubkey> = (0..2).map(|_| Pubkey::new_unique()).collect();
+    let mut lamports = [0u64; 6];
+    let [l0, l1, l2, l3, l4, l5] = &mut lamports;
+    let (mut d3, mut d4, mut d5) = ([0u8; 0], [0u8; 0], [0u8; 0]);
+    // Optional accounts left out are passed as the program id
+    let missing = AccountInfo::new(&owner, false, false, l3, &mut d3, &owner, true, 0);
+    let infos = [
+        AccountInfo::new(&config_key, false, false, l0, &mut config_data, &owner, false, 0),
+        AccountInfo::new(&pool_key, false, true, l1, &mut pool_data, &owner, false, 0),
+        AccountInfo::new(&keys[0], false, true, l2, &mut tree_data, &owner, false, 0),
+        missing.clone(),
+        AccountInfo::new(&keys[1], true, true, l4, &mut d4, &system_id, false, 0),
+        missing,
+        AccountInfo::new(&system_id, false, false, l5, &mut d5, &system_id, true, 0),
+    ];
+
+    // A batch of SOL deposits can't land in a token pool's tree
+    let result = InsertLeaves::try_accounts(
+        &owner,
+        &mut &infos[..],
+        &[],
+        &mut InsertLeavesBumps::default(),
+        &mut std::collections::BTreeSet::new(),
+    );
+    match result {
+        Err(Error::AnchorError(error)) => assert_eq!(
+            error.error_code_number,
+            anchor_lang::error::ERROR_CODE_OFFSET + MixerError::NotSolPool as u32
+        ),
+        Err(other) => panic!("expected NotSolPool, got {:?}", other),
+        Ok(_) => panic!("expected NotSolPool"),
+    }
+}
+
+#[test]
+fn test_valid_denominations() {
+    for denomination in [DENOMINATION_01_SOL, DENOMINATION_1_SOL, DENOMINATION_10_SOL, DENOMINATION_100_SOL] {
+        assert!(is_valid_denomination(denomination));
+    }
+    assert!(!is_valid_denomination(0));
+    assert!(!is_valid_denomination(DENOMINATION_1_SOL + 1));
+}
+
+#[test]
+fn test_valid_time_delays() {
+    assert!(is_valid_time_delay(MIN_TIME_DELAY));
+    assert!(is_valid_time_delay(MAX_TIME_DELAY));
+    assert!(!is_valid_time_delay(MIN_TIME_DELAY - 1));
+
+    // A pool can't hold its notes hostage behind an absurd delay
+    assert!(!is_valid_time_delay(MAX_TIME_DELAY + 1));
+    assert!(!is_valid_time_delay(i64::MAX));
 }
 
 #[test]
@@ -106,6 +295,776 @@ fn test_nullifier_registry_account_size() {
     assert_eq!(NullifierRegistry::LEN, 3245);
 }
 
+#[test]
+fn test_spent_nullifier_account_size() {
+    // SpentNullifier: discriminator (8) + pool (32) + nullifier_hash (32) +
+    // spent_at (8) + bump (1)
+    assert_eq!(SpentNullifier::LEN, 81);
+}
+
+#[test]
+fn test_nullifier_registry_is_used_in() {
+    let key = Pubkey::new_unique();
+    let owner = crate::ID;
+    let nullifier = [7u8; 32];
+
+    // Pools without a pre-PDA registry pass an empty account
+    let mut lamports = 0;
+    let mut empty: Vec<u8> = Vec::new();
+    let account = AccountInfo::new(&key, false, false, &mut lamports, &mut empty, &owner, false, 0);
+    assert!(!NullifierRegistry::is_used_in(&account, &nullifier).unwrap());
+
+    let registry = NullifierRegistry {
+        pool: Pubkey::new_unique(),
+        bump: 255,
+        nullifiers: vec![nullifier],
+    };
+    let mut data = Vec::new();
+    registry.try_serialize(&mut data).unwrap();
+
+    let mut lamports = 0;
+    let account = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+    assert!(NullifierRegistry::is_used_in(&account, &nullifier).unwrap());
+    assert!(!NullifierRegistry::is_used_in(&account, &[8u8; 32]).unwrap());
+}
+
+#[test]
+fn test_retired_pool_reclaim_timelock() {
+    // RetiredPool: discriminator (8) + pool (32) + closed_at (8) + bump (1)
+    assert_eq!(RetiredPool::LEN, 49);
+
+    let retired = RetiredPool {
+        pool: Pubkey::new_unique(),
+        closed_at: 1_700_000_000,
+        bump: 255,
+    };
+
+    assert!(!retired.is_reclaimable(retired.closed_at));
+    assert!(!retired.is_reclaimable(retired.closed_at + NULLIFIER_RECLAIM_DELAY - 1));
+    assert!(retired.is_reclaimable(retired.closed_at + NULLIFIER_RECLAIM_DELAY));
+}
+
+#[test]
+fn test_allowed_mints() {
+    // AllowedMints: discriminator (8) + vec len (4) + mints (32 * 32) + bump (1)
+    assert_eq!(AllowedMints::LEN, 1037);
+
+    let mut allowed = AllowedMints { mints: Vec::new(), bump: 255 };
+    let usdc = Pubkey::new_unique();
+
+    allowed.add(usdc).unwrap();
+    assert!(allowed.contains(&usdc));
+    assert!(allowed.add(usdc).is_err());
+
+    allowed.remove(&usdc).unwrap();
+    assert!(!allowed.contains(&usdc));
+    assert!(allowed.remove(&usdc).is_err());
+
+    for _ in 0..MAX_ALLOWED_MINTS {
+        allowed.add(Pubkey::new_unique()).unwrap();
+    }
+    assert!(allowed.add(Pubkey::new_unique()).is_err());
+    assert!(allowed.try_to_vec().unwrap().len() + 8 <= AllowedMints::LEN);
+}
+
+#[test]
+fn test_fee_totals_record() {
+    // FeeTotals: discriminator (8) + mint (32) + total_fees (8) + withdrawals (8) + bump (1)
+    assert_eq!(FeeTotals::LEN, 57);
+
+    let mint = Pubkey::new_unique();
+    let mut totals = FeeTotals { mint: Pubkey::default(), total_fees: 0, withdrawals: 0, bump: 0 };
+
+    let (_, fee) = split_fee(DENOMINATION_1000_USDC, FEE_BASIS_POINTS).unwrap();
+    totals.record(mint, fee, 254).unwrap();
+    totals.record(mint, fee, 254).unwrap();
+
+    assert_eq!(totals.mint, mint);
+    assert_eq!(totals.total_fees, 2_000_000);
+    assert_eq!(totals.withdrawals, 2);
+
+    totals.total_fees = u64::MAX;
+    assert!(totals.record(mint, 1, 254).is_err());
+}
+
+#[test]
+fn test_lst_pool_info_exchange_rate() {
+    // LstPoolInfo: discriminator (8) + pool (32) + lamports_per_token (8) + updated_at (8) + bump (1)
+    assert_eq!(LstPoolInfo::LEN, 57);
+
+    let mut info = LstPoolInfo { pool: Pubkey::new_unique(), lamports_per_token: 0, updated_at: 0, bump: 255 };
+    assert!(info.set_exchange_rate(0, 1).is_err());
+
+    // 1 mSOL = 1.25 SOL
+    info.set_exchange_rate(1_250_000_000, 1_700_000_000).unwrap();
+    assert_eq!(info.updated_at, 1_700_000_000);
+    assert_eq!(info.lamports_for(DENOMINATION_10_SOL), Some(12_500_000_000));
+    assert_eq!(info.lamports_for(u64::MAX), None);
+}
+
+#[test]
+fn test_vault_backing() {
+    let mut pool = MixerPool::try_from_slice(&[0u8; MixerPool::LEN - 8]).unwrap();
+    pool.initialize(Pubkey::new_unique(), DENOMINATION_100_USDC, MIN_TIME_DELAY, 0, 255);
+    assert!(check_vault_backing(&pool, 0).is_ok());
+
+    pool.total_deposits = 3;
+    pool.total_withdrawals = 1;
+    assert_eq!(pool.outstanding_notes(), 2);
+    assert!(check_vault_backing(&pool, 2 * DENOMINATION_100_USDC).is_ok());
+    assert!(check_vault_backing(&pool, 2 * DENOMINATION_100_USDC - 1).is_err());
+}
+
+#[test]
+fn test_pool_relayer_policy() {
+    use crate::relayer::{PoolRelayers, RelayerPolicy};
+
+    let mut pool = MixerPool::try_from_slice(&[0u8; MixerPool::LEN - 8]).unwrap();
+    pool.initialize(SOL_POOL_MINT, DENOMINATION_1_SOL, MIN_TIME_DELAY, 0, 255);
+    let relayer = Pubkey::new_unique();
+    let pool_relayers = PoolRelayers { pool: Pubkey::new_unique(), relayers: vec![relayer], bump: 0 };
+
+    // New pools are permissionless
+    assert!(pool.permits_relayer(None, &Pubkey::new_unique()));
+
+    pool.relayer_policy = RelayerPolicy::Allowlisted;
+    assert!(pool.permits_relayer(Some(&pool_relayers), &relayer));
+    assert!(!pool.permits_relayer(Some(&pool_relayers), &Pubkey::new_unique()));
+    assert!(!pool.permits_relayer(None, &relayer));
+}
+
+#[test]
+fn test_fee_curve() {
+    let mut pool = MixerPool::try_from_slice(&[0u8; MixerPool::LEN - 8]).unwrap();
+    pool.initialize(SOL_POOL_MINT, DENOMINATION_1_SOL, MIN_TIME_DELAY, 0, 255);
+
+    // Without a curve every withdrawal pays the flat fee
+    assert_eq!(pool.withdrawal_fee_bps(0), FEE_BASIS_POINTS);
+    assert_eq!(pool.split_withdrawal(0).unwrap(), split_fee(DENOMINATION_1_SOL, FEE_BASIS_POINTS).unwrap());
+
+    pool.fee_curve = FeeCurve { enabled: true, floor_bps: 0, ceiling_bps: 10, ramp_deposits: 100 };
+    assert!(pool.fee_curve.validate().is_ok());
+    assert_eq!(pool.split_withdrawal(0).unwrap(), (DENOMINATION_1_SOL, 0));
+
+    pool.total_deposits = 50;
+    assert_eq!(pool.withdrawal_fee_bps(0), 5);
+    pool.total_deposits = 1_000;
+    assert_eq!(pool.withdrawal_fee_bps(0), 10);
+
+    // Curves can only discount the flat fee
+    let above_flat = FeeCurve { ceiling_bps: FEE_BASIS_POINTS as u16 + 1, ..pool.fee_curve };
+    let falling = FeeCurve { floor_bps: 10, ceiling_bps: 5, ..pool.fee_curve };
+    let no_ramp = FeeCurve { ramp_deposits: 0, ..pool.fee_curve };
+    assert!(above_flat.validate().is_err());
+    assert!(falling.validate().is_err());
+    assert!(no_ramp.validate().is_err());
+    assert!(FeeCurve::default().validate().is_ok());
+}
+
+#[test]
+fn test_fee_holiday() {
+    let mut pool = MixerPool::try_from_slice(&[0u8; MixerPool::LEN - 8]).unwrap();
+    pool.initialize(SOL_POOL_MINT, DENOMINATION_1_SOL, MIN_TIME_DELAY, 0, 255);
+    assert!(!pool.in_fee_holiday(0));
+
+    pool.fee_holiday_start = 1_000;
+    pool.fee_holiday_end = 2_000;
+    assert_eq!(pool.withdrawal_fee_bps(999), FEE_BASIS_POINTS);
+    assert_eq!(pool.split_withdrawal(1_000).unwrap(), (DENOMINATION_1_SOL, 0));
+    assert_eq!(pool.split_withdrawal(1_999).unwrap(), (DENOMINATION_1_SOL, 0));
+    assert_eq!(pool.withdrawal_fee_bps(2_000), FEE_BASIS_POINTS);
+
+    // The holiday waives the fee the curve would charge, too
+    pool.fee_curve = FeeCurve { enabled: true, floor_bps: 5, ceiling_bps: 5, ramp_deposits: 1 };
+    assert_eq!(pool.standard_fee_bps(), 5);
+    assert_eq!(pool.withdrawal_fee_bps(1_500), 0);
+}
+
+#[test]
+fn test_fee_stats_roll_over_by_epoch() {
+    let mut stats = FeeStats::default();
+    stats.record(1_000, 10).unwrap();
+    stats.record(500, 10).unwrap();
+    assert_eq!(stats.fees_collected, 1_500);
+    assert_eq!(stats.fees_collected_this_epoch, 1_500);
+    assert_eq!(stats.fees_collected_last_epoch, 0);
+
+    // The next epoch's first withdrawal closes out epoch 10
+    stats.record(200, 11).unwrap();
+    assert_eq!(stats.fees_collected, 1_700);
+    assert_eq!(stats.fees_collected_last_epoch, 1_500);
+    assert_eq!(stats.fees_collected_this_epoch, 200);
+
+    // Reads see epochs without withdrawals too
+    assert_eq!(stats.as_of(11), stats);
+    assert_eq!(stats.as_of(12).fees_collected_last_epoch, 200);
+    assert_eq!(stats.as_of(13).fees_collected_last_epoch, 0);
+    assert_eq!(stats.as_of(13).fees_collected, 1_700);
+}
+
+#[test]
+fn test_fee_vault_account_size() {
+    // FeeVault: discriminator (8) + pool (32) + total_fees_collected (8) +
+    // total_fees_withdrawn (8) + bump (1)
+    assert_eq!(FeeVault::LEN, 57);
+}
+
+#[test]
+fn test_fee_vault_sweeps_only_accrued_fees() {
+    let mut fee_vault = FeeVault {
+        pool: Pubkey::new_unique(),
+        total_fees_collected: 0,
+        total_fees_withdrawn: 0,
+        bump: 255,
+    };
+
+    fee_vault.record_fees(1_000_000).unwrap();
+    fee_vault.record_fees(500_000).unwrap();
+    assert_eq!(fee_vault.unswept_fees(), 1_500_000);
+
+    fee_vault.record_sweep(1_000_000).unwrap();
+    assert_eq!(fee_vault.unswept_fees(), 500_000);
+
+    // Anything beyond the recorded fees is someone's deposit
+    assert!(fee_vault.record_sweep(500_001).is_err());
+    assert_eq!(fee_vault.total_fees_withdrawn, 1_000_000);
+    fee_vault.record_sweep(500_000).unwrap();
+    assert_eq!(fee_vault.unswept_fees(), 0);
+}
+
+#[test]
+fn test_relayer_fee_cap() {
+    let mut config = config_with_attestors(&[], 0);
+
+    // 5% of 1 SOL by default
+    assert!(config.check_relayer_fee(DENOMINATION_1_SOL, 50_000_000).is_ok());
+    assert!(config.check_relayer_fee(DENOMINATION_1_SOL, 50_000_001).is_err());
+
+    config.max_relayer_fee_bps = 0;
+    assert!(config.check_relayer_fee(DENOMINATION_1_SOL, 0).is_ok());
+    assert!(config.check_relayer_fee(DENOMINATION_1_SOL, 1).is_err());
+}
+
+#[test]
+fn test_authority_multisig() {
+    let mut config = config_with_attestors(&[], 0);
+    let members: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+
+    // Without a multisig only the authority's own signature counts
+    assert!(config.is_admin_signer(&config.authority));
+    assert!(!config.is_admin_signer(&members[0]));
+    assert!(config.check_admin_approval(&[config.authority]).is_ok());
+
+    config.authority_members[..3].copy_from_slice(&members);
+    config.authority_member_count = 3;
+    config.authority_threshold = 2;
+
+    // Any member can submit, but it takes two of them
+    assert!(config.is_admin_signer(&members[2]));
+    assert!(!config.is_admin_signer(&Pubkey::new_unique()));
+    assert!(config.check_admin_approval(&[members[0]]).is_err());
+    assert!(config.check_admin_approval(&[config.authority]).is_err());
+    assert!(config.check_admin_approval(&[members[0], members[0]]).is_err());
+    assert!(config.check_admin_approval(&[members[0], Pubkey::new_unique()]).is_err());
+    assert!(config.check_admin_approval(&[members[0], members[2]]).is_ok());
+}
+
+#[test]
+fn test_pool_admin_instructions_need_multisig() {
+    let members: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+    let mut config = config_with_attestors(&[], 0);
+    config.authority_members[..3].copy_from_slice(&members);
+    config.authority_member_count = 3;
+    config.authority_threshold = 2;
+
+    let owner = crate::ID;
+    let system_id = anchor_lang::system_program::ID;
+    let keys: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+    let (mut config_data, mut pool_data, mut log_data) = (Vec::new(), Vec::new(), Vec::new());
+    config.try_serialize(&mut config_data).unwrap();
+    MixerPool::try_from_slice(&[0u8; MixerPool::LEN - 8])
+        .unwrap()
+        .try_serialize(&mut pool_data)
+        .unwrap();
+    AdminLog { count: 0, bump: 255 }.try_serialize(&mut log_data).unwrap();
+    let mut lamports = [0u64; 6];
+    let [l0, l1, l2, l3, l4, l5] = &mut lamports;
+    let (mut d3, mut d4, mut d5) = ([0u8; 0], [0u8; 0], [0u8; 0]);
+    let config_info = AccountInfo::new(&keys[0], false, false, l0, &mut config_data, &owner, false, 0);
+    let pool_info = AccountInfo::new(&keys[1], false, true, l1, &mut pool_data, &owner, false, 0);
+    let log_info = AccountInfo::new(&keys[2], false, true, l2, &mut log_data, &owner, false, 0);
+    let member = AccountInfo::new(&members[0], true, true, l3, &mut d3, &system_id, false, 0);
+    let cosigner = AccountInfo::new(&members[1], true, false, l4, &mut d4, &system_id, false, 0);
+    let system = AccountInfo::new(&system_id, false, false, l5, &mut d5, &system_id, true, 0);
+
+    let mut accounts = UpdatePool {
+        config: Account::try_from(&config_info).unwrap(),
+        pool: Account::try_from(&pool_info).unwrap(),
+        admin_log: Account::try_from(&log_info).unwrap(),
+        authority: Signer::try_from(&member).unwrap(),
+        system_program: Program::try_from(&system).unwrap(),
+    };
+    let approvals_error = anchor_lang::error::ERROR_CODE_OFFSET
+        + MixerError::InsufficientAuthorityApprovals as u32;
+    let error_code = |result: Result<()>| match result {
+        Err(Error::AnchorError(error)) => Some(error.error_code_number),
+        _ => None,
+    };
+
+    // One member alone can't change a pool's withdraw mode
+    let ctx = Context::new(&owner, &mut accounts, &[], UpdatePoolBumps::default());
+    let result = nullifier::set_withdraw_mode(ctx, WithdrawMode::Zk);
+    assert_eq!(error_code(result), Some(approvals_error));
+
+    // With a second member signing it gets past the approval check
+    let cosigners = [cosigner.clone()];
+    let ctx = Context::new(&owner, &mut accounts, &cosigners, UpdatePoolBumps::default());
+    let result = nullifier::set_withdraw_mode(ctx, WithdrawMode::Zk);
+    assert_ne!(error_code(result), Some(approvals_error));
+}
+
+#[test]
+fn test_permissionless_pool_creation() {
+    let mut config = config_with_attestors(&[], 0);
+    let creator = Pubkey::new_unique();
+
+    assert!(config.may_create_pool(&config.authority));
+    assert!(!config.may_create_pool(&creator));
+
+    config.permissionless_pools = true;
+    config.pool_creation_bond = 10 * DENOMINATION_1_SOL;
+    assert!(config.may_create_pool(&creator));
+
+    let bond = PoolBond {
+        pool: Pubkey::new_unique(),
+        creator,
+        amount: config.pool_creation_bond,
+        created_at: 1_000,
+        bump: 255,
+        flagged_at: 0,
+    };
+    assert!(!bond.is_reclaimable(1_000 + POOL_BOND_LOCKUP - 1));
+    assert!(bond.is_reclaimable(1_000 + POOL_BOND_LOCKUP));
+    assert!(!bond.is_flagged());
+
+    // PoolBond: discriminator (8) + pool (32) + creator (32) + amount (8) + created_at (8)
+    // + bump (1) + flagged_at (8)
+    let mut data = Vec::new();
+    bond.try_serialize(&mut data).unwrap();
+    assert_eq!(data.len(), PoolBond::LEN);
+    assert_eq!(PoolBond::LEN, 97);
+
+    let flagged = PoolBond { flagged_at: 2_000, ..bond };
+    assert!(flagged.is_flagged());
+}
+
+#[test]
+fn test_commitment_blocklist() {
+    let mut blocklist = CommitmentBlocklist {
+        pool: Pubkey::new_unique(),
+        entries: Vec::new(),
+        bump: 255,
+    };
+    let commitment = [7u8; 32];
+    let now = 1_700_000_000;
+
+    assert!(blocklist.request_unflag(&commitment, now).is_err());
+    blocklist.flag(commitment, now, false).unwrap();
+    assert!(blocklist.is_blocked(&commitment));
+    assert!(!blocklist.is_blocked(&[8u8; 32]));
+    assert!(blocklist.flag(commitment, now, true).is_err());
+
+    // Unflagging waits out the timelock and stays blocked meanwhile
+    assert!(blocklist.unflag(&commitment, now).is_err());
+    let unflag_at = blocklist.request_unflag(&commitment, now).unwrap();
+    assert_eq!(unflag_at, now + COMMITMENT_UNFLAG_DELAY);
+    assert!(blocklist.is_blocked(&commitment));
+    assert!(blocklist.unflag(&commitment, unflag_at - 1).is_err());
+
+    // The guardian alone can't cancel the pending unflag, the multisig can
+    assert!(blocklist.flag(commitment, now, false).is_err());
+    assert_eq!(blocklist.entries[0].unflag_at, unflag_at);
+    blocklist.flag(commitment, now, true).unwrap();
+    assert!(blocklist.unflag(&commitment, unflag_at).is_err());
+
+    blocklist.request_unflag(&commitment, now).unwrap();
+    blocklist.unflag(&commitment, unflag_at).unwrap();
+    assert!(!blocklist.is_blocked(&commitment));
+}
+
+#[test]
+fn test_commitment_blocklist_size() {
+    let blocklist = CommitmentBlocklist {
+        pool: Pubkey::new_unique(),
+        entries: vec![
+            BlockedCommitment { commitment: [1u8; 32], flagged_at: 1, unflag_at: 2 };
+            MAX_BLOCKED_COMMITMENTS
+        ],
+        bump: 255,
+    };
+    let mut data = Vec::new();
+    blocklist.try_serialize(&mut data).unwrap();
+    assert_eq!(data.len(), CommitmentBlocklist::LEN);
+}
+
+#[test]
+fn test_store_association_verification_key() {
+    use ark_bn254::{G1Affine, G2Affine};
+    use ark_ec::AffineRepr;
+
+    let g1 = crate::bn254::g1_to_be(&G1Affine::generator());
+    let g2 = crate::bn254::g2_to_be(&G2Affine::generator());
+    let key = |inputs: usize| VerificationKey {
+        alpha_g1: g1,
+        beta_g2: g2,
+        gamma_g2: g2,
+        delta_g2: g2,
+        ic: vec![g1; inputs + 1],
+    };
+    let attestation = |key: &VerificationKey| CeremonyAttestation {
+        transcript_hash: [1u8; 32],
+        vk_hash: key.hash(),
+        attested_by: Pubkey::default(),
+        timestamp: 0,
+        bump: 255,
+    };
+
+    let association_key = key(NUM_ASSOCIATION_PUBLIC_INPUTS);
+    let withdrawal_key = key(NUM_PUBLIC_INPUTS);
+    let mut stored = VerificationKey::default();
+
+    store_verification_key(
+        &mut stored,
+        association_key.clone(),
+        NUM_ASSOCIATION_PUBLIC_INPUTS,
+        &attestation(&association_key),
+    )
+    .unwrap();
+    let mut data = Vec::new();
+    stored.try_serialize(&mut data).unwrap();
+    assert_eq!(data.len(), VerificationKey::ASSOCIATION_LEN);
+
+    // The withdrawal circuit's key has one IC point too few
+    assert!(store_verification_key(
+        &mut stored,
+        withdrawal_key.clone(),
+        NUM_ASSOCIATION_PUBLIC_INPUTS,
+        &attestation(&withdrawal_key),
+    )
+    .is_err());
+
+    // A key the ceremony didn't attest
+    assert!(store_verification_key(
+        &mut stored,
+        withdrawal_key,
+        NUM_PUBLIC_INPUTS,
+        &attestation(&association_key),
+    )
+    .is_err());
+}
+
+#[test]
+fn test_note_disclosure_checks() {
+    let pool = Pubkey::new_unique();
+    let nullifier = [3u8; 32];
+    let secret = [4u8; 32];
+
+    let sha_commitment = merkle::compute_commitment(&secret, &nullifier);
+    let zk_commitment = merkle_poseidon::poseidon_commitment(&secret, &nullifier);
+    assert!(note_opens(&sha_commitment, &nullifier, &secret));
+    assert!(note_opens(&zk_commitment, &nullifier, &secret));
+    assert!(!note_opens(&sha_commitment, &secret, &nullifier));
+
+    let legacy_spent = merkle::compute_pool_nullifier(&nullifier, &pool);
+    let zk_spent = merkle::compute_pool_nullifier(
+        &merkle_poseidon::poseidon_nullifier_hash(&nullifier, &pool, CIRCUIT_VERSION),
+        &pool,
+    );
+    assert!(note_spent_as(&pool, &nullifier, &legacy_spent));
+    assert!(note_spent_as(&pool, &nullifier, &zk_spent));
+    assert!(!note_spent_as(&Pubkey::new_unique(), &nullifier, &legacy_spent));
+    assert!(!note_spent_as(&pool, &[5u8; 32], &zk_spent));
+
+    assert_ne!(ViewingKey::hash(&[1u8; 32]), ViewingKey::hash(&[2u8; 32]));
+}
+
+#[test]
+fn test_deposit_receipt_hash() {
+    let depositor = Pubkey::new_unique();
+    let commitment = [1u8; 32];
+    let salt = [2u8; 32];
+    let receipt_hash = DepositReceipt::hash(&depositor, &commitment, &salt);
+
+    // Revealing needs the same depositor, commitment and salt
+    assert_eq!(receipt_hash, DepositReceipt::hash(&depositor, &commitment, &salt));
+    assert_ne!(receipt_hash, DepositReceipt::hash(&Pubkey::new_unique(), &commitment, &salt));
+    assert_ne!(receipt_hash, DepositReceipt::hash(&depositor, &[3u8; 32], &salt));
+    assert_ne!(receipt_hash, DepositReceipt::hash(&depositor, &commitment, &[3u8; 32]));
+
+    let receipt = DepositReceipt {
+        pool: Pubkey::new_unique(),
+        leaf_index: 7,
+        receipt_hash,
+        created_at: 1_700_000_000,
+        revealed_at: 0,
+        bump: 255,
+    };
+    let mut data = Vec::new();
+    receipt.try_serialize(&mut data).unwrap();
+    assert_eq!(data.len(), DepositReceipt::LEN);
+}
+
+#[test]
+fn test_batch_deposit_requires_screening_program() {
+    let owner = crate::ID;
+    let (pool_key, depositor_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let mut pool_state = MixerPool::try_from_slice(&[0u8; MixerPool::LEN - 8]).unwrap();
+    pool_state.denomination = DENOMINATION_1_SOL;
+    let mut pool_data = Vec::new();
+    pool_state.try_serialize(&mut pool_data).unwrap();
+    let (mut pool_lamports, mut depositor_lamports, mut empty) = (0u64, 0u64, [0u8; 0]);
+    let pool_info = AccountInfo::new(&pool_key, false, true, &mut pool_lamports, &mut pool_data, &owner, false, 0);
+    let depositor = AccountInfo::new(&depositor_key, true, true, &mut depositor_lamports, &mut empty, &owner, false, 0);
+    let pool = Account::<MixerPool>::try_from(&pool_info).unwrap();
+    let batch_amount = DENOMINATION_1_SOL * MAX_BATCH_LEAVES as u64;
+
+    // Nothing to screen against while no program is configured
+    let mut config = config_with_attestors(&[], 0);
+    assert!(screen_deposit(&config, &pool, &depositor, None, &[], batch_amount).is_ok());
+
+    // Once one is, a batch deposit can't skip it by leaving the program out
+    config.screening_program = Pubkey::new_unique();
+    match screen_deposit(&config, &pool, &depositor, None, &[], batch_amount) {
+        Err(Error::AnchorError(error)) => assert_eq!(
+            error.error_code_number,
+            anchor_lang::error::ERROR_CODE_OFFSET + MixerError::ScreeningProgramMissing as u32
+        ),
+        other => panic!("expected ScreeningProgramMissing, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_renounce_authority() {
+    let mut config = config_with_attestors(&[], 0);
+    let authority = config.authority;
+    let member = Pubkey::new_unique();
+    config.authority_members[0] = member;
+    config.authority_member_count = 1;
+    config.authority_threshold = 1;
+    config.guardian = Pubkey::new_unique();
+
+    config.renounce();
+
+    // Nobody, not even the all-zero key, can act as admin any more
+    assert!(config.renounced);
+    assert_eq!(config.authority, RENOUNCED_AUTHORITY);
+    assert_eq!(config.guardian, Pubkey::default());
+    assert!(config.authority_members().is_empty());
+    assert!(!config.is_admin_signer(&authority));
+    assert!(!config.is_admin_signer(&member));
+    assert!(!config.is_admin_signer(&RENOUNCED_AUTHORITY));
+}
+
+#[test]
+fn test_rate_counter_sliding_window() {
+    let limit = RateLimit { max: 10, window: 3_600 };
+    let mut counter = RateCounter::default();
+    let start = 1_700_000_000;
+
+    counter.record(&limit, 8, start).unwrap();
+    counter.record(&limit, 2, start + 60).unwrap();
+    assert!(counter.record(&limit, 1, start + 120).is_err());
+    assert_eq!(counter.current, 10);
+
+    // Halfway through the next window half of the last one still counts
+    assert!(counter.record(&limit, 6, start + 3_600 + 1_800).is_err());
+    counter.record(&limit, 5, start + 3_600 + 1_800).unwrap();
+    assert_eq!((counter.previous, counter.current), (10, 5));
+
+    // After two idle windows the counter starts over
+    counter.record(&limit, 10, start + 4 * 3_600).unwrap();
+    assert_eq!((counter.previous, counter.current), (0, 10));
+
+    // No limit, no counting
+    let mut unlimited = RateCounter::default();
+    unlimited.record(&RateLimit::default(), u32::MAX, start).unwrap();
+    assert_eq!(unlimited.current, 0);
+    assert!(RateLimit { max: 1, window: 0 }.validate().is_err());
+}
+
+#[test]
+fn test_force_close_excludes_fund_holding_accounts() {
+    use crate::relayer::RelayerInfo;
+
+    // PendingForceClose: discriminator (8) + account (32) + scheduled_at (8) +
+    // executable_at (8) + bump (1)
+    assert_eq!(PendingForceClose::LEN, 57);
+
+    for protected in [
+        MixerPool::DISCRIMINATOR,
+        FeeVault::DISCRIMINATOR,
+        RelayerInfo::DISCRIMINATOR,
+        Config::DISCRIMINATOR,
+        SpentNullifier::DISCRIMINATOR,
+        PoolBond::DISCRIMINATOR,
+        AdminLog::DISCRIMINATOR,
+        NullifierRegistry::DISCRIMINATOR,
+        NullifierBloom::DISCRIMINATOR,
+    ] {
+        let mut data = protected.to_vec();
+        data.extend_from_slice(&[0u8; 32]);
+        assert!(!is_force_closable(&data));
+    }
+
+    // Only scratch accounts are on the allowlist
+    for closable in [
+        ProofBuffer::DISCRIMINATOR,
+        PendingForceClose::DISCRIMINATOR,
+        AuditCursor::DISCRIMINATOR,
+    ] {
+        let mut data = closable.to_vec();
+        data.extend_from_slice(&[0u8; 32]);
+        assert!(is_force_closable(&data));
+    }
+
+    // Anything without a discriminator is left alone
+    assert!(!is_force_closable(&[]));
+    assert!(!is_force_closable(&[1u8; 7]));
+}
+
+#[test]
+fn test_config_migration() {
+    let mut config = config_with_attestors(&[], 0);
+    config.max_relayer_fee_bps = 0;
+    let mut data = Vec::new();
+    config.try_serialize(&mut data).unwrap();
+    assert_eq!(data.len(), Config::LEN);
+
+    // A pre-versioning config is the current layout without the version
+    // byte and what follows; realloc zeroes what it grows by
+    data.truncate(Config::LEN - 1 - 32 - 8 - 8 - 8 - 1 - 1 - 1 - 8 - 32);
+    data.resize(Config::LEN, 0);
+    let mut old = Config::try_deserialize(&mut &data[..]).unwrap();
+    assert_eq!(old.version, 0);
+    assert_eq!(old.max_relayer_fee_bps, 0);
+
+    old.paused = true;
+    old.migrate(1_000);
+    assert_eq!(old.version, CONFIG_VERSION);
+    assert_eq!(old.max_relayer_fee_bps, DEFAULT_MAX_RELAYER_FEE_BPS);
+    assert_eq!(old.authority, config.authority);
+    assert_eq!(old.deposit_rate_limit, RateLimit::default());
+    assert_eq!(old.upgrade_authority, Pubkey::default());
+    assert_eq!(old.last_deploy_slot, 0);
+    assert_eq!(old.paused_at, 1_000);
+
+    // Current configs keep their settings
+    let mut current = config_with_attestors(&[], 0);
+    current.max_relayer_fee_bps = 0;
+    current.migrate(1_000);
+    assert_eq!(current.max_relayer_fee_bps, 0);
+    assert_eq!(current.paused_at, 0);
+}
+
+#[test]
+fn test_emergency_withdrawals_open() {
+    let mut config = config_with_attestors(&[], 0);
+    assert!(!config.emergency_withdrawals_open(EMERGENCY_PAUSE_DELAY * 2));
+
+    // Pausing again doesn't restart the clock
+    config.pause(1_000);
+    config.pause(5_000);
+    assert_eq!(config.paused_at, 1_000);
+    assert!(!config.emergency_withdrawals_open(1_000 + EMERGENCY_PAUSE_DELAY - 1));
+    assert!(config.emergency_withdrawals_open(1_000 + EMERGENCY_PAUSE_DELAY));
+
+    // Unpausing resets it
+    config.paused = false;
+    config.paused_at = 0;
+    assert!(!config.emergency_withdrawals_open(1_000 + EMERGENCY_PAUSE_DELAY));
+
+    // A sunset opens them right away
+    config.emergency_mode = true;
+    assert!(config.emergency_withdrawals_open(0));
+}
+
+#[test]
+fn test_fee_split() {
+    let mut config = config_with_attestors(&[], 0);
+
+    // Everything goes to the treasury by default
+    assert_eq!(config.split_fees(1_000_000).unwrap(), (1_000_000, 0));
+
+    config.staker_share_bps = 3_000;
+    assert_eq!(config.split_fees(1_000_000).unwrap(), (700_000, 300_000));
+    // Rounding favors the treasury
+    assert_eq!(config.split_fees(1).unwrap(), (1, 0));
+    let (treasury, stakers) = config.split_fees(u64::MAX).unwrap();
+    assert_eq!(treasury + stakers, u64::MAX);
+}
+
+#[test]
+fn test_max_ata_reimbursement() {
+    let mut config = config_with_attestors(&[], 0);
+    let (_, fee) = split_fee(DENOMINATION_1000_USDC, FEE_BASIS_POINTS).unwrap();
+    assert_eq!(config.max_ata_reimbursement(fee).unwrap(), 0);
+
+    config.ata_reimbursement_bps = 5_000;
+    assert_eq!(config.max_ata_reimbursement(fee).unwrap(), fee / 2);
+
+    config.ata_reimbursement_bps = 10_000;
+    assert_eq!(config.max_ata_reimbursement(fee).unwrap(), fee);
+}
+
+#[test]
+fn test_nullifier_bloom_account_size() {
+    // NullifierBloom: discriminator (8) + pool (32) + inserted (8) + bits (8192)
+    assert_eq!(NullifierBloom::LEN, 8240);
+    assert_eq!(NullifierBloom::LEN, 8 + std::mem::size_of::<NullifierBloom>());
+}
+
+#[test]
+fn test_check_and_record_nullifier_with_bloom() {
+    let key = Pubkey::new_unique();
+    let owner = crate::ID;
+    let spent = [7u8; 32];
+    let fresh = [8u8; 32];
+
+    let registry = NullifierRegistry {
+        pool: Pubkey::new_unique(),
+        bump: 255,
+        nullifiers: vec![spent],
+    };
+    let mut registry_data = Vec::new();
+    registry.try_serialize(&mut registry_data).unwrap();
+    let mut registry_lamports = 0;
+    let registry_account = AccountInfo::new(
+        &key, false, false, &mut registry_lamports, &mut registry_data, &owner, false, 0,
+    );
+
+    // Without a filter every nullifier falls through to the registry
+    let mut empty: Vec<u8> = Vec::new();
+    let mut empty_lamports = 0;
+    let no_bloom = AccountInfo::new(&key, false, true, &mut empty_lamports, &mut empty, &owner, false, 0);
+    assert!(NullifierBloom::load_mut(&no_bloom).unwrap().is_none());
+    assert!(check_and_record_nullifier(&registry_account, &no_bloom, &spent).is_err());
+    assert!(check_and_record_nullifier(&registry_account, &no_bloom, &fresh).is_ok());
+
+    // A filter seeded with the registry still catches its entries
+    let mut bloom_data = vec![0u8; NullifierBloom::LEN];
+    bloom_data[..8].copy_from_slice(&NullifierBloom::DISCRIMINATOR);
+    let mut bloom_lamports = 0;
+    let bloom = AccountInfo::new(&key, false, true, &mut bloom_lamports, &mut bloom_data, &owner, false, 0);
+    NullifierBloom::load_mut(&bloom).unwrap().unwrap().insert(&spent);
+
+    assert!(check_and_record_nullifier(&registry_account, &bloom, &spent).is_err());
+    assert!(check_and_record_nullifier(&registry_account, &bloom, &fresh).is_ok());
+
+    let filter = NullifierBloom::load_mut(&bloom).unwrap().unwrap();
+    assert!(filter.might_contain(&fresh));
+    assert_eq!(filter.inserted, 2);
+}
+
 #[test]
 fn test_encrypted_note_max_size() {
     // EncryptedNote: discriminator (8) + owner (32) + vec_len (4) +
@@ -258,7 +1217,7 @@ fn test_min_time_delay_validation() {
 
 #[test]
 fn test_merkle_tree_capacity() {
-    use crate::poseidon::MERKLE_TREE_DEPTH;
+    use crate::merkle_poseidon::MERKLE_TREE_DEPTH;
 
     // Tree depth of 20 allows 2^20 = 1,048,576 deposits per pool
     let max_deposits = 1u32 << MERKLE_TREE_DEPTH;
@@ -350,6 +1309,14 @@ fn test_account_discriminators() {
     assert!(MixerPool::LEN >= discriminator_size);
     assert!(CommitmentRecord::LEN >= discriminator_size);
     assert!(NullifierRegistry::LEN >= discriminator_size);
+    assert!(SpentNullifier::LEN >= discriminator_size);
+    assert!(NullifierBloom::LEN >= discriminator_size);
+    assert!(AllowedMints::LEN >= discriminator_size);
+    assert!(FeeTotals::LEN >= discriminator_size);
+    assert!(FeeVault::LEN >= discriminator_size);
+    assert!(LstPoolInfo::LEN >= discriminator_size);
+    assert!(WithdrawalIntent::MAX_SIZE >= discriminator_size);
+    assert!(IntentQueue::LEN >= discriminator_size);
     assert!(EncryptedNote::MAX_SIZE >= discriminator_size);
 }
 
@@ -387,7 +1354,7 @@ fn test_timestamp_ordering() {
     assert!(t1 < t3);
 
     // Test time delay check
-    let min_delay = MIN_TIME_DELAY as i64;
+    let min_delay = MIN_TIME_DELAY;
     assert!(t2 - t1 >= min_delay);
     assert!(t3 - t1 >= min_delay);
 }
@@ -415,3 +1382,984 @@ fn test_u8_array_32_size() {
     // [u8; 32] should be 32 bytes
     assert_eq!(size_of::<[u8; 32]>(), 32);
 }
+
+#[test]
+fn test_check_deposit_age() {
+    let deposit_time = 1_700_000_000i64;
+
+    // Exactly at the delay boundary is allowed
+    assert!(check_deposit_age(deposit_time + MIN_TIME_DELAY, deposit_time, MIN_TIME_DELAY).is_ok());
+
+    // Well past the delay
+    assert!(check_deposit_age(deposit_time + 3600, deposit_time, MIN_TIME_DELAY).is_ok());
+
+    // Too early
+    assert!(check_deposit_age(deposit_time + MIN_TIME_DELAY - 1, deposit_time, MIN_TIME_DELAY).is_err());
+
+    // Deposit timestamp in the future
+    assert!(check_deposit_age(deposit_time, deposit_time + 1, MIN_TIME_DELAY).is_err());
+}
+
+#[test]
+fn test_check_deposit_age_overflow() {
+    // Underflow in the age calculation is rejected, not wrapped
+    assert!(check_deposit_age(i64::MIN, 1, MIN_TIME_DELAY).is_err());
+}
+
+#[test]
+fn test_withdraw_mode_permissions() {
+    assert!(WithdrawMode::Legacy.allows_legacy());
+    assert!(!WithdrawMode::Legacy.allows_zk());
+
+    assert!(!WithdrawMode::Zk.allows_legacy());
+    assert!(WithdrawMode::Zk.allows_zk());
+
+    assert!(WithdrawMode::Both.allows_legacy());
+    assert!(WithdrawMode::Both.allows_zk());
+}
+
+#[test]
+fn test_withdraw_mode_serialized_size() {
+    // Enum is stored as a single byte tag in MixerPool
+    for mode in [WithdrawMode::Legacy, WithdrawMode::Zk, WithdrawMode::Both] {
+        assert_eq!(mode.try_to_vec().unwrap().len(), 1);
+    }
+}
+
+#[test]
+fn test_proof_buffer_max_size() {
+    // ProofBuffer: discriminator (8) + owner (32) + pool (32) +
+    // vec_len (4) + data (MAX_PROOF_BUFFER_SIZE) + bump (1)
+    let expected_size = 8 + 32 + 32 + 4 + MAX_PROOF_BUFFER_SIZE + 1;
+    assert_eq!(ProofBuffer::MAX_SIZE, expected_size);
+    assert_eq!(ProofBuffer::MAX_SIZE, 1101);
+}
+
+#[test]
+fn test_withdraw_proof_payload_fits_buffer() {
+    use crate::groth16::Groth16Proof;
+
+    let payload = WithdrawProofPayload {
+        proof: ProofData::Uncompressed(Groth16Proof {
+            a: [1u8; 64],
+            b: [2u8; 128],
+            c: [3u8; 64],
+        }),
+        merkle_root: [4u8; 32],
+        nullifier_hash: [5u8; 32],
+        deposit_timestamp: 1_700_000_000,
+    };
+
+    let bytes = payload.try_to_vec().unwrap();
+    assert!(bytes.len() <= MAX_PROOF_BUFFER_SIZE);
+}
+
+#[test]
+fn test_withdraw_proof_payload_chunked_roundtrip() {
+    use crate::groth16::CompressedGroth16Proof;
+
+    let payload = WithdrawProofPayload {
+        proof: ProofData::Compressed(CompressedGroth16Proof {
+            a: [1u8; 32],
+            b: [2u8; 64],
+            c: [3u8; 32],
+        }),
+        merkle_root: [4u8; 32],
+        nullifier_hash: [5u8; 32],
+        deposit_timestamp: 1_700_000_000,
+    };
+    let bytes = payload.try_to_vec().unwrap();
+
+    // Simulate staging the payload in small chunks, as a relayer would
+    let mut data = vec![0u8; bytes.len()];
+    for (i, chunk) in bytes.chunks(50).enumerate() {
+        let start = i * 50;
+        data[start..start + chunk.len()].copy_from_slice(chunk);
+    }
+
+    let decoded = WithdrawProofPayload::deserialize(&mut data.as_slice()).unwrap();
+    assert_eq!(decoded.merkle_root, [4u8; 32]);
+    assert_eq!(decoded.nullifier_hash, [5u8; 32]);
+    assert_eq!(decoded.deposit_timestamp, 1_700_000_000);
+    match decoded.proof {
+        ProofData::Compressed(proof) => {
+            assert_eq!(proof.a, [1u8; 32]);
+            assert_eq!(proof.b, [2u8; 64]);
+            assert_eq!(proof.c, [3u8; 32]);
+        }
+        ProofData::Uncompressed(_) => panic!("Expected compressed proof"),
+    }
+}
+
+#[test]
+fn test_withdraw_proof_payload_truncated() {
+    let payload = WithdrawProofPayload {
+        proof: ProofData::Compressed(crate::groth16::CompressedGroth16Proof {
+            a: [1u8; 32],
+            b: [2u8; 64],
+            c: [3u8; 32],
+        }),
+        merkle_root: [4u8; 32],
+        nullifier_hash: [5u8; 32],
+        deposit_timestamp: 1_700_000_000,
+    };
+    let bytes = payload.try_to_vec().unwrap();
+
+    // A partially written buffer must not decode
+    let truncated = &bytes[..bytes.len() - 1];
+    assert!(WithdrawProofPayload::deserialize(&mut &truncated[..]).is_err());
+}
+
+#[test]
+fn test_withdrawal_intent_max_size() {
+    // WithdrawalIntent: discriminator (8) + pool (32) + poster (32) + nullifier_hash (32) +
+    // fee (8) + recipient (32) + payload_hash (32) + vec_len (4) +
+    // ciphertext (MAX_INTENT_CIPHERTEXT_SIZE) + posted_at (8) + bump (1) + queue_position (1 + 8)
+    assert_eq!(WithdrawalIntent::MAX_SIZE, 710);
+}
+
+#[test]
+fn test_intent_queue_order() {
+    // IntentQueue: discriminator (8) + pool (32) + head (8) + tail (8) + head_since (8) + bump (1)
+    // + cancelled (8)
+    assert_eq!(IntentQueue::LEN, 73);
+
+    let mut queue = intent_queue();
+    let first = queue.enqueue(100);
+    let second = queue.enqueue(110);
+    let third = queue.enqueue(120);
+
+    // Only the head may execute
+    assert!(queue.can_execute(first));
+    assert!(!queue.can_execute(second));
+    assert!(!queue.can_execute(third));
+
+    // Finishing the head lets the next one execute
+    queue.complete(first, 130).unwrap();
+    assert!(queue.can_execute(second));
+    assert!(!queue.can_execute(third));
+    assert_eq!(queue.head_since, 130);
+
+    // A stuck head is skipped after the timeout; it may still execute later
+    assert!(!queue.is_stale(130 + INTENT_QUEUE_TIMEOUT - 1));
+    assert!(queue.is_stale(130 + INTENT_QUEUE_TIMEOUT));
+    queue.advance(130 + INTENT_QUEUE_TIMEOUT);
+    assert!(queue.can_execute(second));
+    assert!(queue.can_execute(third));
+
+    queue.complete(second, 800).unwrap();
+    assert_eq!(queue.head, 2);
+    queue.complete(third, 800).unwrap();
+
+    // Empty queue is never stale
+    assert!(!queue.is_stale(i64::MAX));
+}
+
+fn intent_queue() -> IntentQueue {
+    IntentQueue { pool: Pubkey::new_unique(), head: 0, tail: 0, head_since: 0, bump: 0, cancelled: 0 }
+}
+
+#[test]
+fn test_intent_queue_cancel_middle() {
+    let mut queue = intent_queue();
+    let positions: Vec<u64> = (0..5).map(|_| queue.enqueue(100)).collect();
+
+    // Cancelling positions behind the head doesn't leave them in the way
+    queue.complete(positions[1], 110).unwrap();
+    queue.complete(positions[2], 120).unwrap();
+    assert_eq!(queue.head, 0);
+    assert!(!queue.can_execute(positions[3]));
+
+    queue.complete(positions[0], 130).unwrap();
+    assert_eq!(queue.head, positions[3]);
+    assert_eq!(queue.head_since, 130);
+    assert!(queue.can_execute(positions[3]));
+    assert!(!queue.can_execute(positions[4]));
+
+    // Skipping a stale head moves past cancelled positions too
+    queue.complete(positions[4], 140).unwrap();
+    queue.advance(130 + INTENT_QUEUE_TIMEOUT);
+    assert_eq!(queue.head, queue.tail);
+    assert!(!queue.is_stale(i64::MAX));
+
+    // Positions too far behind the head can't be cancelled yet
+    let far: Vec<u64> = (0..=INTENT_QUEUE_CANCEL_WINDOW + 1).map(|_| queue.enqueue(200)).collect();
+    assert!(queue.complete(far[INTENT_QUEUE_CANCEL_WINDOW as usize], 210).is_ok());
+    assert!(queue.complete(far[INTENT_QUEUE_CANCEL_WINDOW as usize + 1], 210).is_err());
+}
+
+#[test]
+fn test_withdraw_proof_payload_hash() {
+    let payload = WithdrawProofPayload {
+        proof: ProofData::Compressed(crate::groth16::CompressedGroth16Proof {
+            a: [1u8; 32],
+            b: [2u8; 64],
+            c: [3u8; 32],
+        }),
+        merkle_root: [4u8; 32],
+        nullifier_hash: [5u8; 32],
+        deposit_timestamp: 1_700_000_000,
+    };
+    let hash = payload.hash().unwrap();
+    assert_eq!(hash, payload.clone().hash().unwrap());
+
+    // An executor can't swap in a different root or timestamp
+    let other_root = WithdrawProofPayload { merkle_root: [6u8; 32], ..payload.clone() };
+    let other_time = WithdrawProofPayload { deposit_timestamp: 0, ..payload };
+    assert_ne!(hash, other_root.hash().unwrap());
+    assert_ne!(hash, other_time.hash().unwrap());
+}
+
+#[test]
+fn test_pay_out_relayed_withdrawal() {
+    let owner = crate::ID;
+    let keys: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+    let (mut pool_lamports, mut recipient_lamports, mut relayer_lamports, mut collector_lamports) =
+        (DENOMINATION_1_SOL, 0u64, 0u64, 0u64);
+    let (mut d0, mut d1, mut d2, mut d3) = ([0u8; 0], [0u8; 0], [0u8; 0], [0u8; 0]);
+    let pool = AccountInfo::new(&keys[0], false, true, &mut pool_lamports, &mut d0, &owner, false, 0);
+    let recipient = AccountInfo::new(&keys[1], false, true, &mut recipient_lamports, &mut d1, &owner, false, 0);
+    let relayer = AccountInfo::new(&keys[2], false, true, &mut relayer_lamports, &mut d2, &owner, false, 0);
+    let collector = AccountInfo::new(&keys[3], false, true, &mut collector_lamports, &mut d3, &owner, false, 0);
+
+    let (net, fee) = split_fee(DENOMINATION_1_SOL, FEE_BASIS_POINTS).unwrap();
+    let (received, protocol_fee) = pay_out_relayed_withdrawal(
+        &pool, &recipient, &relayer, &collector, (net, fee), 5_000_000, 10_000,
+    )
+    .unwrap();
+
+    // The rebate comes out of the protocol fee, the relayer fee out of the note
+    assert_eq!(received, net - 5_000_000);
+    assert_eq!(protocol_fee, fee - 10_000);
+    assert_eq!(recipient.lamports(), net - 5_000_000);
+    assert_eq!(relayer.lamports(), 5_010_000);
+    assert_eq!(collector.lamports(), fee - 10_000);
+    assert_eq!(pool.lamports(), 0);
+
+    // The relayer fee can't eat into the protocol fee
+    assert!(pay_out_relayed_withdrawal(
+        &recipient, &pool, &relayer, &collector, split_fee(net - 5_000_000, FEE_BASIS_POINTS).unwrap(), net, 0,
+    )
+    .is_err());
+}
+
+#[test]
+fn test_relayer_rebate_capped_at_protocol_fee() {
+    let owner = crate::ID;
+    let keys: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+    let (mut pool_lamports, mut recipient_lamports, mut relayer_lamports, mut collector_lamports) =
+        (DENOMINATION_1_SOL, 0u64, 0u64, 0u64);
+    let (mut d0, mut d1, mut d2, mut d3) = ([0u8; 0], [0u8; 0], [0u8; 0], [0u8; 0]);
+    let pool = AccountInfo::new(&keys[0], false, true, &mut pool_lamports, &mut d0, &owner, false, 0);
+    let recipient = AccountInfo::new(&keys[1], false, true, &mut recipient_lamports, &mut d1, &owner, false, 0);
+    let relayer = AccountInfo::new(&keys[2], false, true, &mut relayer_lamports, &mut d2, &owner, false, 0);
+    let collector = AccountInfo::new(&keys[3], false, true, &mut collector_lamports, &mut d3, &owner, false, 0);
+
+    let (net, fee) = split_fee(DENOMINATION_1_SOL, FEE_BASIS_POINTS).unwrap();
+    let (received, collected) = pay_out_relayed_withdrawal(
+        &pool, &recipient, &relayer, &collector, (net, fee), 0, u64::MAX,
+    )
+    .unwrap();
+
+    // The user's note is never touched
+    assert_eq!(received, net);
+    assert_eq!(collected, 0);
+    assert_eq!(relayer.lamports(), fee);
+    assert_eq!(collector.lamports(), 0);
+}
+
+fn empty_tree() -> MerkleTreeState {
+    test_utils::empty_tree_state(Pubkey::new_unique(), TREE_HASH_SHA256, POOL_VERSION_LEGACY)
+}
+
+#[test]
+fn test_merkle_tree_state_size() {
+    assert_eq!(MerkleTreeState::LEN, 8 + 32 + 640 + 960 + 4032 + 4 + 4 + 4 + 1 + 1 + 1 + 1);
+
+    // Zero-copy layout must match LEN exactly (no implicit padding)
+    assert_eq!(MerkleTreeState::LEN, 8 + std::mem::size_of::<MerkleTreeState>());
+}
+
+#[test]
+fn test_merkle_tree_empty_root() {
+    let tree = empty_tree();
+
+    assert_eq!(tree.root(), ZERO_VALUES[MERKLE_TREE_DEPTH]);
+    assert!(tree.is_known_root(&ZERO_VALUES[MERKLE_TREE_DEPTH]));
+    assert!(!tree.is_known_root(&[0u8; 32]));
+}
+
+#[test]
+fn test_merkle_tree_insert_matches_proof() {
+    let mut tree = empty_tree();
+    let leaves = [[1u8; 32], [2u8; 32], [3u8; 32]];
+
+    for (i, leaf) in leaves.iter().enumerate() {
+        assert_eq!(tree.insert(*leaf).unwrap(), i as u32);
+    }
+
+    // Path for leaf 2: sibling is empty, then hash(leaf0, leaf1), then zeros
+    let mut path: [[u8; 32]; MERKLE_TREE_DEPTH] = ZERO_VALUES[..MERKLE_TREE_DEPTH].try_into().unwrap();
+    let mut indices = [false; MERKLE_TREE_DEPTH];
+    path[1] = hash_pair(&leaves[0], &leaves[1]);
+    indices[1] = true;
+
+    assert_eq!(compute_merkle_root(&leaves[2], &path, &indices), tree.root());
+    assert_eq!(tree.next_index, 3);
+}
+
+#[test]
+fn test_merkle_tree_root_history() {
+    let mut tree = empty_tree();
+    let first_root = {
+        tree.insert([1u8; 32]).unwrap();
+        tree.root()
+    };
+
+    // Still accepted while inside the history window
+    for i in 0..ROOT_HISTORY_SIZE - 1 {
+        tree.insert([i as u8 + 2; 32]).unwrap();
+    }
+    assert!(tree.is_known_root(&first_root));
+
+    // Evicted once the ring buffer wraps around
+    tree.insert([0xaau8; 32]).unwrap();
+    assert!(!tree.is_known_root(&first_root));
+    assert!(tree.is_known_root(&tree.root()));
+}
+
+#[test]
+fn test_merkle_tree_full() {
+    let mut tree = empty_tree();
+    tree.next_index = 1 << MERKLE_TREE_DEPTH;
+
+    assert!(tree.insert([1u8; 32]).is_err());
+}
+
+#[test]
+fn test_merkle_tree_insert_batch_matches_sequential() {
+    let leaves: Vec<[u8; 32]> = (1..=MAX_BATCH_LEAVES as u8).map(|i| [i; 32]).collect();
+
+    // Cover batches starting at both even and odd indices
+    for already_inserted in [0usize, 1, 3, 4] {
+        let mut sequential = empty_tree();
+        let mut batched = empty_tree();
+
+        for i in 0..already_inserted {
+            sequential.insert([0xf0 + i as u8; 32]).unwrap();
+            batched.insert([0xf0 + i as u8; 32]).unwrap();
+        }
+
+        for leaf in &leaves {
+            sequential.insert(*leaf).unwrap();
+        }
+        let start_index = batched.insert_batch(&leaves).unwrap();
+
+        assert_eq!(start_index, already_inserted as u32);
+        assert_eq!(batched.root(), sequential.root());
+        assert_eq!(batched.filled_subtrees, sequential.filled_subtrees);
+        assert_eq!(batched.next_index, sequential.next_index);
+
+        // Later single inserts continue from the same state
+        batched.insert([0xaau8; 32]).unwrap();
+        sequential.insert([0xaau8; 32]).unwrap();
+        assert_eq!(batched.root(), sequential.root());
+    }
+}
+
+#[test]
+fn test_merkle_tree_insert_batch_single_root() {
+    let mut tree = empty_tree();
+    let empty_root = tree.root();
+
+    tree.insert_batch(&[[1u8; 32], [2u8; 32], [3u8; 32]]).unwrap();
+
+    // One new root for the whole batch, the previous one stays known
+    assert_eq!(tree.current_root_index, 1);
+    assert!(tree.is_known_root(&empty_root));
+}
+
+#[test]
+fn test_merkle_tree_insert_batch_bounds() {
+    let mut tree = empty_tree();
+    assert!(tree.insert_batch(&[]).is_err());
+
+    tree.next_index = (1 << MERKLE_TREE_DEPTH) - 1;
+    assert!(tree.insert_batch(&[[1u8; 32], [2u8; 32]]).is_err());
+    assert_eq!(tree.insert_batch(&[[1u8; 32]]).unwrap(), (1 << MERKLE_TREE_DEPTH) - 1);
+}
+
+#[test]
+fn test_merkle_tree_is_full() {
+    let mut tree = empty_tree();
+    assert!(!tree.is_full());
+
+    tree.next_index = (1 << MERKLE_TREE_DEPTH) - 1;
+    tree.insert([1u8; 32]).unwrap();
+    assert!(tree.is_full());
+    assert!(tree.insert([2u8; 32]).is_err());
+}
+
+#[test]
+fn test_merkle_tree_generation_rollover() {
+    let mut archived = empty_tree();
+    archived.insert([1u8; 32]).unwrap();
+    let archived_root = archived.root();
+
+    let mut next = empty_tree();
+    next.initialize(archived.pool, archived.generation + 1, archived.version, TREE_HASH_SHA256, 254);
+    next.insert([2u8; 32]).unwrap();
+
+    // The fresh generation starts empty and doesn't know archived roots,
+    // while the archived tree keeps accepting its own
+    assert_eq!(next.generation, 1);
+    assert_eq!(next.next_index, 1);
+    assert!(!next.is_known_root(&archived_root));
+    assert!(archived.is_known_root(&archived_root));
+}
+
+/// Every level of the tree holding `leaves`, with empty nodes omitted
+fn reference_levels(leaves: &[[u8; 32]]) -> Vec<Vec<[u8; 32]>> {
+    let mut levels = vec![leaves.to_vec()];
+    for level in 0..MERKLE_TREE_DEPTH {
+        let mut nodes = levels[level].clone();
+        if nodes.len() % 2 == 1 {
+            nodes.push(ZERO_VALUES[level]);
+        }
+        levels.push(nodes.chunks(2).map(|pair| hash_pair(&pair[0], &pair[1])).collect());
+    }
+    levels
+}
+
+#[test]
+fn test_canopy_size() {
+    assert_eq!(CANOPY_NODES, 126);
+}
+
+#[test]
+fn test_canopy_tracks_upper_levels() {
+    let leaves: Vec<[u8; 32]> = (1..=40u8).map(|i| [i; 32]).collect();
+    let levels = reference_levels(&leaves);
+
+    let mut tree = empty_tree();
+    for leaf in &leaves[..20] {
+        tree.insert(*leaf).unwrap();
+    }
+    tree.insert_batch(&leaves[20..]).unwrap();
+
+    // Root's children, then the first node of the lowest canopy level
+    assert_eq!(tree.canopy[0], levels[MERKLE_TREE_DEPTH - 1][0]);
+    assert_eq!(tree.canopy[1], ZERO_VALUES[MERKLE_TREE_DEPTH - 1]);
+    assert_eq!(
+        tree.canopy[CANOPY_NODES - (1 << CANOPY_DEPTH)],
+        levels[MERKLE_TREE_DEPTH - CANOPY_DEPTH][0]
+    );
+    assert_eq!(levels[MERKLE_TREE_DEPTH][0], tree.root());
+}
+
+#[test]
+fn test_fill_proof_from_canopy() {
+    let leaves: Vec<[u8; 32]> = (1..=9u8).map(|i| [i; 32]).collect();
+    let levels = reference_levels(&leaves);
+
+    let mut tree = empty_tree();
+    tree.insert_batch(&leaves).unwrap();
+
+    for (leaf_index, leaf) in leaves.iter().enumerate() {
+        let mut lower_proof = [[0u8; 32]; MERKLE_TREE_DEPTH - CANOPY_DEPTH];
+        for (level, sibling) in lower_proof.iter_mut().enumerate() {
+            let index = (leaf_index >> level) ^ 1;
+            *sibling = *levels[level].get(index).unwrap_or(&ZERO_VALUES[level]);
+        }
+
+        let (proof, indices) = tree.fill_proof_from_canopy(leaf_index as u32, &lower_proof).unwrap();
+        assert!(verify_merkle_proof(leaf, &proof, &indices, &tree.root()));
+    }
+
+    // Leaves that don't exist yet are rejected
+    let lower_proof = [[0u8; 32]; MERKLE_TREE_DEPTH - CANOPY_DEPTH];
+    assert!(tree.fill_proof_from_canopy(9, &lower_proof).is_err());
+}
+
+fn config_with_attestors(attestors: &[Pubkey], threshold: u8) -> Config {
+    let mut config = Config {
+        authority: Pubkey::new_unique(),
+        fee_collector: Pubkey::new_unique(),
+        paused: false,
+        bump: 255,
+        attestors: [Pubkey::default(); MAX_ATTESTORS],
+        attestor_count: attestors.len() as u8,
+        attestor_threshold: threshold,
+        ata_reimbursement_bps: 0,
+        relayer_rebate_lamports: 0,
+        max_relayer_fee_bps: DEFAULT_MAX_RELAYER_FEE_BPS,
+        staker_vault: Pubkey::default(),
+        staker_share_bps: 0,
+        reward_hook: Pubkey::default(),
+        authority_members: [Pubkey::default(); MAX_AUTHORITY_MEMBERS],
+        authority_member_count: 0,
+        authority_threshold: 0,
+        guardian: Pubkey::default(),
+        deposit_rate_limit: RateLimit::default(),
+        withdrawal_rate_limit: RateLimit::default(),
+        version: CONFIG_VERSION,
+        upgrade_authority: Pubkey::default(),
+        last_deploy_slot: 0,
+        upgrade_info_recorded_at: 0,
+        paused_at: 0,
+        emergency_mode: false,
+        renounced: false,
+        permissionless_pools: false,
+        pool_creation_bond: 0,
+        screening_program: Pubkey::default(),
+    };
+    config.attestors[..attestors.len()].copy_from_slice(attestors);
+    config
+}
+
+#[test]
+fn test_count_attestations() {
+    let attestors = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+    let config = config_with_attestors(&attestors, 2);
+
+    assert_eq!(config.count_attestations(&[]), 0);
+    assert_eq!(config.count_attestations(&[attestors[0]]), 1);
+    assert_eq!(config.count_attestations(&[attestors[0], attestors[2]]), 2);
+    assert_eq!(config.count_attestations(&attestors), 3);
+}
+
+#[test]
+fn test_count_attestations_ignores_outsiders_and_duplicates() {
+    let attestors = [Pubkey::new_unique(), Pubkey::new_unique()];
+    let config = config_with_attestors(&attestors, 2);
+
+    // A non-attestor signer doesn't count
+    assert_eq!(config.count_attestations(&[Pubkey::new_unique(), attestors[1]]), 1);
+
+    // The same attestor passed twice counts once
+    assert_eq!(config.count_attestations(&[attestors[0], attestors[0]]), 1);
+}
+
+#[test]
+fn test_count_attestations_ignores_unused_slots() {
+    let config = config_with_attestors(&[Pubkey::new_unique()], 1);
+
+    // Empty slots hold the default key, which must never count as an attestor
+    assert_eq!(config.count_attestations(&[Pubkey::default()]), 0);
+}
+
+#[test]
+fn test_tree_checkpoint_account_size() {
+    // TreeCheckpoint: discriminator (8) + pool (32) + generation (4) + subtree_index (4) +
+    // start_leaf (4) + end_leaf (4) + subtree_root (32) + timestamp (8) + bump (1)
+    assert_eq!(TreeCheckpoint::LEN, 8 + 32 + 4 + 4 + 4 + 4 + 32 + 8 + 1);
+    assert_eq!(CHECKPOINT_LEVEL, 14);
+}
+
+#[test]
+fn test_subtree_root_after_subtree_fills() {
+    let leaves: Vec<[u8; 32]> = (0..(1u32 << CHECKPOINT_LEVEL) + 2)
+        .map(|i| {
+            let mut leaf = [0u8; 32];
+            leaf[..4].copy_from_slice(&(i + 1).to_le_bytes());
+            leaf
+        })
+        .collect();
+    let levels = reference_levels(&leaves);
+
+    let mut tree = empty_tree();
+    for leaf in &leaves[..(1 << CHECKPOINT_LEVEL) - 1] {
+        tree.insert(*leaf).unwrap();
+    }
+
+    // One leaf short of a full subtree
+    assert!(tree.subtree_root(0).is_err());
+
+    for leaf in &leaves[(1 << CHECKPOINT_LEVEL) - 1..] {
+        tree.insert(*leaf).unwrap();
+    }
+
+    // Two deposits past the boundary: the first subtree is final, the second isn't
+    assert_eq!(tree.subtree_root(0).unwrap(), levels[CHECKPOINT_LEVEL][0]);
+    assert!(tree.subtree_root(1).is_err());
+}
+
+#[test]
+fn test_subtree_root_index_out_of_range() {
+    let tree = empty_tree();
+
+    assert!(tree.subtree_root(0).is_err());
+    assert!(tree.subtree_root(1 << CANOPY_DEPTH).is_err());
+    assert!(tree.subtree_root(u32::MAX).is_err());
+}
+
+#[test]
+fn test_frozen_tree_rejects_leaves() {
+    let mut tree = empty_tree();
+    tree.insert([1u8; 32]).unwrap();
+    let root = tree.root();
+
+    assert!(!tree.is_frozen());
+    tree.freeze();
+    assert!(tree.is_frozen());
+
+    assert!(tree.insert([2u8; 32]).is_err());
+    assert!(tree.insert_batch(&[[2u8; 32], [3u8; 32]]).is_err());
+
+    // Existing notes can still be proven against the frozen tree
+    assert_eq!(tree.next_index, 1);
+    assert_eq!(tree.root(), root);
+    assert!(tree.is_known_root(&root));
+}
+
+#[test]
+fn test_initialize_unfreezes() {
+    let mut tree = empty_tree();
+    tree.freeze();
+    tree.initialize(Pubkey::new_unique(), 1, POOL_VERSION_LEGACY, TREE_HASH_SHA256, 255);
+
+    assert!(!tree.is_frozen());
+    assert!(tree.insert([1u8; 32]).is_ok());
+}
+
+fn domain_separated_tree() -> MerkleTreeState {
+    let mut tree = empty_tree();
+    tree.initialize(Pubkey::new_unique(), 0, POOL_VERSION_DOMAIN_SEPARATED, TREE_HASH_SHA256, 255);
+    tree
+}
+
+#[test]
+fn test_new_pools_bind_leaf_index() {
+    assert_eq!(CURRENT_POOL_VERSION, POOL_VERSION_INDEX_BOUND);
+}
+
+#[test]
+fn test_domain_separated_tree_empty_root() {
+    let tree = domain_separated_tree();
+
+    assert_eq!(tree.version, POOL_VERSION_DOMAIN_SEPARATED);
+    assert_eq!(tree.root(), DOMAIN_ZERO_VALUES[MERKLE_TREE_DEPTH]);
+    assert_ne!(tree.root(), ZERO_VALUES[MERKLE_TREE_DEPTH]);
+}
+
+#[test]
+fn test_domain_separated_tree_proofs() {
+    let commitments: Vec<[u8; 32]> = (1..=5u8).map(|i| [i; 32]).collect();
+
+    let mut tree = domain_separated_tree();
+    for commitment in &commitments {
+        tree.insert(*commitment).unwrap();
+    }
+
+    // Rebuild the tree by hand: hashed leaves, domain-separated nodes
+    let mut level: Vec<[u8; 32]> = commitments.iter().map(hash_leaf).collect();
+    let mut proof = [[0u8; 32]; MERKLE_TREE_DEPTH];
+    let mut path_indices = [false; MERKLE_TREE_DEPTH];
+    let mut index = 3usize;
+    for depth in 0..MERKLE_TREE_DEPTH {
+        if level.len() % 2 == 1 {
+            level.push(DOMAIN_ZERO_VALUES[depth]);
+        }
+        proof[depth] = level[index ^ 1];
+        path_indices[depth] = index % 2 == 1;
+        level = level.chunks(2).map(|pair| hash_node(&pair[0], &pair[1])).collect();
+        index /= 2;
+    }
+    assert_eq!(tree.root(), level[0]);
+
+    let root = tree.root();
+    assert!(verify_versioned_merkle_proof(
+        POOL_VERSION_DOMAIN_SEPARATED,
+        &commitments[3],
+        &proof,
+        &path_indices,
+        &root
+    ));
+
+    // The same proof is meaningless under the legacy scheme
+    assert!(!verify_versioned_merkle_proof(
+        POOL_VERSION_LEGACY,
+        &commitments[3],
+        &proof,
+        &path_indices,
+        &root
+    ));
+}
+
+#[test]
+fn test_domain_separated_batch_matches_single_inserts() {
+    let commitments: Vec<[u8; 32]> = (1..=7u8).map(|i| [i; 32]).collect();
+
+    let mut single = domain_separated_tree();
+    for commitment in &commitments {
+        single.insert(*commitment).unwrap();
+    }
+
+    let mut batch = domain_separated_tree();
+    batch.insert_batch(&commitments[..3]).unwrap();
+    batch.insert_batch(&commitments[3..]).unwrap();
+
+    assert_eq!(single.root(), batch.root());
+    assert_eq!(single.filled_subtrees, batch.filled_subtrees);
+    assert_eq!(single.canopy, batch.canopy);
+}
+
+#[test]
+fn test_internal_node_cannot_pose_as_leaf() {
+    let left = hash_leaf(&[1u8; 32]);
+    let right = hash_leaf(&[2u8; 32]);
+
+    // Legacy: a commitment over (left, right) is the very same hash as their
+    // parent node, so the children can be passed off as a secret and nullifier
+    assert_eq!(
+        leaf_hash(POOL_VERSION_LEGACY, &commitment_hash(&left, &right), 0),
+        node_hash(POOL_VERSION_LEGACY, &left, &right)
+    );
+
+    // Domain-separated: leaves and nodes can no longer collide
+    assert_ne!(
+        leaf_hash(POOL_VERSION_DOMAIN_SEPARATED, &commitment_hash(&left, &right), 0),
+        node_hash(POOL_VERSION_DOMAIN_SEPARATED, &left, &right)
+    );
+}
+
+fn poseidon_tree() -> MerkleTreeState {
+    let mut tree = empty_tree();
+    tree.initialize(Pubkey::new_unique(), 0, CURRENT_POOL_VERSION, TREE_HASH_POSEIDON, 255);
+    tree
+}
+
+#[test]
+fn test_poseidon_tree_empty_root() {
+    let tree = poseidon_tree();
+
+    assert_eq!(tree.hash, TREE_HASH_POSEIDON);
+    assert_eq!(
+        tree.root(),
+        merkle_poseidon::zero_values(CURRENT_POOL_VERSION)[MERKLE_TREE_DEPTH]
+    );
+}
+
+#[test]
+fn test_poseidon_tree_proofs_verify() {
+    let leaves: Vec<[u8; 32]> = (1..=3u8)
+        .map(|i| merkle_poseidon::poseidon_leaf(&[i; 32], 1_700_000_000 + i as i64))
+        .collect();
+
+    let mut tree = poseidon_tree();
+    tree.insert(leaves[0]).unwrap();
+    tree.insert_batch(&leaves[1..]).unwrap();
+
+    // Leaf 2's siblings: empty leaf 3, then the parent of leaves 0 and 1
+    let zeros = merkle_poseidon::zero_values(CURRENT_POOL_VERSION);
+    let hashed: Vec<[u8; 32]> = leaves
+        .iter()
+        .zip(0..)
+        .map(|(leaf, index)| merkle_poseidon::poseidon_leaf_hash(CURRENT_POOL_VERSION, leaf, index))
+        .collect();
+    let mut path = zeros[..MERKLE_TREE_DEPTH].to_vec();
+    path[1] = merkle_poseidon::poseidon_node_hash(CURRENT_POOL_VERSION, &hashed[0], &hashed[1]);
+    let mut indices = vec![false; MERKLE_TREE_DEPTH];
+    indices[1] = true;
+
+    assert!(merkle_poseidon::verify_versioned_merkle_proof(
+        CURRENT_POOL_VERSION,
+        &leaves[2],
+        &path,
+        &indices,
+        &tree.root()
+    )
+    .unwrap());
+}
+
+#[test]
+fn test_dual_trees_track_same_leaves() {
+    let commitments: Vec<[u8; 32]> = (1..=4u8).map(|i| [i; 32]).collect();
+
+    let mut sha = empty_tree();
+    sha.initialize(Pubkey::new_unique(), 0, CURRENT_POOL_VERSION, TREE_HASH_SHA256, 255);
+    let mut poseidon = poseidon_tree();
+
+    for commitment in &commitments {
+        let sha_index = sha.insert(*commitment).unwrap();
+        let poseidon_index = poseidon
+            .insert(merkle_poseidon::poseidon_leaf(commitment, 1_700_000_000))
+            .unwrap();
+        assert_eq!(sha_index, poseidon_index);
+    }
+
+    // Same positions, different hashes
+    assert_eq!(sha.next_index, poseidon.next_index);
+    assert_ne!(sha.root(), poseidon.root());
+}
+
+#[test]
+fn test_duplicate_commitments_get_distinct_leaves() {
+    let commitment = [4u8; 32];
+
+    let mut tree = empty_tree();
+    tree.initialize(Pubkey::new_unique(), 0, CURRENT_POOL_VERSION, TREE_HASH_SHA256, 255);
+    tree.insert(commitment).unwrap();
+    tree.insert(commitment).unwrap();
+
+    // Leaf 0 is kept as the level-0 filled subtree
+    let first = leaf_hash(CURRENT_POOL_VERSION, &commitment, 0);
+    let second = leaf_hash(CURRENT_POOL_VERSION, &commitment, 1);
+    assert_ne!(first, second);
+    assert_eq!(tree.filled_subtrees[0], first);
+
+    // Both deposits are provable, each only at its own index
+    let mut lower = [[0u8; 32]; MERKLE_TREE_DEPTH - CANOPY_DEPTH];
+    lower.copy_from_slice(&zero_values(CURRENT_POOL_VERSION)[..MERKLE_TREE_DEPTH - CANOPY_DEPTH]);
+    lower[0] = first;
+    let (path, indices) = tree.fill_proof_from_canopy(1, &lower).unwrap();
+    assert!(verify_merkle_proof_with_depth(CURRENT_POOL_VERSION, &commitment, &path, &indices, MERKLE_TREE_DEPTH, &tree.root()).unwrap());
+
+    let mut swapped = indices;
+    swapped[0] = false;
+    assert!(!verify_merkle_proof_with_depth(CURRENT_POOL_VERSION, &commitment, &path, &swapped, MERKLE_TREE_DEPTH, &tree.root()).unwrap());
+}
+
+fn commitment_record(pool: Pubkey, commitment: [u8; 32], leaf_index: u32) -> CommitmentRecord {
+    CommitmentRecord {
+        pool,
+        commitment,
+        leaf_index,
+        timestamp: 0,
+        bump: 255,
+    }
+}
+
+fn audit_cursor(pool: Pubkey, generation: u32) -> AuditCursor {
+    let mut cursor = AuditCursor {
+        pool,
+        generation,
+        next_index: 0,
+        filled_subtrees: [[0u8; 32]; MERKLE_TREE_DEPTH],
+        root: [0u8; 32],
+        bump: 0,
+    };
+    cursor.initialize(pool, generation, TREE_HASH_SHA256, CURRENT_POOL_VERSION, 255);
+    cursor
+}
+
+#[test]
+fn test_audit_replay_matches_tree() {
+    let pool = Pubkey::new_unique();
+    let mut tree = empty_tree();
+    tree.initialize(pool, 0, CURRENT_POOL_VERSION, TREE_HASH_SHA256, 255);
+
+    let mut cursor = audit_cursor(pool, 0);
+    assert_eq!(cursor.root, tree.root());
+
+    for i in 0..5u8 {
+        tree.insert([i + 1; 32]).unwrap();
+    }
+
+    // Partway through, the replayed root still lags the tree
+    for i in 0..5u8 {
+        cursor
+            .replay(&commitment_record(pool, [i + 1; 32], i as u32), TREE_HASH_SHA256, CURRENT_POOL_VERSION)
+            .unwrap();
+        if i == 2 {
+            assert_ne!(cursor.root, tree.root());
+        }
+    }
+
+    assert_eq!(cursor.next_index, 5);
+    assert_eq!(cursor.root, tree.root());
+    assert_eq!(cursor.filled_subtrees, tree.filled_subtrees);
+}
+
+#[test]
+fn test_audit_detects_tampered_commitment() {
+    let pool = Pubkey::new_unique();
+    let mut tree = empty_tree();
+    tree.initialize(pool, 0, CURRENT_POOL_VERSION, TREE_HASH_SHA256, 255);
+    tree.insert([1u8; 32]).unwrap();
+    tree.insert([2u8; 32]).unwrap();
+
+    let mut cursor = audit_cursor(pool, 0);
+    cursor.replay(&commitment_record(pool, [1u8; 32], 0), TREE_HASH_SHA256, CURRENT_POOL_VERSION).unwrap();
+    cursor.replay(&commitment_record(pool, [9u8; 32], 1), TREE_HASH_SHA256, CURRENT_POOL_VERSION).unwrap();
+
+    assert_ne!(cursor.root, tree.root());
+}
+
+#[test]
+fn test_audit_rejects_out_of_order_records() {
+    let pool = Pubkey::new_unique();
+    let mut cursor = audit_cursor(pool, 1);
+    let base = 1u32 << MERKLE_TREE_DEPTH;
+
+    // Wrong pool, skipped index, and an index from the previous generation
+    assert!(cursor.replay(&commitment_record(Pubkey::new_unique(), [1u8; 32], base), TREE_HASH_SHA256, CURRENT_POOL_VERSION).is_err());
+    assert!(cursor.replay(&commitment_record(pool, [1u8; 32], base + 1), TREE_HASH_SHA256, CURRENT_POOL_VERSION).is_err());
+    assert!(cursor.replay(&commitment_record(pool, [1u8; 32], 0), TREE_HASH_SHA256, CURRENT_POOL_VERSION).is_err());
+    assert_eq!(cursor.next_index, 0);
+
+    cursor.replay(&commitment_record(pool, [1u8; 32], base), TREE_HASH_SHA256, CURRENT_POOL_VERSION).unwrap();
+    assert_eq!(cursor.next_index, 1);
+}
+
+#[test]
+fn test_known_path_siblings() {
+    let leaves: Vec<[u8; 32]> = (1..=11u8).map(|i| [i; 32]).collect();
+    let levels = reference_levels(&leaves);
+
+    let mut tree = empty_tree();
+    for leaf in &leaves {
+        tree.insert(*leaf).unwrap();
+    }
+
+    let expected = |leaf_index: u32, level: usize| {
+        let sibling = ((leaf_index >> level) ^ 1) as usize;
+        levels[level].get(sibling).copied().unwrap_or(ZERO_VALUES[level])
+    };
+
+    // Every sibling reported as known is correct
+    for leaf_index in 0..11u32 {
+        let path = tree.known_path(leaf_index).unwrap();
+        assert_eq!(path.root, tree.root());
+        assert_eq!(path.siblings.len(), MERKLE_TREE_DEPTH);
+
+        for (level, sibling) in path.siblings.iter().enumerate() {
+            if let Some(sibling) = sibling {
+                assert_eq!(*sibling, expected(leaf_index, level), "leaf {} level {}", leaf_index, level);
+            }
+        }
+    }
+
+    // The newest leaf's path is complete
+    let newest = tree.known_path(10).unwrap();
+    assert!(newest.siblings.iter().all(Option::is_some));
+
+    // Leaf 0's lower siblings are inside finished subtrees, and at level 3
+    // its sibling is the partly filled node over leaves 8..16
+    let oldest = tree.known_path(0).unwrap();
+    assert_eq!(oldest.siblings[0], None);
+    assert_eq!(oldest.siblings[1], None);
+    assert_eq!(oldest.siblings[3], None);
+    assert_eq!(oldest.siblings[4], Some(ZERO_VALUES[4]));
+
+    assert!(tree.known_path(11).is_err());
+}
+
+#[test]
+fn test_keccak_tree_matches_hasher() {
+    let notes = test_utils::notes(3, 3);
+    let expected = test_utils::TestTree::from_notes(TREE_HASH_KECCAK, CURRENT_POOL_VERSION, &notes);
+
+    let mut tree = test_utils::empty_tree_state(Pubkey::new_unique(), TREE_HASH_KECCAK, CURRENT_POOL_VERSION);
+    tree.insert(notes[0].commitment).unwrap();
+    tree.insert_batch(&[notes[1].commitment, notes[2].commitment]).unwrap();
+    assert_eq!(tree.root(), expected.root());
+
+    let (path, indices) = expected.proof(2);
+    assert!(tree_hasher(TREE_HASH_KECCAK)
+        .verify_proof(CURRENT_POOL_VERSION, &notes[2].commitment, &path, &indices, MERKLE_TREE_DEPTH, &tree.root())
+        .unwrap());
+    assert!(!tree_hasher(TREE_HASH_SHA256)
+        .verify_proof(CURRENT_POOL_VERSION, &notes[2].commitment, &path, &indices, MERKLE_TREE_DEPTH, &tree.root())
+        .unwrap());
+}
@@ -1,5 +1,8 @@
+use anchor_lang::prelude::*;
 use sha2::{Digest, Sha256};
 
+use crate::MixerError;
+
 /// Merkle tree depth (supports 2^20 = 1,048,576 deposits)
 pub const MERKLE_TREE_DEPTH: usize = 20;
 
@@ -51,6 +54,26 @@ pub const ZERO_VALUES: [[u8; 32]; MERKLE_TREE_DEPTH + 1] = [
     [205, 219, 167, 181, 146, 227, 19, 51, 147, 193, 97, 148, 250, 199, 67, 26, 191, 47, 84, 133, 237, 113, 29, 178, 130, 24, 60, 129, 158, 8, 235, 170],
 ];
 
+/// Pool version whose trees hash leaves and nodes with the same function
+pub const POOL_VERSION_LEGACY: u8 = 0;
+
+/// Pool version whose trees prefix leaves and internal nodes with distinct
+/// domain tags, so an internal node can never be presented as a leaf
+pub const POOL_VERSION_DOMAIN_SEPARATED: u8 = 1;
+
+/// Pool version whose leaves also commit to their index in the tree, so the
+/// same commitment inserted twice yields two distinct leaves
+pub const POOL_VERSION_INDEX_BOUND: u8 = 2;
+
+/// Version given to newly created pools
+pub const CURRENT_POOL_VERSION: u8 = POOL_VERSION_INDEX_BOUND;
+
+/// Domain tag hashed in front of leaves
+pub const LEAF_DOMAIN: u8 = 0x00;
+
+/// Domain tag hashed in front of internal nodes
+pub const NODE_DOMAIN: u8 = 0x01;
+
 /// Compute SHA256 hash of two 32-byte values
 pub fn hash_pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
     let mut hasher = Sha256::new();
@@ -60,6 +83,184 @@ pub fn hash_pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
     result.into()
 }
 
+/// SHA256(0x00 || data): a domain-separated leaf
+pub fn hash_leaf(data: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([LEAF_DOMAIN]);
+    hasher.update(data);
+    hasher.finalize().into()
+}
+
+/// SHA256(0x00 || data || leaf_index): a domain-separated leaf bound to its
+/// position, the index encoded as 4 little-endian bytes
+pub fn hash_indexed_leaf(data: &[u8; 32], leaf_index: u32) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([LEAF_DOMAIN]);
+    hasher.update(data);
+    hasher.update(leaf_index.to_le_bytes());
+    hasher.finalize().into()
+}
+
+/// SHA256(0x01 || left || right): a domain-separated internal node
+pub fn hash_node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([NODE_DOMAIN]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// Hash function a Merkle tree is built with
+///
+/// Implementors supply the raw hashes and their embedded zero-value tables.
+/// The pool-version scheme on top (which leaf and node hash a version uses,
+/// zero values, proof checking) is written once in the provided methods.
+pub trait MerkleHasher: Sync {
+    /// Plain two-to-one hash of legacy trees
+    fn hash_pair(&self, left: &[u8; 32], right: &[u8; 32]) -> [u8; 32];
+
+    /// Domain-separated leaf
+    fn hash_leaf(&self, data: &[u8; 32]) -> [u8; 32];
+
+    /// Domain-separated leaf bound to its index
+    fn hash_indexed_leaf(&self, data: &[u8; 32], leaf_index: u32) -> [u8; 32];
+
+    /// Domain-separated internal node
+    fn hash_node(&self, left: &[u8; 32], right: &[u8; 32]) -> [u8; 32];
+
+    /// Embedded zero values of legacy trees
+    fn legacy_zero_values(&self) -> &'static [[u8; 32]; MERKLE_TREE_DEPTH + 1];
+
+    /// Embedded zero values of domain-separated trees
+    fn domain_zero_values(&self) -> &'static [[u8; 32]; MERKLE_TREE_DEPTH + 1];
+
+    /// Leaf stored for `data` at `leaf_index` in a tree of the given pool version
+    fn leaf_hash(&self, version: u8, data: &[u8; 32], leaf_index: u32) -> [u8; 32] {
+        if version >= POOL_VERSION_INDEX_BOUND {
+            self.hash_indexed_leaf(data, leaf_index)
+        } else if version >= POOL_VERSION_DOMAIN_SEPARATED {
+            self.hash_leaf(data)
+        } else {
+            *data
+        }
+    }
+
+    /// Internal node of a tree of the given pool version
+    fn node_hash(&self, version: u8, left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+        if version >= POOL_VERSION_DOMAIN_SEPARATED {
+            self.hash_node(left, right)
+        } else {
+            self.hash_pair(left, right)
+        }
+    }
+
+    /// Empty-subtree values of a tree of the given pool version
+    fn zero_values(&self, version: u8) -> &'static [[u8; 32]; MERKLE_TREE_DEPTH + 1] {
+        if version >= POOL_VERSION_DOMAIN_SEPARATED {
+            self.domain_zero_values()
+        } else {
+            self.legacy_zero_values()
+        }
+    }
+
+    /// Recompute the zero values of a pool version from node_hash, to check
+    /// the embedded tables
+    fn compute_zero_values(&self, version: u8) -> [[u8; 32]; MERKLE_TREE_DEPTH + 1] {
+        let mut zeros = [[0u8; 32]; MERKLE_TREE_DEPTH + 1];
+
+        for i in 1..=MERKLE_TREE_DEPTH {
+            zeros[i] = self.node_hash(version, &zeros[i - 1], &zeros[i - 1]);
+        }
+
+        zeros
+    }
+
+    /// Compute the root of a `depth`-level tree of the given pool version from
+    /// a raw commitment and its proof, passed as slices
+    /// Fails with InvalidProofLength unless both slices hold exactly `depth`
+    /// entries and the depth fits a u32 leaf index
+    fn compute_root(
+        &self,
+        version: u8,
+        commitment: &[u8; 32],
+        path: &[[u8; 32]],
+        path_indices: &[bool],
+        depth: usize,
+    ) -> Result<[u8; 32]> {
+        require!(
+            path.len() == depth && path_indices.len() == depth,
+            MixerError::InvalidProofLength
+        );
+        require!(depth <= u32::BITS as usize, MixerError::InvalidProofLength);
+
+        // A full path spells out the leaf index
+        let leaf_index = pack_path_indices(path_indices);
+
+        Ok(fold_merkle_path(
+            self.leaf_hash(version, commitment, leaf_index),
+            path.iter().zip(path_indices),
+            |left, right| self.node_hash(version, left, right),
+        ))
+    }
+
+    /// Verify a proof of any depth, see compute_root
+    fn verify_proof(
+        &self,
+        version: u8,
+        commitment: &[u8; 32],
+        path: &[[u8; 32]],
+        path_indices: &[bool],
+        depth: usize,
+        root: &[u8; 32],
+    ) -> Result<bool> {
+        Ok(self.compute_root(version, commitment, path, path_indices, depth)? == *root)
+    }
+}
+
+/// SHA256 trees, as kept in MerkleTreeState
+pub struct Sha256Hasher;
+
+impl MerkleHasher for Sha256Hasher {
+    fn hash_pair(&self, left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+        hash_pair(left, right)
+    }
+
+    fn hash_leaf(&self, data: &[u8; 32]) -> [u8; 32] {
+        hash_leaf(data)
+    }
+
+    fn hash_indexed_leaf(&self, data: &[u8; 32], leaf_index: u32) -> [u8; 32] {
+        hash_indexed_leaf(data, leaf_index)
+    }
+
+    fn hash_node(&self, left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+        hash_node(left, right)
+    }
+
+    fn legacy_zero_values(&self) -> &'static [[u8; 32]; MERKLE_TREE_DEPTH + 1] {
+        &ZERO_VALUES
+    }
+
+    fn domain_zero_values(&self) -> &'static [[u8; 32]; MERKLE_TREE_DEPTH + 1] {
+        &DOMAIN_ZERO_VALUES
+    }
+}
+
+/// Leaf stored at `leaf_index` in a SHA256 tree of the given pool version
+pub fn leaf_hash(version: u8, data: &[u8; 32], leaf_index: u32) -> [u8; 32] {
+    Sha256Hasher.leaf_hash(version, data, leaf_index)
+}
+
+/// Internal node of a SHA256 tree of the given pool version
+pub fn node_hash(version: u8, left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    Sha256Hasher.node_hash(version, left, right)
+}
+
+/// Empty-subtree values of a SHA256 tree of the given pool version
+pub fn zero_values(version: u8) -> &'static [[u8; 32]; MERKLE_TREE_DEPTH + 1] {
+    Sha256Hasher.zero_values(version)
+}
+
 /// Compute commitment from secret and nullifier
 pub fn compute_commitment(secret: &[u8; 32], nullifier: &[u8; 32]) -> [u8; 32] {
     let mut hasher = Sha256::new();
@@ -69,6 +270,34 @@ pub fn compute_commitment(secret: &[u8; 32], nullifier: &[u8; 32]) -> [u8; 32] {
     result.into()
 }
 
+/// Pool-scoped form of a revealed nullifier, as stored in SpentNullifier
+/// SHA256(nullifier || pool), so a secret reused across pools spends independently
+pub fn compute_pool_nullifier(nullifier: &[u8; 32], pool: &Pubkey) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(nullifier);
+    hasher.update(pool.as_ref());
+    hasher.finalize().into()
+}
+
+/// Hash a leaf up a proof path of (sibling, is_right) steps with `node`
+/// Works for any depth and streams the path instead of copying it
+pub fn fold_merkle_path<'a, F>(
+    leaf: [u8; 32],
+    steps: impl IntoIterator<Item = (&'a [u8; 32], &'a bool)>,
+    mut node: F,
+) -> [u8; 32]
+where
+    F: FnMut(&[u8; 32], &[u8; 32]) -> [u8; 32],
+{
+    steps.into_iter().fold(leaf, |current, (sibling, is_right)| {
+        if *is_right {
+            node(sibling, &current)
+        } else {
+            node(&current, sibling)
+        }
+    })
+}
+
 /// Verify Merkle proof
 pub fn verify_merkle_proof(
     leaf: &[u8; 32],
@@ -76,19 +305,7 @@ pub fn verify_merkle_proof(
     path_indices: &[bool; MERKLE_TREE_DEPTH],
     root: &[u8; 32],
 ) -> bool {
-    let mut current = *leaf;
-
-    for i in 0..MERKLE_TREE_DEPTH {
-        let (left, right) = if path_indices[i] {
-            (&path[i], &current)
-        } else {
-            (&current, &path[i])
-        };
-
-        current = hash_pair(left, right);
-    }
-
-    current == *root
+    compute_merkle_root(leaf, path, path_indices) == *root
 }
 
 /// Compute Merkle root from leaf and path
@@ -97,19 +314,30 @@ pub fn compute_merkle_root(
     path: &[[u8; 32]; MERKLE_TREE_DEPTH],
     path_indices: &[bool; MERKLE_TREE_DEPTH],
 ) -> [u8; 32] {
-    let mut current = *leaf;
-
-    for i in 0..MERKLE_TREE_DEPTH {
-        let (left, right) = if path_indices[i] {
-            (&path[i], &current)
-        } else {
-            (&current, &path[i])
-        };
+    fold_merkle_path(*leaf, path.iter().zip(path_indices), hash_pair)
+}
 
-        current = hash_pair(left, right);
-    }
+/// Compute the root of a SHA256 tree of any depth, see MerkleHasher::compute_root
+pub fn compute_root_with_depth(
+    version: u8,
+    commitment: &[u8; 32],
+    path: &[[u8; 32]],
+    path_indices: &[bool],
+    depth: usize,
+) -> Result<[u8; 32]> {
+    Sha256Hasher.compute_root(version, commitment, path, path_indices, depth)
+}
 
-    current
+/// Verify a SHA256 proof of any depth, see MerkleHasher::compute_root
+pub fn verify_merkle_proof_with_depth(
+    version: u8,
+    commitment: &[u8; 32],
+    path: &[[u8; 32]],
+    path_indices: &[bool],
+    depth: usize,
+    root: &[u8; 32],
+) -> Result<bool> {
+    Sha256Hasher.verify_proof(version, commitment, path, path_indices, depth, root)
 }
 
 /// Compute zero values for each level of the tree (for testing)
@@ -123,3 +351,91 @@ pub fn compute_zero_values() -> [[u8; 32]; MERKLE_TREE_DEPTH + 1] {
 
     zeros
 }
+
+/// Verify a Merkle proof for a raw commitment in a tree of the given pool version
+/// The commitment is turned into the tree's leaf before hashing up the path
+pub fn verify_versioned_merkle_proof(
+    version: u8,
+    commitment: &[u8; 32],
+    path: &[[u8; 32]; MERKLE_TREE_DEPTH],
+    path_indices: &[bool; MERKLE_TREE_DEPTH],
+    root: &[u8; 32],
+) -> bool {
+    // Fixed-size arrays always have MERKLE_TREE_DEPTH entries
+    compute_root_with_depth(version, commitment, path, path_indices, MERKLE_TREE_DEPTH)
+        .is_ok_and(|computed| computed == *root)
+}
+
+/// Pack path indices into a bitfield, bit i set when level i is a right child
+/// For a full path this is just the leaf index
+pub fn pack_path_indices(path_indices: &[bool]) -> u32 {
+    path_indices
+        .iter()
+        .enumerate()
+        .filter(|(_, is_right)| **is_right)
+        .fold(0, |bits, (level, _)| bits | (1 << level))
+}
+
+/// Unpack a path index bitfield for a `depth`-level tree
+/// Fails with InvalidProofLength if the depth doesn't fit the bitfield or any
+/// bit above the depth is set
+pub fn unpack_path_indices(bits: u32, depth: usize) -> Result<Vec<bool>> {
+    require!(
+        depth <= u32::BITS as usize && (bits as u64) >> depth == 0,
+        MixerError::InvalidProofLength
+    );
+
+    Ok((0..depth).map(|level| (bits >> level) & 1 == 1).collect())
+}
+
+/// Compute zero values of a tree of the given pool version (for testing)
+pub fn compute_zero_values_for_version(version: u8) -> [[u8; 32]; MERKLE_TREE_DEPTH + 1] {
+    Sha256Hasher.compute_zero_values(version)
+}
+
+/// Zero values of domain-separated trees: the empty leaf stays zero and
+/// zeros[i+1] = hash_node(zeros[i], zeros[i])
+pub const DOMAIN_ZERO_VALUES: [[u8; 32]; MERKLE_TREE_DEPTH + 1] = [
+    // Level 0
+    [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+    // Level 1
+    [174, 7, 152, 208, 236, 174, 210, 183, 120, 237, 222, 191, 24, 240, 113, 165, 97, 197, 54, 88, 192, 94, 118, 206, 222, 204, 39, 202, 251, 219, 197, 119],
+    // Level 2
+    [144, 83, 79, 224, 175, 246, 219, 158, 219, 41, 238, 231, 78, 120, 163, 134, 145, 106, 88, 28, 142, 100, 101, 52, 148, 147, 225, 166, 200, 114, 65, 225],
+    // Level 3
+    [190, 161, 97, 98, 114, 27, 202, 75, 110, 23, 130, 203, 220, 105, 90, 71, 21, 34, 21, 124, 103, 22, 245, 8, 219, 71, 197, 153, 25, 83, 64, 244],
+    // Level 4
+    [48, 118, 95, 239, 52, 27, 223, 231, 73, 195, 145, 191, 149, 106, 159, 3, 211, 99, 148, 27, 46, 184, 248, 90, 177, 107, 182, 235, 13, 60, 77, 239],
+    // Level 5
+    [241, 160, 167, 26, 101, 80, 196, 27, 200, 212, 218, 196, 241, 134, 182, 210, 116, 168, 57, 242, 249, 90, 217, 227, 187, 101, 29, 69, 129, 12, 90, 31],
+    // Level 6
+    [198, 152, 73, 7, 210, 229, 52, 150, 67, 81, 57, 59, 200, 95, 4, 55, 64, 101, 182, 56, 112, 204, 133, 156, 104, 25, 45, 239, 9, 12, 16, 23],
+    // Level 7
+    [220, 6, 6, 185, 6, 35, 141, 209, 87, 230, 156, 178, 97, 231, 86, 150, 0, 126, 77, 154, 63, 112, 122, 124, 225, 12, 228, 16, 208, 139, 207, 236],
+    // Level 8
+    [142, 75, 55, 69, 229, 242, 247, 212, 142, 54, 177, 146, 203, 57, 36, 47, 160, 247, 167, 111, 172, 30, 54, 165, 25, 216, 235, 224, 15, 62, 33, 251],
+    // Level 9
+    [189, 117, 43, 142, 118, 245, 248, 145, 229, 211, 161, 3, 82, 219, 243, 172, 37, 18, 59, 110, 180, 138, 58, 209, 84, 2, 12, 208, 200, 77, 33, 118],
+    // Level 10
+    [41, 57, 141, 72, 225, 161, 169, 243, 200, 224, 249, 123, 15, 140, 6, 109, 43, 234, 248, 138, 49, 155, 174, 240, 42, 84, 130, 214, 21, 126, 189, 43],
+    // Level 11
+    [118, 220, 148, 241, 135, 54, 37, 144, 246, 253, 47, 30, 43, 27, 140, 143, 6, 215, 67, 144, 128, 180, 164, 153, 174, 107, 38, 122, 177, 181, 204, 49],
+    // Level 12
+    [209, 0, 57, 159, 96, 123, 169, 86, 214, 135, 55, 4, 94, 169, 235, 62, 117, 71, 93, 250, 99, 59, 124, 171, 236, 240, 175, 160, 92, 159, 58, 248],
+    // Level 13
+    [62, 70, 34, 44, 9, 43, 155, 170, 90, 22, 223, 29, 194, 134, 76, 124, 181, 251, 121, 96, 157, 9, 106, 194, 161, 13, 100, 248, 36, 209, 199, 58],
+    // Level 14
+    [140, 242, 201, 189, 35, 105, 163, 185, 224, 187, 221, 44, 238, 99, 70, 5, 68, 10, 219, 45, 112, 177, 82, 99, 219, 231, 208, 209, 110, 191, 192, 61],
+    // Level 15
+    [251, 187, 148, 37, 135, 239, 37, 103, 56, 210, 187, 176, 80, 100, 35, 136, 2, 6, 207, 191, 162, 237, 77, 52, 13, 121, 107, 15, 126, 41, 9, 128],
+    // Level 16
+    [187, 200, 75, 177, 236, 169, 185, 139, 84, 103, 8, 202, 220, 248, 41, 152, 61, 208, 61, 161, 104, 246, 90, 234, 226, 23, 36, 0, 48, 114, 194, 26],
+    // Level 17
+    [102, 155, 3, 248, 236, 113, 83, 217, 249, 219, 119, 148, 73, 245, 4, 72, 153, 66, 53, 154, 171, 115, 77, 147, 132, 23, 136, 147, 148, 57, 75, 9],
+    // Level 18
+    [163, 246, 113, 74, 35, 200, 249, 185, 31, 179, 221, 110, 49, 27, 209, 207, 95, 139, 203, 54, 24, 222, 232, 174, 92, 215, 171, 14, 196, 12, 8, 118],
+    // Level 19
+    [11, 210, 17, 143, 189, 104, 250, 107, 182, 115, 53, 150, 187, 135, 159, 87, 27, 214, 72, 130, 31, 116, 55, 65, 157, 207, 73, 245, 7, 143, 29, 184],
+    // Level 20
+    [81, 194, 13, 102, 0, 128, 36, 192, 76, 241, 20, 86, 74, 153, 142, 73, 239, 143, 110, 4, 78, 45, 19, 160, 58, 102, 82, 29, 110, 32, 5, 3],
+];
@@ -0,0 +1,162 @@
+/*!
+ * Keccak256 Merkle trees
+ *
+ * Pools created with TREE_HASH_KECCAK keep their main tree in Keccak256, the
+ * hash the EVM exposes natively, so a Solidity contract (e.g. a bridge) can
+ * check roots and proofs of this program's trees. The byte layout matches
+ * the SHA256 scheme exactly, only the hash changes:
+ *
+ *   legacy leaf       data
+ *   legacy node       keccak256(left || right)
+ *   leaf              keccak256(0x00 || data)
+ *   indexed leaf      keccak256(0x00 || data || leaf_index as u32 little-endian)
+ *   node              keccak256(0x01 || left || right)
+ */
+
+use anchor_lang::solana_program::keccak;
+
+use crate::merkle::{MerkleHasher, LEAF_DOMAIN, MERKLE_TREE_DEPTH, NODE_DOMAIN};
+
+/// keccak256(left || right)
+pub fn keccak_hash_pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    keccak::hashv(&[left, right]).to_bytes()
+}
+
+/// keccak256(0x00 || data): a domain-separated leaf
+pub fn keccak_hash_leaf(data: &[u8; 32]) -> [u8; 32] {
+    keccak::hashv(&[&[LEAF_DOMAIN], data]).to_bytes()
+}
+
+/// keccak256(0x00 || data || leaf_index): a domain-separated leaf bound to its
+/// position, the index encoded as 4 little-endian bytes
+pub fn keccak_hash_indexed_leaf(data: &[u8; 32], leaf_index: u32) -> [u8; 32] {
+    keccak::hashv(&[&[LEAF_DOMAIN], data, &leaf_index.to_le_bytes()]).to_bytes()
+}
+
+/// keccak256(0x01 || left || right): a domain-separated internal node
+pub fn keccak_hash_node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    keccak::hashv(&[&[NODE_DOMAIN], left, right]).to_bytes()
+}
+
+/// Keccak256 trees, verifiable by EVM contracts
+pub struct KeccakHasher;
+
+impl MerkleHasher for KeccakHasher {
+    fn hash_pair(&self, left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+        keccak_hash_pair(left, right)
+    }
+
+    fn hash_leaf(&self, data: &[u8; 32]) -> [u8; 32] {
+        keccak_hash_leaf(data)
+    }
+
+    fn hash_indexed_leaf(&self, data: &[u8; 32], leaf_index: u32) -> [u8; 32] {
+        keccak_hash_indexed_leaf(data, leaf_index)
+    }
+
+    fn hash_node(&self, left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+        keccak_hash_node(left, right)
+    }
+
+    fn legacy_zero_values(&self) -> &'static [[u8; 32]; MERKLE_TREE_DEPTH + 1] {
+        &ZERO_VALUES
+    }
+
+    fn domain_zero_values(&self) -> &'static [[u8; 32]; MERKLE_TREE_DEPTH + 1] {
+        &DOMAIN_ZERO_VALUES
+    }
+}
+
+/// Zero values of legacy Keccak256 trees: zeros[i+1] = keccak256(zeros[i] || zeros[i])
+/// Level 1 is the well-known keccak256 of 64 zero bytes
+pub const ZERO_VALUES: [[u8; 32]; MERKLE_TREE_DEPTH + 1] = [
+    // Level 0
+    [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+    // Level 1
+    [173, 50, 40, 182, 118, 247, 211, 205, 66, 132, 165, 68, 63, 23, 241, 150, 43, 54, 228, 145, 179, 10, 64, 178, 64, 88, 73, 229, 151, 186, 95, 181],
+    // Level 2
+    [180, 193, 25, 81, 149, 124, 111, 143, 100, 44, 74, 246, 28, 214, 178, 70, 64, 254, 198, 220, 127, 198, 7, 238, 130, 6, 169, 158, 146, 65, 13, 48],
+    // Level 3
+    [33, 221, 185, 163, 86, 129, 92, 63, 172, 16, 38, 182, 222, 197, 223, 49, 36, 175, 186, 219, 72, 92, 155, 165, 163, 227, 57, 138, 4, 183, 186, 133],
+    // Level 4
+    [229, 135, 105, 179, 42, 27, 234, 241, 234, 39, 55, 90, 68, 9, 90, 13, 31, 182, 100, 206, 45, 211, 88, 231, 252, 191, 183, 140, 38, 161, 147, 68],
+    // Level 5
+    [14, 176, 30, 191, 201, 237, 39, 80, 12, 212, 223, 201, 121, 39, 45, 31, 9, 19, 204, 159, 102, 84, 13, 126, 128, 5, 129, 17, 9, 225, 207, 45],
+    // Level 6
+    [136, 124, 34, 189, 135, 80, 211, 64, 22, 172, 60, 102, 181, 255, 16, 45, 172, 221, 115, 246, 176, 20, 231, 16, 181, 30, 128, 34, 175, 154, 25, 104],
+    // Level 7
+    [255, 215, 1, 87, 228, 128, 99, 252, 51, 201, 122, 5, 15, 127, 100, 2, 51, 191, 100, 108, 201, 141, 149, 36, 198, 185, 43, 207, 58, 181, 111, 131],
+    // Level 8
+    [152, 103, 204, 95, 127, 25, 107, 147, 186, 225, 226, 126, 99, 32, 116, 36, 69, 210, 144, 242, 38, 56, 39, 73, 139, 84, 254, 197, 57, 247, 86, 175],
+    // Level 9
+    [206, 250, 212, 229, 8, 192, 152, 185, 167, 225, 216, 254, 177, 153, 85, 251, 2, 186, 150, 117, 88, 80, 120, 113, 9, 105, 211, 68, 15, 80, 84, 224],
+    // Level 10
+    [249, 220, 62, 127, 224, 22, 224, 80, 239, 242, 96, 51, 79, 24, 165, 212, 254, 57, 29, 130, 9, 35, 25, 245, 150, 79, 46, 46, 183, 193, 195, 165],
+    // Level 11
+    [248, 177, 58, 73, 226, 130, 246, 9, 195, 23, 168, 51, 251, 141, 151, 109, 17, 81, 124, 87, 29, 18, 33, 162, 101, 210, 90, 247, 120, 236, 248, 146],
+    // Level 12
+    [52, 144, 198, 206, 235, 69, 10, 236, 220, 130, 226, 130, 147, 3, 29, 16, 199, 215, 59, 248, 94, 87, 191, 4, 26, 151, 54, 10, 162, 197, 217, 156],
+    // Level 13
+    [193, 223, 130, 217, 196, 184, 116, 19, 234, 226, 239, 4, 143, 148, 180, 211, 85, 76, 234, 115, 217, 43, 15, 122, 249, 110, 2, 113, 198, 145, 226, 187],
+    // Level 14
+    [92, 103, 173, 215, 198, 202, 243, 2, 37, 106, 222, 223, 122, 177, 20, 218, 10, 207, 232, 112, 212, 73, 163, 164, 137, 247, 129, 214, 89, 232, 190, 204],
+    // Level 15
+    [218, 123, 206, 159, 78, 134, 24, 182, 189, 47, 65, 50, 206, 121, 140, 220, 122, 96, 231, 225, 70, 10, 114, 153, 227, 198, 52, 42, 87, 150, 38, 210],
+    // Level 16
+    [39, 51, 229, 15, 82, 110, 194, 250, 25, 162, 43, 49, 232, 237, 80, 242, 60, 209, 253, 249, 76, 145, 84, 237, 58, 118, 9, 162, 241, 255, 152, 31],
+    // Level 17
+    [225, 211, 181, 200, 7, 178, 129, 228, 104, 60, 198, 214, 49, 92, 249, 91, 154, 222, 134, 65, 222, 252, 179, 35, 114, 241, 193, 38, 227, 152, 239, 122],
+    // Level 18
+    [90, 45, 206, 10, 138, 127, 104, 187, 116, 86, 15, 143, 113, 131, 124, 44, 46, 187, 203, 247, 255, 251, 66, 174, 24, 150, 241, 63, 124, 116, 121, 160],
+    // Level 19
+    [180, 106, 40, 182, 245, 85, 64, 248, 148, 68, 246, 61, 224, 55, 142, 61, 18, 27, 224, 158, 6, 204, 157, 237, 28, 32, 230, 88, 118, 211, 106, 160],
+    // Level 20
+    [198, 94, 150, 69, 100, 71, 134, 182, 32, 226, 221, 42, 214, 72, 221, 252, 191, 74, 126, 91, 26, 58, 78, 207, 231, 246, 70, 103, 163, 240, 183, 226],
+];
+
+/// Zero values of domain-separated Keccak256 trees: the empty leaf stays zero
+/// and zeros[i+1] = keccak_hash_node(zeros[i], zeros[i])
+pub const DOMAIN_ZERO_VALUES: [[u8; 32]; MERKLE_TREE_DEPTH + 1] = [
+    // Level 0
+    [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+    // Level 1
+    [192, 122, 30, 139, 126, 0, 87, 103, 63, 220, 42, 255, 225, 144, 216, 169, 96, 197, 254, 97, 86, 99, 242, 123, 124, 232, 79, 61, 147, 239, 146, 166],
+    // Level 2
+    [253, 71, 81, 116, 116, 165, 151, 99, 125, 84, 3, 138, 6, 99, 209, 208, 59, 147, 27, 35, 141, 224, 107, 115, 227, 193, 44, 244, 67, 222, 110, 141],
+    // Level 3
+    [71, 168, 245, 232, 250, 112, 190, 39, 96, 55, 128, 103, 201, 198, 212, 16, 221, 150, 190, 7, 130, 11, 66, 48, 193, 18, 84, 199, 255, 16, 194, 152],
+    // Level 4
+    [174, 209, 156, 164, 191, 226, 54, 91, 27, 51, 250, 148, 116, 76, 208, 198, 162, 213, 80, 80, 108, 126, 126, 252, 7, 56, 121, 203, 121, 69, 155, 154],
+    // Level 5
+    [110, 105, 152, 167, 218, 139, 45, 181, 201, 142, 184, 83, 9, 157, 140, 174, 198, 55, 151, 181, 40, 59, 125, 172, 55, 178, 255, 182, 48, 168, 110, 36],
+    // Level 6
+    [24, 28, 25, 115, 91, 255, 35, 181, 91, 194, 149, 252, 11, 96, 193, 197, 199, 40, 130, 9, 178, 97, 160, 142, 38, 146, 69, 152, 206, 114, 64, 78],
+    // Level 7
+    [236, 180, 8, 178, 144, 171, 41, 32, 230, 54, 17, 239, 30, 140, 169, 100, 174, 187, 102, 234, 87, 57, 241, 157, 36, 185, 32, 148, 242, 142, 68, 248],
+    // Level 8
+    [41, 75, 249, 120, 94, 19, 145, 210, 77, 82, 171, 249, 21, 99, 106, 115, 189, 170, 18, 237, 41, 232, 94, 33, 218, 225, 76, 9, 208, 242, 227, 75],
+    // Level 9
+    [207, 126, 55, 169, 52, 104, 62, 222, 199, 149, 227, 82, 157, 184, 250, 192, 134, 53, 25, 194, 65, 155, 162, 76, 15, 110, 78, 250, 134, 236, 125, 26],
+    // Level 10
+    [114, 206, 72, 204, 169, 188, 116, 63, 237, 132, 160, 189, 192, 10, 210, 197, 181, 64, 50, 56, 73, 201, 130, 246, 113, 169, 189, 141, 82, 209, 87, 25],
+    // Level 11
+    [14, 45, 125, 11, 105, 95, 220, 217, 220, 108, 84, 49, 58, 230, 82, 33, 131, 117, 195, 53, 141, 52, 173, 227, 220, 196, 157, 218, 5, 192, 219, 16],
+    // Level 12
+    [160, 167, 134, 167, 29, 36, 0, 122, 35, 245, 206, 33, 230, 153, 122, 48, 91, 174, 239, 175, 52, 137, 55, 168, 183, 73, 116, 203, 3, 156, 16, 200],
+    // Level 13
+    [6, 40, 203, 100, 150, 25, 62, 106, 85, 236, 228, 168, 36, 3, 132, 225, 66, 39, 62, 235, 156, 196, 189, 174, 104, 15, 151, 31, 209, 146, 159, 16],
+    // Level 14
+    [44, 59, 131, 58, 60, 169, 214, 240, 32, 85, 135, 22, 85, 221, 24, 240, 3, 162, 121, 248, 74, 51, 75, 246, 5, 8, 153, 169, 188, 28, 237, 195],
+    // Level 15
+    [26, 108, 94, 159, 216, 153, 239, 202, 100, 194, 172, 166, 36, 52, 52, 180, 35, 215, 145, 249, 42, 131, 156, 12, 146, 206, 10, 90, 81, 160, 114, 52],
+    // Level 16
+    [169, 148, 43, 15, 171, 223, 155, 243, 34, 65, 248, 36, 94, 178, 103, 55, 255, 189, 145, 130, 42, 196, 132, 174, 133, 127, 195, 93, 33, 251, 101, 47],
+    // Level 17
+    [126, 66, 6, 161, 16, 168, 114, 28, 198, 132, 85, 195, 84, 0, 176, 86, 37, 19, 152, 223, 207, 152, 176, 74, 238, 225, 149, 73, 191, 210, 18, 47],
+    // Level 18
+    [191, 180, 41, 191, 235, 179, 170, 39, 0, 18, 216, 6, 56, 150, 210, 5, 191, 216, 206, 17, 110, 74, 48, 67, 53, 189, 89, 233, 71, 31, 159, 204],
+    // Level 19
+    [226, 47, 168, 255, 242, 140, 251, 31, 54, 100, 56, 44, 237, 109, 63, 29, 99, 78, 242, 133, 28, 16, 177, 220, 225, 140, 87, 201, 87, 152, 91, 249],
+    // Level 20
+    [210, 155, 242, 49, 227, 10, 251, 92, 209, 188, 1, 210, 20, 233, 5, 198, 12, 135, 83, 59, 249, 103, 169, 72, 119, 179, 132, 59, 176, 189, 239, 93],
+];
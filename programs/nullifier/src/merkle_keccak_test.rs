@@ -0,0 +1,52 @@
+/// Tests for the Keccak256 Merkle tree hashes
+use super::merkle::{
+    hash_node, MerkleHasher, CURRENT_POOL_VERSION, POOL_VERSION_DOMAIN_SEPARATED, POOL_VERSION_LEGACY,
+};
+use super::merkle_keccak::*;
+
+#[test]
+fn test_keccak_zero_values_constant() {
+    assert_eq!(ZERO_VALUES, KeccakHasher.compute_zero_values(POOL_VERSION_LEGACY));
+    assert_eq!(DOMAIN_ZERO_VALUES, KeccakHasher.compute_zero_values(POOL_VERSION_DOMAIN_SEPARATED));
+}
+
+#[test]
+fn test_keccak_matches_evm_keccak256() {
+    // keccak256(abi.encodePacked(bytes32(0), bytes32(0)))
+    let expected: [u8; 32] = [
+        0xad, 0x32, 0x28, 0xb6, 0x76, 0xf7, 0xd3, 0xcd, 0x42, 0x84, 0xa5, 0x44, 0x3f, 0x17, 0xf1,
+        0x96, 0x2b, 0x36, 0xe4, 0x91, 0xb3, 0x0a, 0x40, 0xb2, 0x40, 0x58, 0x49, 0xe5, 0x97, 0xba,
+        0x5f, 0xb5,
+    ];
+
+    assert_eq!(keccak_hash_pair(&[0u8; 32], &[0u8; 32]), expected);
+    assert_eq!(ZERO_VALUES[1], expected);
+}
+
+#[test]
+fn test_keccak_domain_separation() {
+    let left = [1u8; 32];
+    let right = [2u8; 32];
+
+    assert_ne!(keccak_hash_node(&left, &right), keccak_hash_pair(&left, &right));
+    assert_ne!(keccak_hash_leaf(&left), keccak_hash_indexed_leaf(&left, 0));
+    assert_ne!(keccak_hash_indexed_leaf(&left, 0), keccak_hash_indexed_leaf(&left, 1));
+
+    // Distinct from the SHA256 tree with the same layout
+    assert_ne!(keccak_hash_node(&left, &right), hash_node(&left, &right));
+}
+
+#[test]
+fn test_keccak_proof_verifies() {
+    let data = [5u8; 32];
+    let version = CURRENT_POOL_VERSION;
+
+    // data at index 2 of a depth-2 tree whose other leaves are empty
+    let leaf = KeccakHasher.leaf_hash(version, &data, 2);
+    let zeros = KeccakHasher.zero_values(version);
+    let root = keccak_hash_node(&zeros[1], &keccak_hash_node(&leaf, &zeros[0]));
+
+    let path = [zeros[0], zeros[1]];
+    assert!(KeccakHasher.verify_proof(version, &data, &path, &[false, true], 2, &root).unwrap());
+    assert!(!KeccakHasher.verify_proof(version, &data, &path, &[true, true], 2, &root).unwrap());
+}
@@ -1,56 +1,164 @@
 use anchor_lang::prelude::*;
-use light_poseidon::{Poseidon, PoseidonHasher};
 use ark_bn254::Fr;
-use ark_ff::{PrimeField, BigInteger};
 
-pub const MERKLE_TREE_DEPTH: usize = 20;
+use crate::bn254::{fr_from_le_bytes as bytes_to_field, fr_to_le_bytes as field_to_bytes};
+use crate::merkle::{fold_merkle_path, MerkleHasher, LEAF_DOMAIN, NODE_DOMAIN};
+use crate::MixerError;
 
-/// Convert bytes to field element
-fn bytes_to_field(bytes: &[u8; 32]) -> Fr {
-    // Convert bytes to a field element
-    // We need to ensure the value fits in the field
-    Fr::from_le_bytes_mod_order(bytes)
+pub const MERKLE_TREE_DEPTH: usize = crate::merkle::MERKLE_TREE_DEPTH;
+
+/// Poseidon hash (circom parameters) of field elements, as little-endian bytes
+pub fn poseidon_hashv(inputs: &[Fr]) -> [u8; 32] {
+    #[cfg(not(feature = "software-poseidon"))]
+    let hash = syscall_hash(inputs);
+    #[cfg(feature = "software-poseidon")]
+    let hash = software_hash(inputs);
+
+    hash
 }
 
-/// Convert field element to bytes
-fn field_to_bytes(field: &Fr) -> [u8; 32] {
-    let mut bytes = [0u8; 32];
-    let field_bytes = field.into_bigint().to_bytes_le();
-    bytes[..field_bytes.len()].copy_from_slice(&field_bytes);
-    bytes
+/// Hash through the sol_poseidon syscall
+/// Off-chain, solana-program computes the same hash in software
+#[cfg(not(feature = "software-poseidon"))]
+fn syscall_hash(inputs: &[Fr]) -> [u8; 32] {
+    use anchor_lang::solana_program::poseidon::{hashv, Endianness, Parameters};
+
+    let bytes: Vec<[u8; 32]> = inputs.iter().map(field_to_bytes).collect();
+    let slices: Vec<&[u8]> = bytes.iter().map(|input| input.as_slice()).collect();
+
+    hashv(Parameters::Bn254X5, Endianness::LittleEndian, &slices)
+        .expect("Failed to compute Poseidon hash")
+        .to_bytes()
 }
 
-/// Poseidon hash of two field elements
-/// NOTE: Stack overflow issue on Solana BPF - will be fixed in Phase 2 with ZK integration
-/// For Phase 1, use SHA256 merkle implementation
-pub fn poseidon_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
-    let left_field = bytes_to_field(left);
-    let right_field = bytes_to_field(right);
+/// Hash with light-poseidon, for builds without the syscall
+#[cfg(feature = "software-poseidon")]
+fn software_hash(inputs: &[Fr]) -> [u8; 32] {
+    use light_poseidon::{Poseidon, PoseidonHasher};
 
-    let mut hasher = Poseidon::<Fr>::new_circom(2)
-        .expect("Failed to create Poseidon hasher with width=2");
-    let hash = hasher.hash(&[left_field, right_field])
+    let mut hasher = Poseidon::<Fr>::new_circom(inputs.len())
+        .expect("Failed to create Poseidon hasher");
+    let hash = hasher.hash(inputs)
         .expect("Failed to compute Poseidon hash");
 
     field_to_bytes(&hash)
 }
 
+/// Poseidon hash of two field elements
+pub fn poseidon_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    poseidon_hashv(&[bytes_to_field(left), bytes_to_field(right)])
+}
+
 /// Hash secret and nullifier to create commitment
 pub fn poseidon_commitment(secret: &[u8; 32], nullifier: &[u8; 32]) -> [u8; 32] {
     poseidon_hash(secret, nullifier)
 }
 
+/// Encode a unix timestamp as a little-endian field element
+pub fn timestamp_to_field_bytes(timestamp: i64) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    bytes[..8].copy_from_slice(&timestamp.to_le_bytes());
+    bytes
+}
+
+/// Hash commitment and deposit timestamp to create the tree leaf
+/// The circuit opens this leaf to prove the note's deposit age
+pub fn poseidon_leaf(commitment: &[u8; 32], deposit_timestamp: i64) -> [u8; 32] {
+    poseidon_hash(commitment, &timestamp_to_field_bytes(deposit_timestamp))
+}
+
+/// Encode a pool pubkey as a little-endian field element (reduced mod r by the hasher)
+pub fn pool_to_field_bytes(pool: &Pubkey) -> [u8; 32] {
+    pool.to_bytes()
+}
+
+/// Encode a circuit version as a little-endian field element
+pub fn circuit_version_to_field_bytes(circuit_version: u8) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    bytes[0] = circuit_version;
+    bytes
+}
+
 /// Hash nullifier to create nullifier hash (public)
-/// NOTE: Stack overflow issue on Solana BPF - will be fixed in Phase 2 with ZK integration
-pub fn poseidon_nullifier_hash(nullifier: &[u8; 32]) -> [u8; 32] {
-    let nullifier_field = bytes_to_field(nullifier);
+/// The pool and circuit version are hashed in so the same note can't produce
+/// a spendable nullifier hash in another pool or under another circuit
+pub fn poseidon_nullifier_hash(
+    nullifier: &[u8; 32],
+    pool: &Pubkey,
+    circuit_version: u8,
+) -> [u8; 32] {
+    poseidon_hashv(&[
+        bytes_to_field(nullifier),
+        bytes_to_field(&pool_to_field_bytes(pool)),
+        bytes_to_field(&circuit_version_to_field_bytes(circuit_version)),
+    ])
+}
 
-    let mut hasher = Poseidon::<Fr>::new_circom(1)
-        .expect("Failed to create Poseidon hasher with width=1");
-    let hash = hasher.hash(&[nullifier_field])
-        .expect("Failed to compute Poseidon nullifier hash");
+/// Poseidon(0, data): a domain-separated leaf
+pub fn poseidon_hash_leaf(data: &[u8; 32]) -> [u8; 32] {
+    poseidon_hashv(&[Fr::from(LEAF_DOMAIN as u64), bytes_to_field(data)])
+}
 
-    field_to_bytes(&hash)
+/// Poseidon(0, data, leaf_index): a domain-separated leaf bound to its position
+pub fn poseidon_hash_indexed_leaf(data: &[u8; 32], leaf_index: u32) -> [u8; 32] {
+    poseidon_hashv(&[
+        Fr::from(LEAF_DOMAIN as u64),
+        bytes_to_field(data),
+        Fr::from(leaf_index as u64),
+    ])
+}
+
+/// Poseidon(1, left, right): a domain-separated internal node
+pub fn poseidon_hash_node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    poseidon_hashv(&[
+        Fr::from(NODE_DOMAIN as u64),
+        bytes_to_field(left),
+        bytes_to_field(right),
+    ])
+}
+
+/// Poseidon trees, as checked by the withdrawal circuit
+pub struct PoseidonHasher;
+
+impl MerkleHasher for PoseidonHasher {
+    fn hash_pair(&self, left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+        poseidon_hash(left, right)
+    }
+
+    fn hash_leaf(&self, data: &[u8; 32]) -> [u8; 32] {
+        poseidon_hash_leaf(data)
+    }
+
+    fn hash_indexed_leaf(&self, data: &[u8; 32], leaf_index: u32) -> [u8; 32] {
+        poseidon_hash_indexed_leaf(data, leaf_index)
+    }
+
+    fn hash_node(&self, left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+        poseidon_hash_node(left, right)
+    }
+
+    fn legacy_zero_values(&self) -> &'static [[u8; 32]; MERKLE_TREE_DEPTH + 1] {
+        &ZERO_VALUES
+    }
+
+    fn domain_zero_values(&self) -> &'static [[u8; 32]; MERKLE_TREE_DEPTH + 1] {
+        &DOMAIN_ZERO_VALUES
+    }
+}
+
+/// Leaf stored at `leaf_index` in a Poseidon tree of the given pool version
+pub fn poseidon_leaf_hash(version: u8, data: &[u8; 32], leaf_index: u32) -> [u8; 32] {
+    PoseidonHasher.leaf_hash(version, data, leaf_index)
+}
+
+/// Internal node of a Poseidon tree of the given pool version
+pub fn poseidon_node_hash(version: u8, left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    PoseidonHasher.node_hash(version, left, right)
+}
+
+/// Empty-subtree values of a Poseidon tree of the given pool version
+pub fn zero_values(version: u8) -> &'static [[u8; 32]; MERKLE_TREE_DEPTH + 1] {
+    PoseidonHasher.zero_values(version)
 }
 
 /// Compute zero values for empty tree nodes
@@ -68,29 +176,84 @@ pub fn compute_zero_values() -> [[u8; 32]; MERKLE_TREE_DEPTH + 1] {
     zeros
 }
 
-/// Precomputed zero values (run compute_zero_values() to generate)
+/// Precomputed zero values, little-endian like poseidon_hash output
+/// Embedded so on-chain code never rehashes them; test_zero_values_constant
+/// checks the table against compute_zero_values()
 pub const ZERO_VALUES: [[u8; 32]; MERKLE_TREE_DEPTH + 1] = [
     [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // Level 0
-    [0x20, 0x98, 0xf5, 0xfb, 0x9e, 0x23, 0x9e, 0xab, 0x3c, 0xea, 0xc3, 0xf2, 0x7b, 0x81, 0xe4, 0x81, 0xdc, 0x31, 0x24, 0xd5, 0x5f, 0xfe, 0xd5, 0x23, 0xa8, 0x39, 0xee, 0x84, 0x46, 0xb6, 0x48, 0x64], // Level 1
-    [0x10, 0x69, 0x67, 0x3d, 0xcd, 0xb1, 0x22, 0x63, 0xdf, 0x30, 0x1a, 0x6f, 0xf5, 0x84, 0xa7, 0xec, 0x26, 0x1a, 0x44, 0xcb, 0x9d, 0xc6, 0x8d, 0xf0, 0x67, 0xa4, 0x77, 0x44, 0x60, 0xb1, 0xf1, 0xe1], // Level 2
-    [0x18, 0xf4, 0x33, 0x31, 0x53, 0x7e, 0xe2, 0xaf, 0x2e, 0x3d, 0x75, 0x8d, 0x50, 0xf7, 0x21, 0x06, 0x46, 0x7c, 0x6e, 0xea, 0x50, 0x37, 0x1d, 0xd5, 0x28, 0xd5, 0x7e, 0xb2, 0xb8, 0x56, 0xd2, 0x38], // Level 3
-    [0x07, 0xf9, 0xd8, 0x37, 0xcb, 0x17, 0xb0, 0xd3, 0x63, 0x20, 0xff, 0xe9, 0x3b, 0xa5, 0x23, 0x45, 0xf1, 0xb7, 0x28, 0x57, 0x1a, 0x56, 0x82, 0x65, 0xca, 0xac, 0x97, 0x55, 0x9d, 0xbc, 0x95, 0x2a], // Level 4
-    [0x2b, 0x94, 0xcf, 0x5e, 0x87, 0x46, 0xb3, 0xf5, 0xc9, 0x63, 0x1f, 0x4c, 0x5d, 0xf3, 0x29, 0x07, 0xa6, 0x99, 0xc5, 0x8c, 0x94, 0xb2, 0xad, 0x4d, 0x7b, 0x5c, 0xec, 0x16, 0x39, 0x18, 0x3f, 0x55], // Level 5
-    [0x2d, 0xee, 0x93, 0xc5, 0xa6, 0x66, 0x45, 0x96, 0x46, 0xea, 0x7d, 0x22, 0xcc, 0xa9, 0xe1, 0xbc, 0xfe, 0xd7, 0x1e, 0x69, 0x51, 0xb9, 0x53, 0x61, 0x1d, 0x11, 0xdd, 0xa3, 0x2e, 0xa0, 0x9d, 0x78], // Level 6
-    [0x07, 0x82, 0x95, 0xe5, 0xa2, 0x2b, 0x84, 0xe9, 0x82, 0xcf, 0x60, 0x1e, 0xb6, 0x39, 0x59, 0x7b, 0x8b, 0x05, 0x15, 0xa8, 0x8c, 0xb5, 0xac, 0x7f, 0xa8, 0xa4, 0xaa, 0xbe, 0x3c, 0x87, 0x34, 0x9d], // Level 7
-    [0x2f, 0xa5, 0xe5, 0xf1, 0x8f, 0x60, 0x27, 0xa6, 0x50, 0x1b, 0xec, 0x86, 0x45, 0x64, 0x47, 0x2a, 0x61, 0x6b, 0x2e, 0x27, 0x4a, 0x41, 0x21, 0x1a, 0x44, 0x4c, 0xbe, 0x3a, 0x99, 0xf3, 0xcc, 0x61], // Level 8
-    [0x0e, 0x88, 0x43, 0x76, 0xd0, 0xd8, 0xfd, 0x21, 0xec, 0xb7, 0x80, 0x38, 0x9e, 0x94, 0x1f, 0x66, 0xe4, 0x5e, 0x7a, 0xcc, 0xe3, 0xe2, 0x28, 0xab, 0x3e, 0x21, 0x56, 0xa6, 0x14, 0xfc, 0xd7, 0x47], // Level 9
-    [0x1b, 0x72, 0x01, 0xda, 0x72, 0x49, 0x4f, 0x1e, 0x28, 0x71, 0x7a, 0xd1, 0xa5, 0x2e, 0xb4, 0x69, 0xf9, 0x58, 0x92, 0xf9, 0x57, 0x71, 0x35, 0x33, 0xde, 0x61, 0x75, 0xe5, 0xda, 0x19, 0x0a, 0xf2], // Level 10
-    [0x1f, 0x8d, 0x88, 0x22, 0x72, 0x5e, 0x36, 0x38, 0x52, 0x00, 0xc0, 0xb2, 0x01, 0x24, 0x98, 0x19, 0xa6, 0xe6, 0xe1, 0xe4, 0x65, 0x08, 0x08, 0xb5, 0xbe, 0xbc, 0x6b, 0xfa, 0xce, 0x7d, 0x76, 0x36], // Level 11
-    [0x2c, 0x5d, 0x82, 0xf6, 0x6c, 0x91, 0x4b, 0xaf, 0xb9, 0x70, 0x15, 0x89, 0xba, 0x8c, 0xfc, 0xfb, 0x61, 0x62, 0xb0, 0xa1, 0x2a, 0xcf, 0x88, 0xa8, 0xd0, 0x87, 0x9a, 0x04, 0x71, 0xb5, 0xf8, 0x5a], // Level 12
-    [0x14, 0xc5, 0x41, 0x48, 0xa0, 0x94, 0x0b, 0xb8, 0x20, 0x95, 0x7f, 0x5a, 0xdf, 0x3f, 0xa1, 0x13, 0x4e, 0xf5, 0xc4, 0xaa, 0xa1, 0x13, 0xf4, 0x64, 0x64, 0x58, 0xf2, 0x70, 0xe0, 0xbf, 0xbf, 0xd0], // Level 13
-    [0x19, 0x0d, 0x33, 0xb1, 0x2f, 0x98, 0x6f, 0x96, 0x1e, 0x10, 0xc0, 0xee, 0x44, 0xd8, 0xb9, 0xaf, 0x11, 0xbe, 0x25, 0x58, 0x8c, 0xad, 0x89, 0xd4, 0x16, 0x11, 0x8e, 0x4b, 0xf4, 0xeb, 0xe8, 0x0c], // Level 14
-    [0x22, 0xf9, 0x8a, 0xa9, 0xce, 0x70, 0x41, 0x52, 0xac, 0x17, 0x35, 0x49, 0x14, 0xad, 0x73, 0xed, 0x11, 0x67, 0xae, 0x65, 0x96, 0xaf, 0x51, 0x0a, 0xa5, 0xb3, 0x64, 0x93, 0x25, 0xe0, 0x6c, 0x92], // Level 15
-    [0x2a, 0x7c, 0x7c, 0x9b, 0x6c, 0xe5, 0x88, 0x0b, 0x9f, 0x6f, 0x22, 0x8d, 0x72, 0xbf, 0x6a, 0x57, 0x5a, 0x52, 0x6f, 0x29, 0xc6, 0x6e, 0xcc, 0xee, 0xf8, 0xb7, 0x53, 0xd3, 0x8b, 0xba, 0x73, 0x23], // Level 16
-    [0x2e, 0x81, 0x86, 0xe5, 0x58, 0x69, 0x8e, 0xc1, 0xc6, 0x7a, 0xf9, 0xc1, 0x4d, 0x46, 0x3f, 0xfc, 0x47, 0x00, 0x43, 0xc9, 0xc2, 0x98, 0x8b, 0x95, 0x4d, 0x75, 0xdd, 0x64, 0x3f, 0x36, 0xb9, 0x92], // Level 17
-    [0x0f, 0x57, 0xc5, 0x57, 0x1e, 0x9a, 0x4e, 0xab, 0x49, 0xe2, 0xc8, 0xcf, 0x05, 0x0d, 0xae, 0x94, 0x8a, 0xef, 0x6e, 0xad, 0x64, 0x73, 0x92, 0x27, 0x35, 0x46, 0x24, 0x9d, 0x1c, 0x1f, 0xf1, 0x0f], // Level 18
-    [0x18, 0x30, 0xee, 0x67, 0xb5, 0xfb, 0x55, 0x4a, 0xd5, 0xf6, 0x3d, 0x43, 0x88, 0x80, 0x0e, 0x1c, 0xfe, 0x78, 0xe3, 0x10, 0x69, 0x7d, 0x46, 0xe4, 0x3c, 0x9c, 0xe3, 0x61, 0x34, 0xf7, 0x2c, 0xca], // Level 19
-    [0x21, 0x34, 0xe7, 0x6a, 0xc5, 0xd2, 0x1a, 0xab, 0x18, 0x6c, 0x2b, 0xe1, 0xdd, 0x8f, 0x84, 0xee, 0x88, 0x0a, 0x1e, 0x46, 0xea, 0xf7, 0x12, 0xf9, 0xd3, 0x71, 0xb6, 0xdf, 0x22, 0x19, 0x1f, 0x3e], // Level 20
+    [0x64, 0x48, 0xb6, 0x46, 0x84, 0xee, 0x39, 0xa8, 0x23, 0xd5, 0xfe, 0x5f, 0xd5, 0x24, 0x31, 0xdc, 0x81, 0xe4, 0x81, 0x7b, 0xf2, 0xc3, 0xea, 0x3c, 0xab, 0x9e, 0x23, 0x9e, 0xfb, 0xf5, 0x98, 0x20], // Level 1
+    [0xe1, 0xf1, 0xb1, 0x60, 0x44, 0x77, 0xa4, 0x67, 0xf0, 0x8d, 0xc6, 0x9d, 0xcb, 0x44, 0x1a, 0x26, 0xec, 0xa7, 0x84, 0xf5, 0x6f, 0x1a, 0x30, 0xdf, 0x63, 0x22, 0xb1, 0xcd, 0x3d, 0x67, 0x69, 0x10], // Level 2
+    [0x38, 0xd2, 0x56, 0xb8, 0xb2, 0x7e, 0xd5, 0x28, 0xd5, 0x1d, 0x37, 0x50, 0xea, 0x6e, 0x7c, 0x46, 0x06, 0x21, 0xf7, 0x50, 0x8d, 0x75, 0x3d, 0x2e, 0xaf, 0xe2, 0x7e, 0x53, 0x31, 0x33, 0xf4, 0x18], // Level 3
+    [0x2a, 0x95, 0xbc, 0x9d, 0x55, 0x97, 0xac, 0xca, 0x65, 0x82, 0x56, 0x1a, 0x57, 0x28, 0xb7, 0xf1, 0x45, 0x23, 0xa5, 0x3b, 0xe9, 0xff, 0x20, 0x63, 0xd3, 0xb0, 0x17, 0xcb, 0x37, 0xd8, 0xf9, 0x07], // Level 4
+    [0x55, 0x3f, 0x18, 0x39, 0x16, 0xec, 0x5c, 0x7b, 0x4d, 0xad, 0xb2, 0x94, 0x8c, 0xc5, 0x99, 0xa6, 0x07, 0x29, 0xf3, 0x5d, 0x4c, 0x1f, 0x63, 0xc9, 0xf5, 0xb3, 0x46, 0x87, 0x5e, 0xcf, 0x94, 0x2b], // Level 5
+    [0x78, 0x9d, 0xa0, 0x2e, 0xa3, 0xdd, 0x11, 0x1d, 0x61, 0x53, 0xb9, 0x51, 0x69, 0x1e, 0xd7, 0xfe, 0xbc, 0xe1, 0xa9, 0xcc, 0x22, 0x7d, 0xea, 0x46, 0x96, 0x45, 0x66, 0xa6, 0xc5, 0x93, 0xee, 0x2d], // Level 6
+    [0x9d, 0x34, 0x87, 0x3c, 0xbe, 0xaa, 0xa4, 0xa8, 0x7f, 0xac, 0xb5, 0x8c, 0xa8, 0x15, 0x05, 0x8b, 0x7b, 0x59, 0x39, 0xb6, 0x1e, 0x60, 0xcf, 0x82, 0xe9, 0x84, 0x2b, 0xa2, 0xe5, 0x95, 0x82, 0x07], // Level 7
+    [0x61, 0xcc, 0xf3, 0x99, 0x3a, 0xbe, 0x4c, 0x44, 0x1a, 0x21, 0x41, 0x4a, 0x27, 0x2e, 0x6b, 0x61, 0x2a, 0x47, 0x64, 0x45, 0x86, 0xec, 0x1b, 0x50, 0xa6, 0x27, 0x60, 0x8f, 0xf1, 0xe5, 0xa5, 0x2f], // Level 8
+    [0x47, 0xd7, 0xfc, 0x14, 0xa6, 0x56, 0x21, 0x3e, 0xab, 0x28, 0xe2, 0xe3, 0xcc, 0x7a, 0x5e, 0xe4, 0x66, 0x1f, 0x94, 0x9e, 0x38, 0x80, 0xb7, 0xec, 0x21, 0xfd, 0xd8, 0xd0, 0x76, 0x43, 0x88, 0x0e], // Level 9
+    [0xf2, 0x0a, 0x19, 0xda, 0xe5, 0x75, 0x61, 0xde, 0x33, 0x35, 0x71, 0x57, 0xf9, 0x92, 0x58, 0xf9, 0x69, 0xb4, 0x2e, 0xa5, 0xd1, 0x7a, 0x71, 0x28, 0x1e, 0x4f, 0x49, 0x72, 0xda, 0x01, 0x72, 0x1b], // Level 10
+    [0x36, 0x76, 0x7d, 0xce, 0xfa, 0x6b, 0xbc, 0xbe, 0xb5, 0x08, 0x08, 0x65, 0xe4, 0xe1, 0xe6, 0xa6, 0x19, 0x98, 0x24, 0x01, 0xb2, 0xc0, 0x00, 0x52, 0x38, 0x36, 0x5e, 0x72, 0x22, 0x88, 0x8d, 0x1f], // Level 11
+    [0x5a, 0xf8, 0xb5, 0x71, 0x04, 0x9a, 0x87, 0xd0, 0xa8, 0x88, 0xcf, 0x2a, 0xa1, 0xb0, 0x62, 0x61, 0xfb, 0xfc, 0x8c, 0xba, 0x89, 0x15, 0x70, 0xb9, 0xaf, 0x4b, 0x91, 0x6c, 0xf6, 0x82, 0x5d, 0x2c], // Level 12
+    [0xd0, 0xbf, 0xbf, 0xe0, 0x70, 0xf2, 0x58, 0x64, 0x64, 0xf4, 0x13, 0xa1, 0xaa, 0xc4, 0xf5, 0x4e, 0x13, 0xa1, 0x3f, 0xdf, 0x5a, 0x7f, 0x95, 0x20, 0xb8, 0x0b, 0x94, 0xa0, 0x48, 0x41, 0xc5, 0x14], // Level 13
+    [0x0c, 0xe8, 0xeb, 0xf4, 0x4b, 0x8e, 0x11, 0x16, 0xd4, 0x89, 0xad, 0x8c, 0x58, 0x25, 0xbe, 0x11, 0xaf, 0xb9, 0xd8, 0x44, 0xee, 0xc0, 0x10, 0x1e, 0x96, 0x6f, 0x98, 0x2f, 0xb1, 0x33, 0x0d, 0x19], // Level 14
+    [0x92, 0x6c, 0xe0, 0x25, 0x93, 0x64, 0xb3, 0xa5, 0x0a, 0x51, 0xaf, 0x96, 0x65, 0xae, 0x67, 0x11, 0xed, 0x73, 0xad, 0x14, 0x49, 0x35, 0x17, 0xac, 0x52, 0x41, 0x70, 0xce, 0xa9, 0x8a, 0xf9, 0x22], // Level 15
+    [0x23, 0x73, 0xba, 0x8b, 0xd3, 0x53, 0xb7, 0xf8, 0xee, 0xcc, 0x6e, 0xc6, 0x29, 0x6f, 0x52, 0x5a, 0x57, 0x6a, 0xbf, 0x72, 0x8d, 0x22, 0x6f, 0x9f, 0x0b, 0x88, 0xe5, 0x6c, 0x9b, 0x7c, 0x7c, 0x2a], // Level 16
+    [0x92, 0xb9, 0x36, 0x3f, 0x64, 0xdd, 0x75, 0x4d, 0x95, 0x8b, 0x98, 0xc2, 0xc9, 0x43, 0x00, 0x47, 0xfc, 0x3f, 0x46, 0x4d, 0xc1, 0xf9, 0x7a, 0xc6, 0xc1, 0x8e, 0x69, 0x58, 0xe5, 0x86, 0x81, 0x2e], // Level 17
+    [0x0f, 0xf1, 0x1f, 0x1c, 0x9d, 0x24, 0x46, 0x35, 0x27, 0x92, 0x73, 0x64, 0xad, 0x6e, 0xef, 0x8a, 0x94, 0xae, 0x0d, 0x05, 0xcf, 0xc8, 0xe2, 0x49, 0xab, 0x4e, 0x9a, 0x1e, 0x57, 0xc5, 0x57, 0x0f], // Level 18
+    [0xca, 0x2c, 0xf7, 0x34, 0x61, 0xe3, 0x9c, 0x3c, 0xe4, 0x46, 0x7d, 0x69, 0x10, 0xe3, 0x78, 0xfe, 0x1c, 0x0e, 0x80, 0x88, 0x43, 0x3d, 0xf6, 0xd5, 0x4a, 0x55, 0xfb, 0xb5, 0x67, 0xee, 0x30, 0x18], // Level 19
+    [0x3e, 0x1f, 0x19, 0x22, 0xdf, 0xb6, 0x71, 0xd3, 0xf9, 0x12, 0xf7, 0xea, 0x46, 0x1e, 0x0a, 0x88, 0xee, 0x84, 0x8f, 0xdd, 0xe1, 0x2b, 0x6c, 0x18, 0xab, 0x1a, 0xd2, 0xc5, 0x6a, 0xe7, 0x34, 0x21], // Level 20
+];
+
+/// Compute zero values of a Poseidon tree of the given pool version
+pub fn compute_zero_values_for_version(version: u8) -> [[u8; 32]; MERKLE_TREE_DEPTH + 1] {
+    PoseidonHasher.compute_zero_values(version)
+}
+
+/// Zero values of domain-separated Poseidon trees, little-endian
+/// test_domain_zero_values_constant checks the table against
+/// compute_zero_values_for_version()
+pub const DOMAIN_ZERO_VALUES: [[u8; 32]; MERKLE_TREE_DEPTH + 1] = [
+    // Level 0
+    [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+    // Level 1
+    [21, 131, 169, 38, 247, 54, 79, 166, 33, 130, 23, 107, 112, 195, 238, 142, 63, 79, 215, 0, 206, 205, 124, 218, 129, 14, 3, 122, 227, 58, 20, 36],
+    // Level 2
+    [72, 231, 122, 198, 63, 10, 58, 29, 166, 161, 133, 173, 168, 160, 69, 145, 249, 188, 243, 231, 158, 199, 168, 153, 218, 205, 201, 117, 9, 243, 214, 14],
+    // Level 3
+    [210, 123, 106, 249, 9, 4, 122, 123, 50, 219, 194, 65, 39, 14, 3, 154, 55, 49, 50, 138, 242, 46, 71, 162, 69, 111, 152, 220, 132, 111, 217, 28],
+    // Level 4
+    [174, 170, 148, 4, 209, 13, 122, 73, 120, 151, 78, 245, 67, 157, 145, 221, 69, 139, 115, 28, 44, 4, 221, 113, 87, 106, 187, 221, 165, 157, 43, 15],
+    // Level 5
+    [78, 132, 175, 64, 75, 148, 164, 96, 70, 213, 172, 90, 161, 77, 85, 165, 236, 246, 155, 159, 249, 164, 126, 77, 27, 200, 141, 33, 227, 214, 175, 23],
+    // Level 6
+    [232, 167, 140, 76, 88, 82, 132, 107, 232, 51, 246, 105, 32, 92, 175, 102, 144, 149, 164, 101, 237, 114, 108, 142, 249, 75, 198, 247, 135, 66, 37, 23],
+    // Level 7
+    [24, 26, 248, 129, 100, 206, 25, 242, 238, 195, 129, 228, 9, 1, 224, 192, 192, 212, 234, 51, 214, 28, 223, 163, 95, 63, 38, 158, 81, 182, 184, 36],
+    // Level 8
+    [224, 131, 63, 78, 237, 90, 15, 146, 78, 176, 7, 131, 19, 172, 111, 174, 37, 117, 109, 193, 77, 16, 248, 30, 215, 54, 157, 17, 152, 224, 178, 27],
+    // Level 9
+    [126, 164, 90, 121, 181, 182, 85, 207, 157, 74, 5, 19, 58, 93, 86, 158, 246, 5, 64, 200, 0, 37, 144, 244, 209, 220, 210, 93, 87, 63, 240, 16],
+    // Level 10
+    [152, 178, 47, 74, 108, 17, 8, 208, 107, 133, 168, 170, 33, 117, 10, 241, 146, 165, 125, 143, 219, 25, 190, 144, 183, 188, 167, 177, 69, 117, 196, 1],
+    // Level 11
+    [144, 83, 7, 213, 170, 250, 14, 76, 177, 191, 253, 57, 244, 31, 166, 167, 6, 10, 183, 169, 8, 18, 183, 87, 25, 151, 55, 139, 100, 126, 40, 20],
+    // Level 12
+    [76, 93, 2, 30, 18, 253, 196, 37, 80, 85, 5, 100, 83, 51, 95, 148, 221, 241, 150, 57, 94, 116, 13, 253, 63, 208, 228, 134, 34, 243, 74, 14],
+    // Level 13
+    [227, 146, 237, 113, 123, 14, 196, 137, 219, 123, 236, 73, 138, 26, 36, 9, 201, 199, 208, 23, 40, 108, 251, 218, 156, 144, 155, 103, 247, 242, 27, 42],
+    // Level 14
+    [169, 85, 63, 86, 49, 235, 128, 40, 120, 158, 122, 201, 54, 125, 173, 3, 96, 150, 2, 211, 124, 14, 82, 47, 128, 194, 90, 59, 239, 175, 149, 22],
+    // Level 15
+    [141, 5, 117, 147, 170, 247, 84, 231, 210, 249, 66, 14, 138, 72, 39, 134, 154, 252, 223, 51, 147, 229, 112, 225, 156, 162, 250, 183, 226, 99, 219, 7],
+    // Level 16
+    [225, 248, 181, 175, 58, 148, 15, 38, 68, 89, 254, 183, 0, 254, 54, 215, 180, 145, 108, 238, 165, 132, 174, 214, 39, 254, 213, 84, 116, 126, 202, 1],
+    // Level 17
+    [220, 184, 171, 188, 39, 251, 80, 204, 227, 236, 248, 113, 187, 61, 71, 68, 172, 109, 108, 115, 246, 148, 72, 12, 114, 155, 137, 207, 123, 20, 120, 8],
+    // Level 18
+    [183, 239, 109, 101, 91, 223, 118, 235, 167, 28, 57, 114, 34, 105, 117, 110, 93, 31, 218, 89, 48, 188, 230, 188, 119, 81, 16, 239, 143, 204, 75, 38],
+    // Level 19
+    [148, 21, 107, 71, 128, 175, 215, 41, 94, 237, 226, 209, 137, 95, 58, 7, 85, 127, 42, 93, 248, 186, 39, 96, 58, 52, 125, 90, 98, 79, 202, 38],
+    // Level 20
+    [82, 187, 190, 230, 135, 247, 128, 58, 105, 167, 125, 9, 113, 230, 153, 76, 5, 23, 160, 64, 160, 121, 191, 42, 236, 19, 57, 79, 208, 54, 185, 13],
 ];
 
 /// Verify Merkle proof
@@ -100,28 +263,35 @@ pub fn verify_merkle_proof(
     indices: &[bool],
     root: &[u8; 32],
 ) -> Result<bool> {
-    require!(path.len() == MERKLE_TREE_DEPTH, ErrorCode::InvalidProofLength);
-    require!(indices.len() == MERKLE_TREE_DEPTH, ErrorCode::InvalidProofLength);
+    require!(
+        path.len() == MERKLE_TREE_DEPTH && indices.len() == MERKLE_TREE_DEPTH,
+        MixerError::InvalidProofLength
+    );
 
-    let mut current_hash = *leaf;
-
-    for i in 0..MERKLE_TREE_DEPTH {
-        let (left, right) = if indices[i] {
-            // Current hash is on the right
-            (&path[i], &current_hash)
-        } else {
-            // Current hash is on the left
-            (&current_hash, &path[i])
-        };
-
-        current_hash = poseidon_hash(left, right);
-    }
+    Ok(fold_merkle_path(*leaf, path.iter().zip(indices), poseidon_hash) == *root)
+}
 
-    Ok(current_hash == *root)
+/// Verify a Merkle proof for leaf data in a Poseidon tree of the given pool version
+/// The data is turned into the tree's leaf before hashing up the path
+pub fn verify_versioned_merkle_proof(
+    version: u8,
+    data: &[u8; 32],
+    path: &[[u8; 32]],
+    indices: &[bool],
+    root: &[u8; 32],
+) -> Result<bool> {
+    verify_merkle_proof_with_depth(version, data, path, indices, MERKLE_TREE_DEPTH, root)
 }
 
-#[error_code]
-pub enum ErrorCode {
-    #[msg("Invalid proof length")]
-    InvalidProofLength,
+/// Verify a Poseidon proof of any depth for leaf data in a tree of the given
+/// pool version, see MerkleHasher::compute_root
+pub fn verify_merkle_proof_with_depth(
+    version: u8,
+    data: &[u8; 32],
+    path: &[[u8; 32]],
+    indices: &[bool],
+    depth: usize,
+    root: &[u8; 32],
+) -> Result<bool> {
+    PoseidonHasher.verify_proof(version, data, path, indices, depth, root)
 }
@@ -1,5 +1,6 @@
 /// Comprehensive tests for SHA256 Merkle tree implementation
 use super::merkle::*;
+use anchor_lang::prelude::Pubkey;
 
 #[test]
 fn test_merkle_tree_depth() {
@@ -87,8 +88,8 @@ fn test_compute_merkle_root_right_path() {
 fn test_compute_merkle_root_mixed_path() {
     let leaf = [1u8; 32];
     let mut path = [[0u8; 32]; MERKLE_TREE_DEPTH];
-    for i in 0..MERKLE_TREE_DEPTH {
-        path[i] = [i as u8; 32];
+    for (i, sibling) in path.iter_mut().enumerate() {
+        *sibling = [i as u8; 32];
     }
     let mut path_indices = [false; MERKLE_TREE_DEPTH];
     path_indices[0] = true;
@@ -187,8 +188,8 @@ fn test_merkle_tree_multiple_leaves() {
     let zeros = compute_zero_values();
 
     // Continue hashing with zeros up to full depth
-    for i in 2..MERKLE_TREE_DEPTH {
-        current_root = hash_pair(&current_root, &zeros[i]);
+    for zero in zeros.iter().take(MERKLE_TREE_DEPTH).skip(2) {
+        current_root = hash_pair(&current_root, zero);
     }
     let root = current_root;
 
@@ -198,9 +199,7 @@ fn test_merkle_tree_multiple_leaves() {
     path0[0] = leaf1; // Sibling at level 0
     path0[1] = node23; // Sibling at level 1
     // Fill remaining levels with zero values
-    for i in 2..MERKLE_TREE_DEPTH {
-        path0[i] = zeros[i];
-    }
+    path0[2..].copy_from_slice(&zeros[2..MERKLE_TREE_DEPTH]);
     let indices0 = [false; MERKLE_TREE_DEPTH]; // all left
     assert!(verify_merkle_proof(&leaf0, &path0, &indices0, &root));
 
@@ -209,9 +208,7 @@ fn test_merkle_tree_multiple_leaves() {
     path1[0] = leaf0;
     path1[1] = node23;
     // Fill remaining levels with zero values
-    for i in 2..MERKLE_TREE_DEPTH {
-        path1[i] = zeros[i];
-    }
+    path1[2..].copy_from_slice(&zeros[2..MERKLE_TREE_DEPTH]);
     let mut indices1 = [false; MERKLE_TREE_DEPTH];
     indices1[0] = true; // right at level 0
     assert!(verify_merkle_proof(&leaf1, &path1, &indices1, &root));
@@ -238,14 +235,27 @@ fn test_commitment_hash() {
     assert_ne!(commitment, commitment3);
 }
 
+#[test]
+fn test_pool_nullifier_scoping() {
+    let nullifier = [84u8; 32];
+    let pool = Pubkey::new_unique();
+
+    // Deterministic per pool, different across pools and from the raw nullifier
+    assert_eq!(compute_pool_nullifier(&nullifier, &pool), compute_pool_nullifier(&nullifier, &pool));
+    assert_ne!(
+        compute_pool_nullifier(&nullifier, &pool),
+        compute_pool_nullifier(&nullifier, &Pubkey::new_unique())
+    );
+    assert_ne!(compute_pool_nullifier(&nullifier, &pool), nullifier);
+}
+
 #[test]
 fn test_commitment_different_nullifiers() {
-    let secret = [1u8; 32];
-    let nullifier1 = [2u8; 32];
-    let nullifier2 = [3u8; 32];
+    let note = crate::test_utils::TestNote::new(1, 0);
+    let other = crate::test_utils::TestNote::new(1, 1);
 
-    let commitment1 = compute_commitment(&secret, &nullifier1);
-    let commitment2 = compute_commitment(&secret, &nullifier2);
+    let commitment1 = compute_commitment(&note.secret, &note.nullifier);
+    let commitment2 = compute_commitment(&note.secret, &other.nullifier);
 
     // Different nullifiers should produce different commitments
     assert_ne!(commitment1, commitment2);
@@ -263,6 +273,9 @@ fn test_zero_values() {
         let expected = hash_pair(&zeros[i - 1], &zeros[i - 1]);
         assert_eq!(zeros[i], expected);
     }
+
+    // The embedded table must match
+    assert_eq!(ZERO_VALUES, zeros);
 }
 
 #[test]
@@ -273,3 +286,155 @@ fn test_empty_tree_root() {
     // Empty tree root should be deterministic
     assert_ne!(empty_root, [0u8; 32]);
 }
+
+#[test]
+fn test_domain_zero_values_constant() {
+    assert_eq!(DOMAIN_ZERO_VALUES, compute_zero_values_for_version(POOL_VERSION_DOMAIN_SEPARATED));
+    assert_eq!(ZERO_VALUES, compute_zero_values_for_version(POOL_VERSION_LEGACY));
+}
+
+#[test]
+fn test_domain_separated_hashes() {
+    let left = [1u8; 32];
+    let right = [2u8; 32];
+
+    // Prefixes make leaf and node hashes differ from the plain hashes
+    assert_ne!(hash_node(&left, &right), hash_pair(&left, &right));
+    assert_ne!(hash_leaf(&left), left);
+
+    // Legacy trees keep the original scheme
+    assert_eq!(leaf_hash(POOL_VERSION_LEGACY, &left, 5), left);
+    assert_eq!(node_hash(POOL_VERSION_LEGACY, &left, &right), hash_pair(&left, &right));
+    assert_eq!(zero_values(POOL_VERSION_LEGACY), &ZERO_VALUES);
+
+    assert_eq!(leaf_hash(POOL_VERSION_DOMAIN_SEPARATED, &left, 5), hash_leaf(&left));
+    assert_eq!(node_hash(POOL_VERSION_DOMAIN_SEPARATED, &left, &right), hash_node(&left, &right));
+    assert_eq!(zero_values(POOL_VERSION_DOMAIN_SEPARATED), &DOMAIN_ZERO_VALUES);
+
+    // Index-bound trees only change the leaf
+    assert_eq!(leaf_hash(POOL_VERSION_INDEX_BOUND, &left, 5), hash_indexed_leaf(&left, 5));
+    assert_ne!(hash_indexed_leaf(&left, 5), hash_indexed_leaf(&left, 6));
+    assert_ne!(hash_indexed_leaf(&left, 0), hash_leaf(&left));
+    assert_eq!(node_hash(POOL_VERSION_INDEX_BOUND, &left, &right), hash_node(&left, &right));
+    assert_eq!(zero_values(POOL_VERSION_INDEX_BOUND), &DOMAIN_ZERO_VALUES);
+}
+
+#[test]
+fn test_versioned_proof_matches_legacy_verifier() {
+    let leaf = [7u8; 32];
+    let path = [[3u8; 32]; MERKLE_TREE_DEPTH];
+    let indices = [false; MERKLE_TREE_DEPTH];
+    let root = compute_merkle_root(&leaf, &path, &indices);
+
+    assert!(verify_merkle_proof(&leaf, &path, &indices, &root));
+    assert!(verify_versioned_merkle_proof(POOL_VERSION_LEGACY, &leaf, &path, &indices, &root));
+    assert!(!verify_versioned_merkle_proof(
+        POOL_VERSION_DOMAIN_SEPARATED,
+        &leaf,
+        &path,
+        &indices,
+        &root
+    ));
+}
+
+#[test]
+fn test_fold_merkle_path_matches_compute_merkle_root() {
+    let leaf = [9u8; 32];
+    let path = [[4u8; 32]; MERKLE_TREE_DEPTH];
+    let mut indices = [false; MERKLE_TREE_DEPTH];
+    indices[0] = true;
+    indices[7] = true;
+
+    let folded = fold_merkle_path(leaf, path.iter().zip(&indices), hash_pair);
+    assert_eq!(folded, compute_merkle_root(&leaf, &path, &indices));
+
+    // An empty path leaves the leaf untouched
+    assert_eq!(fold_merkle_path(leaf, std::iter::empty(), hash_pair), leaf);
+}
+
+#[test]
+fn test_compute_root_with_depth() {
+    let commitment = [3u8; 32];
+    let path = vec![[1u8; 32], [2u8; 32], [5u8; 32]];
+    let indices = vec![false, true, false];
+
+    let leaf = hash_leaf(&commitment);
+    let expected = hash_node(&hash_node(&[2u8; 32], &hash_node(&leaf, &[1u8; 32])), &[5u8; 32]);
+
+    let root = compute_root_with_depth(
+        POOL_VERSION_DOMAIN_SEPARATED,
+        &commitment,
+        &path,
+        &indices,
+        3,
+    )
+    .unwrap();
+    assert_eq!(root, expected);
+
+    assert!(verify_merkle_proof_with_depth(
+        POOL_VERSION_DOMAIN_SEPARATED,
+        &commitment,
+        &path,
+        &indices,
+        3,
+        &expected
+    )
+    .unwrap());
+}
+
+#[test]
+fn test_compute_root_with_depth_rejects_wrong_length() {
+    let path = vec![[1u8; 32]; 3];
+    let indices = vec![false; 3];
+
+    assert!(compute_root_with_depth(POOL_VERSION_LEGACY, &[1u8; 32], &path, &indices, 4).is_err());
+    assert!(compute_root_with_depth(POOL_VERSION_LEGACY, &[1u8; 32], &path, &indices[..2], 3).is_err());
+    assert!(compute_root_with_depth(POOL_VERSION_LEGACY, &[1u8; 32], &path, &indices, 3).is_ok());
+}
+
+#[test]
+fn test_pack_path_indices() {
+    let mut indices = [false; MERKLE_TREE_DEPTH];
+    assert_eq!(pack_path_indices(&indices), 0);
+
+    indices[0] = true;
+    indices[2] = true;
+    indices[MERKLE_TREE_DEPTH - 1] = true;
+    let bits = pack_path_indices(&indices);
+    assert_eq!(bits, 0b101 | (1 << (MERKLE_TREE_DEPTH - 1)));
+
+    assert_eq!(unpack_path_indices(bits, MERKLE_TREE_DEPTH).unwrap(), indices.to_vec());
+}
+
+#[test]
+fn test_unpack_path_indices_rejects_bits_above_depth() {
+    assert!(unpack_path_indices(1 << MERKLE_TREE_DEPTH, MERKLE_TREE_DEPTH).is_err());
+    assert!(unpack_path_indices(0b100, 2).is_err());
+    assert!(unpack_path_indices(0, 33).is_err());
+
+    // Every bit is usable at depth 32
+    assert_eq!(unpack_path_indices(u32::MAX, 32).unwrap(), vec![true; 32]);
+}
+
+#[test]
+fn test_hashers_share_version_scheme() {
+    use crate::merkle_poseidon::PoseidonHasher;
+
+    let hashers: [&dyn MerkleHasher; 2] = [&Sha256Hasher, &PoseidonHasher];
+    let data = [6u8; 32];
+    let sibling = [2u8; 32];
+
+    for hasher in hashers {
+        for version in [POOL_VERSION_LEGACY, POOL_VERSION_DOMAIN_SEPARATED, POOL_VERSION_INDEX_BOUND] {
+            // Embedded tables agree with node_hash
+            assert_eq!(hasher.zero_values(version), &hasher.compute_zero_values(version));
+
+            // A one-level proof hashes the versioned leaf with the versioned node
+            let leaf = hasher.leaf_hash(version, &data, 1);
+            let root = hasher.node_hash(version, &sibling, &leaf);
+            assert_eq!(hasher.compute_root(version, &data, &[sibling], &[true], 1).unwrap(), root);
+            assert!(hasher.verify_proof(version, &data, &[sibling], &[true], 1, &root).unwrap());
+            assert!(hasher.compute_root(version, &data, &[sibling], &[true], 2).is_err());
+        }
+    }
+}
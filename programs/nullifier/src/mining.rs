@@ -0,0 +1,139 @@
+/*!
+ * Anonymity mining
+ *
+ * Pools with an AnonymityMining account, [b"anonymity_mining", pool], reward
+ * notes for the time they sat in the pool, like Tornado's anonymity points.
+ * A pool has a single denomination, so its per-note rate is set in
+ * proportion to it and points work out to denomination x time-in-pool.
+ *
+ * Points are claimed with the ZK withdrawal itself. The proof shows the
+ * timestamp committed in the leaf is <= the public deposit_timestamp, so the
+ * note has been in the pool at least since then and earns points for that
+ * long without revealing which leaf was spent. A withdrawer trades points
+ * for privacy: a later bound hides the note among more deposits but earns
+ * less. Points go to the recipient's AnonymityPoints account,
+ * [b"anonymity_points", recipient].
+ *
+ * Config can name a reward hook program. Every withdrawal that earns points
+ * then CPIs its on_anonymity_points instruction, which mints or transfers
+ * reward tokens to the recipient or relayer as it sees fit; the mixer holds
+ * no tokenomics itself. The pool PDA signs the CPI, so the hook can check
+ * the call came from a mixer pool (a signer owned by this program). The
+ * hook's own accounts are passed through from the withdrawal's remaining
+ * accounts.
+ */
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+
+use crate::compression::instruction_discriminator;
+use crate::MixerError;
+
+pub const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// A pool's anonymity mining rate
+#[account]
+pub struct AnonymityMining {
+    pub pool: Pubkey,                   // 32 - Pool whose notes earn points
+    pub points_per_day: u64,            // 8 - Points a note earns per day in the pool, 0 pauses mining
+    pub total_points: u64,              // 8 - Points awarded to the pool's withdrawals
+    pub bump: u8,                       // 1
+}
+
+impl AnonymityMining {
+    pub const LEN: usize = 8 + 32 + 8 + 8 + 1;
+
+    /// Points for a note in the pool since `deposit_timestamp`, at `now`
+    pub fn accrued_points(&self, deposit_timestamp: i64, now: i64) -> Result<u64> {
+        let seconds = now.saturating_sub(deposit_timestamp).max(0) as u128;
+        let points = (self.points_per_day as u128)
+            .checked_mul(seconds)
+            .ok_or(MixerError::ArithmeticOverflow)?
+            / SECONDS_PER_DAY as u128;
+        u64::try_from(points).map_err(|_| error!(MixerError::ArithmeticOverflow))
+    }
+
+    /// Award the points of a note deposited by `deposit_timestamp` to
+    /// `account`. Returns the points awarded
+    pub fn award(
+        &mut self,
+        account: &mut AnonymityPoints,
+        deposit_timestamp: i64,
+        now: i64,
+    ) -> Result<u64> {
+        let points = self.accrued_points(deposit_timestamp, now)?;
+        self.total_points = self
+            .total_points
+            .checked_add(points)
+            .ok_or(MixerError::ArithmeticOverflow)?;
+        account.points = account
+            .points
+            .checked_add(points)
+            .ok_or(MixerError::ArithmeticOverflow)?;
+        Ok(points)
+    }
+}
+
+/// Anonymity points earned by withdrawals to `owner`
+#[account]
+pub struct AnonymityPoints {
+    pub owner: Pubkey,                  // 32 - Withdrawal recipient the points belong to
+    pub points: u64,                    // 8
+    pub bump: u8,                       // 1
+}
+
+impl AnonymityPoints {
+    pub const LEN: usize = 8 + 32 + 8 + 1;
+}
+
+/// Arguments of the reward hook's on_anonymity_points instruction
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Debug)]
+pub struct RewardHookPayload {
+    pub pool: Pubkey,
+    pub recipient: Pubkey,
+    pub relayer: Pubkey,
+    pub points: u64,
+}
+
+/// Build on_anonymity_points(payload): the pool as signer, then the hook's
+/// own accounts as the withdrawal passed them
+pub fn reward_hook_ix(
+    hook_program: &Pubkey,
+    payload: &RewardHookPayload,
+    hook_accounts: &[AccountInfo],
+) -> Result<Instruction> {
+    let mut data = instruction_discriminator("on_anonymity_points").to_vec();
+    payload.serialize(&mut data)?;
+
+    let mut accounts = vec![AccountMeta::new_readonly(payload.pool, true)];
+    accounts.extend(hook_accounts.iter().map(|account| AccountMeta {
+        pubkey: *account.key,
+        is_signer: account.is_signer,
+        is_writable: account.is_writable,
+    }));
+
+    Ok(Instruction {
+        program_id: *hook_program,
+        accounts,
+        data,
+    })
+}
+
+/// CPI the reward hook for a withdrawal's awarded points, signed by the pool
+pub fn invoke_reward_hook<'info>(
+    hook_program: &AccountInfo<'info>,
+    pool: &AccountInfo<'info>,
+    hook_accounts: &[AccountInfo<'info>],
+    signer_seeds: &[&[u8]],
+    payload: &RewardHookPayload,
+) -> Result<()> {
+    let ix = reward_hook_ix(hook_program.key, payload, hook_accounts)?;
+
+    let mut account_infos = vec![pool.clone()];
+    account_infos.extend_from_slice(hook_accounts);
+    account_infos.push(hook_program.clone());
+    invoke_signed(&ix, &account_infos, &[signer_seeds])?;
+
+    Ok(())
+}
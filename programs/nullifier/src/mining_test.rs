@@ -0,0 +1,78 @@
+/// Tests for anonymity mining
+use anchor_lang::prelude::Pubkey;
+
+use super::mining::*;
+
+fn mining(points_per_day: u64) -> AnonymityMining {
+    AnonymityMining { pool: Pubkey::new_unique(), points_per_day, total_points: 0, bump: 255 }
+}
+
+#[test]
+fn test_anonymity_mining_account_sizes() {
+    // AnonymityMining: discriminator (8) + pool (32) + points_per_day (8) + total_points (8) + bump (1)
+    assert_eq!(AnonymityMining::LEN, 57);
+    // AnonymityPoints: discriminator (8) + owner (32) + points (8) + bump (1)
+    assert_eq!(AnonymityPoints::LEN, 49);
+}
+
+#[test]
+fn test_points_accrue_with_time_in_pool() {
+    let mining = mining(1_000);
+    let deposited = 1_700_000_000;
+
+    assert_eq!(mining.accrued_points(deposited, deposited).unwrap(), 0);
+    assert_eq!(mining.accrued_points(deposited, deposited + SECONDS_PER_DAY / 2).unwrap(), 500);
+    assert_eq!(mining.accrued_points(deposited, deposited + 30 * SECONDS_PER_DAY).unwrap(), 30_000);
+
+    // A bound in the future earns nothing rather than underflowing
+    assert_eq!(mining.accrued_points(deposited + 60, deposited).unwrap(), 0);
+    assert!(AnonymityMining { points_per_day: u64::MAX, ..mining }
+        .accrued_points(0, i64::MAX)
+        .is_err());
+}
+
+#[test]
+fn test_award_credits_recipient_and_pool() {
+    let mut mining = mining(2_400);
+    let mut points = AnonymityPoints { owner: Pubkey::new_unique(), points: 0, bump: 255 };
+
+    assert_eq!(mining.award(&mut points, 0, SECONDS_PER_DAY).unwrap(), 2_400);
+    assert_eq!(mining.award(&mut points, 0, SECONDS_PER_DAY / 24).unwrap(), 100);
+    assert_eq!(points.points, 2_500);
+    assert_eq!(mining.total_points, 2_500);
+
+    // A zero rate pauses mining
+    mining.points_per_day = 0;
+    assert_eq!(mining.award(&mut points, 0, SECONDS_PER_DAY).unwrap(), 0);
+    assert_eq!(points.points, 2_500);
+}
+
+#[test]
+fn test_reward_hook_ix() {
+    use anchor_lang::prelude::{AccountInfo, AnchorDeserialize};
+    use crate::compression::instruction_discriminator;
+
+    let hook_program = Pubkey::new_unique();
+    let payload = RewardHookPayload {
+        pool: Pubkey::new_unique(),
+        recipient: Pubkey::new_unique(),
+        relayer: Pubkey::new_unique(),
+        points: 2_400,
+    };
+    let (mint, owner) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let (mut lamports, mut data) = (0u64, [0u8; 0]);
+    let mint_account = AccountInfo::new(&mint, false, true, &mut lamports, &mut data, &owner, false, 0);
+
+    let ix = reward_hook_ix(&hook_program, &payload, &[mint_account]).unwrap();
+    assert_eq!(ix.program_id, hook_program);
+
+    // The pool signs, the hook's accounts keep the withdrawal's flags
+    assert_eq!(ix.accounts.len(), 2);
+    assert_eq!(ix.accounts[0].pubkey, payload.pool);
+    assert!(ix.accounts[0].is_signer && !ix.accounts[0].is_writable);
+    assert_eq!(ix.accounts[1].pubkey, mint);
+    assert!(!ix.accounts[1].is_signer && ix.accounts[1].is_writable);
+
+    assert_eq!(ix.data[..8], instruction_discriminator("on_anonymity_points"));
+    assert_eq!(RewardHookPayload::try_from_slice(&ix.data[8..]).unwrap(), payload);
+}
@@ -0,0 +1,202 @@
+/*!
+ * On-chain relayer registry
+ *
+ * Relayers submit withdrawals on behalf of recipients that hold no SOL. Each
+ * relayer publishes a RelayerInfo PDA, [b"relayer", relayer], holding its fee
+ * schedule, a hash of its endpoint URL and whether it is taking requests, so
+ * wallets can discover relayers with a getProgramAccounts call instead of a
+ * hardcoded list. The URL itself is served off-chain and checked against
+ * endpoint_hash.
+ *
+ * Registering locks at least MIN_RELAYER_STAKE lamports in the RelayerInfo.
+ * Anyone can report misbehavior (e.g. withholding signed withdrawal intents)
+ * with an evidence hash, putting up RELAYER_REPORT_BOND; the authority then
+ * slashes the stake into the treasury, refunding the bond, or dismisses the
+ * report and keeps the bond, so reports can't be filed just to hold a
+ * relayer's stake hostage. A relayer leaves by pausing and waiting
+ * out RELAYER_UNBONDING_PERIOD with no open report, so it can't withdraw its
+ * stake ahead of a report.
+ *
+ * Withdrawals that pass the signer's RelayerInfo bump its counters, so
+ * frontends can rank relayers from on-chain data alone.
+ *
+ * Pools are permissionless by default. An Allowlisted pool only accepts
+ * withdrawals signed by a relayer in its PoolRelayers list, for enterprise
+ * deployments that need every withdrawal to go through a logging relayer.
+ */
+
+use anchor_lang::prelude::*;
+
+use crate::{MixerError, BASIS_POINTS_DIVISOR};
+
+/// Lamports a relayer must keep staked to be active (1 SOL)
+pub const MIN_RELAYER_STAKE: u64 = 1_000_000_000;
+
+/// Lamports a reporter puts up with a report, forfeited to the treasury if
+/// it is dismissed (0.1 SOL)
+pub const RELAYER_REPORT_BOND: u64 = 100_000_000;
+
+/// Time a paused relayer waits before it can deregister (7 days)
+pub const RELAYER_UNBONDING_PERIOD: i64 = 7 * 24 * 60 * 60;
+
+/// Maximum relayers on one pool's allowlist
+pub const MAX_POOL_RELAYERS: usize = 16;
+
+/// Whether a registered relayer is accepting withdrawals
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RelayerStatus {
+    Active,
+    Paused,
+}
+
+/// Fee a relayer charges on a withdrawal: fee_bps of the amount, at least min_fee
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct RelayerFeeSchedule {
+    pub fee_bps: u16,
+    pub min_fee: u64,
+}
+
+impl RelayerFeeSchedule {
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.fee_bps as u64 <= BASIS_POINTS_DIVISOR,
+            MixerError::InvalidRelayerFee
+        );
+        Ok(())
+    }
+
+    /// Fee on a withdrawal of `amount`, never more than the amount itself
+    pub fn fee_for(&self, amount: u64) -> u64 {
+        let proportional = (amount as u128 * self.fee_bps as u128 / BASIS_POINTS_DIVISOR as u128) as u64;
+        proportional.max(self.min_fee).min(amount)
+    }
+}
+
+#[account]
+pub struct RelayerInfo {
+    pub relayer: Pubkey,                // 32 - Relayer's signing key
+    pub fee_schedule: RelayerFeeSchedule, // 2 + 8
+    pub endpoint_hash: [u8; 32],        // 32 - SHA256 of the relayer's endpoint URL
+    pub status: RelayerStatus,          // 1
+    pub registered_at: i64,             // 8
+    pub updated_at: i64,                // 8
+    pub bump: u8,                       // 1
+    pub stake: u64,                     // 8 - Slashable lamports held above rent
+    pub open_report: bool,              // 1 - A RelayerReport awaits adjudication
+    pub withdrawals_executed: u64,      // 8 - Withdrawals submitted with this RelayerInfo
+    pub fees_earned: u64,               // 8 - Lamports of withdrawal-intent fees collected
+    pub failures: u64,                  // 8 - Misbehavior reports upheld by slashing
+}
+
+impl RelayerInfo {
+    pub const LEN: usize = 8 + 32 + (2 + 8) + 32 + 1 + 8 + 8 + 1 + 8 + 1 + 8 + 8 + 8;
+
+    pub fn is_active(&self) -> bool {
+        self.status == RelayerStatus::Active
+    }
+
+    /// Set `status`; going active needs the full minimum stake
+    pub fn set_status(&mut self, status: RelayerStatus) -> Result<()> {
+        if status == RelayerStatus::Active {
+            require!(
+                self.stake >= MIN_RELAYER_STAKE,
+                MixerError::InsufficientRelayerStake
+            );
+        }
+        self.status = status;
+        Ok(())
+    }
+
+    /// Take up to `amount` from the stake, pausing the relayer if it falls
+    /// below the minimum. Returns the lamports slashed
+    pub fn slash(&mut self, amount: u64) -> u64 {
+        let slashed = amount.min(self.stake);
+        self.stake -= slashed;
+        if self.stake < MIN_RELAYER_STAKE {
+            self.status = RelayerStatus::Paused;
+        }
+        slashed
+    }
+
+    /// Credit a withdrawal the relayer submitted and the fee it earned
+    pub fn record_withdrawal(&mut self, fee: u64) -> Result<()> {
+        self.withdrawals_executed = self
+            .withdrawals_executed
+            .checked_add(1)
+            .ok_or(MixerError::ArithmeticOverflow)?;
+        self.fees_earned = self
+            .fees_earned
+            .checked_add(fee)
+            .ok_or(MixerError::ArithmeticOverflow)?;
+        Ok(())
+    }
+
+    /// A paused relayer with no open report can leave once unbonded
+    pub fn can_deregister(&self, now: i64) -> bool {
+        self.status == RelayerStatus::Paused
+            && !self.open_report
+            && now >= self.updated_at.saturating_add(RELAYER_UNBONDING_PERIOD)
+    }
+}
+
+/// Report of relayer misbehavior awaiting the authority's decision
+#[account]
+pub struct RelayerReport {
+    pub relayer: Pubkey,                // 32 - Reported relayer
+    pub reporter: Pubkey,               // 32 - Refunded the rent when resolved
+    pub evidence_hash: [u8; 32],        // 32 - Hash of the off-chain evidence
+    pub reported_at: i64,               // 8
+    pub bump: u8,                       // 1
+    pub bond: u64,                      // 8 - Held above rent, refunded only on a slash
+}
+
+impl RelayerReport {
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 8 + 1 + 8;
+}
+
+/// Who may submit a pool's withdrawals
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RelayerPolicy {
+    /// Any signer
+    Permissionless,
+    /// Only relayers in the pool's PoolRelayers
+    Allowlisted,
+}
+
+/// Relayers allowed to submit withdrawals from an Allowlisted pool
+#[account]
+pub struct PoolRelayers {
+    pub pool: Pubkey,                   // 32
+    pub relayers: Vec<Pubkey>,          // 4 + 32 * MAX_POOL_RELAYERS
+    pub bump: u8,                       // 1
+}
+
+impl PoolRelayers {
+    pub const LEN: usize = 8 + 32 + 4 + 32 * MAX_POOL_RELAYERS + 1;
+
+    pub fn contains(&self, relayer: &Pubkey) -> bool {
+        self.relayers.contains(relayer)
+    }
+
+    pub fn add(&mut self, relayer: Pubkey) -> Result<()> {
+        require!(!self.contains(&relayer), MixerError::RelayerAlreadyAllowed);
+        require!(
+            self.relayers.len() < MAX_POOL_RELAYERS,
+            MixerError::PoolRelayersFull
+        );
+
+        self.relayers.push(relayer);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, relayer: &Pubkey) -> Result<()> {
+        let position = self
+            .relayers
+            .iter()
+            .position(|allowed| allowed == relayer)
+            .ok_or(MixerError::RelayerNotAllowed)?;
+
+        self.relayers.swap_remove(position);
+        Ok(())
+    }
+}
@@ -0,0 +1,113 @@
+/// Tests for the relayer registry
+use anchor_lang::prelude::Pubkey;
+
+use super::relayer::*;
+
+#[test]
+fn test_relayer_info_account_size() {
+    // RelayerInfo: discriminator (8) + relayer (32) + fee_schedule (2 + 8) +
+    // endpoint_hash (32) + status (1) + registered_at (8) + updated_at (8) + bump (1) +
+    // stake (8) + open_report (1) + withdrawals_executed (8) + fees_earned (8) + failures (8)
+    assert_eq!(RelayerInfo::LEN, 133);
+
+    // RelayerReport: discriminator (8) + relayer (32) + reporter (32) + evidence_hash (32) +
+    // reported_at (8) + bump (1) + bond (8)
+    assert_eq!(RelayerReport::LEN, 121);
+}
+
+#[test]
+fn test_relayer_fee_schedule() {
+    let schedule = RelayerFeeSchedule { fee_bps: 50, min_fee: 1_000_000 };
+    assert!(schedule.validate().is_ok());
+
+    // 0.5% of 1 SOL, and the minimum on small amounts
+    assert_eq!(schedule.fee_for(1_000_000_000), 5_000_000);
+    assert_eq!(schedule.fee_for(100_000_000), 1_000_000);
+
+    // Never more than the withdrawal
+    assert_eq!(schedule.fee_for(500_000), 500_000);
+
+    assert!(RelayerFeeSchedule { fee_bps: 10_001, min_fee: 0 }.validate().is_err());
+}
+
+fn staked_relayer(stake: u64) -> RelayerInfo {
+    RelayerInfo {
+        relayer: Default::default(),
+        fee_schedule: RelayerFeeSchedule { fee_bps: 0, min_fee: 0 },
+        endpoint_hash: [0; 32],
+        status: RelayerStatus::Active,
+        registered_at: 0,
+        updated_at: 0,
+        bump: 0,
+        stake,
+        open_report: false,
+        withdrawals_executed: 0,
+        fees_earned: 0,
+        failures: 0,
+    }
+}
+
+#[test]
+fn test_slash_pauses_understaked_relayer() {
+    let mut info = staked_relayer(2 * MIN_RELAYER_STAKE);
+
+    assert_eq!(info.slash(MIN_RELAYER_STAKE / 2), MIN_RELAYER_STAKE / 2);
+    assert!(info.is_active());
+
+    // Capped at the remaining stake
+    assert_eq!(info.slash(u64::MAX), MIN_RELAYER_STAKE * 3 / 2);
+    assert_eq!(info.stake, 0);
+    assert!(!info.is_active());
+    assert!(info.set_status(RelayerStatus::Active).is_err());
+}
+
+#[test]
+fn test_deregister_waits_for_unbonding() {
+    let mut info = staked_relayer(MIN_RELAYER_STAKE);
+    assert!(!info.can_deregister(RELAYER_UNBONDING_PERIOD));
+
+    info.set_status(RelayerStatus::Paused).unwrap();
+    assert!(!info.can_deregister(RELAYER_UNBONDING_PERIOD - 1));
+    assert!(info.can_deregister(RELAYER_UNBONDING_PERIOD));
+
+    info.open_report = true;
+    assert!(!info.can_deregister(RELAYER_UNBONDING_PERIOD));
+}
+
+#[test]
+fn test_relayer_metrics() {
+    let mut info = staked_relayer(MIN_RELAYER_STAKE);
+
+    for _ in 0..10 {
+        info.record_withdrawal(5_000_000).unwrap();
+    }
+    info.record_withdrawal(0).unwrap();
+
+    assert_eq!(info.withdrawals_executed, 11);
+    assert_eq!(info.fees_earned, 50_000_000);
+
+    info.fees_earned = u64::MAX;
+    assert!(info.record_withdrawal(1).is_err());
+}
+
+#[test]
+fn test_pool_relayers_allowlist() {
+    // PoolRelayers: discriminator (8) + pool (32) + vec len (4) + relayers (32 * 16) + bump (1)
+    assert_eq!(PoolRelayers::LEN, 557);
+
+    let mut pool_relayers = PoolRelayers { pool: Pubkey::new_unique(), relayers: Vec::new(), bump: 0 };
+    let relayer = Pubkey::new_unique();
+
+    pool_relayers.add(relayer).unwrap();
+    assert!(pool_relayers.contains(&relayer));
+    assert!(pool_relayers.add(relayer).is_err());
+
+    for _ in 1..MAX_POOL_RELAYERS {
+        pool_relayers.add(Pubkey::new_unique()).unwrap();
+    }
+    assert!(pool_relayers.add(Pubkey::new_unique()).is_err());
+
+    pool_relayers.remove(&relayer).unwrap();
+    assert!(!pool_relayers.contains(&relayer));
+    assert!(pool_relayers.remove(&relayer).is_err());
+}
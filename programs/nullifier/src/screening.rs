@@ -0,0 +1,73 @@
+/*!
+ * Deposit screening
+ *
+ * Config can name a screening program, e.g. a deployment's own sanctions
+ * screening. Every deposit then CPIs its screen_deposit instruction with the
+ * depositor and amount, and a failing CPI aborts the deposit; the mixer keeps
+ * no screening logic itself. A batch deposit through insert_leaves is
+ * screened once, with the total it deposits. The pool PDA signs the CPI, so
+ * the screening program can check the call came from a mixer pool. Its own
+ * accounts, e.g. a sanctions list, are passed through from the deposit's
+ * remaining accounts.
+ */
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+
+use crate::compression::instruction_discriminator;
+
+/// Arguments of the screening program's screen_deposit instruction
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Debug)]
+pub struct ScreeningPayload {
+    pub pool: Pubkey,
+    pub mint: Pubkey,
+    pub depositor: Pubkey,
+    pub amount: u64,
+}
+
+/// Build screen_deposit(payload): the pool as signer, the depositor, then the
+/// screening program's own accounts as the deposit passed them
+pub fn screening_ix(
+    screening_program: &Pubkey,
+    payload: &ScreeningPayload,
+    screening_accounts: &[AccountInfo],
+) -> Result<Instruction> {
+    let mut data = instruction_discriminator("screen_deposit").to_vec();
+    payload.serialize(&mut data)?;
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(payload.pool, true),
+        AccountMeta::new_readonly(payload.depositor, false),
+    ];
+    accounts.extend(screening_accounts.iter().map(|account| AccountMeta {
+        pubkey: *account.key,
+        is_signer: account.is_signer,
+        is_writable: account.is_writable,
+    }));
+
+    Ok(Instruction {
+        program_id: *screening_program,
+        accounts,
+        data,
+    })
+}
+
+/// CPI the screening program for a deposit, signed by the pool
+pub fn invoke_screening<'info>(
+    screening_program: &AccountInfo<'info>,
+    pool: &AccountInfo<'info>,
+    depositor: &AccountInfo<'info>,
+    screening_accounts: &[AccountInfo<'info>],
+    signer_seeds: &[&[u8]],
+    payload: &ScreeningPayload,
+) -> Result<()> {
+    let ix = screening_ix(screening_program.key, payload, screening_accounts)?;
+
+    let mut account_infos = vec![pool.clone(), depositor.clone()];
+    account_infos.extend_from_slice(screening_accounts);
+    account_infos.push(screening_program.clone());
+    invoke_signed(&ix, &account_infos, &[signer_seeds])?;
+
+    Ok(())
+}
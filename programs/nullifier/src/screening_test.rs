@@ -0,0 +1,34 @@
+/// Tests for deposit screening
+use anchor_lang::prelude::{AccountInfo, AnchorDeserialize, Pubkey};
+
+use super::screening::*;
+use crate::compression::instruction_discriminator;
+
+#[test]
+fn test_screening_ix() {
+    let screening_program = Pubkey::new_unique();
+    let payload = ScreeningPayload {
+        pool: Pubkey::new_unique(),
+        mint: Pubkey::default(),
+        depositor: Pubkey::new_unique(),
+        amount: 1_000_000_000,
+    };
+    let (list, owner) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let (mut lamports, mut data) = (0u64, [0u8; 0]);
+    let list_account = AccountInfo::new(&list, false, false, &mut lamports, &mut data, &owner, false, 0);
+
+    let ix = screening_ix(&screening_program, &payload, &[list_account]).unwrap();
+    assert_eq!(ix.program_id, screening_program);
+
+    // The pool signs, then the depositor and the screening program's accounts
+    assert_eq!(ix.accounts.len(), 3);
+    assert_eq!(ix.accounts[0].pubkey, payload.pool);
+    assert!(ix.accounts[0].is_signer && !ix.accounts[0].is_writable);
+    assert_eq!(ix.accounts[1].pubkey, payload.depositor);
+    assert!(!ix.accounts[1].is_signer && !ix.accounts[1].is_writable);
+    assert_eq!(ix.accounts[2].pubkey, list);
+    assert!(!ix.accounts[2].is_signer && !ix.accounts[2].is_writable);
+
+    assert_eq!(ix.data[..8], instruction_discriminator("screen_deposit"));
+    assert_eq!(ScreeningPayload::try_from_slice(&ix.data[8..]).unwrap(), payload);
+}
@@ -0,0 +1,186 @@
+/*!
+ * Sparse Merkle tree of spent nullifiers
+ *
+ * Every 256-bit nullifier hash owns one leaf of a depth-256 tree, addressed by
+ * its bits (bit 0, the least significant bit of byte 0, picks the branch at the
+ * leaf level). An empty leaf is zero and a spent leaf holds the nullifier hash
+ * itself, so a single root commits to the whole spent set and "not spent" is
+ * proven by showing the nullifier's leaf is still zero.
+ *
+ * Empty subtrees hash to zero at every level, which keeps proofs short: only
+ * non-empty siblings are sent, flagged in a 256-bit bitmap. With n spent
+ * nullifiers a proof carries about log2(n) siblings.
+ *
+ * Nodes are hashed with Poseidon so the same tree can be checked in a circuit.
+ */
+
+use anchor_lang::prelude::*;
+use std::collections::HashMap;
+
+use crate::merkle_poseidon::poseidon_hash;
+use crate::MixerError;
+
+/// One level per bit of the nullifier hash
+pub const SPARSE_TREE_DEPTH: usize = 256;
+
+/// Value of an empty leaf and of every empty subtree
+pub const EMPTY_NODE: [u8; 32] = [0u8; 32];
+
+/// Compressed path from a leaf to the root
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SparseMerkleProof {
+    /// Bit `level` is set when the sibling at that level is non-empty
+    pub bitmap: [u8; 32],
+    /// Non-empty siblings, ordered from the leaf level up
+    pub siblings: Vec<[u8; 32]>,
+}
+
+/// Bit `level` of a key, i.e. whether the node at that level is a right child
+pub fn key_bit(key: &[u8; 32], level: usize) -> bool {
+    (key[level / 8] >> (level % 8)) & 1 == 1
+}
+
+/// Hash two children, keeping empty subtrees at zero
+pub fn hash_node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    if *left == EMPTY_NODE && *right == EMPTY_NODE {
+        EMPTY_NODE
+    } else {
+        poseidon_hash(left, right)
+    }
+}
+
+/// Root obtained by placing `leaf` at `key` and walking up the proof
+/// Returns None if the bitmap and sibling count disagree
+pub fn compute_root(key: &[u8; 32], leaf: &[u8; 32], proof: &SparseMerkleProof) -> Option<[u8; 32]> {
+    let mut siblings = proof.siblings.iter();
+    let mut current = *leaf;
+
+    for level in 0..SPARSE_TREE_DEPTH {
+        let sibling = if key_bit(&proof.bitmap, level) {
+            siblings.next()?
+        } else {
+            &EMPTY_NODE
+        };
+
+        current = if key_bit(key, level) {
+            hash_node(sibling, &current)
+        } else {
+            hash_node(&current, sibling)
+        };
+    }
+
+    // Every supplied sibling must have been consumed
+    if siblings.next().is_some() {
+        return None;
+    }
+
+    Some(current)
+}
+
+/// Check that `key` has been inserted under `root`
+pub fn verify_membership(root: &[u8; 32], key: &[u8; 32], proof: &SparseMerkleProof) -> bool {
+    *key != EMPTY_NODE && compute_root(key, key, proof) == Some(*root)
+}
+
+/// Check that `key` has not been inserted under `root`
+pub fn verify_non_membership(root: &[u8; 32], key: &[u8; 32], proof: &SparseMerkleProof) -> bool {
+    compute_root(key, &EMPTY_NODE, proof) == Some(*root)
+}
+
+/// Mark `key` as spent, given a non-membership proof against `root`
+/// Returns the new root
+pub fn insert(root: &[u8; 32], key: &[u8; 32], proof: &SparseMerkleProof) -> Result<[u8; 32]> {
+    require!(*key != EMPTY_NODE, MixerError::InvalidNullifier);
+    require!(
+        verify_non_membership(root, key, proof),
+        MixerError::InvalidNonMembershipProof
+    );
+
+    // The same siblings lead from the now-occupied leaf to the new root
+    Ok(compute_root(key, key, proof).expect("proof shape checked above"))
+}
+
+/// Off-chain copy of the tree, used to build proofs
+///
+/// Stores only non-empty nodes, keyed by level and the key prefix above it.
+#[derive(Clone, Debug, Default)]
+pub struct SparseMerkleTree {
+    nodes: HashMap<(u16, [u8; 32]), [u8; 32]>,
+}
+
+impl SparseMerkleTree {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        self.node(SPARSE_TREE_DEPTH, &EMPTY_NODE)
+    }
+
+    pub fn contains(&self, key: &[u8; 32]) -> bool {
+        self.node(0, key) != EMPTY_NODE
+    }
+
+    /// Insert a key, failing if it is zero or already present
+    pub fn insert(&mut self, key: [u8; 32]) -> Result<()> {
+        require!(key != EMPTY_NODE, MixerError::InvalidNullifier);
+        require!(!self.contains(&key), MixerError::NullifierAlreadyUsed);
+
+        let mut index = key;
+        let mut current = key;
+        self.nodes.insert((0, index), current);
+
+        for level in 0..SPARSE_TREE_DEPTH {
+            let sibling = self.node(level, &flip_bit(&index, level));
+
+            current = if key_bit(&index, level) {
+                hash_node(&sibling, &current)
+            } else {
+                hash_node(&current, &sibling)
+            };
+
+            index = clear_bit(&index, level);
+            self.nodes.insert(((level + 1) as u16, index), current);
+        }
+
+        Ok(())
+    }
+
+    /// Proof for `key`, valid for membership if present and non-membership otherwise
+    pub fn proof(&self, key: &[u8; 32]) -> SparseMerkleProof {
+        let mut proof = SparseMerkleProof::default();
+        let mut index = *key;
+
+        for level in 0..SPARSE_TREE_DEPTH {
+            let sibling = self.node(level, &flip_bit(&index, level));
+
+            if sibling != EMPTY_NODE {
+                proof.bitmap[level / 8] |= 1 << (level % 8);
+                proof.siblings.push(sibling);
+            }
+
+            index = clear_bit(&index, level);
+        }
+
+        proof
+    }
+
+    fn node(&self, level: usize, index: &[u8; 32]) -> [u8; 32] {
+        self.nodes
+            .get(&(level as u16, *index))
+            .copied()
+            .unwrap_or(EMPTY_NODE)
+    }
+}
+
+fn flip_bit(key: &[u8; 32], level: usize) -> [u8; 32] {
+    let mut flipped = *key;
+    flipped[level / 8] ^= 1 << (level % 8);
+    flipped
+}
+
+fn clear_bit(key: &[u8; 32], level: usize) -> [u8; 32] {
+    let mut cleared = *key;
+    cleared[level / 8] &= !(1 << (level % 8));
+    cleared
+}
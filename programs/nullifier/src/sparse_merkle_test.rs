@@ -0,0 +1,140 @@
+/// Tests for the sparse Merkle tree of spent nullifiers
+use super::sparse_merkle::*;
+
+fn key(byte: u8) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    key[0] = byte;
+    key[31] = byte;
+    key
+}
+
+#[test]
+fn test_empty_tree() {
+    let tree = SparseMerkleTree::new();
+
+    assert_eq!(tree.root(), EMPTY_NODE);
+    assert!(!tree.contains(&key(1)));
+
+    // Non-membership against an empty tree needs no siblings
+    let proof = tree.proof(&key(1));
+    assert!(proof.siblings.is_empty());
+    assert!(verify_non_membership(&tree.root(), &key(1), &proof));
+}
+
+#[test]
+fn test_key_bit() {
+    let key = key(0b0000_0101);
+
+    assert!(key_bit(&key, 0));
+    assert!(!key_bit(&key, 1));
+    assert!(key_bit(&key, 2));
+    assert!(key_bit(&key, 248));
+    assert!(!key_bit(&key, 255));
+}
+
+#[test]
+fn test_insert_and_prove() {
+    let mut tree = SparseMerkleTree::new();
+    tree.insert(key(1)).unwrap();
+    tree.insert(key(2)).unwrap();
+
+    let root = tree.root();
+    assert_ne!(root, EMPTY_NODE);
+
+    let spent = tree.proof(&key(1));
+    assert!(verify_membership(&root, &key(1), &spent));
+    assert!(!verify_non_membership(&root, &key(1), &spent));
+
+    let unspent = tree.proof(&key(3));
+    assert!(verify_non_membership(&root, &key(3), &unspent));
+    assert!(!verify_membership(&root, &key(3), &unspent));
+}
+
+#[test]
+fn test_insert_matches_tree() {
+    let mut tree = SparseMerkleTree::new();
+    let mut root = tree.root();
+
+    for byte in 1..=5u8 {
+        let proof = tree.proof(&key(byte));
+        root = insert(&root, &key(byte), &proof).unwrap();
+
+        tree.insert(key(byte)).unwrap();
+        assert_eq!(root, tree.root());
+    }
+}
+
+#[test]
+fn test_insert_order_independent() {
+    let mut forward = SparseMerkleTree::new();
+    let mut backward = SparseMerkleTree::new();
+
+    for byte in 1..=4u8 {
+        forward.insert(key(byte)).unwrap();
+        backward.insert(key(5 - byte)).unwrap();
+    }
+
+    assert_eq!(forward.root(), backward.root());
+}
+
+#[test]
+fn test_double_spend_rejected() {
+    let mut tree = SparseMerkleTree::new();
+    tree.insert(key(7)).unwrap();
+
+    assert!(tree.insert(key(7)).is_err());
+
+    // A membership proof cannot be passed off as non-membership
+    let proof = tree.proof(&key(7));
+    assert!(insert(&tree.root(), &key(7), &proof).is_err());
+}
+
+#[test]
+fn test_zero_key_rejected() {
+    let mut tree = SparseMerkleTree::new();
+
+    assert!(tree.insert(EMPTY_NODE).is_err());
+    assert!(insert(&tree.root(), &EMPTY_NODE, &tree.proof(&EMPTY_NODE)).is_err());
+}
+
+#[test]
+fn test_stale_proof_rejected() {
+    let mut tree = SparseMerkleTree::new();
+    tree.insert(key(1)).unwrap();
+    let stale = tree.proof(&key(3));
+
+    tree.insert(key(2)).unwrap();
+
+    assert!(!verify_non_membership(&tree.root(), &key(3), &stale));
+    assert!(insert(&tree.root(), &key(3), &stale).is_err());
+}
+
+#[test]
+fn test_malformed_proof_rejected() {
+    let mut tree = SparseMerkleTree::new();
+    tree.insert(key(1)).unwrap();
+    tree.insert(key(2)).unwrap();
+
+    let mut extra = tree.proof(&key(3));
+    extra.siblings.push([9u8; 32]);
+    assert_eq!(compute_root(&key(3), &EMPTY_NODE, &extra), None);
+
+    let mut missing = tree.proof(&key(3));
+    missing.siblings.clear();
+    assert_eq!(compute_root(&key(3), &EMPTY_NODE, &missing), None);
+}
+
+#[test]
+fn test_proof_size_stays_small() {
+    let mut tree = SparseMerkleTree::new();
+
+    for byte in 1..=32u8 {
+        tree.insert(key(byte)).unwrap();
+    }
+
+    // Keys differ only in their first and last bytes, so siblings only
+    // appear near the leaves and near the root
+    let proof = tree.proof(&key(200));
+    assert!(proof.siblings.len() <= 16);
+    assert!(verify_non_membership(&tree.root(), &key(200), &proof));
+}
@@ -0,0 +1,136 @@
+/*!
+ * Deterministic test fixtures
+ *
+ * Notes, trees and proofs are all derived from a u64 seed by hashing, so a
+ * seed reproduces the same fixtures on every run and in every crate. Used by
+ * this crate's tests; integrators get it with the `test-utils` feature.
+ */
+
+use anchor_lang::prelude::*;
+use sha2::{Digest, Sha256};
+
+use crate::merkle::{compute_commitment, MERKLE_TREE_DEPTH};
+use crate::{tree_hasher, MerkleTreeState};
+
+/// 32 bytes derived as SHA256(label || seed || index), integers little-endian
+pub fn derive_bytes(seed: u64, label: &str, index: u32) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(label.as_bytes());
+    hasher.update(seed.to_le_bytes());
+    hasher.update(index.to_le_bytes());
+    hasher.finalize().into()
+}
+
+/// A deposit note: the secret and nullifier a depositor keeps, and the
+/// commitment they deposit
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TestNote {
+    pub secret: [u8; 32],
+    pub nullifier: [u8; 32],
+    pub commitment: [u8; 32],
+}
+
+impl TestNote {
+    /// The index-th note of a seed
+    pub fn new(seed: u64, index: u32) -> Self {
+        let secret = derive_bytes(seed, "secret", index);
+        let nullifier = derive_bytes(seed, "nullifier", index);
+
+        Self {
+            secret,
+            nullifier,
+            commitment: compute_commitment(&secret, &nullifier),
+        }
+    }
+}
+
+/// The first `count` notes of a seed
+pub fn notes(seed: u64, count: u32) -> Vec<TestNote> {
+    (0..count).map(|index| TestNote::new(seed, index)).collect()
+}
+
+/// Empty generation-0 tree of a pool, as create_pool initializes it
+pub fn empty_tree_state(pool: Pubkey, hash: u8, version: u8) -> MerkleTreeState {
+    let mut tree: MerkleTreeState = bytemuck::Zeroable::zeroed();
+    tree.initialize(pool, 0, version, hash, 255);
+    tree
+}
+
+/// Every node of a tree over a list of commitments, for building proofs
+#[derive(Clone, Debug)]
+pub struct TestTree {
+    pub hash: u8,
+    pub version: u8,
+    pub commitments: Vec<[u8; 32]>,
+    /// layers[0] holds the hashed leaves, layers[MERKLE_TREE_DEPTH] the root
+    layers: Vec<Vec<[u8; 32]>>,
+}
+
+impl TestTree {
+    pub fn new(hash: u8, version: u8, commitments: &[[u8; 32]]) -> Self {
+        let hasher = tree_hasher(hash);
+        let zeros = hasher.zero_values(version);
+
+        let mut layers = vec![commitments
+            .iter()
+            .zip(0..)
+            .map(|(commitment, index)| hasher.leaf_hash(version, commitment, index))
+            .collect::<Vec<_>>()];
+
+        for level in 0..MERKLE_TREE_DEPTH {
+            let mut nodes = layers[level].clone();
+            if nodes.len() % 2 == 1 {
+                nodes.push(zeros[level]);
+            }
+            let parents = nodes
+                .chunks(2)
+                .map(|pair| hasher.node_hash(version, &pair[0], &pair[1]))
+                .collect();
+            layers.push(parents);
+        }
+
+        Self {
+            hash,
+            version,
+            commitments: commitments.to_vec(),
+            layers,
+        }
+    }
+
+    /// Tree over the commitments of some notes
+    pub fn from_notes(hash: u8, version: u8, notes: &[TestNote]) -> Self {
+        let commitments: Vec<[u8; 32]> = notes.iter().map(|note| note.commitment).collect();
+        Self::new(hash, version, &commitments)
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        self.layers[MERKLE_TREE_DEPTH]
+            .first()
+            .copied()
+            .unwrap_or(tree_hasher(self.hash).zero_values(self.version)[MERKLE_TREE_DEPTH])
+    }
+
+    /// Sibling path and left/right flags of a leaf, as withdraw takes them
+    /// Panics if no commitment sits at leaf_index
+    pub fn proof(&self, leaf_index: u32) -> (Vec<[u8; 32]>, Vec<bool>) {
+        assert!((leaf_index as usize) < self.commitments.len(), "no leaf at {}", leaf_index);
+
+        let zeros = tree_hasher(self.hash).zero_values(self.version);
+        (0..MERKLE_TREE_DEPTH)
+            .map(|level| {
+                let index = (leaf_index >> level) as usize;
+                let sibling = self.layers[level].get(index ^ 1).copied().unwrap_or(zeros[level]);
+                (sibling, index % 2 == 1)
+            })
+            .unzip()
+    }
+
+    /// On-chain tree state after depositing the commitments in order
+    pub fn tree_state(&self, pool: Pubkey) -> MerkleTreeState {
+        let mut tree = empty_tree_state(pool, self.hash, self.version);
+        for commitment in &self.commitments {
+            tree.insert(*commitment).expect("test tree fits in one generation");
+        }
+        tree
+    }
+}
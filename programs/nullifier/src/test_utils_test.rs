@@ -0,0 +1,54 @@
+/// Tests for the deterministic fixtures
+use super::merkle::*;
+use super::test_utils::*;
+use super::{tree_hasher, TREE_HASH_KECCAK, TREE_HASH_POSEIDON, TREE_HASH_SHA256};
+use anchor_lang::prelude::Pubkey;
+
+#[test]
+fn test_fixtures_are_deterministic() {
+    assert_eq!(notes(7, 4), notes(7, 4));
+    assert_ne!(TestNote::new(7, 0), TestNote::new(8, 0));
+    assert_ne!(TestNote::new(7, 0), TestNote::new(7, 1));
+
+    let note = TestNote::new(7, 0);
+    assert_ne!(note.secret, note.nullifier);
+    assert_eq!(note.commitment, compute_commitment(&note.secret, &note.nullifier));
+}
+
+#[test]
+fn test_test_tree_matches_on_chain_tree() {
+    let notes = notes(1, 9);
+
+    for hash in [TREE_HASH_SHA256, TREE_HASH_POSEIDON, TREE_HASH_KECCAK] {
+        for version in [POOL_VERSION_LEGACY, CURRENT_POOL_VERSION] {
+            let tree = TestTree::from_notes(hash, version, &notes);
+            let state = tree.tree_state(Pubkey::new_unique());
+
+            assert_eq!(tree.root(), state.root());
+            assert_eq!(state.next_index, 9);
+        }
+    }
+}
+
+#[test]
+fn test_test_tree_proofs_verify() {
+    let notes = notes(2, 6);
+    let tree = TestTree::from_notes(TREE_HASH_SHA256, CURRENT_POOL_VERSION, &notes);
+    let hasher = tree_hasher(TREE_HASH_SHA256);
+
+    for (index, note) in notes.iter().enumerate() {
+        let (path, indices) = tree.proof(index as u32);
+        assert!(hasher
+            .verify_proof(CURRENT_POOL_VERSION, &note.commitment, &path, &indices, MERKLE_TREE_DEPTH, &tree.root())
+            .unwrap());
+    }
+}
+
+#[test]
+fn test_empty_test_tree() {
+    let tree = TestTree::new(TREE_HASH_SHA256, CURRENT_POOL_VERSION, &[]);
+    let state = empty_tree_state(Pubkey::new_unique(), TREE_HASH_SHA256, CURRENT_POOL_VERSION);
+
+    assert_eq!(tree.root(), state.root());
+    assert_eq!(tree.root(), DOMAIN_ZERO_VALUES[MERKLE_TREE_DEPTH]);
+}
@@ -0,0 +1,97 @@
+/*!
+ * Token-2022 mint handling for token pools
+ *
+ * Token pools accept mints owned by either SPL Token or Token-2022. A
+ * Token-2022 mint with the transfer-fee extension withholds part of every
+ * transfer at the destination, so deposits are grossed up until the vault
+ * receives exactly the pool's denomination; the fee on the way out is
+ * withheld from the recipient like any other Token-2022 transfer.
+ *
+ * Extensions that let a third party move or lock vault funds, or that need
+ * extra accounts on every transfer, are refused when the pool is created.
+ */
+
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022::spl_token_2022::{
+    self,
+    extension::{
+        transfer_fee::{TransferFee, TransferFeeConfig},
+        BaseStateWithExtensions, ExtensionType, StateWithExtensions,
+    },
+    state::Mint,
+};
+
+use crate::MixerError;
+
+/// Mint extensions a pool vault can't safely hold
+pub const UNSUPPORTED_MINT_EXTENSIONS: [ExtensionType; 3] = [
+    // Delegate can transfer or burn out of the vault
+    ExtensionType::PermanentDelegate,
+    // Every transfer CPIs into a program with its own account list
+    ExtensionType::TransferHook,
+    // Notes could never be withdrawn
+    ExtensionType::NonTransferable,
+];
+
+/// Refuse mints carrying any of UNSUPPORTED_MINT_EXTENSIONS
+pub fn check_mint_extensions(mint: &AccountInfo) -> Result<()> {
+    if *mint.owner != spl_token_2022::ID {
+        return Ok(());
+    }
+
+    let data = mint.try_borrow_data()?;
+    let mint = StateWithExtensions::<Mint>::unpack(&data)?;
+    let extensions = mint.get_extension_types()?;
+
+    require!(
+        !extensions
+            .iter()
+            .any(|extension| UNSUPPORTED_MINT_EXTENSIONS.contains(extension)),
+        MixerError::UnsupportedMintExtension
+    );
+
+    Ok(())
+}
+
+/// Whether `mint` carries the non-transferable extension
+pub fn is_non_transferable(mint: &AccountInfo) -> Result<bool> {
+    if *mint.owner != spl_token_2022::ID {
+        return Ok(false);
+    }
+
+    let data = mint.try_borrow_data()?;
+    let mint = StateWithExtensions::<Mint>::unpack(&data)?;
+    Ok(mint.get_extension_types()?.contains(&ExtensionType::NonTransferable))
+}
+
+/// Transfer fee charged by `mint` in `epoch`, None if it doesn't charge one
+pub fn transfer_fee(mint: &AccountInfo, epoch: u64) -> Result<Option<TransferFee>> {
+    if *mint.owner != spl_token_2022::ID {
+        return Ok(None);
+    }
+
+    let data = mint.try_borrow_data()?;
+    let mint = StateWithExtensions::<Mint>::unpack(&data)?;
+    Ok(mint
+        .get_extension::<TransferFeeConfig>()
+        .ok()
+        .map(|config| *config.get_epoch_fee(epoch)))
+}
+
+/// Amount withheld when `amount` is transferred
+pub fn fee_on(fee: Option<&TransferFee>, amount: u64) -> Result<u64> {
+    match fee {
+        Some(fee) => fee.calculate_fee(amount).ok_or(error!(MixerError::ArithmeticOverflow)),
+        None => Ok(0),
+    }
+}
+
+/// Amount to transfer so that `net` arrives after the fee
+pub fn gross_up(fee: Option<&TransferFee>, net: u64) -> Result<u64> {
+    match fee {
+        Some(fee) => fee
+            .calculate_pre_fee_amount(net)
+            .ok_or(error!(MixerError::ArithmeticOverflow)),
+        None => Ok(net),
+    }
+}
@@ -0,0 +1,125 @@
+/// Tests for Token-2022 mint handling
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022::spl_token_2022::{
+    self,
+    extension::{
+        non_transferable::NonTransferable,
+        permanent_delegate::PermanentDelegate,
+        transfer_fee::{TransferFee, TransferFeeConfig},
+        BaseStateWithExtensionsMut, ExtensionType, StateWithExtensionsMut,
+    },
+    state::Mint,
+};
+
+use super::token_extensions::*;
+
+fn fee(basis_points: u16, maximum_fee: u64) -> TransferFee {
+    TransferFee {
+        epoch: 0.into(),
+        maximum_fee: maximum_fee.into(),
+        transfer_fee_basis_points: basis_points.into(),
+    }
+}
+
+/// Token-2022 mint account data carrying `extensions`
+fn mint_data(extensions: &[ExtensionType], basis_points: u16) -> Vec<u8> {
+    let len = ExtensionType::try_calculate_account_len::<Mint>(extensions).unwrap();
+    let mut data = vec![0u8; len];
+    let mut mint = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut data).unwrap();
+
+    for extension in extensions {
+        match extension {
+            ExtensionType::TransferFeeConfig => {
+                let config = mint.init_extension::<TransferFeeConfig>(true).unwrap();
+                config.older_transfer_fee = fee(basis_points, u64::MAX);
+                config.newer_transfer_fee = fee(basis_points, u64::MAX);
+            }
+            ExtensionType::PermanentDelegate => {
+                mint.init_extension::<PermanentDelegate>(true).unwrap();
+            }
+            ExtensionType::NonTransferable => {
+                mint.init_extension::<NonTransferable>(true).unwrap();
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    mint.base = Mint {
+        decimals: 6,
+        is_initialized: true,
+        ..Mint::default()
+    };
+    mint.pack_base();
+    mint.init_account_type().unwrap();
+    data
+}
+
+#[test]
+fn test_gross_up_covers_transfer_fee() {
+    let one_percent = fee(100, u64::MAX);
+
+    let gross = gross_up(Some(&one_percent), 1_000_000).unwrap();
+    assert!(gross - fee_on(Some(&one_percent), gross).unwrap() >= 1_000_000);
+    assert_eq!(gross, 1_010_102);
+
+    // Without a fee nothing changes
+    assert_eq!(gross_up(None, 1_000_000).unwrap(), 1_000_000);
+    assert_eq!(fee_on(None, 1_000_000).unwrap(), 0);
+}
+
+#[test]
+fn test_gross_up_respects_maximum_fee() {
+    let capped = fee(100, 500);
+
+    assert_eq!(fee_on(Some(&capped), 1_000_000).unwrap(), 500);
+    assert_eq!(gross_up(Some(&capped), 1_000_000).unwrap(), 1_000_500);
+}
+
+#[test]
+fn test_transfer_fee_read_from_mint() {
+    let key = Pubkey::new_unique();
+    let mut lamports = 0;
+    let mut data = mint_data(&[ExtensionType::TransferFeeConfig], 50);
+    let mint = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &spl_token_2022::ID, false, 0);
+
+    let fee = transfer_fee(&mint, 0).unwrap().unwrap();
+    assert_eq!(u16::from(fee.transfer_fee_basis_points), 50);
+    assert!(check_mint_extensions(&mint).is_ok());
+}
+
+#[test]
+fn test_mint_without_fee_extension() {
+    let key = Pubkey::new_unique();
+    let mut lamports = 0;
+    let mut data = mint_data(&[], 0);
+    let mint = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &spl_token_2022::ID, false, 0);
+
+    assert!(transfer_fee(&mint, 0).unwrap().is_none());
+    assert!(check_mint_extensions(&mint).is_ok());
+}
+
+#[test]
+fn test_permanent_delegate_rejected() {
+    let key = Pubkey::new_unique();
+    let mut lamports = 0;
+    let mut data = mint_data(&[ExtensionType::PermanentDelegate], 0);
+    let mint = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &spl_token_2022::ID, false, 0);
+
+    assert!(check_mint_extensions(&mint).is_err());
+}
+
+#[test]
+fn test_non_transferable_detected() {
+    let key = Pubkey::new_unique();
+    let mut lamports = 0;
+    let mut data = mint_data(&[ExtensionType::NonTransferable], 0);
+    let mint = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &spl_token_2022::ID, false, 0);
+
+    assert!(is_non_transferable(&mint).unwrap());
+    assert!(check_mint_extensions(&mint).is_err());
+
+    let mut plain_lamports = 0;
+    let mut plain = mint_data(&[], 0);
+    let plain = AccountInfo::new(&key, false, false, &mut plain_lamports, &mut plain, &spl_token_2022::ID, false, 0);
+    assert!(!is_non_transferable(&plain).unwrap());
+}